@@ -0,0 +1,278 @@
+use crate::input::Event;
+use std::io::{self, Read, Write};
+
+/// A key read from the terminal.
+#[derive(Debug, PartialEq)]
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    /// Ctrl-C.
+    Interrupt,
+    /// Ctrl-D, which ends input at an empty prompt and deletes otherwise.
+    CtrlD,
+    Other,
+}
+
+/// Reads one line from the terminal with the prompt in front of it. The
+/// arrow keys move the cursor and step through `history`.
+pub fn read_line(prompt: &str, history: &[String]) -> io::Result<Event> {
+    #[cfg(unix)]
+    let _raw = RawMode::enable()?;
+    edit(&mut io::stdin().lock(), &mut io::stdout().lock(), prompt, history)
+}
+
+/// Edits a line read key by key from `input`, redrawing it on `output`.
+fn edit(
+    input: &mut impl Read,
+    output: &mut impl Write,
+    prompt: &str,
+    history: &[String],
+) -> io::Result<Event> {
+    let mut line: Vec<char> = Vec::new();
+    let mut cursor = 0;
+    // the entry shown, where `history.len()` is the line being typed
+    let mut shown = history.len();
+    let mut typed = Vec::new();
+    redraw(output, prompt, &line, cursor)?;
+    loop {
+        let Some(key) = read_key(input)? else {
+            return Ok(if line.is_empty() {
+                Event::Eof
+            } else {
+                Event::Line(line.into_iter().collect())
+            });
+        };
+        match key {
+            Key::Enter => {
+                write!(output, "\r\n")?;
+                output.flush()?;
+                return Ok(Event::Line(line.into_iter().collect()));
+            }
+            Key::Interrupt => return Ok(Event::Interrupt),
+            Key::CtrlD if line.is_empty() => {
+                write!(output, "\r\n")?;
+                output.flush()?;
+                return Ok(Event::Eof);
+            }
+            Key::CtrlD | Key::Delete => {
+                if cursor < line.len() {
+                    line.remove(cursor);
+                }
+            }
+            Key::Backspace => {
+                if cursor > 0 {
+                    cursor -= 1;
+                    line.remove(cursor);
+                }
+            }
+            Key::Left => cursor = cursor.saturating_sub(1),
+            Key::Right => cursor = (cursor + 1).min(line.len()),
+            Key::Home => cursor = 0,
+            Key::End => cursor = line.len(),
+            Key::Up | Key::Down => {
+                let next = if key == Key::Up {
+                    shown.checked_sub(1)
+                } else {
+                    (shown < history.len()).then_some(shown + 1)
+                };
+                let Some(next) = next else { continue };
+                if shown == history.len() {
+                    typed = std::mem::take(&mut line);
+                }
+                line = match history.get(next) {
+                    Some(entry) => entry.chars().collect(),
+                    None => std::mem::take(&mut typed),
+                };
+                shown = next;
+                cursor = line.len();
+            }
+            Key::Char(c) => {
+                line.insert(cursor, c);
+                cursor += 1;
+            }
+            Key::Other => continue,
+        }
+        redraw(output, prompt, &line, cursor)?;
+    }
+}
+
+fn redraw(
+    output: &mut impl Write,
+    prompt: &str,
+    line: &[char],
+    cursor: usize,
+) -> io::Result<()> {
+    let text: String = line.iter().collect();
+    write!(output, "\r{prompt}{text}\x1b[K")?;
+    if cursor < line.len() {
+        write!(output, "\x1b[{}D", line.len() - cursor)?;
+    }
+    output.flush()
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Reads a key, or `None` at the end of `input`.
+fn read_key(input: &mut impl Read) -> io::Result<Option<Key>> {
+    let Some(byte) = read_byte(input)? else {
+        return Ok(None);
+    };
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x01 => Key::Home,
+        0x03 => Key::Interrupt,
+        0x04 => Key::CtrlD,
+        0x05 => Key::End,
+        0x1b => read_escape(input)?,
+        0..0x20 => Key::Other,
+        _ => {
+            let len = match byte.leading_ones() {
+                0 => 1,
+                n @ 2..=4 => n as usize,
+                _ => return Ok(Some(Key::Other)),
+            };
+            let mut bytes = vec![byte];
+            for _ in 1..len {
+                bytes.extend(read_byte(input)?);
+            }
+            match std::str::from_utf8(&bytes).ok().and_then(|s| s.chars().next()) {
+                Some(c) => Key::Char(c),
+                None => Key::Other,
+            }
+        }
+    };
+    Ok(Some(key))
+}
+
+/// Reads the rest of an escape sequence such as `ESC [ A` (up) or
+/// `ESC [ 3 ~` (delete).
+fn read_escape(input: &mut impl Read) -> io::Result<Key> {
+    if !matches!(read_byte(input)?, Some(b'[' | b'O')) {
+        return Ok(Key::Other);
+    }
+    let mut params = Vec::new();
+    // parameters run up to a final byte in `@..=~`
+    while let Some(byte) = read_byte(input)? {
+        if !(0x40..=0x7e).contains(&byte) {
+            params.push(byte);
+            continue;
+        }
+        return Ok(match (byte, params.as_slice()) {
+            (b'A', []) => Key::Up,
+            (b'B', []) => Key::Down,
+            (b'C', []) => Key::Right,
+            (b'D', []) => Key::Left,
+            (b'H', []) | (b'~', b"1" | b"7") => Key::Home,
+            (b'F', []) | (b'~', b"4" | b"8") => Key::End,
+            (b'~', b"3") => Key::Delete,
+            _ => Key::Other,
+        });
+    }
+    Ok(Key::Other)
+}
+
+/// Turns off line buffering, echo and Ctrl-C signals on the terminal until
+/// dropped, so keys reach the editor as they are pressed.
+#[cfg(unix)]
+struct RawMode {
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        // SAFETY: `termios` is plain data filled in by `tcgetattr` before use
+        let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+        // SAFETY: the pointer is to a live `termios`
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut termios) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let original = termios;
+        termios.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+        termios.c_iflag &= !(libc::IXON | libc::ICRNL);
+        termios.c_cc[libc::VMIN] = 1;
+        termios.c_cc[libc::VTIME] = 0;
+        // SAFETY: as above
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSADRAIN, &termios) }
+            != 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { original })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: restores the settings read in `enable`
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSADRAIN, &self.original) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(keys: &[u8], history: &[&str]) -> Event {
+        let history: Vec<String> = history.iter().map(|s| s.to_string()).collect();
+        edit(&mut &keys[..], &mut Vec::new(), "> ", &history).unwrap()
+    }
+
+    fn line(keys: &[u8], history: &[&str]) -> String {
+        match run(keys, history) {
+            Event::Line(line) => line,
+            _ => panic!("no line"),
+        }
+    }
+
+    #[test]
+    fn typing_and_editing() {
+        assert_eq!(line(b"SELECT 1;\r", &[]), "SELECT 1;");
+        assert_eq!(line(b"SELEXT\x7f\x7fCT\r", &[]), "SELECT");
+        assert_eq!(line(b"ac\x1b[Db\x1b[Cd\r", &[]), "abcd");
+        assert_eq!(line(b"bc\x01a\x05d\r", &[]), "abcd");
+        assert_eq!(line(b"abc\x1b[H\x1b[3~\x04\r", &[]), "c");
+        assert_eq!(line("é✓\r".as_bytes(), &[]), "é✓");
+    }
+
+    #[test]
+    fn arrows_recall_history() {
+        let history = ["SELECT 1;", "SELECT 2;"];
+        assert_eq!(line(b"\x1b[A\r", &history), "SELECT 2;");
+        assert_eq!(line(b"\x1b[A\x1b[A\x1b[A\r", &history), "SELECT 1;");
+        assert_eq!(line(b"\x1b[A\x1b[A\x1b[B\r", &history), "SELECT 2;");
+        // stepping past the newest entry brings back the typed line
+        assert_eq!(line(b"SEL\x1b[A\x1b[B\x1b[BECT\r", &history), "SELECT");
+        assert_eq!(line(b"\x1b[A\x7f 3;\r", &history), "SELECT 2 3;");
+        assert_eq!(line(b"\x1bOA\r", &history), "SELECT 2;");
+        assert_eq!(line(b"\x1b[A\r", &[]), "");
+    }
+
+    #[test]
+    fn control_keys_end_the_line() {
+        assert!(matches!(run(b"SEL\x03", &[]), Event::Interrupt));
+        assert!(matches!(run(b"\x04", &[]), Event::Eof));
+        assert!(matches!(run(b"", &[]), Event::Eof));
+        assert_eq!(line(b"SEL", &[]), "SEL");
+    }
+}
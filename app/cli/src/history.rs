@@ -0,0 +1,88 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const MAX_ENTRIES: usize = 1000;
+
+/// Entered statements and commands, kept in a file across sessions.
+pub struct History {
+    path: PathBuf,
+    entries: Vec<String>,
+}
+
+impl History {
+    /// Loads `~/.squirrel_history`. Returns `None` when there is no home
+    /// directory to keep it in.
+    pub fn load() -> Option<Self> {
+        let home =
+            std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+        Some(Self::open(PathBuf::from(home).join(".squirrel_history")))
+    }
+
+    /// Loads the history kept at `path`, starting empty if it cannot be read.
+    pub fn open(path: PathBuf) -> Self {
+        let entries = match fs::read_to_string(&path) {
+            Ok(text) => text.lines().map(str::to_string).collect(),
+            Err(_) => Vec::new(),
+        };
+        Self { path, entries }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Adds an entry, skipping it if it repeats the previous one. Line breaks
+    /// are folded into spaces so each entry takes one line in the file.
+    pub fn push(&mut self, entry: &str) {
+        let entry = entry.trim().lines().map(str::trim).collect::<Vec<_>>().join(" ");
+        if entry.is_empty() || self.entries.last() == Some(&entry) {
+            return;
+        }
+        self.entries.push(entry);
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let skip = self.entries.len().saturating_sub(MAX_ENTRIES);
+        let mut text = self.entries[skip..].join("\n");
+        text.push('\n');
+        fs::write(&self.path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history");
+        let mut history = History::open(path.clone());
+        assert!(history.entries().is_empty());
+        history.push("SELECT 1;");
+        history.push("  SELECT 1;  ");
+        history.push("SELECT\n  2;\n");
+        history.push(".tables");
+        history.push("   ");
+        history.save().unwrap();
+
+        let history = History::open(path);
+        assert_eq!(history.entries(), ["SELECT 1;", "SELECT 2;", ".tables"]);
+    }
+
+    #[test]
+    fn save_keeps_the_newest_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history");
+        let mut history = History::open(path.clone());
+        for i in 0..MAX_ENTRIES + 5 {
+            history.push(&format!("SELECT {i};"));
+        }
+        history.save().unwrap();
+
+        let entries = History::open(path).entries;
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0], "SELECT 5;");
+    }
+}
@@ -0,0 +1,219 @@
+use litesqrl::executor::{Executor, Prepared, SQRLErr};
+use litesqrl::query::{Expr, Param, Stmt};
+use litesqrl::schema::{ColumnType, DataValue};
+
+/// Flags of the `.import` command.
+#[derive(Default)]
+pub struct Options {
+    /// The first record names the target columns instead of holding data.
+    pub header: bool,
+    /// Report bad rows and go on instead of importing nothing.
+    pub skip_errors: bool,
+}
+
+/// What an import did.
+pub struct Summary {
+    pub imported: usize,
+    /// One message per skipped row, starting with its line number.
+    pub skipped: Vec<String>,
+}
+
+/// One CSV record and the line it starts on.
+struct Record {
+    line: usize,
+    /// `None` for an empty unquoted field, which stands for NULL.
+    fields: Result<Vec<Option<String>>, String>,
+}
+
+/// Inserts the rows of the CSV file at `path` into `table`.
+///
+/// Fields are converted to the column types as `CAST` would, except that
+/// text columns take them unchanged. A row that is malformed or fails to
+/// insert rolls back the whole import, unless `skip_errors` is set. The rows
+/// go in as one transaction, or under a savepoint inside an open one.
+pub fn import(
+    exec: &mut Executor,
+    path: &str,
+    table: &str,
+    options: &Options,
+) -> Result<Summary, String> {
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read {path}: {e}"))?;
+    let mut columns = exec.table_columns(table).map_err(|e| e.to_string())?;
+    let mut records = parse(src.strip_prefix('\u{feff}').unwrap_or(&src)).into_iter();
+    if options.header {
+        let Some(header) = records.next() else {
+            return Ok(Summary { imported: 0, skipped: Vec::new() });
+        };
+        let line = header.line;
+        let names = header.fields.map_err(|e| format!("line {line}: {e}"))?;
+        columns = names
+            .into_iter()
+            .map(|name| {
+                let name = name.unwrap_or_default();
+                let name = name.trim();
+                columns.iter().find(|(col, _)| col == name).cloned().ok_or_else(|| {
+                    format!("line {line}: no column {name:?} in {table}")
+                })
+            })
+            .collect::<Result<_, _>>()?;
+    }
+    let insert = Prepared::new(Stmt::InsertValues {
+        table_name: table.into(),
+        columns: columns.iter().map(|(name, _)| name.as_str().into()).collect(),
+        values: vec![
+            (0..columns.len())
+                .map(|index| Expr::Param(Param::Positional(index)))
+                .collect(),
+        ],
+        returning: None,
+    });
+
+    let owns_txn = !exec.in_transaction();
+    let begin =
+        if owns_txn { Stmt::Begin } else { Stmt::Savepoint { name: SAVEPOINT.into() } };
+    exec.run(begin).map_err(|e| e.to_string())?;
+    let mut summary = Summary { imported: 0, skipped: Vec::new() };
+    for record in records {
+        let result = match record.fields.and_then(|fields| convert(&columns, fields)) {
+            Ok(values) => {
+                match insert.bind(&values).and_then(|stmt| exec.run(stmt.into_stmt())) {
+                    Ok(_) => Ok(()),
+                    // Ctrl-C stops the import rather than skipping a row
+                    Err(SQRLErr::Interrupted) => {
+                        rollback(exec, owns_txn);
+                        return Err(SQRLErr::Interrupted.to_string());
+                    }
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => summary.imported += 1,
+            Err(e) => {
+                let e = format!("line {}: {e}", record.line);
+                if !options.skip_errors {
+                    rollback(exec, owns_txn);
+                    return Err(e);
+                }
+                summary.skipped.push(e);
+            }
+        }
+    }
+    let end =
+        if owns_txn { Stmt::Commit } else { Stmt::Release { name: SAVEPOINT.into() } };
+    exec.run(end).map_err(|e| e.to_string())?;
+    Ok(summary)
+}
+
+const SAVEPOINT: &str = "sqrl_import";
+
+fn rollback(exec: &mut Executor, owns_txn: bool) {
+    // the import failed already; a failing rollback has nothing to add
+    if owns_txn {
+        let _ = exec.run(Stmt::Rollback);
+    } else {
+        let _ = exec.run(Stmt::RollbackTo { name: SAVEPOINT.into() });
+        let _ = exec.run(Stmt::Release { name: SAVEPOINT.into() });
+    }
+}
+
+fn convert(
+    columns: &[(String, ColumnType)],
+    fields: Vec<Option<String>>,
+) -> Result<Vec<DataValue>, String> {
+    if fields.len() != columns.len() {
+        return Err(format!(
+            "expected {} fields, found {}",
+            columns.len(),
+            fields.len()
+        ));
+    }
+    columns
+        .iter()
+        .zip(fields)
+        .map(|((name, ty), field)| {
+            let Some(field) = field else {
+                return Ok(DataValue::Nil);
+            };
+            let text = DataValue::Text(field.as_str().into());
+            ty.coerce(text.clone())
+                .or_else(|| ty.cast(text))
+                .ok_or_else(|| format!("cannot convert {field:?} to {ty} for {name}"))
+        })
+        .collect()
+}
+
+/// Splits CSV text into records: fields are separated by commas and may be
+/// quoted with `"`, in which case they can hold commas, line breaks and
+/// doubled quotes. Empty lines are skipped. A record with text after a
+/// closing quote is returned as an error, as is an unterminated quote, which
+/// runs to the end of the input.
+fn parse(src: &str) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut chars = src.chars().peekable();
+    let mut line = 1;
+    while chars.peek().is_some() {
+        let start = line;
+        let mut fields = Vec::new();
+        let mut error = None;
+        loop {
+            let field = if chars.next_if_eq(&'"').is_some() {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.next_if_eq(&'"').is_some() => text.push('"'),
+                        Some('"') => break,
+                        Some(ch) => {
+                            if ch == '\n' {
+                                line += 1;
+                            }
+                            text.push(ch);
+                        }
+                        None => {
+                            error = Some("unterminated quoted field".to_string());
+                            break;
+                        }
+                    }
+                }
+                Some(text)
+            } else {
+                let mut text = String::new();
+                while let Some(ch) =
+                    chars.next_if(|ch| !matches!(ch, ',' | '\r' | '\n'))
+                {
+                    text.push(ch);
+                }
+                (!text.is_empty()).then_some(text)
+            };
+            fields.push(field);
+            let end = match chars.next() {
+                Some(',') => continue,
+                Some(ch @ ('\r' | '\n')) => Some(ch),
+                None => None,
+                Some(ch) => {
+                    error.get_or_insert(format!(
+                        "unexpected {ch:?} after a quoted field"
+                    ));
+                    // skip the rest of the record
+                    while chars.next_if(|ch| !matches!(ch, '\r' | '\n')).is_some() {}
+                    chars.next()
+                }
+            };
+            if end == Some('\r') {
+                chars.next_if_eq(&'\n');
+            }
+            break;
+        }
+        line += 1;
+        if fields != [None] || error.is_some() {
+            let fields = match error {
+                Some(error) => Err(error),
+                None => Ok(fields),
+            };
+            records.push(Record { line: start, fields });
+        }
+    }
+    records
+}
@@ -0,0 +1,121 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// Something the REPL reacts to.
+pub enum Event {
+    Line(String),
+    /// Ctrl-C while waiting for input.
+    Interrupt,
+    Failed(io::Error),
+    Eof,
+}
+
+/// Lines of stdin, read on a background thread so Ctrl-C can be reported
+/// between them, or edited on the terminal when stdin is one.
+pub struct Events {
+    rx: Receiver<Event>,
+    busy: Arc<AtomicBool>,
+    interrupt: Option<Arc<AtomicBool>>,
+    edit: bool,
+}
+
+impl Events {
+    /// Reads stdin without touching Ctrl-C, which keeps ending the process.
+    pub fn stdin() -> Self {
+        let (tx, rx) = mpsc::channel();
+        read_lines(tx);
+        Self { rx, busy: Arc::default(), interrupt: None, edit: false }
+    }
+
+    /// Reads stdin and handles Ctrl-C instead of letting it end the process.
+    /// While [`Events::run`] is running, Ctrl-C sets `interrupt` to stop the
+    /// statement; otherwise it comes through as [`Event::Interrupt`].
+    ///
+    /// On a terminal, lines are edited with [`crate::editor`], which reads
+    /// Ctrl-C as a key while a line is being typed.
+    pub fn with_interrupts(interrupt: Arc<AtomicBool>) -> io::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let busy = Arc::new(AtomicBool::new(false));
+        let runtime =
+            tokio::runtime::Builder::new_current_thread().enable_io().build()?;
+        let signal_tx = tx.clone();
+        let (signal_busy, signal_interrupt) =
+            (Arc::clone(&busy), Arc::clone(&interrupt));
+        thread::spawn(move || {
+            runtime.block_on(async move {
+                while tokio::signal::ctrl_c().await.is_ok() {
+                    if signal_busy.load(Ordering::Relaxed) {
+                        signal_interrupt.store(true, Ordering::Relaxed);
+                    } else if signal_tx.send(Event::Interrupt).is_err() {
+                        break;
+                    }
+                }
+            })
+        });
+        let edit = cfg!(unix) && io::stdin().is_terminal();
+        if !edit {
+            read_lines(tx);
+        }
+        Ok(Self { rx, busy, interrupt: Some(interrupt), edit })
+    }
+
+    /// Replays `events` in order, then ends.
+    #[cfg(test)]
+    pub fn script(events: Vec<Event>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        for event in events {
+            tx.send(event).unwrap();
+        }
+        Self { rx, busy: Arc::default(), interrupt: None, edit: false }
+    }
+
+    /// Shows `prompt` and waits for the next line. While it is edited, the
+    /// arrow keys step through `history`.
+    pub fn next(&self, prompt: &str, history: &[String]) -> Event {
+        if self.edit {
+            // a Ctrl-C between a statement finishing and the prompt showing
+            if let Ok(event) = self.rx.try_recv() {
+                return event;
+            }
+            return crate::editor::read_line(prompt, history)
+                .unwrap_or_else(Event::Failed);
+        }
+        if !prompt.is_empty() {
+            print!("{prompt}");
+            // a prompt that fails to show is not worth stopping for
+            let _ = io::stdout().flush();
+        }
+        // both senders only stop once the REPL is gone
+        self.rx.recv().unwrap_or(Event::Eof)
+    }
+
+    /// Runs `f` with Ctrl-C directed at the interrupt flag.
+    pub fn run<T>(&self, f: impl FnOnce() -> T) -> T {
+        if let Some(interrupt) = &self.interrupt {
+            // a Ctrl-C that landed just after the last statement is stale
+            interrupt.store(false, Ordering::Relaxed);
+        }
+        self.busy.store(true, Ordering::Relaxed);
+        let out = f();
+        self.busy.store(false, Ordering::Relaxed);
+        out
+    }
+}
+
+fn read_lines(tx: Sender<Event>) {
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let event = match line {
+                Ok(line) => Event::Line(line),
+                Err(e) => Event::Failed(e),
+            };
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+        let _ = tx.send(Event::Eof);
+    });
+}
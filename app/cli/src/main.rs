@@ -0,0 +1,474 @@
+mod editor;
+mod history;
+mod import;
+mod input;
+mod render;
+
+use history::History;
+use input::{Event, Events};
+use litesqrl::executor::{Executor, QueryResult};
+use litesqrl::query::error::QueryErrKind;
+use litesqrl::query::lexer::Token;
+use litesqrl::query::{StmtCache, tokenize};
+use litesqrl::storage::{Storage, StorageErr};
+use render::Mode;
+use std::io::{self, IsTerminal};
+use std::process::ExitCode;
+use std::time::Instant;
+
+const PROMPT: &str = "sqrl> ";
+const CONTINUE_PROMPT: &str = " ...> ";
+const USAGE: &str = "usage: sqrl [--init <file>] \
+                     [--format column|list|line|table|csv|json] \
+                     [--no-history] [-c <sql>] [-C] [database]";
+
+struct Options {
+    path: String,
+    init: Option<String>,
+    mode: Mode,
+    history: bool,
+    execute: Option<String>,
+    create: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Input {
+    Empty,
+    Incomplete,
+    Complete,
+}
+
+struct Repl {
+    exec: Executor,
+    mode: Mode,
+    headers: bool,
+    max_width: usize,
+    timer: bool,
+    history: Option<History>,
+    cache: StmtCache,
+}
+
+fn main() -> ExitCode {
+    let options = parse_args();
+    let path = options.path;
+    // an in-memory database never exists beforehand, so it needs no --create
+    let opened = if options.create || path == Storage::MEMORY {
+        Storage::open_or_create(&path)
+    } else {
+        Storage::open(&path)
+    };
+    let storage = match opened {
+        Ok(storage) => storage,
+        Err(StorageErr::Io(e)) if e.kind() == io::ErrorKind::NotFound => {
+            eprintln!(
+                "Error: database {path} does not exist (use --create to create it)"
+            );
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            eprintln!("Error: cannot open {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    // piped input runs as a script, without the banner and prompts
+    let interactive = options.execute.is_none() && io::stdin().is_terminal();
+    let mut repl = Repl {
+        exec: Executor::new(storage),
+        mode: options.mode,
+        headers: true,
+        max_width: render::MAX_WIDTH,
+        timer: false,
+        history: if options.history && interactive { History::load() } else { None },
+        cache: StmtCache::default(),
+    };
+    if interactive {
+        println!("SQiurreL {} ({path})", env!("CARGO_PKG_VERSION"));
+        println!("Enter \".exit\" to quit.");
+    }
+    if let Some(init) = &options.init {
+        repl.read_file(init);
+    }
+    if let Some(sql) = &options.execute {
+        return if repl.run(sql) { ExitCode::SUCCESS } else { ExitCode::FAILURE };
+    }
+    repl.interact(interactive)
+}
+
+fn parse_args() -> Options {
+    let mut options = Options {
+        path: "database.sqrl".into(),
+        init: None,
+        mode: Mode::Column,
+        history: true,
+        execute: None,
+        create: false,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--init" => match args.next() {
+                Some(file) => options.init = Some(file),
+                None => usage(),
+            },
+            "--format" => match args.next().as_deref().and_then(Mode::parse) {
+                Some(mode) => options.mode = mode,
+                None => usage(),
+            },
+            "-c" | "--execute" => match args.next() {
+                Some(sql) => options.execute = Some(sql),
+                None => usage(),
+            },
+            "-C" | "--create" => options.create = true,
+            "--no-history" => options.history = false,
+            flag if flag.starts_with('-') => usage(),
+            _ => options.path = arg,
+        }
+    }
+    options
+}
+
+fn usage() -> ! {
+    eprintln!("{USAGE}");
+    std::process::exit(2);
+}
+
+/// Input is complete once its last token is `;`. Input that fails to lex is
+/// also complete, so the error is reported instead of waiting for more lines,
+/// except for a block comment that may still be closed on a later line.
+fn check_input(src: &str) -> Input {
+    match tokenize(src).as_deref() {
+        Ok([Token::Eof]) => Input::Empty,
+        Ok([.., Token::Semicolon, Token::Eof]) => Input::Complete,
+        Ok(_) => Input::Incomplete,
+        Err(e) if e.kind == QueryErrKind::UnterminatedComment => Input::Incomplete,
+        Err(_) => Input::Complete,
+    }
+}
+
+impl Repl {
+    /// Reads statements and `.commands` from stdin until it ends. Without
+    /// `prompts` (piped input), a failing statement does not stop the rest
+    /// but makes the exit status a failure, and a last statement missing its
+    /// `;` still runs.
+    ///
+    /// With `prompts`, Ctrl-C discards the input typed so far or stops the
+    /// running statement. A second Ctrl-C in a row at an empty prompt exits.
+    fn interact(&mut self, prompts: bool) -> ExitCode {
+        let events = if prompts {
+            Events::with_interrupts(self.exec.interrupt_handle()).unwrap_or_else(|e| {
+                eprintln!("Error: cannot handle Ctrl-C: {e}");
+                Events::stdin()
+            })
+        } else {
+            Events::stdin()
+        };
+        self.read_events(&events, prompts)
+    }
+
+    /// The body of [`Repl::interact`], reading from `events`.
+    fn read_events(&mut self, events: &Events, prompts: bool) -> ExitCode {
+        let mut status = ExitCode::SUCCESS;
+        let mut buf = String::new();
+        let mut interrupted = false;
+        loop {
+            let prompt = if !prompts {
+                ""
+            } else if buf.is_empty() {
+                PROMPT
+            } else {
+                CONTINUE_PROMPT
+            };
+            let history = self.history.as_ref().map_or(&[][..], History::entries);
+            let line = match events.next(prompt, history) {
+                Event::Line(line) => line,
+                Event::Interrupt => {
+                    println!();
+                    if buf.is_empty() {
+                        if interrupted {
+                            break;
+                        }
+                        println!("(press Ctrl-C again or enter \".exit\" to quit)");
+                    }
+                    buf.clear();
+                    interrupted = true;
+                    continue;
+                }
+                Event::Failed(e) => {
+                    eprintln!("Error: cannot read input: {e}");
+                    status = ExitCode::FAILURE;
+                    break;
+                }
+                Event::Eof => break,
+            };
+            interrupted = false;
+            if buf.is_empty() && line.trim_start().starts_with('.') {
+                self.remember(&line);
+                if !events.run(|| self.run_meta(line.trim())) {
+                    break;
+                }
+                continue;
+            }
+            buf.push_str(&line);
+            buf.push('\n');
+            match check_input(&buf) {
+                Input::Empty => buf.clear(),
+                Input::Incomplete => {}
+                Input::Complete => {
+                    self.remember(&buf);
+                    if !events.run(|| self.run(&buf)) && !prompts {
+                        status = ExitCode::FAILURE;
+                    }
+                    buf.clear();
+                }
+            }
+        }
+        if !prompts && !buf.is_empty() && !self.run(&buf) {
+            status = ExitCode::FAILURE;
+        }
+        if let Some(history) = &self.history
+            && let Err(e) = history.save()
+        {
+            eprintln!("Error: cannot save history: {e}");
+        }
+        status
+    }
+
+    /// Runs a `.command` line. Returns `false` when the REPL should exit.
+    fn run_meta(&mut self, line: &str) -> bool {
+        let mut args = line.split_whitespace();
+        match args.next() {
+            Some(".exit") => return false,
+            Some(".tables") => {
+                for name in self.exec.table_names() {
+                    println!("{name}");
+                }
+            }
+            Some(".schema") => match args.next() {
+                Some(table_name) => match self.exec.table_ddl(table_name) {
+                    Ok(ddl) => println!("{ddl}"),
+                    Err(e) => eprintln!("Error: {e}"),
+                },
+                None => println!("{}", self.exec.schema_ddl()),
+            },
+            Some(".dump") => println!("{}", self.exec.dump()),
+            Some(".read") => match args.next() {
+                Some(path) => self.read_file(path),
+                None => eprintln!("Error: usage: .read <file>"),
+            },
+            Some(".import") => self.import(args),
+            Some(".mode") => match args.next().and_then(Mode::parse) {
+                Some(mode) => self.mode = mode,
+                None => {
+                    eprintln!("Error: usage: .mode column|list|line|table|csv|json")
+                }
+            },
+            Some(".headers") => match args.next() {
+                Some("on") => self.headers = true,
+                Some("off") => self.headers = false,
+                _ => eprintln!("Error: usage: .headers on|off"),
+            },
+            Some(".maxwidth") => match args.next().map(str::parse) {
+                Some(Ok(width)) => self.max_width = width,
+                _ => eprintln!("Error: usage: .maxwidth <characters> (0 for no limit)"),
+            },
+            Some(".timer") => match args.next() {
+                Some("on") => self.timer = true,
+                Some("off") => self.timer = false,
+                _ => eprintln!("Error: usage: .timer on|off"),
+            },
+            Some(".history") => {
+                for entry in self.history.iter().flat_map(History::entries) {
+                    println!("{entry}");
+                }
+            }
+            Some(cmd) => eprintln!("Error: unknown command: {cmd}"),
+            None => {}
+        }
+        true
+    }
+
+    /// `.import [--header] [--skip-errors] <file> <table>`
+    fn import<'a>(&mut self, args: impl Iterator<Item = &'a str>) {
+        let mut options = import::Options::default();
+        let mut paths = Vec::new();
+        for arg in args {
+            match arg {
+                "--header" => options.header = true,
+                "--skip-errors" => options.skip_errors = true,
+                _ => paths.push(arg),
+            }
+        }
+        let [path, table] = paths[..] else {
+            eprintln!(
+                "Error: usage: .import [--header] [--skip-errors] <file> <table>"
+            );
+            return;
+        };
+        match import::import(&mut self.exec, path, table, &options) {
+            Ok(summary) => {
+                for skipped in &summary.skipped {
+                    eprintln!("Skipped {skipped}");
+                }
+                self.print_result(QueryResult::Count(summary.imported));
+            }
+            Err(e) => eprintln!("Error: {e}"),
+        }
+    }
+
+    fn remember(&mut self, entry: &str) {
+        if let Some(history) = &mut self.history {
+            history.push(entry);
+        }
+    }
+
+    /// Runs every statement in a SQL script, stopping at the first error.
+    fn read_file(&mut self, path: &str) {
+        match std::fs::read_to_string(path) {
+            Ok(src) => {
+                self.run(&src);
+            }
+            Err(e) => eprintln!("Error: cannot read {path}: {e}"),
+        }
+    }
+
+    /// Runs the statements in `src` in order. Returns `false` if one failed, in
+    /// which case the rest are skipped.
+    fn run(&mut self, src: &str) -> bool {
+        // the first statement's time includes parsing the whole input
+        let mut start = Instant::now();
+        let stmts = match self.cache.parse(src) {
+            Ok(stmts) => stmts,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                return false;
+            }
+        };
+        for stmt in stmts {
+            let result = self.exec.run(stmt.stmt);
+            let elapsed = start.elapsed();
+            let ok = match result {
+                Ok(result) => {
+                    self.print_result(result);
+                    true
+                }
+                Err(e) => {
+                    eprintln!("Error: {e}");
+                    false
+                }
+            };
+            if self.timer {
+                println!("Run Time: {:.4}s", elapsed.as_secs_f64());
+            }
+            if !ok {
+                return false;
+            }
+            start = Instant::now();
+        }
+        true
+    }
+
+    fn print_result(&self, result: QueryResult) {
+        match result {
+            QueryResult::Rows { columns, rows } => {
+                let out = render::rows(
+                    self.mode,
+                    self.headers,
+                    self.max_width,
+                    &columns,
+                    &rows,
+                );
+                // line mode prints nothing at all for no rows
+                if !out.is_empty() {
+                    println!("{out}");
+                }
+            }
+            QueryResult::Count(1) => println!("1 row affected"),
+            QueryResult::Count(count) => println!("{count} rows affected"),
+            QueryResult::Success => {}
+            QueryResult::Err(e) => eprintln!("Error: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_input_waits_for_the_semicolon() {
+        let mut buf = String::new();
+        for line in ["CREATE TABLE t", "(a INT,", " b TEXT)"] {
+            buf.push_str(line);
+            buf.push('\n');
+            assert_eq!(check_input(&buf), Input::Incomplete, "{buf}");
+        }
+        buf.push_str(";\n");
+        assert_eq!(check_input(&buf), Input::Complete);
+        assert_eq!(StmtCache::default().parse(&buf).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn check_input_cases() {
+        assert_eq!(check_input(""), Input::Empty);
+        assert_eq!(check_input("  -- just a comment\n"), Input::Empty);
+        assert_eq!(check_input("SELECT ';'"), Input::Incomplete);
+        assert_eq!(check_input("SELECT 1; SELECT"), Input::Incomplete);
+        assert_eq!(check_input("SELECT 1; -- done\n"), Input::Complete);
+        assert_eq!(check_input("SELECT 1 /* a\n"), Input::Incomplete);
+        assert_eq!(check_input("SELECT 1 /* ; */ ;"), Input::Complete);
+        // a lexing error is reported rather than read past
+        assert_eq!(check_input("SELECT 'open\n"), Input::Complete);
+    }
+
+    fn repl() -> Repl {
+        Repl {
+            exec: Executor::new(Storage::memory()),
+            mode: Mode::Column,
+            headers: true,
+            max_width: render::MAX_WIDTH,
+            timer: false,
+            history: None,
+            cache: StmtCache::default(),
+        }
+    }
+
+    fn line(text: &str) -> Event {
+        Event::Line(text.to_string())
+    }
+
+    #[test]
+    fn interrupt_discards_the_unfinished_statement() {
+        let mut repl = repl();
+        let events = Events::script(vec![
+            line("CREATE TABLE t"),
+            line("(a INT,"),
+            Event::Interrupt,
+            line("CREATE TABLE u (a INT);"),
+        ]);
+        repl.read_events(&events, true);
+        assert_eq!(repl.exec.table_names(), ["u"]);
+    }
+
+    #[test]
+    fn second_interrupt_at_an_empty_prompt_exits() {
+        let mut repl = repl();
+        let events = Events::script(vec![
+            line("CREATE TABLE t"),
+            Event::Interrupt,
+            Event::Interrupt,
+            line("CREATE TABLE u (a INT);"),
+        ]);
+        repl.read_events(&events, true);
+        assert!(repl.exec.table_names().is_empty());
+
+        // a line in between starts the count over
+        let events = Events::script(vec![
+            Event::Interrupt,
+            line(""),
+            Event::Interrupt,
+            line("CREATE TABLE u (a INT);"),
+        ]);
+        repl.read_events(&events, true);
+        assert_eq!(repl.exec.table_names(), ["u"]);
+    }
+}
@@ -0,0 +1,463 @@
+use litesqrl::schema::DataValue;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Cells wider than this many terminal columns are cut short in column and table
+/// output unless `.maxwidth` changes it.
+pub const MAX_WIDTH: usize = 40;
+
+pub type Row = Vec<DataValue>;
+
+/// How query results are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Column,
+    List,
+    Line,
+    Table,
+    Csv,
+    Json,
+}
+
+impl Mode {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "column" => Some(Self::Column),
+            "list" => Some(Self::List),
+            "line" => Some(Self::Line),
+            "table" => Some(Self::Table),
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Renders a result set. Without `headers` the column-name line is left out;
+/// line and JSON output name every value, so they ignore it. Column and table
+/// cells wider than `max_width` terminal columns are cut short; 0 means no
+/// limit.
+pub fn rows(
+    mode: Mode,
+    headers: bool,
+    max_width: usize,
+    columns: &[String],
+    rows: &[Row],
+) -> String {
+    let names = columns.iter().map(String::as_str).collect::<Vec<_>>();
+    match mode {
+        Mode::Column => column(&names, rows, headers, max_width),
+        Mode::List => list(columns, rows, headers),
+        Mode::Line => line(columns, rows),
+        Mode::Table => table(&names, rows, headers, max_width),
+        Mode::Csv => csv(columns, rows, headers),
+        Mode::Json => json(columns, rows),
+    }
+}
+
+/// Renders rows as a bordered table with a header, cutting cells at
+/// [`MAX_WIDTH`].
+#[allow(dead_code)] // the REPL goes through `rows` for its settings
+pub fn format_table(columns: &[&str], rows: &[Row]) -> String {
+    table(columns, rows, true, MAX_WIDTH)
+}
+
+/// A value as printed in column and table output. Numbers are right-aligned.
+struct Cell {
+    text: String,
+    right: bool,
+}
+
+impl Cell {
+    fn new(value: &DataValue, max_width: usize) -> Self {
+        let text = match value {
+            DataValue::Nil => "NULL".to_string(),
+            value => value.to_string(),
+        };
+        Self {
+            text: truncate(text, max_width),
+            right: matches!(
+                value,
+                DataValue::Int(_) | DataValue::Real(_) | DataValue::Decimal(_)
+            ),
+        }
+    }
+
+    fn left(text: &str) -> Self {
+        Self { text: text.to_string(), right: false }
+    }
+
+    fn pad(&self, width: usize) -> String {
+        let text = &self.text;
+        let fill = " ".repeat(width.saturating_sub(text.width()));
+        if self.right { format!("{fill}{text}") } else { format!("{text}{fill}") }
+    }
+}
+
+/// Cuts `text` to `max_width` terminal columns, ending in `…` when shortened.
+/// Wide characters such as CJK take two columns.
+fn truncate(text: String, max_width: usize) -> String {
+    if max_width == 0 || text.width() <= max_width {
+        return text;
+    }
+    let mut cut = String::new();
+    let mut width = 0;
+    for ch in text.chars() {
+        width += ch.width().unwrap_or(0);
+        if width > max_width - 1 {
+            break;
+        }
+        cut.push(ch);
+    }
+    cut.push('…');
+    cut
+}
+
+fn cells(rows: &[Row], max_width: usize) -> Vec<Vec<Cell>> {
+    rows.iter()
+        .map(|row| row.iter().map(|value| Cell::new(value, max_width)).collect())
+        .collect()
+}
+
+fn widths(columns: &[&str], rows: &[Vec<Cell>]) -> Vec<usize> {
+    let mut widths = columns.iter().map(|col| col.width()).collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.text.width());
+        }
+    }
+    widths
+}
+
+/// Renders rows as aligned columns under a dashed header, without borders.
+fn column(columns: &[&str], rows: &[Row], headers: bool, max_width: usize) -> String {
+    let rows = cells(rows, max_width);
+    let widths = widths(columns, &rows);
+    let line = |cells: &[Cell]| {
+        let cells = widths
+            .iter()
+            .zip(cells)
+            .map(|(width, cell)| cell.pad(*width))
+            .collect::<Vec<_>>();
+        cells.join("  ").trim_end().to_string()
+    };
+    let header = columns.iter().map(|col| Cell::left(col)).collect::<Vec<_>>();
+    let dashes =
+        widths.iter().map(|width| Cell::left(&"-".repeat(*width))).collect::<Vec<_>>();
+    let mut out = if headers { vec![line(&header), line(&dashes)] } else { vec![] };
+    out.extend(rows.iter().map(|row| line(row)));
+    out.join("\n")
+}
+
+/// Renders rows as `|`-separated values. NULL is an empty field.
+fn list(columns: &[String], rows: &[Row], headers: bool) -> String {
+    let mut lines = if headers { vec![columns.join("|")] } else { vec![] };
+    for row in rows {
+        let fields = row
+            .iter()
+            .map(|value| match value {
+                DataValue::Nil => String::new(),
+                value => value.to_string(),
+            })
+            .collect::<Vec<_>>();
+        lines.push(fields.join("|"));
+    }
+    lines.join("\n")
+}
+
+/// Renders each row as `column = value` lines followed by a blank line.
+/// Column names are right-aligned.
+fn line(columns: &[String], rows: &[Row]) -> String {
+    let width = columns.iter().map(|col| col.width()).max().unwrap_or(0);
+    let records = rows
+        .iter()
+        .map(|row| {
+            let lines = columns
+                .iter()
+                .zip(row)
+                .map(|(col, value)| {
+                    let col = Cell { text: col.to_string(), right: true }.pad(width);
+                    match value {
+                        DataValue::Nil => format!("{col} = NULL"),
+                        value => format!("{col} = {value}"),
+                    }
+                })
+                .collect::<Vec<_>>();
+            lines.join("\n")
+        })
+        .map(|record| record + "\n")
+        .collect::<Vec<_>>();
+    records.join("\n")
+}
+
+/// Renders rows as an ASCII table with each column padded to its widest cell.
+fn table(columns: &[&str], rows: &[Row], headers: bool, max_width: usize) -> String {
+    if !headers && rows.is_empty() {
+        return String::new();
+    }
+    let rows = cells(rows, max_width);
+    let widths = widths(columns, &rows);
+
+    let border = widths.iter().fold(String::from("+"), |mut line, width| {
+        line.push_str(&"-".repeat(width + 2));
+        line.push('+');
+        line
+    });
+    let line = |cells: &[Cell]| {
+        widths.iter().zip(cells).fold(String::from("|"), |mut line, (width, cell)| {
+            line.push_str(&format!(" {} |", cell.pad(*width)));
+            line
+        })
+    };
+
+    let mut out = vec![border.clone()];
+    if headers {
+        out.push(line(&columns.iter().map(|col| Cell::left(col)).collect::<Vec<_>>()));
+        out.push(border.clone());
+    }
+    out.extend(rows.iter().map(|row| line(row)));
+    if !rows.is_empty() {
+        out.push(border);
+    }
+    out.join("\n")
+}
+
+/// Renders rows as RFC 4180 CSV. NULL is an empty field.
+fn csv(columns: &[String], rows: &[Row], headers: bool) -> String {
+    let mut lines = Vec::new();
+    if headers {
+        lines.push(columns.iter().map(|col| csv_field(col)).collect::<Vec<_>>());
+    }
+    for row in rows {
+        lines.push(
+            row.iter()
+                .map(|value| match value {
+                    DataValue::Nil => String::new(),
+                    value => csv_field(&value.to_string()),
+                })
+                .collect(),
+        );
+    }
+    lines.iter().map(|fields| fields.join(",")).collect::<Vec<_>>().join("\r\n")
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Renders rows as a JSON array with one object per row, keyed by column.
+fn json(columns: &[String], rows: &[Row]) -> String {
+    let objects = rows
+        .iter()
+        .map(|row| {
+            let fields = columns
+                .iter()
+                .zip(row)
+                .map(|(col, value)| {
+                    format!("{}:{}", json_string(col), json_value(value))
+                })
+                .collect::<Vec<_>>();
+            format!("{{{}}}", fields.join(","))
+        })
+        .collect::<Vec<_>>();
+    format!("[{}]", objects.join(",\n"))
+}
+
+fn json_value(value: &DataValue) -> String {
+    match value {
+        DataValue::Real(value) if !value.is_finite() => "null".to_string(),
+        DataValue::Nil => "null".to_string(),
+        DataValue::Text(value) => json_string(value),
+        DataValue::Date(_) | DataValue::Timestamp(_) | DataValue::Blob(_) => {
+            json_string(&value.to_string())
+        }
+        value => value.to_string(),
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_table_empty() {
+        assert_eq!(
+            format_table(&["a", "bc"], &[]),
+            "+---+----+\n| a | bc |\n+---+----+"
+        );
+    }
+
+    #[test]
+    fn format_table_single_row() {
+        let rows =
+            [vec![DataValue::Int(7), DataValue::Text("x".into()), DataValue::Nil]];
+        assert_eq!(
+            format_table(&["id", "name", "note"], &rows),
+            "+----+------+------+\n\
+             | id | name | note |\n\
+             +----+------+------+\n\
+             |  7 | x    | NULL |\n\
+             +----+------+------+"
+        );
+    }
+
+    #[test]
+    fn format_table_wide_cell() {
+        let wide = "x".repeat(MAX_WIDTH + 5);
+        let rows = [vec![DataValue::Text(wide.into())]];
+        let out = format_table(&["w"], &rows);
+        let cell = format!("| {}… |", "x".repeat(MAX_WIDTH - 1));
+        assert!(out.lines().any(|line| line == cell), "{out}");
+        assert!(out.lines().all(|line| line.chars().count() == MAX_WIDTH + 4));
+    }
+
+    #[test]
+    fn wide_characters_take_two_columns() {
+        let rows = [
+            vec![DataValue::Text("다람쥐".into())],
+            vec![DataValue::Text("acorn".into())],
+        ];
+        assert_eq!(
+            format_table(&["name"], &rows),
+            "+--------+\n\
+             | name   |\n\
+             +--------+\n\
+             | 다람쥐 |\n\
+             | acorn  |\n\
+             +--------+"
+        );
+        // a cut never splits a wide character across the limit
+        let wide = "松鼠".repeat(MAX_WIDTH);
+        let out = format_table(&["w"], &[vec![DataValue::Text(wide.into())]]);
+        let cell = format!("| {}… |", "松鼠".repeat((MAX_WIDTH - 2) / 4) + "松");
+        assert!(out.lines().any(|line| line == cell), "{out}");
+    }
+
+    #[test]
+    fn zero_max_width_keeps_wide_cells() {
+        let wide = "y".repeat(MAX_WIDTH * 2);
+        let rows = [vec![DataValue::Text(wide.as_str().into())]];
+        let out = super::rows(Mode::Table, true, 0, &["w".to_string()], &rows);
+        assert!(out.contains(&wide), "{out}");
+    }
+
+    #[test]
+    fn modes_render_the_same_rows_differently() {
+        let columns = ["id".to_string(), "name".to_string()];
+        let rows = [
+            vec![DataValue::Int(7), DataValue::Text("acorn".into())],
+            vec![DataValue::Int(12), DataValue::Nil],
+        ];
+        let render = |mode| super::rows(mode, true, MAX_WIDTH, &columns, &rows);
+        assert_eq!(
+            render(Mode::Column),
+            "id  name\n\
+             --  -----\n \
+             7  acorn\n\
+             12  NULL"
+        );
+        assert_eq!(render(Mode::List), "id|name\n7|acorn\n12|");
+        assert_eq!(
+            render(Mode::Line),
+            "  id = 7\nname = acorn\n\n  id = 12\nname = NULL\n"
+        );
+    }
+
+    fn strings(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn csv_quotes_tricky_fields() {
+        let rows = [
+            vec![DataValue::Int(1), DataValue::Text("plain".into()), DataValue::Nil],
+            vec![
+                DataValue::Real(2.5),
+                DataValue::Text("a,b".into()),
+                DataValue::Text("say \"hi\"".into()),
+            ],
+            vec![
+                DataValue::Int(3),
+                DataValue::Text("two\nlines".into()),
+                DataValue::Text("".into()),
+            ],
+        ];
+        let columns = strings(&["id", "text", "note, quoted"]);
+        assert_eq!(
+            super::rows(Mode::Csv, true, MAX_WIDTH, &columns, &rows),
+            "id,text,\"note, quoted\"\r\n\
+             1,plain,\r\n\
+             2.5,\"a,b\",\"say \"\"hi\"\"\"\r\n\
+             3,\"two\nlines\","
+        );
+    }
+
+    #[test]
+    fn csv_header_follows_headers_setting() {
+        let rows = [vec![DataValue::Int(1)]];
+        let columns = strings(&["n"]);
+        assert_eq!(super::rows(Mode::Csv, true, MAX_WIDTH, &columns, &rows), "n\r\n1");
+        assert_eq!(super::rows(Mode::Csv, false, MAX_WIDTH, &columns, &rows), "1");
+        assert_eq!(super::rows(Mode::Csv, true, MAX_WIDTH, &columns, &[]), "n");
+    }
+
+    #[test]
+    fn column_header_follows_headers_setting() {
+        let rows = [vec![DataValue::Int(1), DataValue::Text("ab".into())]];
+        let columns = strings(&["n", "name"]);
+        let render = |headers, rows: &[Row]| {
+            super::rows(Mode::Column, headers, MAX_WIDTH, &columns, rows)
+        };
+        assert_eq!(render(true, &rows), "n  name\n-  ----\n1  ab");
+        assert_eq!(render(false, &rows), "1  ab");
+        assert_eq!(render(false, &[]), "");
+    }
+
+    #[test]
+    fn json_maps_types_and_nulls() {
+        let rows = [
+            vec![
+                DataValue::Int(-1),
+                DataValue::Real(0.5),
+                DataValue::Bool(true),
+                DataValue::Text("a \"q\" \\ \n\u{1}".into()),
+                DataValue::Nil,
+            ],
+            vec![
+                DataValue::Int(2),
+                DataValue::Real(f64::NAN),
+                DataValue::Bool(false),
+                DataValue::Text("다람쥐".into()),
+                DataValue::Blob([0xab].into()),
+            ],
+        ];
+        let columns = strings(&["i", "r", "b", "t", "n"]);
+        let out = super::rows(Mode::Json, true, MAX_WIDTH, &columns, &rows);
+        let expected = [
+            r#"[{"i":-1,"r":0.5,"b":true,"t":"a \"q\" \\ \n\u0001","n":null},"#,
+            r#"{"i":2,"r":null,"b":false,"t":"다람쥐","n":"AB"}]"#,
+        ];
+        assert_eq!(out, expected.join("\n"));
+        assert_eq!(super::rows(Mode::Json, true, MAX_WIDTH, &columns, &[]), "[]");
+    }
+}
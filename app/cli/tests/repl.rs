@@ -0,0 +1,440 @@
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+struct Run {
+    stdout: String,
+    stderr: String,
+    success: bool,
+}
+
+/// Runs `sqrl` with `args` and `input` piped to stdin.
+fn invoke(args: &[&str], input: &str) -> Run {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sqrl"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // sqrl may exit before reading its input, e.g. when it cannot open the
+    // database, so a closed pipe is not an error here
+    let written = child.stdin.take().unwrap().write_all(input.as_bytes());
+    if let Err(e) = written {
+        assert_eq!(e.kind(), ErrorKind::BrokenPipe, "{e}");
+    }
+    let output = child.wait_with_output().unwrap();
+    Run {
+        stdout: String::from_utf8(output.stdout).unwrap(),
+        stderr: String::from_utf8(output.stderr).unwrap(),
+        success: output.status.success(),
+    }
+}
+
+/// Runs `sqrl` on the database at `db`, creating it if needed, with `input`
+/// piped to stdin.
+fn sqrl(db: &Path, args: &[&str], input: &str) -> Run {
+    let db = db.to_str().unwrap();
+    invoke(&[&["--create"], args, &[db]].concat(), input)
+}
+
+#[test]
+fn piped_statements_run_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run = sqrl(
+        &db,
+        &[],
+        "CREATE TABLE t (a INT, b TEXT);\n\
+         INSERT INTO t VALUES (1, 'x');\n\
+         SELECT a, b FROM t WHERE a = 1;\n",
+    );
+    assert_eq!(run.stderr, "");
+    let out = run.stdout;
+    let count = out.find("1 row affected").unwrap();
+    let rows = out.find("a  b\n-  -\n1  x\n").unwrap();
+    assert!(count < rows, "{out}");
+    assert!(db.exists());
+}
+
+#[test]
+fn errors_are_reported_and_the_next_statement_runs() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run = sqrl(&db, &[], "SELEC 1;\nCREATE TABLE t (a INT);\nSELECT * FROM t;\n");
+    assert!(run.stderr.starts_with("Error: "), "{}", run.stderr);
+    assert_eq!(run.stderr.lines().count(), 1, "{}", run.stderr);
+    assert!(run.stdout.contains("a\n-\n"), "{}", run.stdout);
+}
+
+#[test]
+fn exit_stops_reading() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run =
+        sqrl(&db, &[], "CREATE TABLE t (a INT);\n.exit\nCREATE TABLE u (a INT);\n");
+    assert_eq!(run.stderr, "");
+    let run = sqrl(&db, &[], "CREATE TABLE t (a INT);\nCREATE TABLE u (a INT);\n");
+    // t already exists, u was never made
+    assert_eq!(run.stderr, "Error: table already exists: 't' (1)\n");
+}
+
+#[test]
+fn tables_lists_table_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run = sqrl(&db, &[], ".tables\n");
+    assert_eq!(run.stderr, "");
+    assert_eq!(run.stdout, "");
+
+    let run = sqrl(
+        &db,
+        &[],
+        "CREATE TABLE zebras (a INT);\nCREATE TABLE apes (a INT);\n\
+         CREATE TABLE gone (a INT);\nDROP TABLE gone;\n.tables\n",
+    );
+    assert_eq!(run.stderr, "");
+    assert_eq!(run.stdout, "apes\nzebras\n");
+
+    let run = sqrl(&db, &[], ".nope\n");
+    assert_eq!(run.stderr, "Error: unknown command: .nope\n");
+}
+
+#[test]
+fn schema_prints_ddl_that_parses_back() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run = sqrl(
+        &db,
+        &[],
+        "CREATE TABLE users (id INT PRIMARY KEY, name TEXT NOT NULL DEFAULT 'anon');\n\
+         .schema\n",
+    );
+    assert_eq!(run.stderr, "");
+    let schema = run.stdout;
+    assert!(schema.starts_with("CREATE TABLE users ("), "{schema}");
+
+    // the printed DDL recreates the same table
+    let copy = dir.path().join("copy.sqrl");
+    let run = sqrl(&copy, &[], &format!("{schema}.schema users\n"));
+    assert_eq!(run.stderr, "");
+    assert_eq!(run.stdout, schema);
+}
+
+#[test]
+fn statements_continue_across_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run = sqrl(
+        &db,
+        &[],
+        "CREATE TABLE t\n(a INT,\n b TEXT)\n;\nINSERT INTO t\nVALUES (1, 'x;y');\n\
+         SELECT b\nFROM t;\n.tables\n",
+    );
+    assert_eq!(run.stderr, "");
+    assert_eq!(run.stdout, "1 row affected\nb\n---\nx;y\nt\n");
+}
+
+#[test]
+fn syntax_errors_reset_the_statement() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run = sqrl(&db, &[], "SELEC\n1;\n.tables\nCREATE\nTABLE t (a INT);\n.tables\n");
+    assert_eq!(run.stderr.lines().count(), 1, "{}", run.stderr);
+    assert_eq!(run.stdout, "t\n");
+}
+
+#[test]
+fn read_runs_a_script() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let script = dir.path().join("seed.sql");
+    std::fs::write(
+        &script,
+        "CREATE TABLE t (a INT);\n-- seed rows\nINSERT INTO t\nVALUES (1);\n",
+    )
+    .unwrap();
+    let input = format!(".read {}\nSELECT a FROM t;\n", script.display());
+    let run = sqrl(&db, &[], &input);
+    assert_eq!(run.stderr, "");
+    assert_eq!(run.stdout, "1 row affected\na\n-\n1\n");
+
+    let run = sqrl(&db, &[], ".read missing.sql\n");
+    assert!(run.stderr.starts_with("Error: cannot read missing.sql"), "{}", run.stderr);
+}
+
+#[test]
+fn read_stops_at_the_first_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let script = dir.path().join("seed.sql");
+    std::fs::write(
+        &script,
+        "CREATE TABLE t (a INT UNIQUE);\nINSERT INTO t VALUES (1);\n\
+         INSERT INTO t VALUES (1);\nINSERT INTO t VALUES (2);\n",
+    )
+    .unwrap();
+    let input = format!(".read {}\nSELECT a FROM t;\n", script.display());
+    let run = sqrl(&db, &[], &input);
+    assert!(run.stderr.contains("UNIQUE constraint failed"), "{}", run.stderr);
+    assert_eq!(run.stdout, "1 row affected\na\n-\n1\n");
+}
+
+#[test]
+fn init_runs_before_the_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let script = dir.path().join("init.sql");
+    std::fs::write(&script, "CREATE TABLE t (a INT); INSERT INTO t VALUES (7);")
+        .unwrap();
+    let init = script.to_str().unwrap();
+    let run = sqrl(&db, &["--init", init], "SELECT a FROM t;\n");
+    assert_eq!(run.stderr, "");
+    assert_eq!(run.stdout, "1 row affected\na\n-\n7\n");
+}
+
+#[test]
+fn timer_prints_only_when_on() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run = sqrl(
+        &db,
+        &[],
+        "CREATE TABLE t (n INT);\nINSERT INTO t VALUES (1);\nSELECT n FROM t;\n\
+         .timer on\nSELECT n FROM t; INSERT INTO t VALUES (2);\n\
+         .timer off\nSELECT n FROM t;\n",
+    );
+    assert_eq!(run.stderr, "");
+    let out = run.stdout;
+    let timed = out.lines().filter_map(|line| line.strip_prefix("Run Time: "));
+    let secs = timed.map(|line| line.strip_suffix('s').unwrap().parse::<f64>());
+    assert_eq!(secs.filter(Result::is_ok).count(), 2, "{out}");
+    // each time follows the output of its own statement
+    assert!(out.contains("n\n-\n1\nRun Time: "), "{out}");
+    assert!(out.contains("1 row affected\nRun Time: "), "{out}");
+
+    let run = sqrl(&db, &[], ".timer maybe\n");
+    assert_eq!(run.stderr, "Error: usage: .timer on|off\n");
+}
+
+#[test]
+fn execute_runs_and_exits() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let db = db.to_str().unwrap();
+    // several statements, and stdin is never read
+    let run = invoke(
+        &[
+            "-C",
+            "-c",
+            "CREATE TABLE t (a INT); INSERT INTO t VALUES (5); SELECT a FROM t;",
+            db,
+        ],
+        "DROP TABLE t;",
+    );
+    assert!(run.success, "{}", run.stderr);
+    assert_eq!(run.stdout, "1 row affected\na\n-\n5\n");
+
+    let run = invoke(&["--format", "csv", "-c", "SELECT a FROM t", db], "");
+    assert!(run.success, "{}", run.stderr);
+    assert_eq!(run.stdout, "a\r\n5\n");
+
+    let run = invoke(&["-c", "SELECT nope FROM t; SELECT a FROM t", db], "");
+    assert!(!run.success);
+    assert_eq!(run.stdout, "");
+    assert!(run.stderr.starts_with("Error: "), "{}", run.stderr);
+}
+
+#[test]
+fn create_makes_a_database_that_reopens() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("new.sqrl");
+    let run = sqrl(&db, &[], "CREATE TABLE t (a INT);\n");
+    assert!(run.success, "{}", run.stderr);
+    assert!(db.exists());
+
+    // a second run opens it without the flag
+    let db = db.to_str().unwrap();
+    let run = invoke(&["-c", "SELECT a FROM t", db], "");
+    assert!(run.success, "{}", run.stderr);
+    assert_eq!(run.stdout, "a\n-\n");
+    // and with it, the existing file is kept
+    let run = invoke(&["--create", "-c", "SELECT a FROM t", db], "");
+    assert!(run.success, "{}", run.stderr);
+}
+
+#[test]
+fn missing_database_is_a_clean_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("missing.sqrl");
+    let run = invoke(&[db.to_str().unwrap()], ".tables\n");
+    assert!(!run.success);
+    assert!(run.stderr.starts_with("Error: "), "{}", run.stderr);
+    assert!(!run.stderr.contains("panicked"), "{}", run.stderr);
+    assert!(run.stdout.is_empty());
+    assert!(!db.exists());
+}
+
+#[test]
+fn memory_database_runs_without_a_file() {
+    let run = invoke(
+        &["--format", "csv", ":memory:"],
+        "CREATE TABLE t (a INT);
+         INSERT INTO t VALUES (1), (2);
+         UPDATE t SET a = 3 WHERE a = 2;
+         DELETE FROM t WHERE a = 1;
+         SELECT a FROM t;\n",
+    );
+    assert!(run.success, "{}", run.stderr);
+    assert_eq!(run.stdout, "2 rows affected\n1 row affected\n1 row affected\na\r\n3\n");
+    assert!(!Path::new(":memory:").exists());
+}
+
+#[test]
+fn piped_input_prints_every_result_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run = sqrl(
+        &db,
+        &["--format", "csv"],
+        "CREATE TABLE t (a INT); INSERT INTO t VALUES (1);\n\
+         SELECT a FROM t; SELECT a + 1 AS b FROM t;\n\
+         .tables\n\
+         DELETE FROM t;\n",
+    );
+    assert!(run.success, "{}", run.stderr);
+    assert_eq!(run.stdout, "1 row affected\na\r\n1\nb\r\n2\nt\n1 row affected\n");
+}
+
+#[test]
+fn piped_errors_fail_the_run() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run = sqrl(&db, &["--format", "csv"], "SELEC 1;\nSELECT 2 AS n;\n");
+    assert!(!run.success);
+    assert!(run.stderr.starts_with("Error: "), "{}", run.stderr);
+    // the statement after the error still runs
+    assert_eq!(run.stdout, "n\r\n2\n");
+}
+
+#[test]
+fn piped_last_statement_needs_no_semicolon() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run = sqrl(&db, &["--format", "csv"], "SELECT 1 AS n");
+    assert!(run.success, "{}", run.stderr);
+    assert_eq!(run.stdout, "n\r\n1\n");
+}
+
+#[test]
+fn mode_defaults_to_column_and_persists() {
+    let run = invoke(
+        &[":memory:"],
+        "SELECT 1 AS n;\n.mode list\nSELECT 2 AS n;\nSELECT 3 AS m;\n.mode bogus\n",
+    );
+    assert_eq!(run.stdout, "n\n-\n1\nn\n2\nm\n3\n");
+    assert!(run.stderr.starts_with("Error: usage: .mode"), "{}", run.stderr);
+}
+
+#[test]
+fn headers_toggle_in_column_and_csv_modes() {
+    let run = invoke(
+        &[":memory:"],
+        ".headers off\nSELECT 1 AS n;\n.mode csv\nSELECT 2 AS n;\n\
+         .headers on\nSELECT 3 AS n;\n",
+    );
+    assert_eq!(run.stdout, "1\n2\nn\r\n3\n");
+}
+
+#[test]
+fn writes_print_the_rows_they_affect() {
+    let run = invoke(
+        &[":memory:"],
+        "CREATE TABLE t (a INT);\nINSERT INTO t VALUES (1), (2), (3);\n\
+         UPDATE t SET a = 0 WHERE a = 2;\nDELETE FROM t WHERE a > 5;\n",
+    );
+    assert_eq!(run.stdout, "3 rows affected\n1 row affected\n0 rows affected\n");
+}
+
+#[test]
+fn import_loads_csv_rows() {
+    let dir = tempfile::tempdir().unwrap();
+    let csv = dir.path().join("people.csv");
+    std::fs::write(&csv, "name,id\r\n\"Lee, Ada\",1\r\nBo,2\r\n,3\r\n").unwrap();
+    let csv = csv.to_str().unwrap();
+    let run = invoke(
+        &[":memory:"],
+        &format!(
+            "CREATE TABLE people (id INT, name TEXT);\n\
+             .import --header {csv} people\n\
+             .mode list\n\
+             SELECT id, name FROM people;\n"
+        ),
+    );
+    assert_eq!(run.stderr, "");
+    assert_eq!(run.stdout, "3 rows affected\nid|name\n1|Lee, Ada\n2|Bo\n3|\n");
+}
+
+#[test]
+fn import_reports_bad_rows_by_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let csv = dir.path().join("bad.csv");
+    std::fs::write(&csv, "1,a\nx,b\n3\n4,d\n").unwrap();
+    let csv = csv.to_str().unwrap();
+    let setup = "CREATE TABLE t (id INT, name TEXT);\n";
+    let count = "SELECT COUNT(*) AS n FROM t;\n";
+
+    // a bad row aborts the whole import
+    let run = invoke(&[":memory:"], &format!("{setup}.import {csv} t\n{count}"));
+    assert!(run.stderr.starts_with("Error: line 2: "), "{}", run.stderr);
+    assert_eq!(run.stdout, "n\n-\n0\n");
+
+    let run = invoke(
+        &[":memory:"],
+        &format!("{setup}.import --skip-errors {csv} t\n{count}"),
+    );
+    let skipped = run.stderr.lines().collect::<Vec<_>>();
+    assert_eq!(skipped.len(), 2, "{}", run.stderr);
+    assert!(skipped[0].starts_with("Skipped line 2: cannot convert"), "{}", skipped[0]);
+    assert_eq!(skipped[1], "Skipped line 3: expected 2 fields, found 1");
+    assert_eq!(run.stdout, "2 rows affected\nn\n-\n2\n");
+}
+
+#[test]
+fn schema_output_parses_back() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run = sqrl(
+        &db,
+        &[],
+        "CREATE TABLE users (id INT PRIMARY KEY AUTOINCREMENT,
+                             name VARCHAR(20) NOT NULL DEFAULT 'anon',
+                             email TEXT UNIQUE COLLATE NOCASE,
+                             score DECIMAL(5, 2), CHECK (score >= 0));
+         CREATE TABLE posts (id INT PRIMARY KEY, author INT,
+                             FOREIGN KEY (author) REFERENCES users(id));
+         .schema\n",
+    );
+    assert!(run.success, "{}", run.stderr);
+    let schema = run.stdout;
+    assert!(schema.contains("CREATE TABLE users ("), "{schema}");
+
+    // the printed DDL recreates the same tables
+    let copy = dir.path().join("copy.sqrl");
+    let run = sqrl(&copy, &[], &format!("{schema}\n.schema\n"));
+    assert!(run.success, "{}", run.stderr);
+    assert_eq!(run.stdout, schema);
+
+    let run = sqrl(&copy, &[], ".schema posts\n");
+    assert!(run.stdout.starts_with("CREATE TABLE posts ("), "{}", run.stdout);
+    assert!(!run.stdout.contains("users ("));
+}
+
+#[test]
+fn schema_of_an_unknown_table_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("db.sqrl");
+    let run = sqrl(&db, &["--format", "list"], ".schema nope\nSELECT 1 AS n;\n");
+    assert_eq!(run.stderr, "Error: table not found: nope\n");
+    assert_eq!(run.stdout, "n\n1\n");
+}
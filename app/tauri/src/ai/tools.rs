@@ -115,7 +115,11 @@ fn format_results(results: Vec<QueryResult>) -> Result<String, String> {
             QueryResult::Count(count) => json!({ "type": "count", "count": count }),
             QueryResult::Rows { columns, rows } => {
                 let total_rows = rows.len();
-                let preview_rows = rows.into_iter().take(50).collect::<Vec<_>>();
+                let preview_rows = rows
+                    .iter()
+                    .take(50)
+                    .map(|row| row.iter().map(ToString::to_string).collect::<Vec<_>>())
+                    .collect::<Vec<_>>();
                 json!({
                     "type": "rows",
                     "columns": columns,
@@ -39,7 +39,8 @@ pub fn run() {
             let data_dir = app.path().app_data_dir().expect("no app data dir");
             std::fs::create_dir_all(&data_dir)?;
             let db_path = data_dir.join("database.sqrl");
-            let storage = Storage::open(db_path).expect("failed to open storage");
+            let storage =
+                Storage::open_or_create(db_path).expect("failed to open storage");
             app.manage(Mutex::new(Executor::new(storage)));
             Ok(())
         })
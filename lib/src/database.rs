@@ -0,0 +1,265 @@
+use crate::executor::{Executor, QueryResult, Result};
+use crate::query::{Lexer, Parser, Stmt, StmtCache};
+use crate::schema::DataValue;
+use crate::storage::Storage;
+use std::path::PathBuf;
+#[cfg(feature = "async")]
+use std::sync::{Arc, Mutex};
+
+/// An open database, for running SQL text from other Rust programs.
+///
+/// Parsed statements are cached by their text, so running the same SQL
+/// again skips the parser.
+pub struct Database {
+    exec: Executor,
+    cache: StmtCache,
+}
+
+/// Rows returned by [`Database::query`], with the values in column order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultSet {
+    columns: Vec<String>,
+    rows: Vec<Vec<DataValue>>,
+}
+
+/// What [`Database::execute_batch`] does after a statement fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// Skip the statements after the failing one.
+    #[default]
+    Stop,
+    /// Run the remaining statements anyway.
+    Continue,
+}
+
+impl Database {
+    /// Opens an existing database file.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        Ok(Self::from_storage(Storage::open(path)?))
+    }
+
+    /// Creates a new database file. Fails if the file already exists.
+    pub fn create(path: impl Into<PathBuf>) -> Result<Self> {
+        Ok(Self::from_storage(Storage::create(path)?))
+    }
+
+    /// Opens the database file, creating it first if it does not exist. The
+    /// name `:memory:` gives a new in-memory database instead.
+    pub fn open_or_create(path: impl Into<PathBuf>) -> Result<Self> {
+        Ok(Self::from_storage(Storage::open_or_create(path)?))
+    }
+
+    /// Creates a database that lives only in memory.
+    pub fn memory() -> Self {
+        Self::from_storage(Storage::memory())
+    }
+
+    pub fn from_storage(storage: Storage) -> Self {
+        Self { exec: Executor::new(storage), cache: StmtCache::default() }
+    }
+
+    /// The underlying executor, e.g. for running [`crate::executor::Prepared`]
+    /// statements.
+    pub fn executor(&mut self) -> &mut Executor {
+        &mut self.exec
+    }
+
+    /// The cache of parsed statements, e.g. to resize or clear it.
+    pub fn cache(&mut self) -> &mut StmtCache {
+        &mut self.cache
+    }
+
+    /// Runs every statement in `sql` and returns the total number of rows
+    /// they inserted, updated or deleted. Stops at the first failing
+    /// statement; earlier statements stay applied.
+    pub fn execute(&mut self, sql: &str) -> Result<u64> {
+        let mut count = 0;
+        for stmt in self.cache.parse(sql)? {
+            let writes = matches!(
+                stmt.stmt,
+                Stmt::InsertValues { .. }
+                    | Stmt::InsertSelect { .. }
+                    | Stmt::Update { .. }
+                    | Stmt::Delete { .. }
+            );
+            count += match self.exec.run(stmt.stmt)? {
+                QueryResult::Count(n) => n as u64,
+                // writes with RETURNING list the rows they changed
+                QueryResult::Rows { rows, .. } if writes => rows.len() as u64,
+                _ => 0,
+            };
+        }
+        Ok(count)
+    }
+
+    /// Runs every statement in `sql` and returns the rows of the last one.
+    /// A last statement that returns no rows gives an empty result set.
+    pub fn query(&mut self, sql: &str) -> Result<ResultSet> {
+        match self.run(sql)?.pop() {
+            Some(QueryResult::Rows { columns, rows }) => {
+                Ok(ResultSet { columns, rows })
+            }
+            _ => Ok(ResultSet::default()),
+        }
+    }
+
+    /// Runs the statements in `sql` in order and returns one result per
+    /// statement run, with failures as [`QueryResult::Err`]. With
+    /// [`OnError::Stop`] the failing statement's result is the last one.
+    ///
+    /// Each statement commits on its own, so statements before a failure stay
+    /// applied unless the batch itself opens a transaction. Text that fails to
+    /// parse runs nothing and is returned as an error.
+    pub fn execute_batch(
+        &mut self,
+        sql: &str,
+        on_error: OnError,
+    ) -> Result<Vec<QueryResult>> {
+        let stmts = self.cache.parse(sql)?;
+        let mut results = Vec::with_capacity(stmts.len());
+        for stmt in stmts {
+            match self.exec.run(stmt.stmt) {
+                Ok(result) => results.push(result),
+                Err(e) => {
+                    results.push(QueryResult::Err(e.to_string()));
+                    if on_error == OnError::Stop {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Parses the statements in `sql` and checks them against the current
+    /// schema without running them; see [`Executor::check`]. Returns the
+    /// first error. Every statement is checked against the schema as it is
+    /// now, so one that uses a table created earlier in `sql` fails.
+    pub fn check(&self, sql: &str) -> Result<()> {
+        for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+            self.exec.check(&stmt.stmt)?;
+        }
+        Ok(())
+    }
+
+    fn run(&mut self, sql: &str) -> Result<Vec<QueryResult>> {
+        let stmts = self.cache.parse(sql)?;
+        let mut results = Vec::with_capacity(stmts.len());
+        for stmt in stmts {
+            results.push(self.exec.run(stmt.stmt)?);
+        }
+        Ok(results)
+    }
+}
+
+impl ResultSet {
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Position of the column labelled `name`.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|col| col == name)
+    }
+
+    pub fn rows(&self) -> &[Vec<DataValue>] {
+        &self.rows
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The value at `row` in the column named `column`.
+    pub fn get(&self, row: usize, column: &str) -> Option<&DataValue> {
+        self.rows.get(row)?.get(self.column_index(column)?)
+    }
+
+    pub fn into_rows(self) -> Vec<Vec<DataValue>> {
+        self.rows
+    }
+}
+
+impl IntoIterator for ResultSet {
+    type Item = Vec<DataValue>;
+    type IntoIter = std::vec::IntoIter<Vec<DataValue>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.into_iter()
+    }
+}
+
+/// A [`Database`] shared between tasks of a tokio runtime.
+///
+/// Each call runs on tokio's blocking thread pool, so file I/O never stalls
+/// the async workers. Calls are serialized: a statement waits for the one
+/// before it to finish.
+///
+/// Dropping a returned future does not cancel the work. Once a call has
+/// started it runs every statement to completion and commits them as usual;
+/// only its result is discarded. A call that has not started yet when its
+/// future is dropped may still run.
+#[cfg(feature = "async")]
+#[derive(Clone)]
+pub struct AsyncDatabase {
+    inner: Arc<Mutex<Database>>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncDatabase {
+    /// Opens an existing database file.
+    pub async fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        Ok(blocking(move || Database::open(path)).await?.into())
+    }
+
+    /// Creates a new database file. Fails if the file already exists.
+    pub async fn create(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        Ok(blocking(move || Database::create(path)).await?.into())
+    }
+
+    /// Opens the database file, creating it first if it does not exist.
+    pub async fn open_or_create(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        Ok(blocking(move || Database::open_or_create(path)).await?.into())
+    }
+
+    /// See [`Database::execute`].
+    pub async fn execute_async(&self, sql: &str) -> Result<u64> {
+        let inner = Arc::clone(&self.inner);
+        let sql = sql.to_string();
+        blocking(move || inner.lock().unwrap().execute(&sql)).await
+    }
+
+    /// See [`Database::query`].
+    pub async fn query_async(&self, sql: &str) -> Result<ResultSet> {
+        let inner = Arc::clone(&self.inner);
+        let sql = sql.to_string();
+        blocking(move || inner.lock().unwrap().query(&sql)).await
+    }
+}
+
+#[cfg(feature = "async")]
+impl From<Database> for AsyncDatabase {
+    fn from(db: Database) -> Self {
+        Self { inner: Arc::new(Mutex::new(db)) }
+    }
+}
+
+/// Runs `f` on the blocking thread pool, re-raising its panic if it panics.
+#[cfg(feature = "async")]
+async fn blocking<T, F>(f: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(value) => value,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
@@ -0,0 +1,468 @@
+use super::{Executor, Result, SQRLErr};
+use crate::query::lexer::Token;
+use crate::query::{Expr, SelectSource, Stmt};
+use crate::schema::{ColumnType, DataType, DataValue, Decimal};
+use crate::storage::TableState;
+use std::collections::HashSet;
+
+/// Column names with the type of their values, for checking expressions.
+type ColumnTypes = [(String, DataType)];
+
+impl Executor {
+    /// Checks a statement against the current schema without running it or
+    /// changing anything.
+    ///
+    /// The tables, columns and savepoints it names must exist, and the names
+    /// CREATE and RENAME introduce must not. INSERT rows must have as many
+    /// values as there are target columns, and set operations as many
+    /// columns on each side. Operators must get operands of types they
+    /// accept, WHERE clauses must be BOOL, and the values INSERT and UPDATE
+    /// write must convert to the column type. Errors that depend on the
+    /// stored rows or on the values, such as constraint violations, division
+    /// by zero or unparsable dates, only show up when the statement runs.
+    pub fn check(&self, stmt: &Stmt) -> Result<()> {
+        match stmt {
+            Stmt::Create {
+                table_name,
+                defines,
+                foreign_keys,
+                checks,
+                if_not_exists,
+            } => {
+                if self.storage.state.get_table_by_name(table_name).is_some() {
+                    if *if_not_exists {
+                        return Ok(());
+                    }
+                    return Err(SQRLErr::TableAlreadyExists(table_name.to_string()));
+                }
+                let mut seen = HashSet::new();
+                if let Some(define) =
+                    defines.iter().find(|define| !seen.insert(&define.name))
+                {
+                    return Err(SQRLErr::DuplicateColumn(define.name.to_string()));
+                }
+                let columns = defines
+                    .iter()
+                    .map(|define| define.name.to_string())
+                    .collect::<Vec<_>>();
+                for fk in foreign_keys {
+                    Self::check_column_refs(
+                        &[Expr::Ident(fk.column.clone())],
+                        &columns,
+                        &[],
+                    )?;
+                    // the new table may reference itself
+                    let ref_columns = if fk.ref_table == *table_name {
+                        columns.clone()
+                    } else {
+                        Self::live_col_names(self.storage.get_table(&fk.ref_table)?)
+                    };
+                    Self::check_column_refs(
+                        &[Expr::Ident(fk.ref_column.clone())],
+                        &ref_columns,
+                        &[],
+                    )?;
+                }
+                let column_checks = defines.iter().flat_map(Self::column_checks);
+                Self::check_column_refs(
+                    checks.iter().chain(column_checks),
+                    &columns,
+                    &[],
+                )
+            }
+            Stmt::InsertValues { table_name, columns, values, returning } => {
+                let (_, live_cols, source_indexes, expected) =
+                    self.resolve_insert_targets(table_name, columns)?;
+                if let Some(row) = values.iter().find(|row| row.len() != expected) {
+                    return Err(SQRLErr::ColumnCountMismatch {
+                        expected,
+                        got: row.len(),
+                    });
+                }
+                // values are computed before there is a row to read from
+                Self::check_column_refs(values.iter().flatten(), &[], &[])?;
+                for row in values {
+                    for (col, index) in live_cols.iter().zip(&source_indexes) {
+                        if let Some(index) = index {
+                            let value = self.check_expr(&row[*index], &[])?;
+                            Self::check_assign(&col.name, col.data_type, value)?;
+                        }
+                    }
+                }
+                self.check_returning(table_name, returning.as_deref())
+            }
+            Stmt::InsertSelect { table_name, columns, select, returning } => {
+                let (_, _, _, expected) =
+                    self.resolve_insert_targets(table_name, columns)?;
+                let got = self.check_query(select)?.len();
+                if got != expected {
+                    return Err(SQRLErr::ColumnCountMismatch { expected, got });
+                }
+                self.check_returning(table_name, returning.as_deref())
+            }
+            Stmt::Select { .. } | Stmt::SetOp { .. } | Stmt::With { .. } => {
+                self.check_query(stmt).map(drop)
+            }
+            Stmt::Explain(query) => self.check_query(query).map(drop),
+            Stmt::Update { table_name, assigns, where_clause, returning } => {
+                let table = self.storage.get_table(table_name)?;
+                let columns = Self::live_col_names(table);
+                let mut seen = HashSet::new();
+                for (name, _) in assigns {
+                    if !seen.insert(name) {
+                        return Err(SQRLErr::DuplicateColumn(name.to_string()));
+                    }
+                    if !columns.iter().any(|col| **col == **name) {
+                        return Err(SQRLErr::ColumnNotFound(name.to_string()));
+                    }
+                }
+                let mut exprs = assigns
+                    .iter()
+                    .map(|(_, expr)| expr)
+                    .chain(where_clause)
+                    .chain(returning.iter().flatten())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                Self::unqualify(&mut exprs, Some(table_name))?;
+                Self::check_column_refs(&exprs, &columns, &[])?;
+                let types = Self::column_types(table);
+                let (values, rest) = exprs.split_at(assigns.len());
+                for ((name, _), value) in assigns.iter().zip(values) {
+                    let value = self.check_expr(value, &types)?;
+                    let col = table.get_col_by_name(name).filter(|col| col.alive);
+                    if let Some(col) = col {
+                        Self::check_assign(&col.name, col.data_type, value)?;
+                    }
+                }
+                let (where_clause, returning) =
+                    rest.split_at(where_clause.is_some() as usize);
+                self.check_predicate_type(where_clause.first(), &types)?;
+                self.check_exprs(returning, &types)
+            }
+            Stmt::Delete { table_name, where_clause, returning } => {
+                let table = self.storage.get_table(table_name)?;
+                let columns = Self::live_col_names(table);
+                let mut exprs = where_clause
+                    .iter()
+                    .chain(returning.iter().flatten())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                Self::unqualify(&mut exprs, Some(table_name))?;
+                Self::check_column_refs(&exprs, &columns, &[])?;
+                let types = Self::column_types(table);
+                let (where_clause, returning) =
+                    exprs.split_at(where_clause.is_some() as usize);
+                self.check_predicate_type(where_clause.first(), &types)?;
+                self.check_exprs(returning, &types)
+            }
+            Stmt::AlterAdd { table_name, define } => {
+                let table = self.storage.get_table(table_name)?;
+                if table.live_cols().any(|col| col.name == define.name) {
+                    return Err(SQRLErr::DuplicateColumn(define.name.to_string()));
+                }
+                Ok(())
+            }
+            Stmt::AlterDrop { table_name, column }
+            | Stmt::AlterRestart { table_name, column, .. } => {
+                let columns = Self::live_col_names(self.storage.get_table(table_name)?);
+                Self::check_column_refs(&[Expr::Ident(column.clone())], &columns, &[])
+            }
+            Stmt::AlterRename { table_name, new_name } => {
+                let table = self.storage.get_table(table_name)?;
+                match self.storage.state.get_table_by_name(new_name) {
+                    Some(existing) if existing.id != table.id => {
+                        Err(SQRLErr::TableAlreadyExists(new_name.to_string()))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            Stmt::Truncate { table_name } => {
+                self.storage.get_table(table_name)?;
+                Ok(())
+            }
+            Stmt::Drop { table_name, if_exists, .. } => {
+                if !*if_exists {
+                    self.storage.get_table(table_name)?;
+                }
+                Ok(())
+            }
+            Stmt::RollbackTo { name } | Stmt::Release { name } => {
+                self.find_savepoint(name).map(drop)
+            }
+            Stmt::Vacuum
+            | Stmt::Begin
+            | Stmt::Commit
+            | Stmt::Rollback
+            | Stmt::Savepoint { .. } => Ok(()),
+        }
+    }
+
+    fn check_returning(
+        &self,
+        table_name: &str,
+        returning: Option<&[Expr]>,
+    ) -> Result<()> {
+        let table = self.storage.get_table(table_name)?;
+        let columns = Self::live_col_names(table);
+        let mut exprs = returning.unwrap_or_default().to_vec();
+        Self::unqualify(&mut exprs, Some(table_name))?;
+        Self::check_column_refs(&exprs, &columns, &[])?;
+        self.check_exprs(&exprs, &Self::column_types(table))
+    }
+
+    /// Checks a SELECT or set operation, returning its result column labels.
+    pub(super) fn check_query(&self, stmt: &Stmt) -> Result<Vec<String>> {
+        match stmt {
+            Stmt::Select {
+                from,
+                columns,
+                where_clause,
+                group_by,
+                having,
+                order_by,
+                limit,
+                offset,
+                ..
+            } => {
+                if group_by.is_some() {
+                    return Err(SQRLErr::UnsupportedFeature("GROUP BY".to_string()));
+                }
+                if having.is_some() {
+                    return Err(SQRLErr::UnsupportedFeature("HAVING".to_string()));
+                }
+                // the types of derived columns are not tracked
+                let mut types = Vec::new();
+                let source_columns = match from {
+                    Some(SelectSource::Table { name, .. }) => match self.cte_rows(name)
+                    {
+                        Some((columns, _)) => columns,
+                        None => {
+                            let table = self.storage.get_table(name)?;
+                            types = Self::column_types(table);
+                            Self::live_col_names(table)
+                        }
+                    },
+                    Some(SelectSource::Subquery { query, .. }) => {
+                        self.check_query(query)?
+                    }
+                    None if columns.is_empty() => {
+                        return Err(SQRLErr::UnsupportedFeature(
+                            "SELECT * without FROM".to_string(),
+                        ));
+                    }
+                    None => Vec::new(),
+                };
+                let mut columns = columns.clone();
+                let mut where_clause = where_clause.clone();
+                let mut order_by = order_by.clone();
+                Self::unqualify(
+                    columns.iter_mut().chain(&mut where_clause).chain(
+                        order_by.iter_mut().flatten().map(|item| &mut item.expr),
+                    ),
+                    Self::source_qualifier(from.as_ref()),
+                )?;
+                let projections = if columns.is_empty() {
+                    source_columns
+                        .iter()
+                        .map(|name| Expr::Ident(name.as_str().into()))
+                        .collect()
+                } else {
+                    Self::expand_wildcards(&columns, from.as_ref(), &source_columns)?
+                };
+                if projections.iter().any(Self::is_aggregate)
+                    && projections.iter().any(|expr| !Self::is_aggregate(expr))
+                {
+                    return Err(SQRLErr::UnsupportedFeature(
+                        "mixing aggregate and non-aggregate projections".to_string(),
+                    ));
+                }
+                let labels =
+                    projections.iter().map(Self::expr_label).collect::<Vec<_>>();
+                Self::check_column_refs(
+                    columns.iter().chain(&where_clause),
+                    &source_columns,
+                    &[],
+                )?;
+                for item in order_by.iter().flatten() {
+                    Self::check_column_refs([&item.expr], &source_columns, &labels)?;
+                    Self::order_position(&item.expr, labels.len())?;
+                }
+                // computed before any row is read
+                Self::check_column_refs(limit.iter().chain(offset), &[], &[])?;
+                self.check_exprs(&projections, &types)?;
+                self.check_predicate_type(where_clause.as_ref(), &types)?;
+                Ok(labels)
+            }
+            Stmt::SetOp { .. } => {
+                let (first, chain) = Self::set_op_chain(stmt);
+                let labels = self.check_query(first)?;
+                for set_op in chain {
+                    let Stmt::SetOp { right, order_by, limit, offset, .. } = set_op
+                    else {
+                        unreachable!()
+                    };
+                    let right_labels = self.check_query(right)?;
+                    if labels.len() != right_labels.len() {
+                        return Err(SQRLErr::ColumnCountMismatch {
+                            expected: labels.len(),
+                            got: right_labels.len(),
+                        });
+                    }
+                    for item in order_by.iter().flatten() {
+                        Self::check_column_refs([&item.expr], &labels, &[])?;
+                        Self::order_position(&item.expr, labels.len())?;
+                    }
+                    Self::check_column_refs(limit.iter().chain(offset), &[], &[])?;
+                }
+                Ok(labels)
+            }
+            Stmt::With { ctes, body } => self.with_ctes(
+                ctes,
+                |query| Ok((self.check_query(query)?, Vec::new())),
+                || self.check_query(body),
+            ),
+            _ => Err(SQRLErr::UnsupportedFeature(
+                "query source must be SELECT or a set operation".to_string(),
+            )),
+        }
+    }
+
+    fn column_types(table: &TableState) -> Vec<(String, DataType)> {
+        table
+            .live_cols()
+            .map(|col| (col.name.to_string(), col.data_type.value_type()))
+            .collect()
+    }
+
+    fn check_exprs(&self, exprs: &[Expr], types: &ColumnTypes) -> Result<()> {
+        for expr in exprs {
+            self.check_expr(expr, types)?;
+        }
+        Ok(())
+    }
+
+    fn check_predicate_type(
+        &self,
+        expr: Option<&Expr>,
+        types: &ColumnTypes,
+    ) -> Result<()> {
+        match expr.map(|expr| self.check_expr(expr, types)).transpose()?.flatten() {
+            Some(DataValue::Bool(_)) | None => Ok(()),
+            Some(other) => Err(SQRLErr::InvalidPredicate(other.data_type())),
+        }
+    }
+
+    /// Checks that every operator in `expr` gets operands of types it
+    /// accepts, returning a sample value of the type `expr` evaluates to, or
+    /// `None` when the type is unknown (NULL, parameters, most functions and
+    /// columns not in `types`).
+    ///
+    /// The operators themselves run on the samples, so the rules are the
+    /// ones execution applies. Samples stand in even for constants, so a
+    /// value error such as `1 / 0` is left to execution.
+    fn check_expr(
+        &self,
+        expr: &Expr,
+        types: &ColumnTypes,
+    ) -> Result<Option<DataValue>> {
+        let sample = |ty: DataType| match ty {
+            DataType::Nil => None,
+            // non-zero, so samples never divide by zero
+            DataType::Int => Some(DataValue::Int(1)),
+            DataType::Real => Some(DataValue::Real(1.0)),
+            DataType::Decimal => Some(DataValue::Decimal(Decimal::from_int(1))),
+            ty => Some(ty.default()),
+        };
+        let value = |expr| -> Result<DataValue> {
+            Ok(self.check_expr(expr, types)?.unwrap_or(DataValue::Nil))
+        };
+        let ty = match expr {
+            Expr::Nil
+            | Expr::Param(_)
+            | Expr::Wildcard
+            | Expr::QualifiedWildcard(_) => {
+                return Ok(None);
+            }
+            Expr::Int(_) => DataType::Int,
+            Expr::Real(_) => DataType::Real,
+            Expr::Bool(_) => DataType::Bool,
+            Expr::Text(_) => DataType::Text,
+            Expr::Date(_) => DataType::Date,
+            Expr::Timestamp(_) => DataType::Timestamp,
+            Expr::Decimal(_) => DataType::Decimal,
+            Expr::Blob(_) => DataType::Blob,
+            Expr::Ident(name) => {
+                let ty = types.iter().find(|(col, _)| **col == **name);
+                return Ok(ty.and_then(|(_, ty)| sample(*ty)));
+            }
+            Expr::QualifiedIdent(..) => return Ok(None),
+            Expr::List(exprs) => {
+                self.check_exprs(exprs, types)?;
+                return Ok(None);
+            }
+            Expr::Call { name, args, .. } => {
+                self.check_exprs(args, types)?;
+                if !name.eq_ignore_ascii_case("COUNT") {
+                    return Ok(None);
+                }
+                DataType::Int
+            }
+            Expr::Cast { expr, ty } => {
+                self.check_expr(expr, types)?;
+                ty.value_type()
+            }
+            Expr::Alias { expr, .. } | Expr::Collate { expr, .. } => {
+                return self.check_expr(expr, types);
+            }
+            Expr::Unary { op, right } => {
+                Self::eval_unary(op, value(right)?)?.data_type()
+            }
+            Expr::Like { expr, pattern, escape } => {
+                Self::eval_like(value(expr)?, value(pattern)?, *escape)?;
+                DataType::Bool
+            }
+            Expr::Binary { op: Token::In, left, right } => {
+                let left = value(left)?;
+                let Expr::List(values) = right.as_ref() else {
+                    return Err(SQRLErr::InvalidBinaryOp(
+                        "IN requires a parenthesized value list".to_string(),
+                    ));
+                };
+                for expr in values {
+                    Self::values_equal(&left, &value(expr)?)?;
+                }
+                DataType::Bool
+            }
+            Expr::Binary { op, left, right } => {
+                self.eval_binary(op, value(left)?, value(right)?)?.data_type()
+            }
+        };
+        Ok(sample(ty))
+    }
+
+    /// Fails when a value of the sample's type can never be stored in a
+    /// column of type `ty`.
+    fn check_assign(
+        column: &str,
+        ty: ColumnType,
+        value: Option<DataValue>,
+    ) -> Result<()> {
+        let Some(value) = value else {
+            return Ok(());
+        };
+        // TEXT parses into DATE and TIMESTAMP, so only the value can tell
+        let parses = matches!(
+            (ty, &value),
+            (ColumnType::Date | ColumnType::Timestamp, DataValue::Text(_))
+        );
+        let got = value.data_type();
+        if parses || ty.coerce(value).is_some() {
+            return Ok(());
+        }
+        Err(SQRLErr::TypeMismatch {
+            column: column.to_string(),
+            expected: ty.to_string(),
+            got,
+        })
+    }
+}
@@ -0,0 +1,82 @@
+use super::Executor;
+use super::prepared::literal;
+use crate::query::lexer::Token;
+use crate::query::{Expr, Stmt};
+use crate::schema::{ColumnType, DataValue};
+
+impl Executor {
+    /// A SQL script that recreates the live tables and their rows in one
+    /// transaction: the `CREATE TABLE` statements, each followed by one
+    /// `INSERT` per row and an `ALTER COLUMN ... RESTART` per used
+    /// AUTOINCREMENT sequence.
+    ///
+    /// Tables come in the order they were created, so a table is created
+    /// before the tables whose foreign keys reference it. Sequences are
+    /// restored after the rows, as values of deleted rows are not reused.
+    pub fn dump(&self) -> String {
+        let mut tables = self
+            .storage
+            .state
+            .tables
+            .values()
+            .filter(|table| table.alive)
+            .collect::<Vec<_>>();
+        tables.sort_by_key(|table| table.id);
+
+        let mut lines = vec![Stmt::Begin.to_sql() + ";"];
+        for table in tables {
+            lines.push(self.format_table_ddl(table));
+            let cols = table.live_cols().collect::<Vec<_>>();
+            for row in table.live_rows() {
+                let insert = Stmt::InsertValues {
+                    table_name: table.name.clone(),
+                    columns: cols.iter().map(|col| col.name.clone()).collect(),
+                    values: vec![
+                        cols.iter()
+                            .map(|col| {
+                                dump_literal(Self::read_value(row, col), col.data_type)
+                            })
+                            .collect(),
+                    ],
+                    returning: None,
+                };
+                lines.push(insert.to_sql() + ";");
+            }
+            for col in &cols {
+                // an exhausted sequence has no next value to restart with
+                let next = col.sequence.checked_add(1);
+                if let (true, Some(value)) = (col.sequence > 0, next) {
+                    let restart = Stmt::AlterRestart {
+                        table_name: table.name.clone(),
+                        column: col.name.clone(),
+                        value,
+                    };
+                    lines.push(restart.to_sql() + ";");
+                }
+            }
+        }
+        lines.push(Stmt::Commit.to_sql() + ";");
+        lines.join("\n")
+    }
+}
+
+/// An expression that reads back as exactly `value`.
+fn dump_literal(value: DataValue, ty: ColumnType) -> Expr {
+    match value {
+        // a DECIMAL literal would be read as REAL and lose digits
+        DataValue::Decimal(value) => {
+            Expr::Cast { expr: Expr::Text(value.to_string().into()).boxed(), ty }
+        }
+        DataValue::Real(value) if !value.is_finite() => Expr::Cast {
+            expr: Expr::Text(value.to_string().into()).boxed(),
+            ty: ColumnType::Real,
+        },
+        // 9223372036854775808 itself does not fit in an INT
+        DataValue::Int(i64::MIN) => Expr::Binary {
+            op: Token::OpSub,
+            left: Expr::Int(-i64::MAX).boxed(),
+            right: Expr::Int(1).boxed(),
+        },
+        value => literal(value),
+    }
+}
@@ -1,19 +1,53 @@
+mod check;
+mod dump;
+mod prepared;
+
+pub use prepared::{BoundStmt, Prepared};
+
 use crate::query::lexer::Token;
-use crate::query::{Expr, QueryErr, SelectSource, Stmt};
-use crate::schema::{DataType, DataValue};
-use crate::storage::{ColId, RowId, RowState, Storage, StorageErr, TableState};
+use crate::query::visit::{self, Visitor};
+use crate::query::{
+    ColumnConstraint, ColumnDef, Expr, ForeignKeyDef, Lexer, OrderBy, Param, Parser,
+    QueryErr, SelectSource, SetOp, Stmt, quote_ident,
+};
+use crate::schema::{
+    Char, ColConstraints, Collation, ColumnType, DataType, DataValue, Decimal, VarChar,
+    compare_int_real,
+};
+use crate::storage::{
+    ColState, ForeignKey, Index, IndexKey, RowId, RowState, Savepoint, Storage,
+    StorageErr, TableId, TableState,
+};
+use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Bound;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 
-#[derive(serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 #[serde(tag = "type", content = "data")]
 pub enum QueryResult {
-    Rows { columns: Vec<String>, rows: Vec<Vec<String>> },
+    Rows {
+        columns: Vec<String>,
+        #[serde(serialize_with = "serialize_rows")]
+        rows: Vec<Vec<DataValue>>,
+    },
     Count(usize),
     Success,
     Err(String),
 }
 
+/// Rows are sent to the UI as display strings.
+fn serialize_rows<S: serde::Serializer>(
+    rows: &[Vec<DataValue>],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        rows.iter().map(|row| row.iter().map(DataValue::to_string).collect::<Vec<_>>()),
+    )
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SQRLErr {
     #[error("{0}")]
@@ -34,11 +68,23 @@ pub enum SQRLErr {
     #[error("duplicate column: {0}")]
     DuplicateColumn(String),
 
+    #[error("ambiguous column: {0}")]
+    AmbiguousColumn(String),
+
+    #[error(
+        "ORDER BY position {pos} is out of range: the result has {count} column{}",
+        if *.count == 1 { "" } else { "s" }
+    )]
+    OrderByPositionOutOfRange { pos: i64, count: usize },
+
     #[error("column count mismatch: expected {expected}, got {got}")]
     ColumnCountMismatch { expected: usize, got: usize },
 
-    #[error("type mismatch for column '{column}': expected {expected:?}, got {got:?}")]
-    TypeMismatch { column: String, expected: DataType, got: DataType },
+    #[error("type mismatch for column '{column}': expected {expected}, got {got:?}")]
+    TypeMismatch { column: String, expected: String, got: DataType },
+
+    #[error("invalid value for column '{column}': {reason}")]
+    ValueTooLong { column: String, reason: String },
 
     #[error("cannot resolve identifier: {0}")]
     CannotResolveIdentifier(String),
@@ -49,6 +95,9 @@ pub enum SQRLErr {
     #[error("invalid binary operation: {0}")]
     InvalidBinaryOp(String),
 
+    #[error("numeric overflow")]
+    Overflow,
+
     #[error("predicate must evaluate to BOOL, got {0:?}")]
     InvalidPredicate(DataType),
 
@@ -57,19 +106,144 @@ pub enum SQRLErr {
 
     #[error("invalid function call: {0}")]
     InvalidFunction(String),
+
+    #[error("invalid cast: {0}")]
+    InvalidCast(String),
+
+    #[error("constraint violation: {0}")]
+    ConstraintViolation(String),
+
+    #[error("parameter count mismatch: expected {expected}, got {got}")]
+    ParamCountMismatch { expected: usize, got: usize },
+
+    #[error("unbound parameter: {0}")]
+    UnboundParam(String),
+
+    #[error("{clause} must be a non-negative INT, got {value}")]
+    InvalidLimit { clause: &'static str, value: String },
+
+    #[error("savepoint not found: {0}")]
+    SavepointNotFound(String),
+
+    #[error("interrupted")]
+    Interrupted,
 }
 
 pub type Result<T> = std::result::Result<T, SQRLErr>;
 
+type InsertTargets = (TableId, Vec<ColState>, Vec<Option<usize>>, usize);
+
+/// How a statement reads the rows of a table.
+enum AccessPath<'a> {
+    FullScan,
+    // <col> = <key>
+    IndexSeek {
+        col: &'a ColState,
+        index: &'a Index,
+        key: DataValue,
+    },
+    // <col> > <key>, <col> <= <key>, ...
+    IndexRange {
+        col: &'a ColState,
+        index: &'a Index,
+        low: Bound<DataValue>,
+        high: Bound<DataValue>,
+    },
+}
+
+impl AccessPath<'_> {
+    /// Estimated number of rows read from a table of `rows` rows. Indexes
+    /// are UNIQUE, so a seek reads at most one; a range is guessed to keep a
+    /// third of the rows per bound, and never beats a seek.
+    fn cost(&self, rows: usize) -> usize {
+        match self {
+            AccessPath::FullScan => rows,
+            AccessPath::IndexSeek { .. } => 1,
+            AccessPath::IndexRange { low, high, .. } => match (low, high) {
+                (Bound::Unbounded, _) | (_, Bound::Unbounded) => rows / 3 + 2,
+                _ => rows / 9 + 2,
+            },
+        }
+    }
+}
+
+/// The rows of a WITH query, which the rest of its statement reads like a
+/// table.
+struct Cte {
+    name: Box<str>,
+    columns: Vec<String>,
+    rows: Vec<Vec<DataValue>>,
+}
+
 pub struct Executor {
     storage: Storage,
+    savepoints: Vec<(Box<str>, Savepoint)>,
+    interrupt: Arc<AtomicBool>,
+    ctes: RefCell<Vec<Cte>>,
 }
 
 impl Executor {
     pub fn new(storage: Storage) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            savepoints: Vec::new(),
+            interrupt: Arc::default(),
+            ctes: RefCell::default(),
+        }
+    }
+
+    /// A flag another thread can set to stop the running statement. The
+    /// statement fails with [`SQRLErr::Interrupted`] and its writes are rolled
+    /// back. The flag is cleared once a statement has stopped for it.
+    pub fn interrupt_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.interrupt)
+    }
+
+    /// Checked once per row by the loops that scan or write rows.
+    fn check_interrupt(&self) -> Result<()> {
+        if self.interrupt.swap(false, AtomicOrdering::Relaxed) {
+            return Err(SQRLErr::Interrupted);
+        }
+        Ok(())
+    }
+
+    /// Names of the live tables, sorted.
+    pub fn table_names(&self) -> Vec<String> {
+        let mut names = self
+            .storage
+            .state
+            .tables
+            .values()
+            .filter(|table| table.alive)
+            .map(|table| table.name.to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// `CREATE TABLE` statement for a single table.
+    pub fn table_ddl(&self, table_name: &str) -> Result<String> {
+        let table = self
+            .storage
+            .state
+            .get_table_by_name(table_name)
+            .ok_or_else(|| SQRLErr::TableNotFound(table_name.to_string()))?;
+        Ok(self.format_table_ddl(table))
+    }
+
+    /// Names and types of a table's columns, in order.
+    pub fn table_columns(&self, table_name: &str) -> Result<Vec<(String, ColumnType)>> {
+        let table = self.storage.get_table(table_name)?;
+        Ok(table.live_cols().map(|col| (col.name.to_string(), col.data_type)).collect())
+    }
+
+    /// Whether a BEGIN is waiting for its COMMIT or ROLLBACK.
+    pub fn in_transaction(&self) -> bool {
+        self.storage.in_txn()
     }
 
+    /// `CREATE TABLE` statements for every table, in the order they were
+    /// created so that tables named by a FOREIGN KEY come first.
     pub fn schema_ddl(&self) -> String {
         let mut tables = self
             .storage
@@ -78,51 +252,131 @@ impl Executor {
             .values()
             .filter(|table| table.alive)
             .collect::<Vec<_>>();
-        tables.sort_by(|left, right| left.name.cmp(&right.name));
+        tables.sort_by_key(|table| table.id);
 
         if tables.is_empty() {
             return "-- No tables defined.".to_string();
         }
 
-        tables.into_iter().map(Self::format_table_ddl).collect::<Vec<_>>().join("\n\n")
+        tables
+            .into_iter()
+            .map(|table| self.format_table_ddl(table))
+            .collect::<Vec<_>>()
+            .join("\n\n")
     }
 }
 
 impl Executor {
-    fn format_table_ddl(table: &TableState) -> String {
-        let columns = table
+    fn format_table_ddl(&self, table: &TableState) -> String {
+        let mut columns = table
             .live_cols()
             .map(|column| {
-                format!(
-                    "  {} {}",
-                    column.name,
-                    Self::format_data_type(column.data_type)
-                )
+                let mut define =
+                    format!("  {} {}", quote_ident(&column.name), column.data_type);
+                if column.constraints.primary_key {
+                    define.push_str(" PRIMARY KEY");
+                    if column.constraints.autoincrement {
+                        define.push_str(" AUTOINCREMENT");
+                    }
+                } else {
+                    if column.constraints.not_null {
+                        define.push_str(" NOT NULL");
+                    }
+                    if column.constraints.unique {
+                        define.push_str(" UNIQUE");
+                    }
+                    if column.constraints.autoincrement {
+                        define.push_str(" AUTOINCREMENT");
+                    }
+                }
+                if let Some(default) = &column.constraints.default {
+                    define.push_str(" DEFAULT ");
+                    define.push_str(&Self::format_literal(default));
+                }
+                if column.constraints.collation != Collation::Binary {
+                    define.push_str(&format!(
+                        " COLLATE {}",
+                        column.constraints.collation
+                    ));
+                }
+                define
             })
-            .collect::<Vec<_>>()
-            .join(",\n");
+            .collect::<Vec<_>>();
+        for fk in &table.foreign_keys {
+            let state = &self.storage.state;
+            let ref_table = state.get_table(&fk.ref_table_id);
+            let name = |table: Option<&TableState>, col_id| {
+                table
+                    .and_then(|t| t.get_col(&col_id))
+                    .map_or("?".to_string(), |col| quote_ident(&col.name).into_owned())
+            };
+            let mut define = format!(
+                "  FOREIGN KEY ({}) REFERENCES {}({})",
+                name(Some(table), fk.col_id),
+                ref_table.map_or("?".into(), |t| quote_ident(&t.name)),
+                name(ref_table, fk.ref_col_id),
+            );
+            if fk.on_delete_cascade {
+                define.push_str(" ON DELETE CASCADE");
+            }
+            columns.push(define);
+        }
+        columns.extend(table.checks.iter().map(|expr| format!("  CHECK ({expr})")));
+        let columns = columns.join(",\n");
 
-        format!("CREATE TABLE {} (\n{}\n);", table.name, columns)
+        format!("CREATE TABLE {} (\n{}\n);", quote_ident(&table.name), columns)
     }
 
-    fn format_data_type(data_type: DataType) -> &'static str {
-        match data_type {
-            DataType::Nil => "NIL",
-            DataType::Int => "INT",
-            DataType::Real => "REAL",
-            DataType::Bool => "BOOL",
-            DataType::Text => "TEXT",
+    fn format_literal(value: &DataValue) -> String {
+        match value {
+            DataValue::Nil => "NULL".to_string(),
+            DataValue::Real(value) if value.fract() == 0.0 => format!("{value:.1}"),
+            DataValue::Text(value) => {
+                format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+            }
+            DataValue::Date(_) => format!("DATE '{value}'"),
+            DataValue::Timestamp(_) => format!("TIMESTAMP '{value}'"),
+            DataValue::Blob(_) => format!("X'{value}'"),
+            value => value.to_string(),
         }
     }
 
-    fn format_value(value: &DataValue) -> String {
-        match value {
-            DataValue::Nil => "nil".to_string(),
-            DataValue::Int(value) => value.to_string(),
-            DataValue::Real(value) => value.to_string(),
-            DataValue::Bool(value) => value.to_string(),
-            DataValue::Text(value) => value.to_string(),
+    /// Checks that every column the expressions name is one of `labels` or
+    /// appears exactly once in `columns`. Checking up front makes a bad
+    /// reference fail even when no row is evaluated.
+    fn check_column_refs<'e>(
+        exprs: impl IntoIterator<Item = &'e Expr>,
+        columns: &[String],
+        labels: &[String],
+    ) -> Result<()> {
+        struct Refs<'a> {
+            columns: &'a [String],
+            labels: &'a [String],
+            error: Option<SQRLErr>,
+        }
+
+        impl Visitor for Refs<'_> {
+            fn visit_column(&mut self, name: &str) {
+                if self.error.is_some() || self.labels.iter().any(|l| l == name) {
+                    return;
+                }
+                match self.columns.iter().filter(|col| *col == name).count() {
+                    0 => self.error = Some(SQRLErr::ColumnNotFound(name.to_string())),
+                    1 => {}
+                    _ => self.error = Some(SQRLErr::AmbiguousColumn(name.to_string())),
+                }
+            }
+        }
+
+        let mut refs = Refs { columns, labels, error: None };
+        for expr in exprs {
+            refs.visit_expr(expr);
         }
+        refs.error.map_or(Ok(()), Err)
+    }
+
+    fn live_col_names(table: &TableState) -> Vec<String> {
+        table.live_cols().map(|col| col.name.to_string()).collect()
     }
 
     fn expr_label(expr: &Expr) -> String {
@@ -133,11 +387,18 @@ impl Executor {
                 "({})",
                 values.iter().map(Self::expr_label).collect::<Vec<_>>().join(", ")
             ),
-            Expr::Call { name, args } => format!(
-                "{}({})",
+            Expr::Call { name, args, distinct } => format!(
+                "{}({}{})",
                 name,
+                if *distinct { "DISTINCT " } else { "" },
                 args.iter().map(Self::expr_label).collect::<Vec<_>>().join(", ")
             ),
+            Expr::Cast { expr, ty } => {
+                format!("CAST({} AS {ty})", Self::expr_label(expr))
+            }
+            Expr::Collate { expr, collation } => {
+                format!("{} COLLATE {collation}", Self::expr_label(expr))
+            }
             Expr::Alias { alias, .. } => alias.to_string(),
             _ => format!("{expr:?}"),
         }
@@ -149,27 +410,169 @@ impl Executor {
             Expr::Call { name, .. } => {
                 matches!(
                     name.to_ascii_uppercase().as_str(),
-                    "MAX" | "MIN" | "SUM" | "AVG" | "COUNT"
+                    "MAX" | "MIN" | "SUM" | "AVG" | "COUNT" | "GROUP_CONCAT"
                 )
             }
             _ => false,
         }
     }
 
+    fn coerce_value(
+        col_name: &str,
+        col_type: ColumnType,
+        value: DataValue,
+    ) -> Result<DataValue> {
+        let value_type = value.data_type();
+        let value = col_type.coerce(value).ok_or_else(|| SQRLErr::TypeMismatch {
+            column: col_name.to_string(),
+            expected: col_type.to_string(),
+            got: value_type,
+        })?;
+        let too_long = |reason: String| SQRLErr::ValueTooLong {
+            column: col_name.to_string(),
+            reason,
+        };
+        match (col_type, value) {
+            (ColumnType::VarChar(max_len), DataValue::Text(text)) => {
+                VarChar::new(&text, max_len as usize)
+                    .map(|v| DataValue::Text(v.into_inner()))
+                    .map_err(|e| too_long(e.to_string()))
+            }
+            (ColumnType::Char(width), DataValue::Text(text)) => {
+                Char::new(&text, width as usize)
+                    .map(|v| DataValue::Text(v.into_inner()))
+                    .map_err(|e| too_long(e.to_string()))
+            }
+            (ColumnType::Decimal(precision, scale), DataValue::Decimal(value))
+                if !value.fits(precision, scale) =>
+            {
+                Err(too_long(format!("{value} is out of range for {col_type}")))
+            }
+            (_, value) => Ok(value),
+        }
+    }
+
+    fn cast_value(value: DataValue, ty: ColumnType) -> Result<DataValue> {
+        ty.cast(value.clone()).ok_or_else(|| {
+            SQRLErr::InvalidCast(format!(
+                "cannot cast {:?} '{value}' to {ty}",
+                value.data_type()
+            ))
+        })
+    }
+
+    /// Reads a stored value for use in a query. CHAR padding is not
+    /// significant, so it is dropped here.
+    fn read_value(row: &RowState, col: &ColState) -> DataValue {
+        match (col.data_type, row.value(col)) {
+            (ColumnType::Char(_), DataValue::Text(text)) => {
+                DataValue::Text(text.trim_end_matches(' ').into())
+            }
+            (_, value) => value,
+        }
+    }
+
+    fn unbound_param(param: &Param) -> SQRLErr {
+        match param {
+            Param::Positional(index) => {
+                SQRLErr::UnboundParam(format!("?{}", index + 1))
+            }
+            Param::Named(name) => SQRLErr::UnboundParam(format!(":{name}")),
+        }
+    }
+
+    fn check_not_null(
+        table_name: &str,
+        col: &ColState,
+        value: &DataValue,
+    ) -> Result<()> {
+        if col.constraints.not_null && *value == DataValue::Nil {
+            return Err(SQRLErr::ConstraintViolation(format!(
+                "NOT NULL constraint failed: {table_name}.{}",
+                col.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Rejects duplicate non-NULL values in a UNIQUE column, as they would be
+    /// after replacing the values in `changes` and appending `inserted`.
+    fn check_unique(
+        table_name: &str,
+        table: &TableState,
+        col: &ColState,
+        changes: &HashMap<RowId, DataValue>,
+        inserted: &[DataValue],
+    ) -> Result<()> {
+        let Some(index) = table.get_index(&col.id) else {
+            return Ok(());
+        };
+        let mut seen = BTreeSet::new();
+        for value in changes.values().chain(inserted) {
+            if *value == DataValue::Nil {
+                continue;
+            }
+            let key = col.index_key(value.clone());
+            let collides = !seen.insert(IndexKey(key.clone()))
+                || index.get(&key).any(|row_id| !changes.contains_key(&row_id));
+            if collides {
+                return Err(SQRLErr::ConstraintViolation(format!(
+                    "UNIQUE constraint failed: {table_name}.{}",
+                    col.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// `=`, `<=>` and IN between two values.
+    ///
+    /// Values are never converted to another type to compare them, except
+    /// that INT, REAL and DECIMAL compare by numeric value. Any other pair of
+    /// different types is an error rather than unequal, so `id = '1'` and
+    /// `flag = 1` fail instead of silently matching nothing. NULL equals only
+    /// NULL, so `=` already behaves like `<=>` (IS NOT DISTINCT FROM); the
+    /// latter is accepted for SQL written against databases where it does
+    /// not.
+    fn values_equal(left: &DataValue, right: &DataValue) -> Result<bool> {
+        if let Some((left, right)) = Self::decimal_operands(left, right) {
+            return Ok(left == right);
+        }
+        match (left, right) {
+            (DataValue::Nil, _) | (_, DataValue::Nil) => Ok(left == right),
+            (DataValue::Int(int), DataValue::Real(real))
+            | (DataValue::Real(real), DataValue::Int(int)) => {
+                Ok(compare_int_real(*int, *real) == Some(Ordering::Equal))
+            }
+            _ if left.data_type() == right.data_type() => Ok(left == right),
+            _ => Err(SQRLErr::InvalidBinaryOp(format!(
+                "{:?} = {:?}",
+                left.data_type(),
+                right.data_type()
+            ))),
+        }
+    }
+
     fn compare_values(left: &DataValue, right: &DataValue) -> Result<Ordering> {
         let ord = match (left, right) {
             (DataValue::Int(left), DataValue::Int(right)) => Some(left.cmp(right)),
             (DataValue::Int(left), DataValue::Real(right)) => {
-                (*left as f64).partial_cmp(right)
+                compare_int_real(*left, *right)
             }
             (DataValue::Real(left), DataValue::Int(right)) => {
-                left.partial_cmp(&(*right as f64))
+                compare_int_real(*right, *left).map(Ordering::reverse)
             }
             (DataValue::Real(left), DataValue::Real(right)) => left.partial_cmp(right),
             (DataValue::Text(left), DataValue::Text(right)) => Some(left.cmp(right)),
             (DataValue::Bool(left), DataValue::Bool(right)) => Some(left.cmp(right)),
+            (DataValue::Date(left), DataValue::Date(right)) => Some(left.cmp(right)),
+            (DataValue::Timestamp(left), DataValue::Timestamp(right)) => {
+                Some(left.cmp(right))
+            }
+            (DataValue::Blob(left), DataValue::Blob(right)) => Some(left.cmp(right)),
             (DataValue::Nil, DataValue::Nil) => Some(Ordering::Equal),
-            _ => None,
+            _ => Self::decimal_operands(left, right)
+                .map(|(left, right)| left.cmp(&right)),
         };
         ord.ok_or_else(|| {
             SQRLErr::InvalidFunction(format!(
@@ -180,13 +583,98 @@ impl Executor {
         })
     }
 
+    /// `text LIKE pattern`, unknown when either side is NULL.
+    fn eval_unary(op: &Token, value: DataValue) -> Result<DataValue> {
+        match op {
+            Token::Not => match value {
+                DataValue::Bool(value) => Ok(DataValue::Bool(!value)),
+                DataValue::Nil => Ok(DataValue::Nil),
+                other => {
+                    Err(SQRLErr::InvalidUnaryOp(format!("NOT {:?}", other.data_type())))
+                }
+            },
+            Token::OpSub => match value {
+                DataValue::Nil => Ok(DataValue::Nil),
+                DataValue::Int(value) => {
+                    value.checked_neg().map(DataValue::Int).ok_or(SQRLErr::Overflow)
+                }
+                DataValue::Real(value) => Ok(DataValue::Real(-value)),
+                other => {
+                    Err(SQRLErr::InvalidUnaryOp(format!("- {:?}", other.data_type())))
+                }
+            },
+            _ => Err(SQRLErr::InvalidUnaryOp(format!("{op:?}"))),
+        }
+    }
+
+    fn eval_like(
+        text: DataValue,
+        pattern: DataValue,
+        escape: char,
+    ) -> Result<DataValue> {
+        match (text, pattern) {
+            (DataValue::Nil, _) | (_, DataValue::Nil) => Ok(DataValue::Nil),
+            (DataValue::Text(text), DataValue::Text(pattern)) => {
+                Ok(DataValue::Bool(Self::like_matches(&text, &pattern, escape)))
+            }
+            (text, pattern) => Err(SQRLErr::InvalidBinaryOp(format!(
+                "{:?} LIKE {:?}",
+                text.data_type(),
+                pattern.data_type()
+            ))),
+        }
+    }
+
+    /// Matches `text` against a LIKE `pattern`.
+    ///
+    /// `%` matches any sequence of characters (including none) and `_` matches
+    /// exactly one character. The character following `escape` is matched
+    /// literally, so `\%` matches a percent sign.
+    fn like_matches(text: &str, pattern: &str, escape: char) -> bool {
+        enum Part {
+            Any,
+            One,
+            Char(char),
+        }
+
+        let mut parts = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(ch) = chars.next() {
+            parts.push(match ch {
+                '%' => Part::Any,
+                '_' => Part::One,
+                ch if ch == escape => Part::Char(chars.next().unwrap_or(escape)),
+                ch => Part::Char(ch),
+            });
+        }
+
+        let text = text.chars().collect::<Vec<_>>();
+        // matched[i]: parts so far can consume exactly text[..i]
+        let mut matched = vec![false; text.len() + 1];
+        matched[0] = true;
+        for part in &parts {
+            let mut next = vec![false; text.len() + 1];
+            for i in 0..=text.len() {
+                match part {
+                    Part::Any => next[i] = matched[i] || (i > 0 && next[i - 1]),
+                    Part::One => next[i] = i > 0 && matched[i - 1],
+                    Part::Char(ch) => {
+                        next[i] = i > 0 && matched[i - 1] && text[i - 1] == *ch
+                    }
+                }
+            }
+            matched = next;
+        }
+        matched[text.len()]
+    }
+
     fn eval_aggregate(
         &self,
         expr: &Expr,
         source_columns: &[String],
         rows: &[Vec<DataValue>],
     ) -> Result<DataValue> {
-        let Expr::Call { name, args } = expr else {
+        let Expr::Call { name, args, distinct } = expr else {
             if let Expr::Alias { expr: inner_expr, .. } = expr {
                 return self.eval_aggregate(inner_expr, source_columns, rows);
             }
@@ -203,9 +691,14 @@ impl Executor {
                     ));
                 }
                 let count = match &args[0] {
+                    Expr::Wildcard if *distinct => {
+                        return Err(SQRLErr::InvalidFunction(
+                            "COUNT(DISTINCT *) is not allowed".to_string(),
+                        ));
+                    }
                     Expr::Wildcard => rows.len() as i64,
                     arg => self
-                        .collect_aggregate_values(arg, source_columns, rows)?
+                        .collect_aggregate_values(arg, *distinct, source_columns, rows)?
                         .into_iter()
                         .filter(|value| *value != DataValue::Nil)
                         .count() as i64,
@@ -218,8 +711,12 @@ impl Executor {
                         "MAX() expects exactly one argument".to_string(),
                     ));
                 }
-                let values =
-                    self.collect_aggregate_values(&args[0], source_columns, rows)?;
+                let values = self.collect_aggregate_values(
+                    &args[0],
+                    *distinct,
+                    source_columns,
+                    rows,
+                )?;
                 let mut max_value: Option<DataValue> = None;
                 for value in values {
                     if value == DataValue::Nil {
@@ -240,8 +737,12 @@ impl Executor {
                         "MIN() expects exactly one argument".to_string(),
                     ));
                 }
-                let values =
-                    self.collect_aggregate_values(&args[0], source_columns, rows)?;
+                let values = self.collect_aggregate_values(
+                    &args[0],
+                    *distinct,
+                    source_columns,
+                    rows,
+                )?;
                 let mut min_value: Option<DataValue> = None;
                 for value in values {
                     if value == DataValue::Nil {
@@ -262,8 +763,12 @@ impl Executor {
                         "SUM() expects exactly one argument".to_string(),
                     ));
                 }
-                let values =
-                    self.collect_aggregate_values(&args[0], source_columns, rows)?;
+                let values = self.collect_aggregate_values(
+                    &args[0],
+                    *distinct,
+                    source_columns,
+                    rows,
+                )?;
                 self.sum_values(&values)
             }
             "AVG" => {
@@ -272,15 +777,61 @@ impl Executor {
                         "AVG() expects exactly one argument".to_string(),
                     ));
                 }
-                let values =
-                    self.collect_aggregate_values(&args[0], source_columns, rows)?;
+                let values = self.collect_aggregate_values(
+                    &args[0],
+                    *distinct,
+                    source_columns,
+                    rows,
+                )?;
                 self.avg_values(&values)
             }
+            // GROUP_CONCAT(<expr> [, <sep>]) joins values in input order
+            "GROUP_CONCAT" => {
+                if !(1..=2).contains(&args.len()) {
+                    return Err(SQRLErr::InvalidFunction(
+                        "GROUP_CONCAT() expects one or two arguments".to_string(),
+                    ));
+                }
+                let sep = match args.get(1).map(|arg| self.eval(arg)).transpose()? {
+                    None => ",".into(),
+                    Some(DataValue::Text(sep)) => sep,
+                    Some(other) => {
+                        return Err(SQRLErr::InvalidFunction(format!(
+                            "GROUP_CONCAT() separator must be text, got {:?}",
+                            other.data_type()
+                        )));
+                    }
+                };
+                let values = self.collect_aggregate_values(
+                    &args[0],
+                    *distinct,
+                    source_columns,
+                    rows,
+                )?;
+                Ok(Self::concat_values(&values, &sep))
+            }
             _ => Err(SQRLErr::UnsupportedFeature(format!("function {name}"))),
         }
     }
 
+    /// Evaluates an aggregate argument over every row. With `distinct`, only
+    /// the first occurrence of each value is kept.
     fn collect_aggregate_values(
+        &self,
+        expr: &Expr,
+        distinct: bool,
+        source_columns: &[String],
+        rows: &[Vec<DataValue>],
+    ) -> Result<Vec<DataValue>> {
+        let mut values = self.eval_aggregate_arg(expr, source_columns, rows)?;
+        if distinct {
+            let mut seen = BTreeSet::new();
+            values.retain(|value| seen.insert(IndexKey(value.clone())));
+        }
+        Ok(values)
+    }
+
+    fn eval_aggregate_arg(
         &self,
         expr: &Expr,
         source_columns: &[String],
@@ -309,6 +860,7 @@ impl Executor {
         let mut has_real = false;
         let mut int_sum: i64 = 0;
         let mut real_sum: f64 = 0.0;
+        let mut decimal_sum: Option<Decimal> = None;
         let mut seen = false;
 
         for value in values {
@@ -319,7 +871,8 @@ impl Executor {
                     if has_real {
                         real_sum += *value as f64;
                     } else {
-                        int_sum += *value;
+                        int_sum =
+                            int_sum.checked_add(*value).ok_or(SQRLErr::Overflow)?;
                     }
                 }
                 DataValue::Real(value) => {
@@ -330,6 +883,12 @@ impl Executor {
                     }
                     real_sum += *value;
                 }
+                DataValue::Decimal(value) => {
+                    seen = true;
+                    let sum = decimal_sum.unwrap_or(Decimal::from_int(0));
+                    decimal_sum =
+                        Some(sum.checked_add(*value).ok_or(SQRLErr::Overflow)?);
+                }
                 other => {
                     return Err(SQRLErr::InvalidFunction(format!(
                         "SUM() requires numeric values, got {:?}",
@@ -339,12 +898,29 @@ impl Executor {
             }
         }
 
-        if !seen {
-            Ok(DataValue::Nil)
-        } else if has_real {
-            Ok(DataValue::Real(real_sum))
+        match decimal_sum {
+            _ if !seen => Ok(DataValue::Nil),
+            Some(sum) if has_real => Ok(DataValue::Real(real_sum + sum.to_f64())),
+            Some(sum) => sum
+                .checked_add(Decimal::from_int(int_sum))
+                .map(DataValue::Decimal)
+                .ok_or(SQRLErr::Overflow),
+            None if has_real => Ok(DataValue::Real(real_sum)),
+            None => Ok(DataValue::Int(int_sum)),
+        }
+    }
+
+    /// Joins the non-NULL values with `sep`, or returns NULL if there are none.
+    fn concat_values(values: &[DataValue], sep: &str) -> DataValue {
+        let parts = values
+            .iter()
+            .filter(|value| **value != DataValue::Nil)
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>();
+        if parts.is_empty() {
+            DataValue::Nil
         } else {
-            Ok(DataValue::Int(int_sum))
+            DataValue::Text(parts.join(sep).into())
         }
     }
 
@@ -363,6 +939,10 @@ impl Executor {
                     total += *value;
                     count += 1;
                 }
+                DataValue::Decimal(value) => {
+                    total += value.to_f64();
+                    count += 1;
+                }
                 other => {
                     return Err(SQRLErr::InvalidFunction(format!(
                         "AVG() requires numeric values, got {:?}",
@@ -395,16 +975,35 @@ impl Executor {
             Expr::Real(r) => Ok(DataValue::Real(*r)),
             Expr::Bool(b) => Ok(DataValue::Bool(*b)),
             Expr::Text(s) => Ok(DataValue::Text(s.clone())),
-            Expr::Wildcard => {
+            Expr::Date(d) => Ok(DataValue::Date(*d)),
+            Expr::Timestamp(t) => Ok(DataValue::Timestamp(*t)),
+            Expr::Decimal(d) => Ok(DataValue::Decimal(*d)),
+            Expr::Blob(b) => Ok(DataValue::Blob(b.clone())),
+            Expr::Param(param) => Err(Self::unbound_param(param)),
+            Expr::Wildcard | Expr::QualifiedWildcard(_) => {
                 Err(SQRLErr::UnsupportedFeature("wildcard expression".to_string()))
             }
+            // only left where no single source resolves it
+            Expr::QualifiedIdent(table, _) => {
+                Err(SQRLErr::TableNotFound(table.to_string()))
+            }
             Expr::List(_) => {
                 Err(SQRLErr::UnsupportedFeature("list expression".to_string()))
             }
-            Expr::Alias { expr, .. } => self.eval_in_row(expr, table, row),
-            Expr::Call { name, .. } => Err(SQRLErr::UnsupportedFeature(format!(
-                "function {name} outside aggregate SELECT"
-            ))),
+            // the collation only matters to the comparison around it
+            Expr::Alias { expr, .. } | Expr::Collate { expr, .. } => {
+                self.eval_in_row(expr, table, row)
+            }
+            Expr::Cast { expr, ty } => {
+                Self::cast_value(self.eval_in_row(expr, table, row)?, *ty)
+            }
+            Expr::Call { name, args, distinct } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval_in_row(arg, table, row))
+                    .collect::<Result<Vec<_>>>()?;
+                self.eval_call(expr, name, args, *distinct)
+            }
             Expr::Ident(name) => {
                 let (table, row) = match (table, row) {
                     (Some(table), Some(row)) => (table, row),
@@ -415,53 +1014,70 @@ impl Executor {
                 let col = table.get_col_by_name(name).ok_or_else(|| {
                     SQRLErr::CannotResolveIdentifier(name.to_string())
                 })?;
-                row.values
-                    .get(&col.id)
-                    .cloned()
-                    .ok_or_else(|| SQRLErr::CannotResolveIdentifier(name.to_string()))
+                Ok(Self::read_value(row, col))
             }
             Expr::Unary { op, right } => {
                 let value = self.eval_in_row(right, table, row)?;
-                match op {
-                    Token::Not => match value {
-                        DataValue::Bool(value) => Ok(DataValue::Bool(!value)),
-                        other => Err(SQRLErr::InvalidUnaryOp(format!(
-                            "NOT {:?}",
-                            other.data_type()
-                        ))),
-                    },
-                    Token::OpSub => match value {
-                        DataValue::Int(value) => Ok(DataValue::Int(-value)),
-                        DataValue::Real(value) => Ok(DataValue::Real(-value)),
-                        other => Err(SQRLErr::InvalidUnaryOp(format!(
-                            "- {:?}",
-                            other.data_type()
-                        ))),
-                    },
-                    _ => Err(SQRLErr::InvalidUnaryOp(format!("{op:?}"))),
-                }
+                Self::eval_unary(op, value)
             }
-            Expr::Binary { op, left, right } => {
-                if *op == Token::In {
-                    let left = self.eval_in_row(left, table, row)?;
-                    let Expr::List(values) = right.as_ref() else {
-                        return Err(SQRLErr::InvalidBinaryOp(
-                            "IN requires a parenthesized value list".to_string(),
-                        ));
-                    };
-                    let is_match = values
-                        .iter()
-                        .map(|expr| self.eval_in_row(expr, table, row))
-                        .collect::<Result<Vec<_>>>()?
-                        .into_iter()
-                        .any(|value| value == left);
-                    return Ok(DataValue::Bool(is_match));
+            Expr::Like { expr, pattern, escape } => {
+                let collation = Self::comparison_collation(&Token::Like, expr, pattern);
+                let text = collation.key(self.eval_in_row(expr, table, row)?);
+                let pattern = collation.key(self.eval_in_row(pattern, table, row)?);
+                Self::eval_like(text, pattern, *escape)
+            }
+            Expr::Binary { op: Token::In, left, right } => {
+                let collation = Self::comparison_collation(&Token::In, left, right);
+                let left = collation.key(self.eval_in_row(left, table, row)?);
+                let Expr::List(values) = right.as_ref() else {
+                    return Err(SQRLErr::InvalidBinaryOp(
+                        "IN requires a parenthesized value list".to_string(),
+                    ));
+                };
+                let mut is_match = false;
+                for expr in values {
+                    let value = collation.key(self.eval_in_row(expr, table, row)?);
+                    is_match |= Self::values_equal(&left, &value)?;
                 }
-                let left = self.eval_in_row(left, table, row)?;
-                let right = self.eval_in_row(right, table, row)?;
-                self.eval_binary(op, left, right)
+                Ok(DataValue::Bool(is_match))
+            }
+            Expr::Binary { .. } => {
+                self.eval_chain(expr, |expr| self.eval_in_row(expr, table, row))
+            }
+        }
+    }
+
+    /// Evaluates a binary operator along with those nested in its left
+    /// operand, as `a OR b OR c` parses, in a loop rather than recursing once
+    /// per operator.
+    fn eval_chain(
+        &self,
+        expr: &Expr,
+        eval: impl Fn(&Expr) -> Result<DataValue>,
+    ) -> Result<DataValue> {
+        let mut chain = Vec::new();
+        let mut first = expr;
+        while let Expr::Binary { op, left, right } = first
+            && *op != Token::In
+        {
+            chain.push((op, left, right));
+            first = left;
+        }
+        let mut value = eval(first)?;
+        for (op, left, right) in chain.into_iter().rev() {
+            // the right side cannot change these results, so skip it
+            if matches!(
+                (op, &value),
+                (Token::And, DataValue::Bool(false))
+                    | (Token::Or, DataValue::Bool(true))
+            ) {
+                continue;
             }
+            let collation = Self::comparison_collation(op, left, right);
+            let right = eval(right)?;
+            value = self.eval_binary(op, collation.key(value), collation.key(right))?;
         }
+        Ok(value)
     }
 
     fn eval_in_source_row(
@@ -476,18 +1092,35 @@ impl Executor {
             Expr::Real(r) => Ok(DataValue::Real(*r)),
             Expr::Bool(b) => Ok(DataValue::Bool(*b)),
             Expr::Text(s) => Ok(DataValue::Text(s.clone())),
-            Expr::Wildcard => {
+            Expr::Date(d) => Ok(DataValue::Date(*d)),
+            Expr::Timestamp(t) => Ok(DataValue::Timestamp(*t)),
+            Expr::Decimal(d) => Ok(DataValue::Decimal(*d)),
+            Expr::Blob(b) => Ok(DataValue::Blob(b.clone())),
+            Expr::Param(param) => Err(Self::unbound_param(param)),
+            Expr::Wildcard | Expr::QualifiedWildcard(_) => {
                 Err(SQRLErr::UnsupportedFeature("wildcard expression".to_string()))
             }
+            // only left where no single source resolves it
+            Expr::QualifiedIdent(table, _) => {
+                Err(SQRLErr::TableNotFound(table.to_string()))
+            }
             Expr::List(_) => {
                 Err(SQRLErr::UnsupportedFeature("list expression".to_string()))
             }
-            Expr::Alias { expr, .. } => {
+            Expr::Alias { expr, .. } | Expr::Collate { expr, .. } => {
                 self.eval_in_source_row(expr, source_columns, row)
             }
-            Expr::Call { name, .. } => Err(SQRLErr::UnsupportedFeature(format!(
-                "function {name} outside aggregate SELECT"
-            ))),
+            Expr::Cast { expr, ty } => Self::cast_value(
+                self.eval_in_source_row(expr, source_columns, row)?,
+                *ty,
+            ),
+            Expr::Call { name, args, distinct } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval_in_source_row(arg, source_columns, row))
+                    .collect::<Result<Vec<_>>>()?;
+                self.eval_call(expr, name, args, *distinct)
+            }
             Expr::Ident(name) => {
                 let Some(index) =
                     source_columns.iter().position(|column| column == name.as_ref())
@@ -500,70 +1133,540 @@ impl Executor {
             }
             Expr::Unary { op, right } => {
                 let value = self.eval_in_source_row(right, source_columns, row)?;
-                match op {
-                    Token::Not => match value {
-                        DataValue::Bool(value) => Ok(DataValue::Bool(!value)),
-                        other => Err(SQRLErr::InvalidUnaryOp(format!(
-                            "NOT {:?}",
-                            other.data_type()
-                        ))),
-                    },
-                    Token::OpSub => match value {
-                        DataValue::Int(value) => Ok(DataValue::Int(-value)),
-                        DataValue::Real(value) => Ok(DataValue::Real(-value)),
-                        other => Err(SQRLErr::InvalidUnaryOp(format!(
-                            "- {:?}",
-                            other.data_type()
-                        ))),
-                    },
-                    _ => Err(SQRLErr::InvalidUnaryOp(format!("{op:?}"))),
-                }
+                Self::eval_unary(op, value)
             }
-            Expr::Binary { op, left, right } => {
-                if *op == Token::In {
-                    let left = self.eval_in_source_row(left, source_columns, row)?;
-                    let Expr::List(values) = right.as_ref() else {
-                        return Err(SQRLErr::InvalidBinaryOp(
-                            "IN requires a parenthesized value list".to_string(),
-                        ));
-                    };
-                    let is_match = values
-                        .iter()
-                        .map(|expr| self.eval_in_source_row(expr, source_columns, row))
-                        .collect::<Result<Vec<_>>>()?
-                        .into_iter()
-                        .any(|value| value == left);
-                    return Ok(DataValue::Bool(is_match));
+            Expr::Like { expr, pattern, escape } => {
+                let collation = Self::comparison_collation(&Token::Like, expr, pattern);
+                let text = collation.key(self.eval_in_source_row(
+                    expr,
+                    source_columns,
+                    row,
+                )?);
+                let pattern = collation.key(self.eval_in_source_row(
+                    pattern,
+                    source_columns,
+                    row,
+                )?);
+                Self::eval_like(text, pattern, *escape)
+            }
+            Expr::Binary { op: Token::In, left, right } => {
+                let collation = Self::comparison_collation(&Token::In, left, right);
+                let left = collation.key(self.eval_in_source_row(
+                    left,
+                    source_columns,
+                    row,
+                )?);
+                let Expr::List(values) = right.as_ref() else {
+                    return Err(SQRLErr::InvalidBinaryOp(
+                        "IN requires a parenthesized value list".to_string(),
+                    ));
+                };
+                let mut is_match = false;
+                for expr in values {
+                    let value = collation.key(self.eval_in_source_row(
+                        expr,
+                        source_columns,
+                        row,
+                    )?);
+                    is_match |= Self::values_equal(&left, &value)?;
                 }
-                let left = self.eval_in_source_row(left, source_columns, row)?;
-                let right = self.eval_in_source_row(right, source_columns, row)?;
-                self.eval_binary(op, left, right)
+                Ok(DataValue::Bool(is_match))
             }
+            Expr::Binary { .. } => self.eval_chain(expr, |expr| {
+                self.eval_in_source_row(expr, source_columns, row)
+            }),
         }
     }
 
-    fn eval_binary(
+    /// Evaluates a scalar function call on already evaluated arguments.
+    fn eval_call(
         &self,
-        op: &Token,
-        left: DataValue,
-        right: DataValue,
+        expr: &Expr,
+        name: &str,
+        args: Vec<DataValue>,
+        distinct: bool,
     ) -> Result<DataValue> {
-        match op {
-            Token::OpEq => Ok(DataValue::Bool(left == right)),
-            Token::And => match (left, right) {
-                (DataValue::Bool(left), DataValue::Bool(right)) => {
-                    Ok(DataValue::Bool(left && right))
-                }
-                (left, right) => Err(SQRLErr::InvalidBinaryOp(format!(
-                    "{:?} AND {:?}",
-                    left.data_type(),
-                    right.data_type()
-                ))),
-            },
-            Token::Or => match (left, right) {
-                (DataValue::Bool(left), DataValue::Bool(right)) => {
-                    Ok(DataValue::Bool(left || right))
+        if Self::is_aggregate(expr) {
+            return Err(SQRLErr::UnsupportedFeature(format!(
+                "function {name} outside aggregate SELECT"
+            )));
+        }
+        if distinct {
+            return Err(SQRLErr::InvalidFunction(format!(
+                "DISTINCT is not allowed in non-aggregate function {name}()"
+            )));
+        }
+        match name.to_ascii_uppercase().as_str() {
+            // COALESCE(<a>, <b>, ...): the first non-NULL argument
+            "COALESCE" => {
+                if args.is_empty() {
+                    return Err(SQRLErr::InvalidFunction(
+                        "COALESCE() expects at least one argument".to_string(),
+                    ));
                 }
+                Ok(args
+                    .into_iter()
+                    .find(|arg| *arg != DataValue::Nil)
+                    .unwrap_or(DataValue::Nil))
+            }
+            // NULLIF(<a>, <b>): NULL if a = b, else a
+            "NULLIF" => {
+                let Ok([left, right]) = <[DataValue; 2]>::try_from(args) else {
+                    return Err(SQRLErr::InvalidFunction(
+                        "NULLIF() expects exactly two arguments".to_string(),
+                    ));
+                };
+                let equal = self.eval_binary(&Token::OpEq, left.clone(), right)?;
+                Ok(if equal == DataValue::Bool(true) { DataValue::Nil } else { left })
+            }
+            func @ ("UPPER" | "LOWER" | "TRIM" | "RTRIM" | "LENGTH" | "SUBSTR") => {
+                Self::eval_string_func(func, args)
+            }
+            func @ ("ABS" | "ROUND" | "CEIL" | "CEILING" | "FLOOR" | "MOD" | "DIV") => {
+                Self::eval_numeric_func(func, args)
+            }
+            _ => Err(SQRLErr::UnsupportedFeature(format!("function {name}"))),
+        }
+    }
+
+    /// Returns the characters of `text` from the 1-based position `start`,
+    /// `len` of them if given. The range is clamped to the text, so
+    /// `SUBSTR('abc', 0, 2)` is `'a'` and positions past the end give `''`.
+    fn substr(text: &str, start: i64, len: Option<i64>) -> String {
+        let first = start.max(1);
+        let end = len.map_or(i64::MAX, |len| start.saturating_add(len));
+        if end <= first {
+            return String::new();
+        }
+        let skip = usize::try_from(first - 1).unwrap_or(usize::MAX);
+        let take = usize::try_from(end - first).unwrap_or(usize::MAX);
+        text.chars().skip(skip).take(take).collect()
+    }
+
+    /// Evaluates the string functions. A NULL argument yields NULL, and
+    /// lengths and positions count characters, not bytes.
+    fn eval_string_func(name: &str, args: Vec<DataValue>) -> Result<DataValue> {
+        if args.contains(&DataValue::Nil) {
+            return Ok(DataValue::Nil);
+        }
+        let type_err = |value: &DataValue| {
+            SQRLErr::InvalidFunction(format!(
+                "{name}() got an invalid argument of type {:?}",
+                value.data_type()
+            ))
+        };
+        match (name, args.as_slice()) {
+            ("UPPER", [DataValue::Text(text)]) => {
+                Ok(DataValue::Text(text.to_uppercase().into()))
+            }
+            ("LOWER", [DataValue::Text(text)]) => {
+                Ok(DataValue::Text(text.to_lowercase().into()))
+            }
+            ("TRIM", [DataValue::Text(text)]) => {
+                Ok(DataValue::Text(text.trim().into()))
+            }
+            // only spaces, the padding of CHAR values
+            ("RTRIM", [DataValue::Text(text)]) => {
+                Ok(DataValue::Text(text.trim_end_matches(' ').into()))
+            }
+            ("LENGTH", [DataValue::Text(text)]) => {
+                Ok(DataValue::Int(text.chars().count() as i64))
+            }
+            ("LENGTH", [DataValue::Blob(bytes)]) => {
+                Ok(DataValue::Int(bytes.len() as i64))
+            }
+            ("SUBSTR", [text, start, rest @ ..]) if rest.len() <= 1 => {
+                let DataValue::Text(text) = text else {
+                    return Err(type_err(text));
+                };
+                let DataValue::Int(start) = start else {
+                    return Err(type_err(start));
+                };
+                let len = match rest.first() {
+                    None => None,
+                    Some(DataValue::Int(len)) if *len < 0 => {
+                        return Err(SQRLErr::InvalidFunction(
+                            "SUBSTR() length must not be negative".to_string(),
+                        ));
+                    }
+                    Some(DataValue::Int(len)) => Some(*len),
+                    Some(other) => return Err(type_err(other)),
+                };
+                Ok(DataValue::Text(Self::substr(text, *start, len).into()))
+            }
+            ("SUBSTR", _) => Err(SQRLErr::InvalidFunction(
+                "SUBSTR() expects two or three arguments".to_string(),
+            )),
+            (_, [value]) => Err(type_err(value)),
+            _ => Err(SQRLErr::InvalidFunction(format!(
+                "{name}() expects exactly one argument"
+            ))),
+        }
+    }
+
+    /// Evaluates the numeric functions. A NULL argument yields NULL.
+    ///
+    /// `ABS`, `ROUND`, `CEIL` and `FLOOR` return the type of their argument
+    /// (a DECIMAL loses its fraction digits in `CEIL` and `FLOOR`). `MOD`
+    /// returns INT for two INTs and REAL otherwise, with the sign of the
+    /// dividend. `DIV` divides two INTs rounding toward zero, the integer
+    /// counterpart of `/`. `ROUND(x, digits)` rounds half away from zero; negative
+    /// `digits` round to tens, hundreds and so on.
+    fn eval_numeric_func(name: &str, args: Vec<DataValue>) -> Result<DataValue> {
+        if args.contains(&DataValue::Nil) {
+            return Ok(DataValue::Nil);
+        }
+        let type_err = |value: &DataValue| {
+            SQRLErr::InvalidFunction(format!(
+                "{name}() requires numeric arguments, got {:?}",
+                value.data_type()
+            ))
+        };
+        match (name, args.as_slice()) {
+            ("ABS", [DataValue::Int(value)]) => {
+                value.checked_abs().map(DataValue::Int).ok_or(SQRLErr::Overflow)
+            }
+            ("ABS", [DataValue::Real(value)]) => Ok(DataValue::Real(value.abs())),
+            ("ABS", [DataValue::Decimal(value)]) => value
+                .units()
+                .checked_abs()
+                .map(|units| DataValue::Decimal(Decimal::new(units, value.scale())))
+                .ok_or(SQRLErr::Overflow),
+            ("CEIL" | "CEILING" | "FLOOR", [DataValue::Int(value)]) => {
+                Ok(DataValue::Int(*value))
+            }
+            ("CEIL" | "CEILING", [DataValue::Real(value)]) => {
+                Ok(DataValue::Real(value.ceil()))
+            }
+            ("FLOOR", [DataValue::Real(value)]) => Ok(DataValue::Real(value.floor())),
+            ("CEIL" | "CEILING" | "FLOOR", [DataValue::Decimal(value)]) => {
+                let unit = 10i128.pow(value.scale() as u32);
+                let floor = value.units().div_euclid(unit);
+                let ceil = floor + (value.units().rem_euclid(unit) != 0) as i128;
+                let units = if name == "FLOOR" { floor } else { ceil };
+                Ok(DataValue::Decimal(Decimal::new(units, 0)))
+            }
+            ("ROUND", [value, rest @ ..]) if rest.len() <= 1 => {
+                let digits = match rest.first() {
+                    None => 0,
+                    Some(DataValue::Int(digits @ -38..=38)) => *digits as i32,
+                    Some(DataValue::Int(_)) => {
+                        return Err(SQRLErr::InvalidFunction(
+                            "ROUND() digits must be between -38 and 38".to_string(),
+                        ));
+                    }
+                    Some(other) => return Err(type_err(other)),
+                };
+                Self::round(value, digits)?.ok_or(SQRLErr::Overflow)
+            }
+            ("MOD", [DataValue::Int(_) | DataValue::Real(_), right])
+                if matches!(right, DataValue::Int(0))
+                    || *right == DataValue::Real(0.0) =>
+            {
+                Err(SQRLErr::InvalidFunction("MOD() division by zero".to_string()))
+            }
+            ("MOD", [DataValue::Int(left), DataValue::Int(right)]) => {
+                Ok(DataValue::Int(left.wrapping_rem(*right)))
+            }
+            ("MOD", [left, right]) => match (left, right) {
+                (DataValue::Int(left), DataValue::Real(right)) => {
+                    Ok(DataValue::Real(*left as f64 % right))
+                }
+                (DataValue::Real(left), DataValue::Int(right)) => {
+                    Ok(DataValue::Real(left % *right as f64))
+                }
+                (DataValue::Real(left), DataValue::Real(right)) => {
+                    Ok(DataValue::Real(left % right))
+                }
+                (DataValue::Int(_) | DataValue::Real(_), other) | (other, _) => {
+                    Err(type_err(other))
+                }
+            },
+            ("DIV", [DataValue::Int(_), DataValue::Int(0)]) => {
+                Err(SQRLErr::InvalidFunction("DIV() division by zero".to_string()))
+            }
+            ("DIV", [DataValue::Int(left), DataValue::Int(right)]) => {
+                left.checked_div(*right).map(DataValue::Int).ok_or(SQRLErr::Overflow)
+            }
+            ("DIV", [DataValue::Int(_), other] | [other, _]) => {
+                Err(SQRLErr::InvalidFunction(format!(
+                    "DIV() requires INT arguments, got {:?}",
+                    other.data_type()
+                )))
+            }
+            ("MOD" | "DIV", _) => Err(SQRLErr::InvalidFunction(format!(
+                "{name}() expects exactly two arguments"
+            ))),
+            ("ROUND", _) => Err(SQRLErr::InvalidFunction(
+                "ROUND() expects one or two arguments".to_string(),
+            )),
+            (_, [value]) => Err(type_err(value)),
+            _ => Err(SQRLErr::InvalidFunction(format!(
+                "{name}() expects exactly one argument"
+            ))),
+        }
+    }
+
+    /// Rounds half away from zero to `digits` places after the point. Returns
+    /// `None` on overflow.
+    fn round(value: &DataValue, digits: i32) -> Result<Option<DataValue>> {
+        let rounded = match value {
+            DataValue::Int(value) if digits >= 0 => Some(DataValue::Int(*value)),
+            DataValue::Int(value) => {
+                let unit = 10i64.checked_pow(-digits as u32);
+                let rounded = match unit {
+                    Some(unit) => {
+                        (*value as i128 + value.signum() as i128 * unit as i128 / 2)
+                            / unit as i128
+                            * unit as i128
+                    }
+                    None => 0,
+                };
+                i64::try_from(rounded).ok().map(DataValue::Int)
+            }
+            DataValue::Real(value) => {
+                let scale = 10f64.powi(digits);
+                let rounded = (value * scale).round() / scale;
+                Some(DataValue::Real(if rounded.is_finite() {
+                    rounded
+                } else {
+                    *value
+                }))
+            }
+            DataValue::Decimal(value) if digits >= value.scale() as i32 => {
+                Some(DataValue::Decimal(*value))
+            }
+            DataValue::Decimal(value) if digits >= 0 => {
+                value.rescale(digits as u8).map(DataValue::Decimal)
+            }
+            DataValue::Decimal(value) => {
+                // shift the point left, round to an integer, then shift back
+                let shift = -digits as u8;
+                Decimal::new(value.units(), value.scale() + shift)
+                    .rescale(0)
+                    .and_then(|value| value.rescale(shift))
+                    .map(|value| DataValue::Decimal(Decimal::new(value.units(), 0)))
+            }
+            other => {
+                return Err(SQRLErr::InvalidFunction(format!(
+                    "ROUND() requires numeric arguments, got {:?}",
+                    other.data_type()
+                )));
+            }
+        };
+        Ok(rounded)
+    }
+
+    /// Converts a pair of numbers to DECIMAL when at least one of them is a
+    /// DECIMAL, so mixed arithmetic stays exact.
+    fn decimal_operands(
+        left: &DataValue,
+        right: &DataValue,
+    ) -> Option<(Decimal, Decimal)> {
+        let to_decimal = |value: &DataValue| match value {
+            DataValue::Int(value) => Some(Decimal::from_int(*value)),
+            DataValue::Real(value) => Decimal::from_f64(*value),
+            DataValue::Decimal(value) => Some(*value),
+            _ => None,
+        };
+        match (left, right) {
+            (DataValue::Decimal(_), _) | (_, DataValue::Decimal(_)) => {
+                Some((to_decimal(left)?, to_decimal(right)?))
+            }
+            _ => None,
+        }
+    }
+
+    fn eval_decimal(op: &Token, left: Decimal, right: Decimal) -> Result<DataValue> {
+        let result = match op {
+            Token::OpAdd => left.checked_add(right),
+            Token::OpSub => left.checked_sub(right),
+            Token::OpMul => left.checked_mul(right),
+            Token::OpDiv if right.units() == 0 => {
+                return Err(SQRLErr::InvalidBinaryOp("division by zero".to_string()));
+            }
+            Token::OpDiv => left.checked_div(right),
+            Token::OpEq => return Ok(DataValue::Bool(left == right)),
+            Token::OpGt => return Ok(DataValue::Bool(left > right)),
+            Token::OpLt => return Ok(DataValue::Bool(left < right)),
+            Token::OpGe => return Ok(DataValue::Bool(left >= right)),
+            Token::OpLe => return Ok(DataValue::Bool(left <= right)),
+            _ => {
+                return Err(SQRLErr::InvalidBinaryOp(format!(
+                    "Decimal {op:?} Decimal"
+                )));
+            }
+        };
+        result.map(DataValue::Decimal).ok_or(SQRLErr::Overflow)
+    }
+
+    /// The collation a comparison uses: the one on its left operand, else the
+    /// one on its right. Other operators always see the values unchanged.
+    fn comparison_collation(op: &Token, left: &Expr, right: &Expr) -> Collation {
+        if !matches!(
+            op,
+            Token::OpEq
+                | Token::OpSafeEq
+                | Token::OpGt
+                | Token::OpLt
+                | Token::OpGe
+                | Token::OpLe
+                | Token::Like
+                | Token::In
+        ) {
+            return Collation::Binary;
+        }
+        match Self::collation(left) {
+            Collation::Binary => Self::collation(right),
+            collation => collation,
+        }
+    }
+
+    /// The collation written on `expr`, or given to the column it names by
+    /// [`Self::apply_collations`].
+    fn collation(expr: &Expr) -> Collation {
+        match expr {
+            Expr::Collate { collation, .. } => *collation,
+            Expr::Alias { expr, .. } => Self::collation(expr),
+            _ => Collation::Binary,
+        }
+    }
+
+    /// Marks each reference to a column of `table` that has a collation, so
+    /// comparisons with it use that collation. An explicit COLLATE around
+    /// the reference still wins. Comparisons with a CHAR column ignore
+    /// trailing spaces on the other side too, as the column's own padding
+    /// is dropped when it is read.
+    fn apply_collations<'e>(
+        exprs: impl IntoIterator<Item = &'e mut Expr>,
+        table: &TableState,
+    ) {
+        for expr in exprs {
+            visit::walk_expr_mut(expr, &mut |expr| {
+                if let Expr::Ident(name) = expr
+                    && let Some(col) =
+                        table.get_col_by_name(name).filter(|col| col.alive)
+                    && col.constraints.collation != Collation::Binary
+                {
+                    let collation = col.constraints.collation;
+                    let ident = std::mem::replace(expr, Expr::Nil).boxed();
+                    *expr = Expr::Collate { expr: ident, collation };
+                }
+                if let Expr::Binary { op, left, right } = expr
+                    && matches!(
+                        op,
+                        Token::OpEq
+                            | Token::OpSafeEq
+                            | Token::OpGt
+                            | Token::OpLt
+                            | Token::OpGe
+                            | Token::OpLe
+                            | Token::In
+                    )
+                {
+                    let is_char = |expr: &Expr| Self::is_char_col(expr, table);
+                    if is_char(left) {
+                        match right.as_mut() {
+                            Expr::List(values) => {
+                                values.iter_mut().for_each(Self::rtrim)
+                            }
+                            right => Self::rtrim(right),
+                        }
+                    } else if is_char(right) {
+                        Self::rtrim(left);
+                    }
+                }
+            });
+        }
+    }
+
+    /// Whether `expr` names a CHAR column of `table`, directly or under a
+    /// COLLATE.
+    fn is_char_col(expr: &Expr, table: &TableState) -> bool {
+        match expr {
+            Expr::Collate { expr, .. } => Self::is_char_col(expr, table),
+            Expr::Ident(name) => table.get_col_by_name(name).is_some_and(|col| {
+                col.alive && matches!(col.data_type, ColumnType::Char(_))
+            }),
+            _ => false,
+        }
+    }
+
+    /// Wraps `expr` in `RTRIM()`.
+    fn rtrim(expr: &mut Expr) {
+        let arg = std::mem::replace(expr, Expr::Nil);
+        *expr = Expr::Call { name: "RTRIM".into(), args: vec![arg], distinct: false };
+    }
+
+    fn eval_binary(
+        &self,
+        op: &Token,
+        left: DataValue,
+        right: DataValue,
+    ) -> Result<DataValue> {
+        if matches!(
+            op,
+            Token::OpAdd
+                | Token::OpSub
+                | Token::OpMul
+                | Token::OpDiv
+                | Token::OpEq
+                | Token::OpGt
+                | Token::OpLt
+                | Token::OpGe
+                | Token::OpLe
+        ) && let Some((left, right)) = Self::decimal_operands(&left, &right)
+        {
+            return Self::eval_decimal(op, left, right);
+        }
+        // arithmetic and ordering with an unknown value are unknown
+        let is_nil = |value: &DataValue| *value == DataValue::Nil;
+        if matches!(
+            op,
+            Token::OpAdd
+                | Token::OpSub
+                | Token::OpMul
+                | Token::OpDiv
+                | Token::OpGt
+                | Token::OpLt
+                | Token::OpGe
+                | Token::OpLe
+        ) && (is_nil(&left) || is_nil(&right))
+        {
+            return Ok(DataValue::Nil);
+        }
+        match op {
+            Token::OpEq | Token::OpSafeEq => {
+                Self::values_equal(&left, &right).map(DataValue::Bool)
+            }
+            Token::And => match (left, right) {
+                (DataValue::Bool(left), DataValue::Bool(right)) => {
+                    Ok(DataValue::Bool(left && right))
+                }
+                (DataValue::Bool(false), DataValue::Nil)
+                | (DataValue::Nil, DataValue::Bool(false)) => {
+                    Ok(DataValue::Bool(false))
+                }
+                (
+                    DataValue::Bool(_) | DataValue::Nil,
+                    DataValue::Bool(_) | DataValue::Nil,
+                ) => Ok(DataValue::Nil),
+                (left, right) => Err(SQRLErr::InvalidBinaryOp(format!(
+                    "{:?} AND {:?}",
+                    left.data_type(),
+                    right.data_type()
+                ))),
+            },
+            Token::Or => match (left, right) {
+                (DataValue::Bool(left), DataValue::Bool(right)) => {
+                    Ok(DataValue::Bool(left || right))
+                }
+                (DataValue::Bool(true), DataValue::Nil)
+                | (DataValue::Nil, DataValue::Bool(true)) => Ok(DataValue::Bool(true)),
+                (
+                    DataValue::Bool(_) | DataValue::Nil,
+                    DataValue::Bool(_) | DataValue::Nil,
+                ) => Ok(DataValue::Nil),
                 (left, right) => Err(SQRLErr::InvalidBinaryOp(format!(
                     "{:?} OR {:?}",
                     left.data_type(),
@@ -572,7 +1675,7 @@ impl Executor {
             },
             Token::OpAdd => match (left, right) {
                 (DataValue::Int(left), DataValue::Int(right)) => {
-                    Ok(DataValue::Int(left + right))
+                    left.checked_add(right).map(DataValue::Int).ok_or(SQRLErr::Overflow)
                 }
                 (DataValue::Int(left), DataValue::Real(right)) => {
                     Ok(DataValue::Real(left as f64 + right))
@@ -592,13 +1695,12 @@ impl Executor {
                     right.data_type()
                 ))),
             },
-            Token::OpConcat => Ok(DataValue::Text(
-                format!("{}{}", Self::format_value(&left), Self::format_value(&right))
-                    .into_boxed_str(),
-            )),
+            Token::OpConcat => {
+                Ok(DataValue::Text(format!("{left}{right}").into_boxed_str()))
+            }
             Token::OpSub => match (left, right) {
                 (DataValue::Int(left), DataValue::Int(right)) => {
-                    Ok(DataValue::Int(left - right))
+                    left.checked_sub(right).map(DataValue::Int).ok_or(SQRLErr::Overflow)
                 }
                 (DataValue::Int(left), DataValue::Real(right)) => {
                     Ok(DataValue::Real(left as f64 - right))
@@ -617,7 +1719,7 @@ impl Executor {
             },
             Token::OpMul => match (left, right) {
                 (DataValue::Int(left), DataValue::Int(right)) => {
-                    Ok(DataValue::Int(left * right))
+                    left.checked_mul(right).map(DataValue::Int).ok_or(SQRLErr::Overflow)
                 }
                 (DataValue::Int(left), DataValue::Real(right)) => {
                     Ok(DataValue::Real(left as f64 * right))
@@ -647,8 +1749,9 @@ impl Executor {
                 (DataValue::Real(_), DataValue::Int(0)) => {
                     Err(SQRLErr::InvalidBinaryOp("division by zero".to_string()))
                 }
+                // never truncates: 5 / 2 is 2.5; DIV() divides integers
                 (DataValue::Int(left), DataValue::Int(right)) => {
-                    Ok(DataValue::Int(left / right))
+                    Ok(DataValue::Real(left as f64 / right as f64))
                 }
                 (DataValue::Int(left), DataValue::Real(right)) => {
                     Ok(DataValue::Real(left as f64 / right))
@@ -674,28 +1777,49 @@ impl Executor {
                         Token::OpLe => left <= right,
                         _ => unreachable!(),
                     },
-                    (DataValue::Int(left), DataValue::Real(right)) => match op {
-                        Token::OpGt => (*left as f64) > *right,
-                        Token::OpLt => (*left as f64) < *right,
-                        Token::OpGe => (*left as f64) >= *right,
-                        Token::OpLe => (*left as f64) <= *right,
+                    (DataValue::Int(_), DataValue::Real(_))
+                    | (DataValue::Real(_), DataValue::Int(_)) => {
+                        // NaN is neither greater nor less than anything
+                        let ord = Self::compare_values(&left, &right).ok();
+                        match op {
+                            Token::OpGt => ord.is_some_and(Ordering::is_gt),
+                            Token::OpLt => ord.is_some_and(Ordering::is_lt),
+                            Token::OpGe => ord.is_some_and(Ordering::is_ge),
+                            Token::OpLe => ord.is_some_and(Ordering::is_le),
+                            _ => unreachable!(),
+                        }
+                    }
+                    (DataValue::Real(left), DataValue::Real(right)) => match op {
+                        Token::OpGt => left > right,
+                        Token::OpLt => left < right,
+                        Token::OpGe => left >= right,
+                        Token::OpLe => left <= right,
                         _ => unreachable!(),
                     },
-                    (DataValue::Real(left), DataValue::Int(right)) => match op {
-                        Token::OpGt => *left > (*right as f64),
-                        Token::OpLt => *left < (*right as f64),
-                        Token::OpGe => *left >= (*right as f64),
-                        Token::OpLe => *left <= (*right as f64),
+                    (DataValue::Text(left), DataValue::Text(right)) => match op {
+                        Token::OpGt => left > right,
+                        Token::OpLt => left < right,
+                        Token::OpGe => left >= right,
+                        Token::OpLe => left <= right,
                         _ => unreachable!(),
                     },
-                    (DataValue::Real(left), DataValue::Real(right)) => match op {
+                    (DataValue::Date(left), DataValue::Date(right)) => match op {
                         Token::OpGt => left > right,
                         Token::OpLt => left < right,
                         Token::OpGe => left >= right,
                         Token::OpLe => left <= right,
                         _ => unreachable!(),
                     },
-                    (DataValue::Text(left), DataValue::Text(right)) => match op {
+                    (DataValue::Timestamp(left), DataValue::Timestamp(right)) => {
+                        match op {
+                            Token::OpGt => left > right,
+                            Token::OpLt => left < right,
+                            Token::OpGe => left >= right,
+                            Token::OpLe => left <= right,
+                            _ => unreachable!(),
+                        }
+                    }
+                    (DataValue::Blob(left), DataValue::Blob(right)) => match op {
                         Token::OpGt => left > right,
                         Token::OpLt => left < right,
                         Token::OpGe => left >= right,
@@ -716,6 +1840,193 @@ impl Executor {
         }
     }
 
+    /// Splits a predicate into its AND-ed terms.
+    fn conjuncts(expr: &Expr) -> Vec<&Expr> {
+        match expr {
+            Expr::Binary { op: Token::And, left, right } => {
+                let mut terms = Self::conjuncts(left);
+                terms.extend(Self::conjuncts(right));
+                terms
+            }
+            expr => vec![expr],
+        }
+    }
+
+    /// Whether an expression has the same value for every row.
+    fn is_constant(expr: &Expr) -> bool {
+        match expr {
+            Expr::Ident(_)
+            | Expr::QualifiedIdent(..)
+            | Expr::Wildcard
+            | Expr::QualifiedWildcard(_) => false,
+            Expr::Call { args, .. } => {
+                !Self::is_aggregate(expr) && args.iter().all(Self::is_constant)
+            }
+            Expr::List(exprs) => exprs.iter().all(Self::is_constant),
+            Expr::Cast { expr, .. }
+            | Expr::Alias { expr, .. }
+            | Expr::Collate { expr, .. } => Self::is_constant(expr),
+            Expr::Unary { right, .. } => Self::is_constant(right),
+            Expr::Binary { left, right, .. } => {
+                Self::is_constant(left) && Self::is_constant(right)
+            }
+            Expr::Like { expr, pattern, .. } => {
+                Self::is_constant(expr) && Self::is_constant(pattern)
+            }
+            _ => true,
+        }
+    }
+
+    /// Picks how to read the rows of `table` that may satisfy `where_clause`.
+    ///
+    /// Every AND-ed term comparing an indexed column with a constant gives a
+    /// candidate path, and the one with the lowest estimated row count wins.
+    /// The whole clause is still applied to the rows read, so the chosen term
+    /// only narrows the search.
+    fn plan_access<'a>(
+        &self,
+        table: &'a TableState,
+        where_clause: Option<&Expr>,
+    ) -> AccessPath<'a> {
+        let mut paths = Vec::new();
+        for term in where_clause.map(Self::conjuncts).unwrap_or_default() {
+            let Expr::Binary { op, left, right } = term else {
+                continue;
+            };
+            // put the column on the left
+            let (name, op, expr) = match (&**left, &**right) {
+                (Expr::Ident(name), expr) if Self::is_constant(expr) => {
+                    (name, op.clone(), expr)
+                }
+                (expr, Expr::Ident(name)) if Self::is_constant(expr) => {
+                    let op = match op {
+                        Token::OpGt => Token::OpLt,
+                        Token::OpLt => Token::OpGt,
+                        Token::OpGe => Token::OpLe,
+                        Token::OpLe => Token::OpGe,
+                        op => op.clone(),
+                    };
+                    (name, op, expr)
+                }
+                _ => continue,
+            };
+            let Some(col) = table.get_col_by_name(name).filter(|col| col.alive) else {
+                continue;
+            };
+            let Some(index) = table.get_index(&col.id) else {
+                continue;
+            };
+            let Ok(key) = self.eval(expr) else {
+                continue;
+            };
+            // NULL is never indexed, and `= NULL` matches NULL values
+            if key == DataValue::Nil {
+                continue;
+            }
+            let (low, high) = match op {
+                // a key of another type is compared row by row, which either
+                // converts it (numbers) or reports the mismatch
+                Token::OpEq if key.data_type() == col.data_type.value_type() => {
+                    paths.push(AccessPath::IndexSeek {
+                        col,
+                        index,
+                        key: Self::index_key(col, key),
+                    });
+                    continue;
+                }
+                _ if !Self::is_range_key(col, &key) => continue,
+                Token::OpGt => (Bound::Excluded(key), Bound::Unbounded),
+                Token::OpGe => (Bound::Included(key), Bound::Unbounded),
+                Token::OpLt => (Bound::Unbounded, Bound::Excluded(key)),
+                Token::OpLe => (Bound::Unbounded, Bound::Included(key)),
+                _ => continue,
+            };
+            // `a > 1 AND a < 5` reads one range
+            let existing = paths.iter_mut().find_map(|path| match path {
+                AccessPath::IndexRange { col: range_col, low, high, .. }
+                    if range_col.id == col.id =>
+                {
+                    Some((low, high))
+                }
+                _ => None,
+            });
+            match existing {
+                Some((range_low, range_high)) => {
+                    if matches!(range_low, Bound::Unbounded) {
+                        *range_low = low;
+                    }
+                    if matches!(range_high, Bound::Unbounded) {
+                        *range_high = high;
+                    }
+                }
+                None => paths.push(AccessPath::IndexRange { col, index, low, high }),
+            }
+        }
+        // the first cheapest path wins, and a scan only if nothing else does
+        paths.push(AccessPath::FullScan);
+        let rows = table.live_rows().count();
+        paths
+            .into_iter()
+            .reduce(
+                |best, path| {
+                    if path.cost(rows) < best.cost(rows) { path } else { best }
+                },
+            )
+            .unwrap_or(AccessPath::FullScan)
+    }
+
+    /// CHAR values are indexed with their padding, so a key differing only
+    /// in trailing spaces finds the same row, and NOCASE values case-folded.
+    fn index_key(col: &ColState, key: DataValue) -> DataValue {
+        let key = match (col.data_type, key) {
+            (ColumnType::Char(width), DataValue::Text(text)) => {
+                match Char::new(text.trim_end_matches(' '), width as usize) {
+                    Ok(padded) => DataValue::Text(padded.into_inner()),
+                    Err(_) => DataValue::Text(text),
+                }
+            }
+            (_, key) => key,
+        };
+        col.index_key(key)
+    }
+
+    /// Whether index order agrees with `<` and `>` between the column and
+    /// `key`. CHAR padding and mixed types would not.
+    fn is_range_key(col: &ColState, key: &DataValue) -> bool {
+        let numeric = |ty: DataType| {
+            matches!(ty, DataType::Int | DataType::Real | DataType::Decimal)
+        };
+        let col_type = col.data_type.value_type();
+        match col.data_type {
+            ColumnType::Char(_) | ColumnType::Bool => false,
+            _ => {
+                key.data_type() == col_type
+                    || numeric(key.data_type()) && numeric(col_type)
+            }
+        }
+    }
+
+    /// Live rows that may satisfy `where_clause`, in row order. Callers still
+    /// filter them with the whole clause.
+    fn candidate_rows<'a>(
+        &self,
+        table: &'a TableState,
+        where_clause: Option<&Expr>,
+    ) -> Vec<&'a RowState> {
+        let row_ids = match self.plan_access(table, where_clause) {
+            AccessPath::FullScan => return table.live_rows().collect(),
+            AccessPath::IndexSeek { index, key, .. } => index.get(&key).collect(),
+            AccessPath::IndexRange { index, low, high, .. } => {
+                index.range(low.as_ref(), high.as_ref()).collect::<BTreeSet<_>>()
+            }
+        };
+        row_ids
+            .into_iter()
+            .filter_map(|row_id| table.rows.get(&row_id))
+            .filter(|row| row.alive)
+            .collect()
+    }
+
     fn matches_where(
         &self,
         table: &TableState,
@@ -727,6 +2038,7 @@ impl Executor {
         };
         match self.eval_in_row(expr, Some(table), Some(row))? {
             DataValue::Bool(value) => Ok(value),
+            DataValue::Nil => Ok(false),
             other => Err(SQRLErr::InvalidPredicate(other.data_type())),
         }
     }
@@ -742,17 +2054,88 @@ impl Executor {
         };
         match self.eval_in_source_row(expr, source_columns, row)? {
             DataValue::Bool(value) => Ok(value),
+            DataValue::Nil => Ok(false),
             other => Err(SQRLErr::InvalidPredicate(other.data_type())),
         }
     }
 }
 
 impl Executor {
+    /// Runs a statement. Writes made by a statement are committed together,
+    /// and rolled back if the statement fails partway.
+    ///
+    /// Between BEGIN and COMMIT, statements share one batch that ROLLBACK
+    /// discards. A failing statement inside it only undoes its own writes.
+    /// BEGIN inside a transaction is an error rather than a nested one.
     pub fn run(&mut self, stmt: Stmt) -> Result<QueryResult> {
         match stmt {
-            Stmt::Create { table_name, defines, if_not_exists } => {
-                self.run_create(&table_name, defines, if_not_exists)
+            Stmt::Begin => self.storage.begin()?,
+            Stmt::Commit => {
+                self.storage.commit()?;
+                self.savepoints.clear();
+            }
+            Stmt::Rollback => {
+                self.storage.rollback()?;
+                self.savepoints.clear();
+            }
+            Stmt::Savepoint { name } => {
+                let savepoint = self.storage.savepoint()?;
+                self.savepoints.push((name, savepoint));
+            }
+            Stmt::RollbackTo { name } => {
+                // the savepoint itself stays, later ones are discarded
+                let index = self.find_savepoint(&name)?;
+                self.savepoints.truncate(index + 1);
+                self.storage.rollback_to(self.savepoints[index].1)?;
+            }
+            Stmt::Release { name } => {
+                let index = self.find_savepoint(&name)?;
+                self.savepoints.truncate(index);
+            }
+            Stmt::Vacuum => self.storage.vacuum()?,
+            stmt if self.storage.in_txn() => {
+                let savepoint = self.storage.savepoint()?;
+                let result = self.run_stmt(stmt);
+                if result.is_err() {
+                    self.storage.rollback_to(savepoint)?;
+                }
+                return result;
             }
+            stmt => {
+                self.storage.begin()?;
+                let result = self.run_stmt(stmt);
+                match result {
+                    Ok(_) => self.storage.commit()?,
+                    Err(_) => self.storage.rollback()?,
+                }
+                return result;
+            }
+        }
+        Ok(QueryResult::Success)
+    }
+
+    fn find_savepoint(&self, name: &str) -> Result<usize> {
+        self.savepoints
+            .iter()
+            .rposition(|(saved, _)| &**saved == name)
+            .ok_or_else(|| SQRLErr::SavepointNotFound(name.to_string()))
+    }
+
+    fn run_stmt(&mut self, stmt: Stmt) -> Result<QueryResult> {
+        match stmt {
+            Stmt::Create {
+                table_name,
+                defines,
+                foreign_keys,
+                checks,
+                if_not_exists,
+            } => self.run_create(
+                &table_name,
+                defines,
+                foreign_keys,
+                checks,
+                if_not_exists,
+            ),
             Stmt::AlterAdd { table_name, define } => {
                 self.run_alter_add(&table_name, define)
             }
@@ -762,19 +2145,24 @@ impl Executor {
             Stmt::AlterRename { table_name, new_name } => {
                 self.run_alter_rename(&table_name, &new_name)
             }
-            Stmt::InsertValues { table_name, columns, values } => {
-                self.run_insert_values(&table_name, columns, values)
+            Stmt::AlterRestart { table_name, column, value } => {
+                self.run_alter_restart(&table_name, &column, value)
+            }
+            Stmt::InsertValues { table_name, columns, values, returning } => {
+                self.run_insert_values(&table_name, columns, values, returning)
             }
-            Stmt::InsertSelect { table_name, columns, select } => {
-                self.run_insert_select(&table_name, columns, *select)
+            Stmt::InsertSelect { table_name, columns, select, returning } => {
+                self.run_insert_select(&table_name, columns, *select, returning)
             }
             stmt @ Stmt::Select { .. } => self.run_select(stmt),
-            stmt @ Stmt::UnionAll { .. } => self.run_select(stmt),
-            Stmt::Update { table_name, assigns, where_clause } => {
-                self.run_update(&table_name, assigns, where_clause)
+            stmt @ Stmt::SetOp { .. } => self.run_select(stmt),
+            stmt @ Stmt::With { .. } => self.run_select(stmt),
+            Stmt::Explain(query) => self.run_explain(&query),
+            Stmt::Update { table_name, assigns, where_clause, returning } => {
+                self.run_update(&table_name, assigns, where_clause, returning)
             }
-            Stmt::Delete { table_name, where_clause } => {
-                self.run_delete(&table_name, where_clause)
+            Stmt::Delete { table_name, where_clause, returning } => {
+                self.run_delete(&table_name, where_clause, returning)
             }
             Stmt::Drop { table_name, if_exists, cascade } => {
                 self.run_drop(&table_name, if_exists, cascade)
@@ -783,44 +2171,285 @@ impl Executor {
         }
     }
 
+    /// Column names must be unique within the table. Like every identifier
+    /// they compare case-sensitively, so `a` and `A` are different columns.
     fn run_create(
         &mut self,
         table_name: &str,
-        defines: Vec<(Box<str>, DataType)>,
+        defines: Vec<ColumnDef>,
+        foreign_keys: Vec<ForeignKeyDef>,
+        mut checks: Vec<Expr>,
         if_not_exists: bool,
     ) -> Result<QueryResult> {
+        let mut seen = HashSet::new();
+        if let Some(define) = defines.iter().find(|define| !seen.insert(&define.name)) {
+            return Err(SQRLErr::DuplicateColumn(define.name.to_string()));
+        }
+        let columns = defines
+            .iter()
+            .map(|define| Ok((define, self.build_constraints(define)?)))
+            .collect::<Result<Vec<_>>>()?;
+        if columns.iter().filter(|(_, c)| c.primary_key).count() > 1 {
+            return Err(StorageErr::InvalidSchema("multiple primary keys").into());
+        }
         let table_id = match self.storage.create_table(table_name) {
             Err(_) if if_not_exists => return Ok(QueryResult::Success),
             Err(e) => return Err(e.into()),
             Ok(id) => id,
         };
-        for (name, dt) in defines {
-            self.storage.create_column(table_id, dt, &name)?;
+        for (define, constraints) in columns {
+            self.storage.create_column(
+                table_id,
+                define.data_type,
+                &define.name,
+                constraints,
+            )?;
+        }
+        for define in foreign_keys {
+            let foreign_key = self.resolve_foreign_key(table_id, &define)?;
+            self.storage.create_foreign_key(table_id, foreign_key)?;
+        }
+        // column CHECKs are stored like table CHECKs
+        let column_checks = defines.iter().flat_map(Self::column_checks).cloned();
+        checks.splice(0..0, column_checks);
+        for expr in checks {
+            self.create_check(table_id, &expr)?;
         }
         Ok(QueryResult::Success)
     }
 
-    fn run_alter_add(
-        &mut self,
-        table_name: &str,
-        define: (Box<str>, DataType),
-    ) -> Result<QueryResult> {
-        let table_id = self.storage.get_table(table_name)?.id;
-        let (name, data_type) = define;
-        self.storage.create_column(table_id, data_type, &name)?;
-        Ok(QueryResult::Success)
+    fn column_checks(define: &ColumnDef) -> impl Iterator<Item = &Expr> {
+        define.constraints.iter().filter_map(|constraint| match constraint {
+            ColumnConstraint::Check(expr) => Some(expr),
+            _ => None,
+        })
     }
 
-    fn run_alter_drop(
-        &mut self,
+    /// Adds a CHECK constraint after testing it on the existing rows. Testing
+    /// it also rejects predicates naming unknown columns or parameters.
+    fn create_check(&mut self, table_id: TableId, expr: &Expr) -> Result<()> {
+        let table = self
+            .storage
+            .state
+            .get_table(&table_id)
+            .ok_or(StorageErr::TableNotFound(table_id))?;
+        let live_cols = table.live_cols().collect::<Vec<_>>();
+        let mut rows = table
+            .live_rows()
+            .map(|row| live_cols.iter().map(|col| Self::read_value(row, col)).collect())
+            .collect::<Vec<_>>();
+        rows.push(vec![DataValue::Nil; live_cols.len()]);
+        let expr_sql = expr.to_sql();
+        self.check_predicate(&table.name, table, expr, &expr_sql, &rows)?;
+        self.storage.create_check(table_id, &expr_sql)?;
+        Ok(())
+    }
+
+    /// Parses the CHECK predicates of a table, paired with their SQL text.
+    fn table_checks(table: &TableState) -> Result<Vec<(Expr, &str)>> {
+        table
+            .checks
+            .iter()
+            .map(|sql| {
+                let expr = Parser::new(Lexer::new(sql))?.parse_expression()?;
+                Ok((expr, &**sql))
+            })
+            .collect()
+    }
+
+    /// Rejects rows for which a CHECK predicate is false. `rows` hold the
+    /// values of the live columns; a NULL result passes.
+    fn check_predicate(
+        &self,
         table_name: &str,
-        column: &str,
+        table: &TableState,
+        expr: &Expr,
+        expr_sql: &str,
+        rows: &[Vec<DataValue>],
+    ) -> Result<()> {
+        let live_cols = table.live_cols().collect::<Vec<_>>();
+        let source_columns =
+            live_cols.iter().map(|col| col.name.to_string()).collect::<Vec<_>>();
+        for row in rows {
+            // compare CHAR values without their padding, as queries do
+            let row = live_cols
+                .iter()
+                .zip(row)
+                .map(|(col, value)| match (col.data_type, value) {
+                    (ColumnType::Char(_), DataValue::Text(text)) => {
+                        DataValue::Text(text.trim_end_matches(' ').into())
+                    }
+                    (_, value) => value.clone(),
+                })
+                .collect::<Vec<_>>();
+            match self.eval_in_source_row(expr, &source_columns, &row)? {
+                DataValue::Bool(true) | DataValue::Nil => {}
+                DataValue::Bool(false) => {
+                    return Err(SQRLErr::ConstraintViolation(format!(
+                        "CHECK constraint failed: {table_name} ({expr_sql})"
+                    )));
+                }
+                other => return Err(SQRLErr::InvalidPredicate(other.data_type())),
+            }
+        }
+        Ok(())
+    }
+
+    fn check_rows(
+        &self,
+        table_name: &str,
+        table: &TableState,
+        rows: &[Vec<DataValue>],
+    ) -> Result<()> {
+        for (expr, expr_sql) in Self::table_checks(table)? {
+            self.check_predicate(table_name, table, &expr, expr_sql, rows)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_foreign_key(
+        &self,
+        table_id: TableId,
+        define: &ForeignKeyDef,
+    ) -> Result<ForeignKey> {
+        let table = self
+            .storage
+            .state
+            .get_table(&table_id)
+            .ok_or(StorageErr::TableNotFound(table_id))?;
+        // the new table may reference itself
+        let ref_table = if *define.ref_table == *table.name {
+            table
+        } else {
+            self.storage.get_table(&define.ref_table)?
+        };
+        let col_id = |table: &TableState, name: &str| {
+            table
+                .get_col_by_name(name)
+                .filter(|col| col.alive)
+                .map(|col| col.id)
+                .ok_or_else(|| SQRLErr::ColumnNotFound(name.to_string()))
+        };
+        Ok(ForeignKey {
+            col_id: col_id(table, &define.column)?,
+            ref_table_id: ref_table.id,
+            ref_col_id: col_id(ref_table, &define.ref_column)?,
+            on_delete_cascade: define.on_delete_cascade,
+        })
+    }
+
+    fn run_alter_add(
+        &mut self,
+        table_name: &str,
+        define: ColumnDef,
+    ) -> Result<QueryResult> {
+        let constraints = self.build_constraints(&define)?;
+        let table = self.storage.get_table(table_name)?;
+        if table.live_cols().any(|col| col.name == define.name) {
+            return Err(SQRLErr::DuplicateColumn(define.name.to_string()));
+        }
+        // existing rows would read the missing value as NULL
+        if constraints.not_null
+            && constraints.default.is_none()
+            && table.live_rows().next().is_some()
+        {
+            return Err(SQRLErr::ConstraintViolation(format!(
+                "cannot add NOT NULL column {table_name}.{} without DEFAULT",
+                define.name
+            )));
+        }
+        if constraints.primary_key
+            && table.live_cols().any(|c| c.constraints.primary_key)
+        {
+            return Err(StorageErr::InvalidSchema("multiple primary keys").into());
+        }
+        if constraints.unique
+            && constraints.default.is_some()
+            && table.live_rows().count() > 1
+        {
+            return Err(SQRLErr::ConstraintViolation(format!(
+                "UNIQUE constraint failed: {table_name}.{}",
+                define.name
+            )));
+        }
+        let table_id = table.id;
+        self.storage.create_column(
+            table_id,
+            define.data_type,
+            &define.name,
+            constraints,
+        )?;
+        for expr in Self::column_checks(&define) {
+            self.create_check(table_id, expr)?;
+        }
+        Ok(QueryResult::Success)
+    }
+
+    fn build_constraints(&self, define: &ColumnDef) -> Result<ColConstraints> {
+        let mut constraints = ColConstraints::default();
+        for constraint in &define.constraints {
+            match constraint {
+                ColumnConstraint::Null => constraints.not_null = false,
+                ColumnConstraint::NotNull => constraints.not_null = true,
+                ColumnConstraint::Unique => constraints.unique = true,
+                ColumnConstraint::PrimaryKey => {
+                    constraints.primary_key = true;
+                    constraints.unique = true;
+                    constraints.not_null = true;
+                }
+                ColumnConstraint::Autoincrement => {
+                    if define.data_type != ColumnType::Int {
+                        return Err(StorageErr::InvalidSchema(
+                            "AUTOINCREMENT requires an INT column",
+                        )
+                        .into());
+                    }
+                    constraints.autoincrement = true;
+                }
+                ColumnConstraint::Default(expr) => {
+                    let value = self.eval(expr)?;
+                    let value =
+                        Self::coerce_value(&define.name, define.data_type, value)?;
+                    constraints.default = Some(value);
+                }
+                ColumnConstraint::Collate(collation) => {
+                    if define.data_type.value_type() != DataType::Text {
+                        return Err(StorageErr::InvalidSchema(
+                            "COLLATE requires a text column",
+                        )
+                        .into());
+                    }
+                    constraints.collation = *collation;
+                }
+                // stored with the table, see `create_check`
+                ColumnConstraint::Check(_) => {}
+            }
+        }
+        Ok(constraints)
+    }
+
+    fn run_alter_drop(
+        &mut self,
+        table_name: &str,
+        column: &str,
     ) -> Result<QueryResult> {
         let table = self.storage.get_table(table_name)?;
         let column_id = table
             .get_col_by_name(column)
             .ok_or_else(|| SQRLErr::ColumnNotFound(column.to_string()))?
             .id;
+        for (mut expr, _) in Self::table_checks(table)? {
+            let mut referenced = false;
+            visit::walk_expr_mut(&mut expr, &mut |expr| {
+                referenced |= matches!(expr, Expr::Ident(name) if **name == *column);
+            });
+            if referenced {
+                return Err(StorageErr::InvalidSchema(
+                    "column is used by a CHECK constraint",
+                )
+                .into());
+            }
+        }
         self.storage.drop_column(table.id, column_id)?;
         Ok(QueryResult::Success)
     }
@@ -835,31 +2464,123 @@ impl Executor {
         Ok(QueryResult::Success)
     }
 
+    fn run_alter_restart(
+        &mut self,
+        table_name: &str,
+        column: &str,
+        value: i64,
+    ) -> Result<QueryResult> {
+        let table = self.storage.get_table(table_name)?;
+        let column_id = table
+            .get_col_by_name(column)
+            .ok_or_else(|| SQRLErr::ColumnNotFound(column.to_string()))?
+            .id;
+        let sequence = value.checked_sub(1).ok_or(SQRLErr::Overflow)?;
+        self.storage.restart_sequence(table.id, column_id, sequence)?;
+        Ok(QueryResult::Success)
+    }
+
+    /// Rejects non-NULL values of FOREIGN KEY columns that the referenced
+    /// column does not hold, comparing under the referenced column's
+    /// collation. `rows` are the values a statement writes; for a
+    /// self-referencing key they may also supply the referenced values.
+    fn check_foreign_keys(
+        &self,
+        table_name: &str,
+        table: &TableState,
+        rows: &[Vec<(ColState, DataValue)>],
+    ) -> Result<()> {
+        let written = |ref_col: &ColState, key: &DataValue| {
+            rows.iter().flatten().any(|(col, written)| {
+                col.id == ref_col.id
+                    && IndexKey(ref_col.index_key(written.clone()))
+                        == IndexKey(key.clone())
+            })
+        };
+        for fk in &table.foreign_keys {
+            let Some((ref_col, index)) =
+                self.storage.state.get_table(&fk.ref_table_id).and_then(|ref_table| {
+                    Some((
+                        ref_table.get_col(&fk.ref_col_id)?,
+                        ref_table.get_index(&fk.ref_col_id)?,
+                    ))
+                })
+            else {
+                continue;
+            };
+            for (col, value) in rows.iter().flatten() {
+                if col.id != fk.col_id || *value == DataValue::Nil {
+                    continue;
+                }
+                let key = ref_col.index_key(value.clone());
+                let found = index.get(&key).next().is_some()
+                    || fk.ref_table_id == table.id && written(ref_col, &key);
+                if !found {
+                    return Err(Self::foreign_key_failed(table_name, &col.name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Live rows of `child` whose FOREIGN KEY column holds `key` of
+    /// `ref_col`, compared under that column's collation.
+    fn referencing_rows<'a>(
+        child: &'a TableState,
+        fk: &ForeignKey,
+        ref_col: &'a ColState,
+        key: &DataValue,
+    ) -> impl Iterator<Item = &'a RowState> {
+        let col = child.get_col(&fk.col_id);
+        let key = IndexKey(ref_col.index_key(key.clone()));
+        child.live_rows().filter(move |row| {
+            col.is_some_and(|col| IndexKey(ref_col.index_key(row.value(col))) == key)
+        })
+    }
+
+    fn foreign_key_failed(table_name: &str, col_name: &str) -> SQRLErr {
+        SQRLErr::ConstraintViolation(format!(
+            "FOREIGN KEY constraint failed: {table_name}.{col_name}"
+        ))
+    }
+
     fn run_insert_values(
         &mut self,
         table_name: &str,
         columns: Vec<Box<str>>,
-        values: Vec<Expr>,
+        values: Vec<Vec<Expr>>,
+        returning: Option<Vec<Expr>>,
     ) -> Result<QueryResult> {
         let (table_id, live_cols, source_indexes, expected) =
             self.resolve_insert_targets(table_name, &columns)?;
 
-        let evaluated = values
-            .into_iter()
-            .map(|expr| self.eval(&expr))
-            .collect::<Result<Vec<_>>>()?;
-
-        if evaluated.len() != expected {
-            return Err(SQRLErr::ColumnCountMismatch {
-                expected,
-                got: evaluated.len(),
-            });
+        let mut sequences =
+            live_cols.iter().map(|col| col.sequence).collect::<Vec<_>>();
+        let mut rows = Vec::with_capacity(values.len());
+        for exprs in values {
+            self.check_interrupt()?;
+            let evaluated =
+                exprs.iter().map(|expr| self.eval(expr)).collect::<Result<Vec<_>>>()?;
+            if evaluated.len() != expected {
+                return Err(SQRLErr::ColumnCountMismatch {
+                    expected,
+                    got: evaluated.len(),
+                });
+            }
+            rows.push(self.build_insert_row(
+                table_name,
+                &live_cols,
+                &source_indexes,
+                &evaluated,
+                &mut sequences,
+            )?);
         }
+        self.check_insert_unique(table_name, &live_cols, &rows)?;
+        self.check_insert_foreign_keys(table_name, &live_cols, &rows)?;
+        self.check_rows(table_name, self.storage.get_table(table_name)?, &rows)?;
 
-        let row = self.build_insert_row(&live_cols, &source_indexes, &evaluated)?;
-
-        self.storage.insert_row(table_id, row)?;
-        Ok(QueryResult::Count(1))
+        let row_ids = self.storage.bulk_insert(table_id, rows)?;
+        self.write_result(table_id, &row_ids, returning)
     }
 
     fn run_insert_select(
@@ -867,42 +2588,46 @@ impl Executor {
         table_name: &str,
         columns: Vec<Box<str>>,
         select: Stmt,
+        returning: Option<Vec<Expr>>,
     ) -> Result<QueryResult> {
         let (table_id, live_cols, source_indexes, expected) =
             self.resolve_insert_targets(table_name, &columns)?;
         let (_, source_rows) = self.collect_query_rows(&select)?;
 
-        let count = source_rows.len();
+        let mut sequences =
+            live_cols.iter().map(|col| col.sequence).collect::<Vec<_>>();
+        let mut rows = Vec::with_capacity(source_rows.len());
         for source_row in source_rows {
+            self.check_interrupt()?;
             if source_row.len() != expected {
                 return Err(SQRLErr::ColumnCountMismatch {
                     expected,
                     got: source_row.len(),
                 });
             }
-            let row =
-                self.build_insert_row(&live_cols, &source_indexes, &source_row)?;
-            self.storage.insert_row(table_id, row)?;
+            rows.push(self.build_insert_row(
+                table_name,
+                &live_cols,
+                &source_indexes,
+                &source_row,
+                &mut sequences,
+            )?);
         }
+        self.check_insert_unique(table_name, &live_cols, &rows)?;
+        self.check_insert_foreign_keys(table_name, &live_cols, &rows)?;
+        self.check_rows(table_name, self.storage.get_table(table_name)?, &rows)?;
 
-        Ok(QueryResult::Count(count))
+        let row_ids = self.storage.bulk_insert(table_id, rows)?;
+        self.write_result(table_id, &row_ids, returning)
     }
 
     fn resolve_insert_targets(
         &self,
         table_name: &str,
         columns: &[Box<str>],
-    ) -> Result<(
-        crate::storage::TableId,
-        Vec<(Box<str>, DataType)>,
-        Vec<Option<usize>>,
-        usize,
-    )> {
+    ) -> Result<InsertTargets> {
         let table = self.storage.get_table(table_name)?;
-        let live_cols = table
-            .live_cols()
-            .map(|col| (col.name.clone(), col.data_type))
-            .collect::<Vec<_>>();
+        let live_cols = table.live_cols().cloned().collect::<Vec<_>>();
         let expected = if columns.is_empty() { live_cols.len() } else { columns.len() };
         let source_indexes = if columns.is_empty() {
             (0..live_cols.len()).map(Some).collect::<Vec<_>>()
@@ -911,7 +2636,7 @@ impl Executor {
             for (value_index, column) in columns.iter().enumerate() {
                 let Some(col_index) = live_cols
                     .iter()
-                    .position(|(name, _)| name.as_ref() == column.as_ref())
+                    .position(|col| col.name.as_ref() == column.as_ref())
                 else {
                     return Err(SQRLErr::ColumnNotFound(column.to_string()));
                 };
@@ -925,41 +2650,269 @@ impl Executor {
         Ok((table.id, live_cols, source_indexes, expected))
     }
 
+    fn check_insert_unique(
+        &self,
+        table_name: &str,
+        live_cols: &[ColState],
+        rows: &[Vec<DataValue>],
+    ) -> Result<()> {
+        let table = self.storage.get_table(table_name)?;
+        for (col_index, col) in live_cols.iter().enumerate() {
+            let inserted =
+                rows.iter().map(|row| row[col_index].clone()).collect::<Vec<_>>();
+            Self::check_unique(table_name, table, col, &HashMap::new(), &inserted)?;
+        }
+        Ok(())
+    }
+
+    fn check_insert_foreign_keys(
+        &self,
+        table_name: &str,
+        live_cols: &[ColState],
+        rows: &[Vec<DataValue>],
+    ) -> Result<()> {
+        let table = self.storage.get_table(table_name)?;
+        if table.foreign_keys.is_empty() {
+            return Ok(());
+        }
+        let rows = rows
+            .iter()
+            .map(|row| live_cols.iter().cloned().zip(row.iter().cloned()).collect())
+            .collect::<Vec<_>>();
+        self.check_foreign_keys(table_name, table, &rows)
+    }
+
     fn build_insert_row(
         &self,
-        live_cols: &[(Box<str>, DataType)],
+        table_name: &str,
+        live_cols: &[ColState],
         source_indexes: &[Option<usize>],
         source_values: &[DataValue],
+        sequences: &mut [i64],
     ) -> Result<Vec<DataValue>> {
         source_indexes
             .iter()
             .enumerate()
             .map(|(col_index, source_index)| {
-                let (col_name, col_type) = &live_cols[col_index];
+                let col = &live_cols[col_index];
                 let value = match source_index {
                     Some(value_index) => source_values[*value_index].clone(),
-                    None => col_type.default(),
+                    None => col.constraints.default.clone().unwrap_or(DataValue::Nil),
                 };
-                let value_type = value.data_type();
-                if value_type != *col_type {
-                    return Err(SQRLErr::TypeMismatch {
-                        column: col_name.to_string(),
-                        expected: *col_type,
-                        got: value_type,
-                    });
-                }
+                let value = Self::coerce_value(&col.name, col.data_type, value)?;
+                let value = if col.constraints.autoincrement {
+                    Self::next_sequence_value(
+                        table_name,
+                        col,
+                        value,
+                        &mut sequences[col_index],
+                    )?
+                } else {
+                    value
+                };
+                Self::check_not_null(table_name, col, &value)?;
                 Ok(value)
             })
             .collect::<Result<Vec<_>>>()
     }
 
+    /// Fills a NULL in an AUTOINCREMENT column with the next value after
+    /// `sequence`. Explicit values are kept and move the sequence past them.
+    fn next_sequence_value(
+        table_name: &str,
+        col: &ColState,
+        value: DataValue,
+        sequence: &mut i64,
+    ) -> Result<DataValue> {
+        let value = match value {
+            DataValue::Nil => sequence.checked_add(1).ok_or_else(|| {
+                SQRLErr::ConstraintViolation(format!(
+                    "AUTOINCREMENT exhausted: {table_name}.{}",
+                    col.name
+                ))
+            })?,
+            DataValue::Int(value) => value,
+            value => return Ok(value),
+        };
+        *sequence = (*sequence).max(value);
+        Ok(DataValue::Int(value))
+    }
+
     fn run_select(&mut self, stmt: Stmt) -> Result<QueryResult> {
         let (result_columns, rows) = self.collect_query_rows(&stmt)?;
-        let rows = rows
+        Ok(QueryResult::Rows { columns: result_columns, rows })
+    }
+
+    /// Describes how a query would run, one plan step per row. Steps are
+    /// listed outermost first, and the steps feeding one are indented below
+    /// it.
+    fn run_explain(&self, stmt: &Stmt) -> Result<QueryResult> {
+        let mut lines = Vec::new();
+        self.explain_query(stmt, 0, &mut lines)?;
+        let rows = lines
             .into_iter()
-            .map(|row| row.iter().map(Self::format_value).collect::<Vec<_>>())
+            .map(|line| vec![DataValue::Text(line.into_boxed_str())])
+            .collect();
+        Ok(QueryResult::Rows { columns: vec!["plan".to_string()], rows })
+    }
+
+    fn explain_query(
+        &self,
+        stmt: &Stmt,
+        depth: usize,
+        lines: &mut Vec<String>,
+    ) -> Result<()> {
+        let mut push = |depth: usize, line: String| {
+            lines.push(format!("{}{line}", "  ".repeat(depth)));
+        };
+        let list = |exprs: &[Expr]| {
+            exprs.iter().map(Expr::to_sql).collect::<Vec<_>>().join(", ")
+        };
+        match stmt {
+            Stmt::Select {
+                from,
+                columns,
+                distinct,
+                where_clause,
+                order_by,
+                limit,
+                offset,
+                ..
+            } => {
+                let mut depth = depth;
+                if let Some(line) = Self::explain_limit(limit.as_ref(), offset.as_ref())
+                {
+                    push(depth, line);
+                    depth += 1;
+                }
+                if let Some(order_by) = order_by {
+                    push(depth, Self::explain_sort(order_by));
+                    depth += 1;
+                }
+                if *distinct {
+                    push(depth, "DISTINCT".to_string());
+                    depth += 1;
+                }
+                let projections =
+                    if columns.is_empty() { "*".to_string() } else { list(columns) };
+                if columns.iter().any(Self::is_aggregate) {
+                    push(depth, format!("AGGREGATE {projections}"));
+                } else {
+                    push(depth, format!("PROJECT {projections}"));
+                }
+                depth += 1;
+                if let Some(where_clause) = where_clause {
+                    push(depth, format!("FILTER {}", where_clause.to_sql()));
+                    depth += 1;
+                }
+                match from {
+                    None => push(depth, "SINGLE ROW".to_string()),
+                    Some(SelectSource::Table { name, .. })
+                        if self.cte_rows(name).is_some() =>
+                    {
+                        push(depth, format!("CTE SCAN {name}"));
+                    }
+                    Some(SelectSource::Table { name, .. }) => {
+                        let table = self.storage.get_table(name)?;
+                        let line = match self.plan_access(table, where_clause.as_ref())
+                        {
+                            AccessPath::FullScan => format!("FULL SCAN {}", table.name),
+                            AccessPath::IndexSeek { col, .. } => {
+                                format!("INDEX SEEK {} ON {}", table.name, col.name)
+                            }
+                            AccessPath::IndexRange { col, .. } => {
+                                format!("INDEX RANGE {} ON {}", table.name, col.name)
+                            }
+                        };
+                        push(depth, line);
+                    }
+                    Some(SelectSource::Subquery { query, alias }) => {
+                        match alias {
+                            Some(alias) => push(depth, format!("SUBQUERY AS {alias}")),
+                            None => push(depth, "SUBQUERY".to_string()),
+                        }
+                        self.explain_query(query, depth + 1, lines)?;
+                    }
+                }
+            }
+            Stmt::SetOp { .. } => {
+                // each operation is listed above its operands, the innermost
+                // last, and their right operands follow the first operand
+                let (first, chain) = Self::set_op_chain(stmt);
+                let mut depths = Vec::with_capacity(chain.len());
+                let mut depth = depth;
+                for set_op in chain.iter().rev() {
+                    let Stmt::SetOp { op, all, order_by, limit, offset, .. } = set_op
+                    else {
+                        unreachable!()
+                    };
+                    if let Some(line) =
+                        Self::explain_limit(limit.as_ref(), offset.as_ref())
+                    {
+                        push(depth, line);
+                        depth += 1;
+                    }
+                    if let Some(order_by) = order_by {
+                        push(depth, Self::explain_sort(order_by));
+                        depth += 1;
+                    }
+                    let all = if *all { " ALL" } else { "" };
+                    push(depth, format!("{}{all}", op.keyword()));
+                    depth += 1;
+                    depths.push(depth);
+                }
+                self.explain_query(first, depth, lines)?;
+                for (set_op, depth) in chain.into_iter().zip(depths.into_iter().rev()) {
+                    let Stmt::SetOp { right, .. } = set_op else { unreachable!() };
+                    self.explain_query(right, depth, lines)?;
+                }
+            }
+            Stmt::With { ctes, body } => {
+                self.with_ctes(
+                    ctes,
+                    |query| Ok((self.check_query(query)?, Vec::new())),
+                    || {
+                        for (name, query) in ctes {
+                            lines.push(format!("{}WITH {name}", "  ".repeat(depth)));
+                            self.explain_query(query, depth + 1, lines)?;
+                        }
+                        self.explain_query(body, depth, lines)
+                    },
+                )?;
+            }
+            _ => {
+                return Err(SQRLErr::UnsupportedFeature(
+                    "EXPLAIN of a statement other than SELECT".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn explain_limit(limit: Option<&Expr>, offset: Option<&Expr>) -> Option<String> {
+        match (limit, offset) {
+            (Some(limit), Some(offset)) => {
+                Some(format!("LIMIT {} OFFSET {}", limit.to_sql(), offset.to_sql()))
+            }
+            (Some(limit), None) => Some(format!("LIMIT {}", limit.to_sql())),
+            (None, Some(offset)) => Some(format!("OFFSET {}", offset.to_sql())),
+            (None, None) => None,
+        }
+    }
+
+    fn explain_sort(order_by: &[OrderBy]) -> String {
+        let keys = order_by
+            .iter()
+            .map(|item| {
+                format!(
+                    "{} {} NULLS {}",
+                    item.expr.to_sql(),
+                    if item.desc { "DESC" } else { "ASC" },
+                    if item.nulls_first() { "FIRST" } else { "LAST" }
+                )
+            })
             .collect::<Vec<_>>();
-        Ok(QueryResult::Rows { columns: result_columns, rows })
+        format!("SORT BY {}", keys.join(", "))
     }
 
     fn collect_query_rows(
@@ -976,40 +2929,261 @@ impl Executor {
                 having,
                 order_by,
                 limit,
-            } => self.collect_select_rows(
-                from,
-                columns,
-                *distinct,
-                where_clause.as_ref(),
-                group_by.as_ref(),
-                having.as_ref(),
-                order_by.as_ref(),
-                *limit,
-            ),
-            Stmt::UnionAll { left, right } => {
-                let (left_columns, mut left_rows) = self.collect_query_rows(left)?;
-                let (right_columns, right_rows) = self.collect_query_rows(right)?;
-                if left_columns.len() != right_columns.len() {
-                    return Err(SQRLErr::ColumnCountMismatch {
-                        expected: left_columns.len(),
-                        got: right_columns.len(),
-                    });
+                offset,
+            } => {
+                let limit = self.eval_limit("LIMIT", limit.as_ref())?;
+                let offset = self.eval_limit("OFFSET", offset.as_ref())?.unwrap_or(0);
+                // only the rows up to the end of the page are needed
+                let (columns, rows) = self.collect_select_rows(
+                    from.as_ref(),
+                    columns,
+                    *distinct,
+                    where_clause.as_ref(),
+                    group_by.as_ref(),
+                    having.as_ref(),
+                    order_by.as_ref(),
+                    limit.map(|limit| limit.saturating_add(offset)),
+                )?;
+                Ok((columns, Self::page(rows, limit, offset)))
+            }
+            Stmt::SetOp { .. } => {
+                let (first, chain) = Self::set_op_chain(stmt);
+                let (mut columns, mut rows) = self.collect_query_rows(first)?;
+                for set_op in chain {
+                    (columns, rows) = self.combine_set_op(set_op, columns, rows)?;
                 }
-                left_rows.extend(right_rows);
-                Ok((left_columns, left_rows))
+                Ok((columns, rows))
             }
+            Stmt::With { ctes, body } => self.with_ctes(
+                ctes,
+                |query| self.collect_query_rows(query),
+                || self.collect_query_rows(body),
+            ),
             _ => Err(SQRLErr::UnsupportedFeature(
-                "query source must be SELECT or UNION ALL".to_string(),
+                "query source must be SELECT or a set operation".to_string(),
             )),
         }
     }
 
+    /// Splits a set operation into its first operand and the set operations
+    /// that fold the other operands into it, innermost first. `a UNION b
+    /// UNION c` nests to the left, so this walks it in a loop rather than
+    /// recursing once per operand.
+    pub(super) fn set_op_chain(stmt: &Stmt) -> (&Stmt, Vec<&Stmt>) {
+        let mut chain = Vec::new();
+        let mut first = stmt;
+        while let Stmt::SetOp { left, .. } = first {
+            chain.push(first);
+            first = left;
+        }
+        chain.reverse();
+        (first, chain)
+    }
+
+    /// Combines the rows of a set operation's left operand, already
+    /// collected, with those of its right operand.
+    fn combine_set_op(
+        &self,
+        set_op: &Stmt,
+        left_columns: Vec<String>,
+        mut left_rows: Vec<Vec<DataValue>>,
+    ) -> Result<(Vec<String>, Vec<Vec<DataValue>>)> {
+        let Stmt::SetOp { right, op, all, order_by, limit, offset, .. } = set_op else {
+            unreachable!()
+        };
+        let limit = self.eval_limit("LIMIT", limit.as_ref())?;
+        let offset = self.eval_limit("OFFSET", offset.as_ref())?.unwrap_or(0);
+        let (right_columns, right_rows) = self.collect_query_rows(right)?;
+        if left_columns.len() != right_columns.len() {
+            return Err(SQRLErr::ColumnCountMismatch {
+                expected: left_columns.len(),
+                got: right_columns.len(),
+            });
+        }
+        // both sides are unified together, so 1 and 1.0 match
+        let split = left_rows.len();
+        left_rows.extend(right_rows);
+        Self::unify_set_op_types(&left_columns, &mut left_rows)?;
+        let mut right_rows = left_rows.split_off(split);
+        match (op, all) {
+            (SetOp::Union, _) => left_rows.extend(right_rows),
+            (SetOp::Intersect, false) => {
+                left_rows.retain(|row| right_rows.contains(row));
+            }
+            (SetOp::Except, false) => {
+                left_rows.retain(|row| !right_rows.contains(row));
+            }
+            // with ALL, each right row matches at most one left row
+            (SetOp::Intersect | SetOp::Except, true) => {
+                let keep_matched = *op == SetOp::Intersect;
+                left_rows.retain(|row| {
+                    match right_rows.iter().position(|other| other == row) {
+                        Some(index) => {
+                            right_rows.swap_remove(index);
+                            keep_matched
+                        }
+                        None => !keep_matched,
+                    }
+                });
+            }
+        }
+        if !all {
+            // keyed like an index so each row is looked up in O(log n)
+            let mut seen = BTreeSet::new();
+            left_rows.retain(|row| {
+                seen.insert(row.iter().cloned().map(IndexKey).collect::<Vec<_>>())
+            });
+        }
+        // the combined rows are sorted by their own columns
+        if let Some(order_by) = order_by {
+            let exprs = order_by.iter().map(|item| &item.expr);
+            Self::check_column_refs(exprs, &left_columns, &[])?;
+            let width = left_columns.len();
+            for item in order_by {
+                Self::order_position(&item.expr, width)?;
+            }
+            let mut keyed = Vec::with_capacity(left_rows.len());
+            for row in left_rows {
+                self.check_interrupt()?;
+                let keys = order_by
+                    .iter()
+                    .map(|item| {
+                        let key = match Self::order_position(&item.expr, width)? {
+                            Some(index) => row[index].clone(),
+                            None => self.eval_in_source_row(
+                                &item.expr,
+                                &left_columns,
+                                &row,
+                            )?,
+                        };
+                        Ok(Self::collation(&item.expr).key(key))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                keyed.push((row, keys));
+            }
+            Self::sort_rows(&mut keyed, order_by)?;
+            left_rows = keyed.into_iter().map(|(row, _)| row).collect();
+        }
+        Ok((left_columns, Self::page(left_rows, limit, offset)))
+    }
+
+    /// Runs `body` with the queries of a WITH clause readable as tables, each
+    /// one also readable by those after it. `materialize` gives a query's
+    /// columns and rows; checks and plans only need the columns.
+    fn with_ctes<T>(
+        &self,
+        ctes: &[(Box<str>, Stmt)],
+        materialize: impl Fn(&Stmt) -> Result<(Vec<String>, Vec<Vec<DataValue>>)>,
+        body: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let scope = self.ctes.borrow().len();
+        let result = ctes
+            .iter()
+            .try_for_each(|(name, query)| {
+                let (columns, rows) = materialize(query)?;
+                self.ctes.borrow_mut().push(Cte { name: name.clone(), columns, rows });
+                Ok(())
+            })
+            .and_then(|()| body());
+        self.ctes.borrow_mut().truncate(scope);
+        result
+    }
+
+    /// The columns and rows of the WITH query named `name`. A WITH query
+    /// hides a table of the same name, and an inner one an outer one.
+    fn cte_rows(&self, name: &str) -> Option<(Vec<String>, Vec<Vec<DataValue>>)> {
+        let ctes = self.ctes.borrow();
+        let cte = ctes.iter().rev().find(|cte| *cte.name == *name)?;
+        Some((cte.columns.clone(), cte.rows.clone()))
+    }
+
+    /// Evaluates a LIMIT or OFFSET value, which must be a non-negative INT.
+    fn eval_limit(
+        &self,
+        clause: &'static str,
+        expr: Option<&Expr>,
+    ) -> Result<Option<u64>> {
+        let Some(expr) = expr else {
+            return Ok(None);
+        };
+        match self.eval(expr)? {
+            DataValue::Int(n) if n >= 0 => Ok(Some(n as u64)),
+            value => Err(SQRLErr::InvalidLimit {
+                clause,
+                value: Self::format_literal(&value),
+            }),
+        }
+    }
+
+    /// Skips the first `offset` rows and keeps at most `limit` of the rest.
+    fn page(
+        rows: Vec<Vec<DataValue>>,
+        limit: Option<u64>,
+        offset: u64,
+    ) -> Vec<Vec<DataValue>> {
+        let rows = rows.into_iter().skip(usize::try_from(offset).unwrap_or(usize::MAX));
+        match limit {
+            Some(limit) => {
+                rows.take(usize::try_from(limit).unwrap_or(usize::MAX)).collect()
+            }
+            None => rows.collect(),
+        }
+    }
+
+    /// Unifies the column types of the rows combined by a set operation.
+    ///
+    /// NIL is compatible with every type. INT and REAL mixed in the same
+    /// column are widened to REAL. Any other mismatch is rejected.
+    fn unify_set_op_types(
+        columns: &[String],
+        rows: &mut [Vec<DataValue>],
+    ) -> Result<()> {
+        for (index, column) in columns.iter().enumerate() {
+            let mut unified: Option<DataType> = None;
+            for row in rows.iter() {
+                let value_type = row[index].data_type();
+                unified = match (unified, value_type) {
+                    (_, DataType::Nil) => unified,
+                    (None, value_type) => Some(value_type),
+                    (Some(current), value_type) if current == value_type => unified,
+                    (Some(DataType::Int), DataType::Real)
+                    | (Some(DataType::Real), DataType::Int) => Some(DataType::Real),
+                    (Some(current), value_type) => {
+                        return Err(SQRLErr::TypeMismatch {
+                            column: column.clone(),
+                            expected: format!("{current:?}"),
+                            got: value_type,
+                        });
+                    }
+                };
+            }
+            if unified == Some(DataType::Real) {
+                for row in rows.iter_mut() {
+                    if let DataValue::Int(value) = row[index] {
+                        row[index] = DataValue::Real(value as f64);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads the source rows of a SELECT. Without a FROM clause the source is
+    /// a single row with no columns. A table source may skip rows that cannot
+    /// match `where_clause`, which the caller still applies.
     fn load_select_source(
         &self,
-        from: &SelectSource,
+        from: Option<&SelectSource>,
+        where_clause: Option<&Expr>,
     ) -> Result<(Vec<String>, Vec<Vec<DataValue>>)> {
+        let Some(from) = from else {
+            return Ok((Vec::new(), vec![Vec::new()]));
+        };
         match from {
             SelectSource::Table { name, alias: _ } => {
+                if let Some(cte) = self.cte_rows(name) {
+                    return Ok(cte);
+                }
                 let table = self.storage.get_table(name)?;
                 let live_cols = table.live_cols().collect::<Vec<_>>();
                 let columns = live_cols
@@ -1017,21 +3191,13 @@ impl Executor {
                     .map(|col| col.name.to_string())
                     .collect::<Vec<_>>();
 
-                let mut live_rows =
-                    table.rows.values().filter(|row| row.alive).collect::<Vec<_>>();
-                live_rows.sort_by_key(|row| row.id.0);
-
-                let rows = live_rows
+                let rows = self
+                    .candidate_rows(table, where_clause)
                     .into_iter()
                     .map(|row| {
                         live_cols
                             .iter()
-                            .map(|col| {
-                                row.values
-                                    .get(&col.id)
-                                    .cloned()
-                                    .unwrap_or(DataValue::Nil)
-                            })
+                            .map(|col| Self::read_value(row, col))
                             .collect::<Vec<_>>()
                     })
                     .collect::<Vec<_>>();
@@ -1044,15 +3210,16 @@ impl Executor {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn collect_select_rows(
         &self,
-        from: &SelectSource,
+        from: Option<&SelectSource>,
         columns: &[Expr],
         distinct: bool,
         where_clause: Option<&Expr>,
         group_by: Option<&Vec<Expr>>,
         having: Option<&Expr>,
-        order_by: Option<&Vec<(Expr, bool)>>,
+        order_by: Option<&Vec<OrderBy>>,
         limit: Option<u64>,
     ) -> Result<(Vec<String>, Vec<Vec<DataValue>>)> {
         if group_by.is_some() {
@@ -1061,24 +3228,71 @@ impl Executor {
         if having.is_some() {
             return Err(SQRLErr::UnsupportedFeature("HAVING".to_string()));
         }
-        if order_by.is_some() {
-            return Err(SQRLErr::UnsupportedFeature("ORDER BY".to_string()));
+
+        if from.is_none() && columns.is_empty() {
+            return Err(SQRLErr::UnsupportedFeature(
+                "SELECT * without FROM".to_string(),
+            ));
+        }
+        // qualified names must name the source; past here they are used bare
+        let mut columns = columns.to_vec();
+        let mut where_clause = where_clause.cloned();
+        let mut order_by = order_by.cloned();
+        Self::unqualify(
+            columns
+                .iter_mut()
+                .chain(&mut where_clause)
+                .chain(order_by.iter_mut().flatten().map(|item| &mut item.expr)),
+            Self::source_qualifier(from),
+        )?;
+        let table = match from {
+            Some(SelectSource::Table { name, .. }) if self.cte_rows(name).is_none() => {
+                Some(self.storage.get_table(name)?)
+            }
+            _ => None,
+        };
+        if let Some(table) = table {
+            Self::apply_collations(&mut where_clause, table);
         }
+        let (columns, where_clause, order_by) =
+            (&columns[..], where_clause.as_ref(), order_by.as_ref());
 
-        let (source_columns, source_rows) = self.load_select_source(from)?;
-        let projections = if columns.is_empty() {
+        let (source_columns, source_rows) =
+            self.load_select_source(from, where_clause)?;
+        let mut projections = if columns.is_empty() {
             source_columns
                 .iter()
                 .map(|name| Expr::Ident(name.clone().into_boxed_str()))
                 .collect::<Vec<_>>()
         } else {
-            columns.to_vec()
+            Self::expand_wildcards(columns, from, &source_columns)?
         };
         let result_columns =
             projections.iter().map(Self::expr_label).collect::<Vec<_>>();
+        if let Some(table) = table {
+            Self::apply_collations(&mut projections, table);
+        }
+        // expanded wildcards name every source column, so only the written
+        // projections are checked
+        Self::check_column_refs(
+            columns.iter().chain(where_clause),
+            &source_columns,
+            &[],
+        )?;
+        if let Some(order_by) = order_by {
+            Self::check_column_refs(
+                order_by.iter().map(|item| &item.expr),
+                &source_columns,
+                &result_columns,
+            )?;
+            for item in order_by {
+                Self::order_position(&item.expr, result_columns.len())?;
+            }
+        }
 
         let mut filtered_rows = Vec::new();
         for row in source_rows {
+            self.check_interrupt()?;
             if self.matches_source_where(&source_columns, &row, where_clause)? {
                 filtered_rows.push(row);
             }
@@ -1098,34 +3312,240 @@ impl Executor {
             return Ok((result_columns, vec![values]));
         }
 
-        let mut result_rows = Vec::new();
+        let order_by = order_by.map(Vec::as_slice).unwrap_or_default();
+        let order_collations = order_by
+            .iter()
+            .map(|item| {
+                Self::order_collation(&item.expr, &result_columns, &projections, table)
+            })
+            .collect::<Vec<_>>();
+        let mut result_rows: Vec<(Vec<DataValue>, Vec<DataValue>)> = Vec::new();
         for row in filtered_rows {
+            self.check_interrupt()?;
             let values = projections
                 .iter()
                 .map(|expr| self.eval_in_source_row(expr, &source_columns, &row))
                 .collect::<Result<Vec<_>>>()?;
 
-            if distinct && result_rows.contains(&values) {
+            if distinct && result_rows.iter().any(|(existing, _)| *existing == values) {
                 continue;
             }
 
-            result_rows.push(values);
-            if let Some(limit) = limit {
-                if result_rows.len() as u64 >= limit {
-                    break;
+            let keys = order_by
+                .iter()
+                .zip(&order_collations)
+                .map(|(item, collation)| {
+                    let key = self.eval_order_key(
+                        &item.expr,
+                        &result_columns,
+                        &values,
+                        &source_columns,
+                        &row,
+                    )?;
+                    Ok(collation.key(key))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            result_rows.push((values, keys));
+            if order_by.is_empty()
+                && let Some(limit) = limit
+                && result_rows.len() as u64 >= limit
+            {
+                break;
+            }
+        }
+
+        if !order_by.is_empty() {
+            Self::sort_rows(&mut result_rows, order_by)?;
+        }
+        if let Some(limit) = limit {
+            result_rows.truncate(limit as usize);
+        }
+
+        Ok((
+            result_columns,
+            result_rows.into_iter().map(|(values, _)| values).collect(),
+        ))
+    }
+
+    /// The name that qualifies the source's columns: its alias, else the
+    /// table name. A subquery without an alias has none.
+    fn source_qualifier(from: Option<&SelectSource>) -> Option<&str> {
+        match from {
+            Some(SelectSource::Table { name, alias }) => {
+                Some(alias.as_ref().unwrap_or(name))
+            }
+            Some(SelectSource::Subquery { alias, .. }) => alias.as_deref(),
+            None => None,
+        }
+    }
+
+    /// Replaces every `<table>.<col>` with `<col>`, after checking that
+    /// `<table>` is the `qualifier` of the statement's only source.
+    fn unqualify<'e>(
+        exprs: impl IntoIterator<Item = &'e mut Expr>,
+        qualifier: Option<&str>,
+    ) -> Result<()> {
+        let mut error = None;
+        for expr in exprs {
+            visit::walk_expr_mut(expr, &mut |expr| {
+                if let Expr::QualifiedIdent(table, column) = expr {
+                    if qualifier == Some(&**table) {
+                        *expr = Expr::Ident(std::mem::take(column));
+                    } else {
+                        error.get_or_insert(SQRLErr::TableNotFound(table.to_string()));
+                    }
+                }
+            });
+        }
+        error.map_or(Ok(()), Err)
+    }
+
+    /// Replaces each `*` in a projection list with the source columns, and
+    /// each `<table>.*` with the columns of the source it names, by alias or
+    /// else by table name.
+    fn expand_wildcards(
+        columns: &[Expr],
+        from: Option<&SelectSource>,
+        source_columns: &[String],
+    ) -> Result<Vec<Expr>> {
+        let qualifier = Self::source_qualifier(from);
+        let mut projections = Vec::with_capacity(columns.len());
+        for expr in columns {
+            match expr {
+                Expr::Wildcard if from.is_none() && source_columns.is_empty() => {
+                    return Err(SQRLErr::UnsupportedFeature(
+                        "SELECT * without FROM".to_string(),
+                    ));
+                }
+                Expr::Wildcard => projections.extend(
+                    source_columns.iter().map(|name| Expr::Ident(name.as_str().into())),
+                ),
+                Expr::QualifiedWildcard(table) => {
+                    if qualifier != Some(&**table) {
+                        return Err(SQRLErr::TableNotFound(table.to_string()));
+                    }
+                    projections.extend(
+                        source_columns
+                            .iter()
+                            .map(|name| Expr::Ident(name.as_str().into())),
+                    );
                 }
+                expr => projections.push(expr.clone()),
             }
         }
+        Ok(projections)
+    }
 
-        Ok((result_columns, result_rows))
+    /// A bare integer ORDER BY key names a result column by its position,
+    /// counting from 1. Any other expression is a value to sort by.
+    fn order_position(expr: &Expr, columns: usize) -> Result<Option<usize>> {
+        match expr {
+            Expr::Int(n) if (1..=columns as i64).contains(n) => {
+                Ok(Some(*n as usize - 1))
+            }
+            Expr::Int(n) => {
+                Err(SQRLErr::OrderByPositionOutOfRange { pos: *n, count: columns })
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Evaluates an ORDER BY key, preferring a result column label over a
+    /// source column of the same name.
+    fn eval_order_key(
+        &self,
+        expr: &Expr,
+        result_columns: &[String],
+        values: &[DataValue],
+        source_columns: &[String],
+        row: &[DataValue],
+    ) -> Result<DataValue> {
+        if let Some(index) = Self::order_position(expr, values.len())? {
+            return Ok(values[index].clone());
+        }
+        if let Expr::Ident(name) = expr
+            && let Some(index) = result_columns.iter().position(|c| **c == **name)
+        {
+            return Ok(values[index].clone());
+        }
+        self.eval_in_source_row(expr, source_columns, row)
+    }
+
+    /// The collation an ORDER BY key sorts by: that of the result column it
+    /// names, else that of the source column it names, else the one written
+    /// on it.
+    fn order_collation(
+        expr: &Expr,
+        result_columns: &[String],
+        projections: &[Expr],
+        table: Option<&TableState>,
+    ) -> Collation {
+        let index = match expr {
+            Expr::Int(_) => {
+                Self::order_position(expr, projections.len()).ok().flatten()
+            }
+            Expr::Ident(name) => result_columns.iter().position(|c| **c == **name),
+            _ => None,
+        };
+        match (index, expr) {
+            (Some(index), _) => Self::collation(&projections[index]),
+            (None, Expr::Ident(name)) => table
+                .and_then(|table| table.get_col_by_name(name))
+                .map_or(Collation::Binary, |col| col.constraints.collation),
+            (None, expr) => Self::collation(expr),
+        }
+    }
+
+    /// Stable-sorts rows by their ORDER BY keys. NULLs go where
+    /// [`OrderBy::nulls_first`] puts them regardless of the direction, and
+    /// TEXT (including VARCHAR) compares byte-wise; NOCASE keys come in
+    /// already case-folded.
+    fn sort_rows(
+        rows: &mut Vec<(Vec<DataValue>, Vec<DataValue>)>,
+        order_by: &[OrderBy],
+    ) -> Result<()> {
+        let compare = |(_, left): &(_, Vec<DataValue>),
+                       (_, right): &(_, Vec<DataValue>)| {
+            for ((left, right), item) in left.iter().zip(right).zip(order_by) {
+                let nulls =
+                    if item.nulls_first() { Ordering::Less } else { Ordering::Greater };
+                let ord = match (left, right) {
+                    (DataValue::Nil, DataValue::Nil) => Ordering::Equal,
+                    (DataValue::Nil, _) => return Ok(nulls),
+                    (_, DataValue::Nil) => return Ok(nulls.reverse()),
+                    _ => Self::compare_values(left, right)?,
+                };
+                match ord {
+                    Ordering::Equal => continue,
+                    ord if item.desc => return Ok(ord.reverse()),
+                    ord => return Ok(ord),
+                }
+            }
+            Ok(Ordering::Equal)
+        };
+        *rows = try_sort_by(std::mem::take(rows), &compare)?;
+        Ok(())
     }
 
     fn run_update(
         &mut self,
         table_name: &str,
-        assigns: Vec<(Box<str>, Expr)>,
-        where_clause: Option<Expr>,
+        mut assigns: Vec<(Box<str>, Expr)>,
+        mut where_clause: Option<Expr>,
+        mut returning: Option<Vec<Expr>>,
     ) -> Result<QueryResult> {
+        Self::unqualify(
+            assigns
+                .iter_mut()
+                .map(|(_, expr)| expr)
+                .chain(&mut where_clause)
+                .chain(returning.iter_mut().flatten()),
+            Some(table_name),
+        )?;
+        Self::apply_collations(
+            assigns.iter_mut().map(|(_, expr)| expr).chain(&mut where_clause),
+            self.storage.get_table(table_name)?,
+        );
         let plans = {
             let table = self.storage.get_table(table_name)?;
             let mut seen = HashSet::new();
@@ -1138,62 +3558,190 @@ impl Executor {
                     let col = table
                         .get_col_by_name(name)
                         .ok_or_else(|| SQRLErr::ColumnNotFound(name.to_string()))?;
-                    Ok((col.id, col.name.clone(), col.data_type))
+                    Ok(col)
                 })
-                .collect::<Result<Vec<(ColId, Box<str>, DataType)>>>()?;
-
-            let mut rows =
-                table.rows.values().filter(|row| row.alive).collect::<Vec<_>>();
-            rows.sort_by_key(|row| row.id.0);
+                .collect::<Result<Vec<&ColState>>>()?;
+            Self::check_column_refs(
+                assigns
+                    .iter()
+                    .map(|(_, expr)| expr)
+                    .chain(&where_clause)
+                    .chain(returning.iter().flatten()),
+                &Self::live_col_names(table),
+                &[],
+            )?;
 
             let mut plans = Vec::new();
-            for row in rows {
+            for row in self.candidate_rows(table, where_clause.as_ref()) {
+                self.check_interrupt()?;
                 if !self.matches_where(table, row, where_clause.as_ref())? {
                     continue;
                 }
                 let mut patches = Vec::with_capacity(assigns.len());
-                for ((_, expr), (col_id, col_name, col_type)) in
-                    assigns.iter().zip(targets.iter())
-                {
+                for ((_, expr), col) in assigns.iter().zip(targets.iter()) {
                     let value = self.eval_in_row(expr, Some(table), Some(row))?;
-                    if value.data_type() != *col_type {
-                        return Err(SQRLErr::TypeMismatch {
-                            column: col_name.to_string(),
-                            expected: *col_type,
-                            got: value.data_type(),
-                        });
-                    }
-                    patches.push((*col_id, value));
+                    let value = Self::coerce_value(&col.name, col.data_type, value)?;
+                    Self::check_not_null(table_name, col, &value)?;
+                    patches.push((col.id, value));
                 }
                 plans.push((row.id, patches));
             }
+
+            for (col_index, col) in targets.iter().enumerate() {
+                let changes = plans
+                    .iter()
+                    .map(|(row_id, patches)| (*row_id, patches[col_index].1.clone()))
+                    .collect::<HashMap<_, _>>();
+                Self::check_unique(table_name, table, col, &changes, &[])?;
+                self.check_referenced_update(table_name, table, col, &changes)?;
+            }
+            let written = plans
+                .iter()
+                .map(|(_, patches)| {
+                    targets
+                        .iter()
+                        .zip(patches)
+                        .map(|(col, (_, value))| ((*col).clone(), value.clone()))
+                        .collect()
+                })
+                .collect::<Vec<_>>();
+            self.check_foreign_keys(table_name, table, &written)?;
+
+            if !table.checks.is_empty() {
+                let live_cols = table.live_cols().collect::<Vec<_>>();
+                let rows = plans
+                    .iter()
+                    .map(|(row_id, patches)| {
+                        let row = &table.rows[row_id];
+                        live_cols
+                            .iter()
+                            .map(|col| {
+                                match patches.iter().find(|(id, _)| *id == col.id) {
+                                    Some((_, value)) => value.clone(),
+                                    None => row.value(col),
+                                }
+                            })
+                            .collect()
+                    })
+                    .collect::<Vec<_>>();
+                self.check_rows(table_name, table, &rows)?;
+            }
             plans
         };
 
-        let count = plans.len();
+        let table_id = self.storage.get_table(table_name)?.id;
+        let mut row_ids = Vec::with_capacity(plans.len());
         for (row_id, patches) in plans {
-            self.storage.update_row(
-                self.storage.get_table(table_name)?.id,
-                row_id,
-                patches,
-            )?;
+            self.storage.update_row(table_id, row_id, patches)?;
+            row_ids.push(row_id);
         }
-        Ok(QueryResult::Count(count))
+        self.write_result(table_id, &row_ids, returning)
+    }
+
+    /// Rejects changing a value that FOREIGN KEY rows still reference.
+    fn check_referenced_update(
+        &self,
+        table_name: &str,
+        table: &TableState,
+        col: &ColState,
+        changes: &HashMap<RowId, DataValue>,
+    ) -> Result<()> {
+        for (child, fk) in self.storage.state.referencing(table.id) {
+            if fk.ref_col_id != col.id {
+                continue;
+            }
+            for (row_id, value) in changes {
+                let Some(row) = table.rows.get(row_id) else {
+                    continue;
+                };
+                let old = row.value(col);
+                if old == DataValue::Nil
+                    || IndexKey(col.index_key(old.clone()))
+                        == IndexKey(col.index_key(value.clone()))
+                {
+                    continue;
+                }
+                if Self::referencing_rows(child, fk, col, &old).next().is_some() {
+                    return Err(Self::foreign_key_failed(table_name, &col.name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the rows a delete removes along with `row_ids`: rows that
+    /// reference them through `ON DELETE CASCADE`, recursively. Fails if
+    /// any other row would be left referencing a deleted one.
+    fn cascade_deletes(
+        &self,
+        table_id: TableId,
+        row_ids: &[RowId],
+    ) -> Result<BTreeSet<(TableId, RowId)>> {
+        let state = &self.storage.state;
+        let mut doomed =
+            row_ids.iter().map(|row_id| (table_id, *row_id)).collect::<BTreeSet<_>>();
+        let mut pending = doomed.iter().copied().collect::<Vec<_>>();
+        let mut restricted = Vec::new();
+        while let Some((table_id, row_id)) = pending.pop() {
+            let Some(table) = state.get_table(&table_id) else {
+                continue;
+            };
+            let Some(row) = table.rows.get(&row_id) else {
+                continue;
+            };
+            for (child, fk) in state.referencing(table_id) {
+                let Some(ref_col) = table.get_col(&fk.ref_col_id) else {
+                    continue;
+                };
+                let key = row.value(ref_col);
+                if key == DataValue::Nil {
+                    continue;
+                }
+                for child_row in Self::referencing_rows(child, fk, ref_col, &key) {
+                    let target = (child.id, child_row.id);
+                    if !fk.on_delete_cascade {
+                        restricted.push((target, child, fk.col_id));
+                    } else if doomed.insert(target) {
+                        pending.push(target);
+                    }
+                }
+            }
+        }
+        // a restricting row is fine if the delete removes it anyway
+        for (target, child, col_id) in restricted {
+            if !doomed.contains(&target) {
+                let col_name = child.get_col(&col_id).map_or("?", |col| &*col.name);
+                return Err(Self::foreign_key_failed(&child.name, col_name));
+            }
+        }
+        Ok(doomed)
     }
 
     fn run_delete(
         &mut self,
         table_name: &str,
-        where_clause: Option<Expr>,
+        mut where_clause: Option<Expr>,
+        mut returning: Option<Vec<Expr>>,
     ) -> Result<QueryResult> {
+        Self::unqualify(
+            where_clause.iter_mut().chain(returning.iter_mut().flatten()),
+            Some(table_name),
+        )?;
+        Self::apply_collations(&mut where_clause, self.storage.get_table(table_name)?);
         let (table_id, row_ids) = {
             let table = self.storage.get_table(table_name)?;
-            let mut rows =
-                table.rows.values().filter(|row| row.alive).collect::<Vec<_>>();
-            rows.sort_by_key(|row| row.id.0);
-            let row_ids = rows
+            Self::check_column_refs(
+                where_clause.iter().chain(returning.iter().flatten()),
+                &Self::live_col_names(table),
+                &[],
+            )?;
+            let row_ids = self
+                .candidate_rows(table, where_clause.as_ref())
                 .into_iter()
                 .filter_map(|row| {
+                    if let Err(err) = self.check_interrupt() {
+                        return Some(Err(err));
+                    }
                     match self.matches_where(table, row, where_clause.as_ref()) {
                         Ok(true) => Some(Ok(row.id)),
                         Ok(false) => None,
@@ -1204,11 +3752,62 @@ impl Executor {
             (table.id, row_ids)
         };
 
-        let count = row_ids.len();
-        for row_id in row_ids {
+        let doomed = self.cascade_deletes(table_id, &row_ids)?;
+        // deleted rows are gone afterwards, so project them first
+        let result = self.write_result(table_id, &row_ids, returning)?;
+        for (table_id, row_id) in doomed {
             self.storage.delete_row(table_id, row_id)?;
         }
-        Ok(QueryResult::Count(count))
+        Ok(result)
+    }
+
+    /// Builds the result of a write: the number of affected rows, or their
+    /// `RETURNING` projection. An empty projection list stands for `*`.
+    fn write_result(
+        &self,
+        table_id: TableId,
+        row_ids: &[RowId],
+        returning: Option<Vec<Expr>>,
+    ) -> Result<QueryResult> {
+        let Some(returning) = returning else {
+            return Ok(QueryResult::Count(row_ids.len()));
+        };
+        let live_cols = self
+            .storage
+            .state
+            .get_table(&table_id)
+            .ok_or(StorageErr::TableNotFound(table_id))?
+            .live_cols()
+            .collect::<Vec<_>>();
+        let source_columns =
+            live_cols.iter().map(|col| col.name.to_string()).collect::<Vec<_>>();
+        let projections = if returning.is_empty() {
+            source_columns
+                .iter()
+                .map(|name| Expr::Ident(name.clone().into_boxed_str()))
+                .collect::<Vec<_>>()
+        } else {
+            Self::expand_wildcards(&returning, None, &source_columns)?
+        };
+
+        let mut rows = Vec::with_capacity(row_ids.len());
+        for row_id in row_ids {
+            let row = self.storage.get_row(table_id, *row_id)?;
+            let source_row = live_cols
+                .iter()
+                .map(|col| Self::read_value(row, col))
+                .collect::<Vec<_>>();
+            rows.push(
+                projections
+                    .iter()
+                    .map(|expr| {
+                        self.eval_in_source_row(expr, &source_columns, &source_row)
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            );
+        }
+        let columns = projections.iter().map(Self::expr_label).collect();
+        Ok(QueryResult::Rows { columns, rows })
     }
 
     fn run_drop(
@@ -1226,3 +3825,26 @@ impl Executor {
         Ok(QueryResult::Success)
     }
 }
+
+/// Stable merge sort whose comparison can fail. Stops at the first error
+/// rather than sorting by an order that is not total.
+fn try_sort_by<T>(
+    mut items: Vec<T>,
+    compare: &impl Fn(&T, &T) -> Result<Ordering>,
+) -> Result<Vec<T>> {
+    if items.len() < 2 {
+        return Ok(items);
+    }
+    let right = items.split_off(items.len() / 2);
+    let mut left = try_sort_by(items, compare)?.into_iter().peekable();
+    let mut right = try_sort_by(right, compare)?.into_iter().peekable();
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        // ties keep the left item first
+        let next =
+            if compare(r, l)? == Ordering::Less { &mut right } else { &mut left };
+        merged.extend(next.next());
+    }
+    merged.extend(left.chain(right));
+    Ok(merged)
+}
@@ -0,0 +1,116 @@
+use super::{Result, SQRLErr};
+use crate::query::visit::walk_stmt_mut;
+use crate::query::{Expr, Param, Stmt};
+use crate::schema::DataValue;
+
+/// A parsed statement whose `?` / `:name` placeholders are bound later.
+///
+/// Every `?` is its own slot, while repeated `:name` placeholders share one
+/// slot. Slots are numbered in order of first appearance.
+#[derive(Debug, Clone)]
+pub struct Prepared {
+    stmt: Stmt,
+    slots: Vec<Param>,
+}
+
+/// A statement with every placeholder replaced by a literal.
+#[derive(Debug, Clone)]
+pub struct BoundStmt {
+    stmt: Stmt,
+}
+
+impl BoundStmt {
+    pub fn stmt(&self) -> &Stmt {
+        &self.stmt
+    }
+
+    pub fn into_stmt(self) -> Stmt {
+        self.stmt
+    }
+}
+
+impl Prepared {
+    pub fn new(mut stmt: Stmt) -> Self {
+        let mut slots = Vec::new();
+        walk_stmt_mut(&mut stmt, &mut |expr| {
+            if let Expr::Param(param) = expr
+                && !slots.contains(param)
+            {
+                slots.push(param.clone());
+            }
+        });
+        Self { stmt, slots }
+    }
+
+    pub fn param_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Binds `values` to the placeholder slots in order.
+    pub fn bind(&self, values: &[DataValue]) -> Result<BoundStmt> {
+        if values.len() != self.slots.len() {
+            return Err(SQRLErr::ParamCountMismatch {
+                expected: self.slots.len(),
+                got: values.len(),
+            });
+        }
+        self.bind_with(|param| {
+            let index = self.slots.iter().position(|slot| slot == param);
+            Ok(values[index.expect("slot collected in Prepared::new")].clone())
+        })
+    }
+
+    /// Binds `:name` placeholders by name. Positional `?` slots cannot be
+    /// bound this way.
+    pub fn bind_named(&self, values: &[(&str, DataValue)]) -> Result<BoundStmt> {
+        if values.len() != self.slots.len() {
+            return Err(SQRLErr::ParamCountMismatch {
+                expected: self.slots.len(),
+                got: values.len(),
+            });
+        }
+        self.bind_with(|param| match param {
+            Param::Named(name) => values
+                .iter()
+                .find(|(key, _)| *key == name.as_ref())
+                .map(|(_, value)| value.clone())
+                .ok_or_else(|| SQRLErr::UnboundParam(format!(":{name}"))),
+            Param::Positional(index) => {
+                Err(SQRLErr::UnboundParam(format!("?{}", index + 1)))
+            }
+        })
+    }
+
+    fn bind_with<F>(&self, mut lookup: F) -> Result<BoundStmt>
+    where
+        F: FnMut(&Param) -> Result<DataValue>,
+    {
+        let mut stmt = self.stmt.clone();
+        let mut result = Ok(());
+        walk_stmt_mut(&mut stmt, &mut |expr| {
+            if let Expr::Param(param) = expr
+                && result.is_ok()
+            {
+                match lookup(param) {
+                    Ok(value) => *expr = literal(value),
+                    Err(e) => result = Err(e),
+                }
+            }
+        });
+        result.map(|_| BoundStmt { stmt })
+    }
+}
+
+pub(super) fn literal(value: DataValue) -> Expr {
+    match value {
+        DataValue::Nil => Expr::Nil,
+        DataValue::Int(value) => Expr::Int(value),
+        DataValue::Real(value) => Expr::Real(value),
+        DataValue::Bool(value) => Expr::Bool(value),
+        DataValue::Text(value) => Expr::Text(value),
+        DataValue::Date(value) => Expr::Date(value),
+        DataValue::Timestamp(value) => Expr::Timestamp(value),
+        DataValue::Decimal(value) => Expr::Decimal(value),
+        DataValue::Blob(value) => Expr::Blob(value),
+    }
+}
@@ -1,4 +1,9 @@
+pub mod database;
 pub mod executor;
 pub mod query;
-mod schema;
+pub mod schema;
 pub mod storage;
+
+#[cfg(feature = "async")]
+pub use database::AsyncDatabase;
+pub use database::{Database, OnError, ResultSet};
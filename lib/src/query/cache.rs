@@ -0,0 +1,95 @@
+use super::error::Result;
+use super::lexer::Lexer;
+use super::parser::{Parser, SpannedStmt};
+use std::collections::HashMap;
+
+/// Least-recently-used cache of parsed statements, keyed on the SQL text.
+///
+/// Running the same text again clones the cached statements instead of
+/// lexing and parsing it. Text that fails to parse is not cached. A cache
+/// with capacity 0 keeps nothing.
+#[derive(Debug, Clone)]
+pub struct StmtCache {
+    capacity: usize,
+    entries: HashMap<Box<str>, Entry>,
+    clock: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    stmts: Vec<SpannedStmt>,
+    last_used: u64,
+}
+
+impl StmtCache {
+    pub const DEFAULT_CAPACITY: usize = 64;
+
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: HashMap::new(), clock: 0 }
+    }
+
+    /// Returns the statements in `src`, parsing it only on a cache miss.
+    pub fn parse(&mut self, src: &str) -> Result<Vec<SpannedStmt>> {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(src) {
+            entry.last_used = self.clock;
+            return Ok(entry.stmts.clone());
+        }
+        let stmts = Parser::new(Lexer::new(src))?.parse()?;
+        if self.capacity > 0 {
+            self.evict(self.capacity - 1);
+            let entry = Entry { stmts: stmts.clone(), last_used: self.clock };
+            self.entries.insert(src.into(), entry);
+        }
+        Ok(stmts)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes the capacity, evicting the least recently used entries that
+    /// no longer fit.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict(capacity);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns whether `src` is cached, without counting as a use.
+    pub fn contains(&self, src: &str) -> bool {
+        self.entries.contains_key(src)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drops the least recently used entries until at most `len` remain.
+    fn evict(&mut self, len: usize) {
+        while self.entries.len() > len {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(src, _)| src.clone());
+            match oldest {
+                Some(src) => self.entries.remove(&src),
+                None => break,
+            };
+        }
+    }
+}
+
+impl Default for StmtCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
@@ -1,9 +1,8 @@
-use thiserror::Error;
 use super::span::Span;
+use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, QueryErr>;
 
-
 #[derive(Debug, Clone, PartialEq, Error)]
 #[error("{kind} at line {}, col {}", span.line, span.col)]
 pub struct QueryErr {
@@ -22,6 +21,12 @@ pub enum QueryErrKind {
     #[error("Unterminated text literal")]
     UnterminatedText,
 
+    #[error("Unterminated quoted identifier")]
+    UnterminatedIdent,
+
+    #[error("Unterminated block comment")]
+    UnterminatedComment,
+
     #[error("Invalid character: '{0}'")]
     InvalidToken(char),
 
@@ -33,4 +38,22 @@ pub enum QueryErrKind {
 
     #[error("Expected {expected}, but found {found}")]
     UnexpectedToken { expected: String, found: String },
+
+    #[error("Unknown type: '{0}'")]
+    UnknownType(String),
+
+    #[error("Invalid {0} literal: '{1}'")]
+    InvalidDate(&'static str, String),
+
+    #[error("Invalid blob literal: X'{0}' (expected pairs of hex digits)")]
+    InvalidBlob(String),
+
+    #[error("Expression nested too deeply (more than {0} levels)")]
+    TooDeep(usize),
+
+    #[error("Query chains too many operators (more than {0})")]
+    TooLongChain(usize),
+
+    #[error("Query too long (more than {0} tokens)")]
+    TooLong(usize),
 }
@@ -1,64 +1,102 @@
 use super::error::{QueryErr, QueryErrKind, Result};
 use super::span::Span;
-use std::collections::VecDeque;
+use std::borrow::Cow;
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct SpannedToken {
-    pub token: Token,
+pub struct SpannedToken<'a> {
+    pub token: Token<'a>,
     pub span: Span,
 }
 
+/// 문자열 토큰은 가능한 한 원본 소스를 빌려 쓴다.
 #[derive(Debug, Clone, PartialEq)]
-pub enum Token {
+pub enum Token<'a> {
     // 특수
     Eof,
+    Comment(&'a str), // -- ..., /* ... */ (주석을 남기는 모드에서만)
     // 리터럴
     Nil,
     Int(i64),
     Real(f64),
     Bool(bool),
-    Text(String),
+    Text(Cow<'a, str>), // 이스케이프가 있을 때만 소유
+    Blob(Vec<u8>),      // X'<hex>'
     // 타입
-    IntType,  // INT, INTEGER
-    RealType, // FLOAT, DOUBLE
-    BoolType, // BOOL, BOOLEAN
-    TextType, // TEXT, STRING, VARCHAR
+    IntType,     // INT, INTEGER
+    RealType,    // REAL, FLOAT, DOUBLE
+    BoolType,    // BOOL, BOOLEAN
+    TextType,    // TEXT, STRING
+    VarCharType, // VARCHAR
+    CharType,    // CHAR, CHARACTER
+    DateType,    // DATE
+    TimeType,    // TIMESTAMP, DATETIME
+    DecimalType, // DECIMAL, NUMERIC
+    BlobType,    // BLOB
     // 식별자
-    Ident(String),
+    Ident(&'a str),
+    // 파라미터
+    Param,               // ?
+    NamedParam(&'a str), // :name
     // 키워드
-    Create,   // CREATE
-    Table,    // TABLE
-    If,       // IF
-    Exists,   // EXISTS
-    Insert,   // INSERT
-    Into,     // INTO
-    Values,   // VALUES
-    Select,   // SELECT
-    Distinct, // DISTINCT
-    As,       // AS
-    From,     // FROM
-    Where,    // WHERE
-    Group,    // GROUP
-    By,       // BY
-    Having,   // HAVING
-    Order,    // ORDER
-    Asc,      // ASC
-    Desc,     // DESC
-    Limit,    // LIMIT
-    Update,   // UPDATE
-    Set,      // SET
-    Alter,    // ALTER
-    Add,      // ADD
-    Column,   // COLUMN
-    Rename,   // RENAME
-    To,       // TO
-    Delete,   // DELETE
-    Truncate, // TRUNCATE
-    Drop,     // DROP
-    Restrict, // RESTRICT
-    Cascade,  // CASCADE
-    Union,    // UNION
-    All,      // ALL
+    Create,        // CREATE
+    Table,         // TABLE
+    If,            // IF
+    Exists,        // EXISTS
+    Insert,        // INSERT
+    Into,          // INTO
+    Values,        // VALUES
+    Select,        // SELECT
+    Distinct,      // DISTINCT
+    As,            // AS
+    Cast,          // CAST
+    From,          // FROM
+    Where,         // WHERE
+    Group,         // GROUP
+    By,            // BY
+    Having,        // HAVING
+    Order,         // ORDER
+    Asc,           // ASC
+    Desc,          // DESC
+    Limit,         // LIMIT
+    Offset,        // OFFSET
+    Update,        // UPDATE
+    Set,           // SET
+    Alter,         // ALTER
+    Add,           // ADD
+    Column,        // COLUMN
+    Rename,        // RENAME
+    To,            // TO
+    Delete,        // DELETE
+    Truncate,      // TRUNCATE
+    Drop,          // DROP
+    Restrict,      // RESTRICT
+    Cascade,       // CASCADE
+    Union,         // UNION
+    Intersect,     // INTERSECT
+    Except,        // EXCEPT
+    All,           // ALL
+    Default,       // DEFAULT
+    Primary,       // PRIMARY
+    Key,           // KEY
+    Unique,        // UNIQUE
+    Autoincrement, // AUTOINCREMENT, AUTO_INCREMENT
+    Foreign,       // FOREIGN
+    References,    // REFERENCES
+    On,            // ON
+    Check,         // CHECK
+    Collate,       // COLLATE
+    Nocase,        // NOCASE
+    Vacuum,        // VACUUM
+    Explain,       // EXPLAIN
+    Begin,         // BEGIN
+    Commit,        // COMMIT
+    Rollback,      // ROLLBACK
+    Transaction,   // TRANSACTION
+    Savepoint,     // SAVEPOINT
+    Release,       // RELEASE
+    Returning,     // RETURNING
+    With,          // WITH
+    Escape,        // ESCAPE
     // 구분자
     Dot,       // .
     Comma,     // ,
@@ -72,12 +110,13 @@ pub enum Token {
     In,       // IN
     Like,     // LIKE
     Between,  // BETWEEN
-    Is,       // I
+    Is,       // IS
     OpEq,     // =
     OpGt,     // >
     OpLt,     // <
     OpGe,     // >=
     OpLe,     // <=
+    OpSafeEq, // <=>
     OpConcat, // ||
     OpAdd,    // +
     OpSub,    // -
@@ -85,44 +124,86 @@ pub enum Token {
     OpDiv,    // /
 }
 
-pub struct Lexer {
-    src: VecDeque<char>,
+/// 방언마다 다르게 읽는 토큰의 규칙.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /// 표준 SQL: `||`는 문자열 연결이고 `"..."`는 식별자다.
+    #[default]
+    Standard,
+    /// MySQL: `||`는 OR이고 `"..."`는 `'...'`처럼 문자열이다.
+    MySql,
+}
+
+pub struct Lexer<'a> {
+    src: &'a str,
+    offset: usize, // 다음 문자의 바이트 오프셋
     span: Span,
+    keep_comments: bool,
+    dialect: Dialect,
 }
 
-impl Lexer {
-    pub fn new(src: &str) -> Self {
-        Self { src: src.chars().collect(), span: Span::default() }
+impl<'a> Lexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            offset: 0,
+            span: Span::default(),
+            keep_comments: false,
+            dialect: Dialect::default(),
+        }
+    }
+
+    /// 방언을 바꾼다. 파서는 렉서가 읽은 토큰만 보므로 파서에도 그대로 적용된다.
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// `true`이면 주석을 버리지 않고 구분자까지 포함해 `Token::Comment`로
+    /// 내보낸다. 파서는 이 토큰을 건너뛰고 [`Parser::comments`]에 모아 둔다.
+    ///
+    /// [`Parser::comments`]: super::Parser::comments
+    pub fn with_comments(mut self, keep_comments: bool) -> Self {
+        self.keep_comments = keep_comments;
+        self
     }
 
-    fn is_letter(ch: char) -> bool {
-        ch.is_alphabetic() || ch == '_'
+    /// 식별자는 유니코드 XID_Start 문자나 `_`로 시작하고 XID_Continue 문자로
+    /// 이어진다 (`名前`, `café`). 숫자로는 시작할 수 없다.
+    fn is_ident_start(ch: char) -> bool {
+        ch == '_' || unicode_ident::is_xid_start(ch)
+    }
+
+    fn is_ident_continue(ch: char) -> bool {
+        unicode_ident::is_xid_continue(ch)
     }
 
     fn is_digit(ch: char) -> bool {
         ch.is_ascii_digit()
     }
 
-    fn finished(&self) -> bool {
-        self.src.is_empty()
+    fn rest(&self) -> &'a str {
+        &self.src[self.offset..]
     }
 
-    fn curr(&self) -> Option<char> {
-        self.src.front().copied()
+    fn finished(&self) -> bool {
+        self.offset == self.src.len()
     }
 
-    fn peek(&self, step: usize) -> String {
-        self.src.iter().take(step).collect()
+    fn curr(&self) -> Option<char> {
+        self.rest().chars().next()
     }
 
     fn walk(&mut self) -> Option<char> {
-        let ch = self.src.pop_front()?;
+        let ch = self.curr()?;
+        self.offset += ch.len_utf8();
         self.span.len += 1;
         Some(ch)
     }
 
     fn skip(&mut self) -> Option<char> {
-        let ch = self.src.pop_front()?;
+        let ch = self.curr()?;
+        self.offset += ch.len_utf8();
         self.span.pos += 1;
         if ch == '\n' {
             self.span.line += 1;
@@ -133,6 +214,20 @@ impl Lexer {
         Some(ch)
     }
 
+    /// `pred`를 만족하는 문자를 계속 읽고, 토큰 시작부터 읽은 부분을 돌려준다.
+    fn walk_while(
+        &mut self,
+        start: usize,
+        mut pred: impl FnMut(char) -> bool,
+    ) -> &'a str {
+        while let Some(ch) = self.curr()
+            && pred(ch)
+        {
+            self.walk();
+        }
+        &self.src[start..self.offset]
+    }
+
     fn skip_ws(&mut self) {
         while let Some(ch) = self.curr()
             && ch.is_whitespace()
@@ -141,6 +236,33 @@ impl Lexer {
         }
     }
 
+    /// 주석 하나를 건너뛴다. 주석이 없으면 `false`를 돌려준다.
+    fn skip_comment(&mut self) -> Result<bool> {
+        if self.rest().starts_with("--") {
+            // 줄바꿈은 주석에 포함하지 않음
+            while let Some(ch) = self.curr()
+                && ch != '\n'
+            {
+                self.skip();
+            }
+            return Ok(true);
+        }
+        if !self.rest().starts_with("/*") {
+            return Ok(false);
+        }
+        let span = self.span;
+        self.skip();
+        self.skip();
+        while !self.rest().starts_with("*/") {
+            if self.skip().is_none() {
+                return Err(QueryErr { kind: QueryErrKind::UnterminatedComment, span });
+            }
+        }
+        self.skip();
+        self.skip();
+        Ok(true)
+    }
+
     fn begin_span(&mut self) {
         self.span.pos += self.span.len;
         self.span.col += self.span.len;
@@ -151,24 +273,27 @@ impl Lexer {
         QueryErr { kind, span: self.span }
     }
 
-    pub fn next(&mut self) -> Result<SpannedToken> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<SpannedToken<'a>> {
         self.begin_span();
         loop {
             self.skip_ws();
-            if self.peek(2) == "--" {
-                self.skip();
-                self.skip();
-                while let Some(ch) = self.skip()
-                    && ch != '\n'
-                {}
-                continue;
+            let (start, span) = (self.offset, self.span);
+            if !self.skip_comment()? {
+                break;
+            }
+            if self.keep_comments {
+                // 주석은 skip()으로 읽었으므로 self.span에는 길이를 더하지 않음
+                let text = &self.src[start..self.offset];
+                let span = Span { len: text.chars().count(), ..span };
+                return Ok(SpannedToken { token: Token::Comment(text), span });
             }
-            break;
         }
         // 렉싱이 성공적으로 끝난 경우
         if self.finished() {
             return Ok(SpannedToken { token: Token::Eof, span: self.span });
         }
+        let start = self.offset;
         let ch = self.walk().ok_or_else(|| self.err(QueryErrKind::UnexpectedEof))?;
         let token = match ch {
             '.' => Token::Dot,
@@ -188,39 +313,59 @@ impl Lexer {
             '<' => {
                 if self.curr() == Some('=') {
                     self.walk();
-                    Token::OpLe
+                    if self.curr() == Some('>') {
+                        self.walk();
+                        Token::OpSafeEq
+                    } else {
+                        Token::OpLe
+                    }
                 } else {
                     Token::OpLt
                 }
             }
-            '|' => {
-                if self.curr() == Some('|') {
-                    self.walk();
-                    Token::OpConcat
-                } else {
-                    return Err(self.err(QueryErrKind::InvalidToken(ch)));
+            '|' if self.curr() == Some('|') => {
+                self.walk();
+                match self.dialect {
+                    Dialect::Standard => Token::OpConcat,
+                    Dialect::MySql => Token::Or,
                 }
             }
+            '?' => Token::Param,
+            ':' if self.curr().is_some_and(Self::is_ident_start) => {
+                let name = self.walk_while(self.offset, Self::is_ident_continue);
+                Token::NamedParam(name)
+            }
             '+' => Token::OpAdd,
             '-' => Token::OpSub,
             '*' => Token::OpMul,
             '/' => Token::OpDiv,
+            '"' if self.dialect == Dialect::Standard => self.lex_quoted_ident()?,
             '\'' | '"' => self.lex_text(ch)?,
-            _ if Self::is_digit(ch) => self.lex_num(ch)?,
-            _ if Self::is_letter(ch) => self.lex_keyword(ch)?,
+            'x' | 'X' if self.curr() == Some('\'') => self.lex_blob()?,
+            _ if Self::is_digit(ch) => self.lex_num(start)?,
+            _ if Self::is_ident_start(ch) => self.lex_keyword(start),
             _ => return Err(self.err(QueryErrKind::InvalidToken(ch))),
         };
         Ok(SpannedToken { token, span: self.span })
     }
 
-    fn lex_text(&mut self, quote: char) -> Result<Token> {
-        let mut out = String::new();
+    fn lex_text(&mut self, quote: char) -> Result<Token<'a>> {
+        let src = self.src;
+        let start = self.offset;
+        // 이스케이프를 만나기 전까지는 원본을 빌려 쓴다
+        let mut owned: Option<String> = None;
         while let Some(ch) = self.walk() {
             if ch == quote {
-                return Ok(Token::Text(out));
+                let text = match owned {
+                    Some(out) => Cow::Owned(out),
+                    None => Cow::Borrowed(&src[start..self.offset - 1]),
+                };
+                return Ok(Token::Text(text));
             } else if ch == '\n' {
                 return Err(self.err(QueryErrKind::UnterminatedText));
             } else if ch == '\\' {
+                let out = owned
+                    .get_or_insert_with(|| src[start..self.offset - 1].to_string());
                 let esc = self
                     .walk()
                     .ok_or_else(|| self.err(QueryErrKind::UnterminatedText))?;
@@ -236,64 +381,93 @@ impl Lexer {
                         out.push(esc);
                     }
                 }
-            } else {
+            } else if let Some(out) = &mut owned {
                 out.push(ch);
             }
         }
         Err(self.err(QueryErrKind::UnterminatedText))
     }
 
-    fn lex_num(&mut self, start: char) -> Result<Token> {
+    /// 큰따옴표로 감싼 식별자는 키워드와 이름이 같아도 식별자다 (`"order"`).
+    /// 큰따옴표와 줄바꿈은 담을 수 없다.
+    fn lex_quoted_ident(&mut self) -> Result<Token<'a>> {
+        let name = self.walk_while(self.offset, |ch| ch != '"' && ch != '\n');
+        if self.walk() != Some('"') {
+            return Err(self.err(QueryErrKind::UnterminatedIdent));
+        }
+        if name.is_empty() {
+            return Err(self.err(QueryErrKind::InvalidToken('"')));
+        }
+        Ok(Token::Ident(name))
+    }
+
+    fn lex_blob(&mut self) -> Result<Token<'a>> {
+        // 여는 따옴표는 `next()`에서 확인함
+        self.walk();
+        let hex = self.walk_while(self.offset, |ch| ch != '\'' && ch != '\n');
+        if self.walk() != Some('\'') {
+            return Err(self.err(QueryErrKind::UnterminatedText));
+        }
+        if !hex.len().is_multiple_of(2) || !hex.chars().all(|ch| ch.is_ascii_hexdigit())
+        {
+            return Err(self.err(QueryErrKind::InvalidBlob(hex.to_string())));
+        }
+        // ! 16진수 숫자만 있는 것을 확인했으므로 안전함
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        Ok(Token::Blob(bytes))
+    }
+
+    fn lex_num(&mut self, start: usize) -> Result<Token<'a>> {
         let mut float = false;
-        let mut out = String::from(start);
-        while let Some(ch) = self.curr() {
-            // ! `curr()`의 반환값이 `Some`이므로 안전함
-            if Self::is_digit(ch) {
-                out.push(self.walk().unwrap());
-            } else if ch == '.' && !float {
+        let out = self.walk_while(start, |ch| {
+            if ch == '.' && !float {
                 float = true;
-                out.push(self.walk().unwrap());
-            } else {
-                break;
-            }
-        }
-        if out.is_empty() {
-            Err(self.err(QueryErrKind::InvalidNum(out)))
-        } else if float {
-            if out.ends_with('.') {
-                out.push('0');
+                return true;
             }
-            Ok(Token::Real(
-                out.parse::<f64>()
-                    .map_err(|_| self.err(QueryErrKind::InvalidNum(out)))?,
-            ))
+            Self::is_digit(ch)
+        });
+        let invalid = || self.err(QueryErrKind::InvalidNum(out.to_string()));
+        // `1.`처럼 소수부가 없어도 f64 파싱은 성공함
+        if float {
+            Ok(Token::Real(out.parse::<f64>().map_err(|_| invalid())?))
         } else {
-            Ok(Token::Int(
-                out.parse::<i64>()
-                    .map_err(|_| self.err(QueryErrKind::InvalidNum(out)))?,
-            ))
+            Ok(Token::Int(out.parse::<i64>().map_err(|_| invalid())?))
         }
     }
 
-    fn lex_keyword(&mut self, start: char) -> Result<Token> {
-        let mut out = String::from(start);
-        while let Some(ch) = self.curr()
-            && (Self::is_letter(ch) || Self::is_digit(ch))
-        {
-            // ! `curr()`의 반환값이 `Some`이므로 안전함
-            out.push(self.walk().unwrap());
-        }
+    fn lex_keyword(&mut self, start: usize) -> Token<'a> {
+        let word = self.walk_while(start, Self::is_ident_continue);
+        // 키워드는 모두 ASCII이므로 스택 버퍼에서 대문자로 바꿔 비교함
+        let mut buf = [0u8; 16];
+        let upper = match buf.get_mut(..word.len()) {
+            Some(buf) if word.is_ascii() => {
+                buf.copy_from_slice(word.as_bytes());
+                buf.make_ascii_uppercase();
+                // ! ASCII 바이트만 있으므로 안전함
+                std::str::from_utf8(buf).unwrap()
+            }
+            _ => "",
+        };
         // 키워드 매칭
-        Ok(match out.to_uppercase().as_str() {
+        match upper {
             // 리터럴
             "NULL" => Token::Nil,
             "TRUE" => Token::Bool(true),
             "FALSE" => Token::Bool(false),
             // 타입
             "INT" | "INTEGER" => Token::IntType,
-            "FLOAT" | "DOUBLE" => Token::RealType,
+            "REAL" | "FLOAT" | "DOUBLE" => Token::RealType,
             "BOOL" | "BOOLEAN" => Token::BoolType,
-            "TEXT" | "STRING" | "VARCHAR" => Token::TextType,
+            "TEXT" | "STRING" => Token::TextType,
+            "VARCHAR" => Token::VarCharType,
+            "CHAR" | "CHARACTER" => Token::CharType,
+            "DATE" => Token::DateType,
+            "TIMESTAMP" | "DATETIME" => Token::TimeType,
+            "DECIMAL" | "NUMERIC" => Token::DecimalType,
+            "BLOB" => Token::BlobType,
             // 키워드
             "CREATE" => Token::Create,
             "TABLE" => Token::Table,
@@ -305,6 +479,7 @@ impl Lexer {
             "SELECT" => Token::Select,
             "DISTINCT" => Token::Distinct,
             "AS" => Token::As,
+            "CAST" => Token::Cast,
             "FROM" => Token::From,
             "WHERE" => Token::Where,
             "GROUP" => Token::Group,
@@ -314,6 +489,7 @@ impl Lexer {
             "ASC" => Token::Asc,
             "DESC" => Token::Desc,
             "LIMIT" => Token::Limit,
+            "OFFSET" => Token::Offset,
             "UPDATE" => Token::Update,
             "SET" => Token::Set,
             "ALTER" => Token::Alter,
@@ -327,7 +503,31 @@ impl Lexer {
             "RESTRICT" => Token::Restrict,
             "CASCADE" => Token::Cascade,
             "UNION" => Token::Union,
+            "INTERSECT" => Token::Intersect,
+            "EXCEPT" => Token::Except,
             "ALL" => Token::All,
+            "DEFAULT" => Token::Default,
+            "PRIMARY" => Token::Primary,
+            "KEY" => Token::Key,
+            "UNIQUE" => Token::Unique,
+            "AUTOINCREMENT" | "AUTO_INCREMENT" => Token::Autoincrement,
+            "FOREIGN" => Token::Foreign,
+            "REFERENCES" => Token::References,
+            "ON" => Token::On,
+            "CHECK" => Token::Check,
+            "COLLATE" => Token::Collate,
+            "NOCASE" => Token::Nocase,
+            "VACUUM" => Token::Vacuum,
+            "EXPLAIN" => Token::Explain,
+            "BEGIN" => Token::Begin,
+            "COMMIT" => Token::Commit,
+            "ROLLBACK" => Token::Rollback,
+            "TRANSACTION" => Token::Transaction,
+            "SAVEPOINT" => Token::Savepoint,
+            "RELEASE" => Token::Release,
+            "RETURNING" => Token::Returning,
+            "WITH" => Token::With,
+            "ESCAPE" => Token::Escape,
             // 연산자
             "NOT" => Token::Not,
             "AND" => Token::And,
@@ -336,7 +536,23 @@ impl Lexer {
             "LIKE" => Token::Like,
             "BETWEEN" => Token::Between,
             "IS" => Token::Is,
-            _ => Token::Ident(out),
-        })
+            _ => Token::Ident(word),
+        }
+    }
+}
+
+/// `src`를 끝까지 렉싱해 마지막 `Token::Eof`까지 모든 토큰을 돌려준다.
+/// 주석은 건너뛰며, 도중에 렉싱 오류가 나면 그 오류를 그대로 돌려준다.
+/// 구문 강조처럼 파싱 없이 토큰만 필요한 곳에서 쓴다.
+pub fn tokenize(src: &str) -> Result<Vec<Token<'_>>> {
+    let mut lexer = Lexer::new(src);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next()?.token;
+        let eof = token == Token::Eof;
+        tokens.push(token);
+        if eof {
+            return Ok(tokens);
+        }
     }
 }
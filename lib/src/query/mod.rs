@@ -1,9 +1,16 @@
+pub mod cache;
 pub mod error;
 pub mod lexer;
 pub mod parser;
 pub mod span;
+pub mod visit;
 
+pub use cache::StmtCache;
 pub use error::QueryErr;
-pub use lexer::{Lexer, SpannedToken};
-pub use parser::{Expr, Parser, SelectSource, Stmt};
+pub use lexer::{Dialect, Lexer, SpannedToken, tokenize};
+pub use parser::{
+    ColumnConstraint, ColumnDef, Expr, ForeignKeyDef, OrderBy, Param, Parser,
+    SelectSource, SetOp, Stmt, quote_ident,
+};
 pub use span::Span;
+pub use visit::{Visitor, collect_idents};
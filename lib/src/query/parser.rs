@@ -1,7 +1,11 @@
 use super::error::{QueryErr, QueryErrKind, Result};
-use super::lexer::{Lexer, SpannedToken, Token};
+use super::lexer::{Lexer, SpannedToken, Token, tokenize};
 use super::span::Span;
-use crate::schema::DataType;
+use crate::schema::{
+    Collation, ColumnType, Decimal, format_date, format_timestamp, parse_date,
+    parse_timestamp,
+};
+use std::borrow::Cow;
 use std::mem::{discriminant, replace};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -13,48 +17,67 @@ pub struct SpannedStmt {
 #[derive(Debug, Clone, PartialEq)]
 #[non_exhaustive]
 pub enum Stmt {
-    // CREATE TABLE [IF NOT EXISTS] <table> (<col1> <type>, <col2> <type>, ...)
+    // CREATE TABLE [IF NOT EXISTS] <table> (<col1> <type>, <col2> <type>, ...
+    //     [, FOREIGN KEY ...] [, CHECK (<expr>)])
     Create {
-        table_name: Box<str>,               // table name
-        defines: Vec<(Box<str>, DataType)>, // col name, col type
-        if_not_exists: bool,                // run if not exists
+        table_name: Box<str>,             // table name
+        defines: Vec<ColumnDef>,          // col definitions
+        foreign_keys: Vec<ForeignKeyDef>, // table constraints
+        checks: Vec<Expr>,                // table CHECK predicates
+        if_not_exists: bool,              // run if not exists
     },
     // INSERT INTO <table> [(<col1>, <col2>, ...)] VALUES (<val1>, <val2>, ...)
+    //     [, (...)] [RETURNING]
     InsertValues {
-        table_name: Box<str>,   // table name
-        columns: Vec<Box<str>>, // col name
-        values: Vec<Expr>,      // val expr
+        table_name: Box<str>,         // table name
+        columns: Vec<Box<str>>,       // col name
+        values: Vec<Vec<Expr>>,       // val exprs of each row
+        returning: Option<Vec<Expr>>, // col name (or expr)
     },
     InsertSelect {
-        table_name: Box<str>,   // target table name
-        columns: Vec<Box<str>>, // target column names
-        select: Box<Stmt>,      // source SELECT statement
+        table_name: Box<str>,         // target table name
+        columns: Vec<Box<str>>,       // target column names
+        select: Box<Stmt>,            // source SELECT statement
+        returning: Option<Vec<Expr>>, // col name (or expr)
     },
-    // SELECT [DISTINCT] <col1>, <col2>, ... FROM <source>
-    //     [WHERE] [GROUP BY] [HAVING] [ORDER BY] [LIMIT]
+    // SELECT [DISTINCT] <col1>, <col2>, ... [FROM <source>]
+    //     [WHERE] [GROUP BY] [HAVING] [ORDER BY] [LIMIT] [OFFSET]
     Select {
-        from: SelectSource,                  // table or subquery source
-        columns: Vec<Expr>,                  // col name (or expr)
-        distinct: bool,                      // distinct flag
-        where_clause: Option<Expr>,          // condition expr
-        group_by: Option<Vec<Expr>>,         // col name (or expr)
-        having: Option<Expr>,                // condition expr
-        order_by: Option<Vec<(Expr, bool)>>, // col name, ASC/DESC
-        limit: Option<u64>,                  // limit count
+        from: Option<SelectSource>,     // table or subquery source
+        columns: Vec<Expr>,             // col name (or expr)
+        distinct: bool,                 // distinct flag
+        where_clause: Option<Expr>,     // condition expr
+        group_by: Option<Vec<Expr>>,    // col name (or expr)
+        having: Option<Expr>,           // condition expr
+        order_by: Option<Vec<OrderBy>>, // sort keys
+        limit: Option<Expr>,            // max row count
+        offset: Option<Expr>,           // rows skipped first
+    },
+    // <select> {UNION | INTERSECT | EXCEPT} [ALL] <select> [ORDER BY] [LIMIT] [OFFSET]
+    SetOp {
+        left: Box<Stmt>,                // left query
+        right: Box<Stmt>,               // right query
+        op: SetOp,                      // how the rows combine
+        all: bool,                      // keep duplicates
+        order_by: Option<Vec<OrderBy>>, // sort keys of the combined rows
+        limit: Option<Expr>,            // max row count
+        offset: Option<Expr>,           // rows skipped first
     },
-    UnionAll {
-        left: Box<Stmt>,
-        right: Box<Stmt>,
+    // WITH <name> AS (<query>), ... <query>
+    With {
+        ctes: Vec<(Box<str>, Stmt)>, // named queries, in order
+        body: Box<Stmt>,             // query that may read them as tables
     },
-    // UPDATE <table> SET <col1> = <val1>, <col2> = <val2>, ... [WHERE]
+    // UPDATE <table> SET <col1> = <val1>, <col2> = <val2>, ... [WHERE] [RETURNING]
     Update {
         table_name: Box<str>,           // table name
         assigns: Vec<(Box<str>, Expr)>, // col name, val expr
         where_clause: Option<Expr>,     // condition expr
+        returning: Option<Vec<Expr>>,   // col name (or expr)
     },
     AlterAdd {
-        table_name: Box<str>,         // table name
-        define: (Box<str>, DataType), // col name, col type
+        table_name: Box<str>, // table name
+        define: ColumnDef,    // col definition
     },
     AlterDrop {
         table_name: Box<str>, // table name
@@ -64,10 +87,16 @@ pub enum Stmt {
         table_name: Box<str>, // table name
         new_name: Box<str>,   // new table name
     },
-    // DELETE FROM <table> [WHERE]
+    AlterRestart {
+        table_name: Box<str>, // table name
+        column: Box<str>,     // AUTOINCREMENT col name
+        value: i64,           // next value handed out
+    },
+    // DELETE FROM <table> [WHERE] [RETURNING]
     Delete {
-        table_name: Box<str>,       // table name
-        where_clause: Option<Expr>, // condition expr
+        table_name: Box<str>,         // table name
+        where_clause: Option<Expr>,   // condition expr
+        returning: Option<Vec<Expr>>, // col name (or expr)
     },
     // TRUNCATE TABLE <table>
     Truncate {
@@ -79,12 +108,392 @@ pub enum Stmt {
         if_exists: bool,      // run if exists
         cascade: bool,        // run despite dependent
     },
+    // VACUUM
+    Vacuum,
+    // EXPLAIN <select>
+    Explain(Box<Stmt>),
+    // BEGIN [TRANSACTION]
+    Begin,
+    // COMMIT [TRANSACTION]
+    Commit,
+    // ROLLBACK [TRANSACTION]
+    Rollback,
+    // SAVEPOINT <name>
+    Savepoint {
+        name: Box<str>, // savepoint name
+    },
+    // ROLLBACK [TRANSACTION] TO [SAVEPOINT] <name>
+    RollbackTo {
+        name: Box<str>, // savepoint name
+    },
+    // RELEASE [SAVEPOINT] <name>
+    Release {
+        name: Box<str>, // savepoint name
+    },
 }
 
 impl Stmt {
     pub fn boxed(self) -> Box<Self> {
         Box::new(self)
     }
+
+    /// Renders the statement as canonical SQL that parses back to the same
+    /// tree: keywords in upper case, optional words left out.
+    pub fn to_sql(&self) -> String {
+        let list = |exprs: &[Expr]| {
+            exprs.iter().map(Expr::to_sql).collect::<Vec<_>>().join(", ")
+        };
+        let names = |names: &[Box<str>]| match names {
+            [] => String::new(),
+            names => {
+                let names =
+                    names.iter().map(|name| quote_ident(name)).collect::<Vec<_>>();
+                format!(" ({})", names.join(", "))
+            }
+        };
+        let clause = |keyword: &str, expr: &Option<Expr>| match expr {
+            Some(expr) => format!(" {keyword} {}", expr.to_sql()),
+            None => String::new(),
+        };
+        let returning = |exprs: &Option<Vec<Expr>>| match exprs.as_deref() {
+            Some([]) => " RETURNING *".to_string(),
+            Some(exprs) => format!(" RETURNING {}", list(exprs)),
+            None => String::new(),
+        };
+        match self {
+            Stmt::Create {
+                table_name,
+                defines,
+                foreign_keys,
+                checks,
+                if_not_exists,
+            } => {
+                let items = defines
+                    .iter()
+                    .map(ColumnDef::to_sql)
+                    .chain(foreign_keys.iter().map(ForeignKeyDef::to_sql))
+                    .chain(
+                        checks.iter().map(|expr| format!("CHECK ({})", expr.to_sql())),
+                    )
+                    .collect::<Vec<_>>();
+                format!(
+                    "CREATE TABLE {}{} ({})",
+                    if *if_not_exists { "IF NOT EXISTS " } else { "" },
+                    quote_ident(table_name),
+                    items.join(", ")
+                )
+            }
+            Stmt::InsertValues { table_name, columns, values, returning: ret } => {
+                let rows = values
+                    .iter()
+                    .map(|row| format!("({})", list(row)))
+                    .collect::<Vec<_>>();
+                format!(
+                    "INSERT INTO {}{} VALUES {}{}",
+                    quote_ident(table_name),
+                    names(columns),
+                    rows.join(", "),
+                    returning(ret)
+                )
+            }
+            Stmt::InsertSelect { table_name, columns, select, returning: ret } => {
+                format!(
+                    "INSERT INTO {}{} {}{}",
+                    quote_ident(table_name),
+                    names(columns),
+                    select.to_sql(),
+                    returning(ret)
+                )
+            }
+            Stmt::Select {
+                from,
+                columns,
+                distinct,
+                where_clause,
+                group_by,
+                having,
+                order_by,
+                limit,
+                offset,
+            } => {
+                let mut sql = "SELECT ".to_string();
+                if *distinct {
+                    sql.push_str("DISTINCT ");
+                }
+                if columns.is_empty() {
+                    sql.push('*');
+                } else {
+                    sql.push_str(&list(columns));
+                }
+                if let Some(from) = from {
+                    sql.push_str(" FROM ");
+                    sql.push_str(&from.to_sql());
+                }
+                sql.push_str(&clause("WHERE", where_clause));
+                if let Some(group_by) = group_by {
+                    sql.push_str(" GROUP BY ");
+                    sql.push_str(&list(group_by));
+                }
+                sql.push_str(&clause("HAVING", having));
+                if let Some(order_by) = order_by {
+                    let items =
+                        order_by.iter().map(OrderBy::to_sql).collect::<Vec<_>>();
+                    sql.push_str(" ORDER BY ");
+                    sql.push_str(&items.join(", "));
+                }
+                sql.push_str(&clause("LIMIT", limit));
+                sql.push_str(&clause("OFFSET", offset));
+                sql
+            }
+            Stmt::SetOp { left, right, op, all, order_by, limit, offset } => {
+                // operands that sort on their own, and operations that would
+                // otherwise regroup, only keep their meaning in parentheses
+                let operand = |stmt: &Stmt, right: bool| match stmt {
+                    Stmt::Select { order_by, limit, offset, .. }
+                    | Stmt::SetOp { order_by, limit, offset, .. }
+                        if order_by.is_some()
+                            || limit.is_some()
+                            || offset.is_some() =>
+                    {
+                        format!("({})", stmt.to_sql())
+                    }
+                    Stmt::With { .. } => format!("({})", stmt.to_sql()),
+                    Stmt::SetOp { op: inner, .. }
+                        if inner.precedence() < op.precedence()
+                            || right && inner.precedence() == op.precedence() =>
+                    {
+                        format!("({})", stmt.to_sql())
+                    }
+                    _ => stmt.to_sql(),
+                };
+                let mut sql = format!(
+                    "{} {} {}{}",
+                    operand(left, false),
+                    op.keyword(),
+                    if *all { "ALL " } else { "" },
+                    operand(right, true)
+                );
+                if let Some(order_by) = order_by {
+                    let items =
+                        order_by.iter().map(OrderBy::to_sql).collect::<Vec<_>>();
+                    sql.push_str(" ORDER BY ");
+                    sql.push_str(&items.join(", "));
+                }
+                sql.push_str(&clause("LIMIT", limit));
+                sql.push_str(&clause("OFFSET", offset));
+                sql
+            }
+            Stmt::With { ctes, body } => {
+                let ctes = ctes
+                    .iter()
+                    .map(|(name, query)| {
+                        format!("{} AS ({})", quote_ident(name), query.to_sql())
+                    })
+                    .collect::<Vec<_>>();
+                format!("WITH {} {}", ctes.join(", "), body.to_sql())
+            }
+            Stmt::Update { table_name, assigns, where_clause, returning: ret } => {
+                let assigns = assigns
+                    .iter()
+                    .map(|(col, expr)| {
+                        format!("{} = {}", quote_ident(col), expr.to_sql())
+                    })
+                    .collect::<Vec<_>>();
+                format!(
+                    "UPDATE {} SET {}{}{}",
+                    quote_ident(table_name),
+                    assigns.join(", "),
+                    clause("WHERE", where_clause),
+                    returning(ret)
+                )
+            }
+            Stmt::AlterAdd { table_name, define } => format!(
+                "ALTER TABLE {} ADD COLUMN {}",
+                quote_ident(table_name),
+                define.to_sql()
+            ),
+            Stmt::AlterDrop { table_name, column } => format!(
+                "ALTER TABLE {} DROP COLUMN {}",
+                quote_ident(table_name),
+                quote_ident(column)
+            ),
+            Stmt::AlterRename { table_name, new_name } => format!(
+                "ALTER TABLE {} RENAME TO {}",
+                quote_ident(table_name),
+                quote_ident(new_name)
+            ),
+            Stmt::AlterRestart { table_name, column, value } => format!(
+                "ALTER TABLE {} ALTER COLUMN {} RESTART WITH {value}",
+                quote_ident(table_name),
+                quote_ident(column)
+            ),
+            Stmt::Delete { table_name, where_clause, returning: ret } => format!(
+                "DELETE FROM {}{}{}",
+                quote_ident(table_name),
+                clause("WHERE", where_clause),
+                returning(ret)
+            ),
+            Stmt::Truncate { table_name } => {
+                format!("TRUNCATE TABLE {}", quote_ident(table_name))
+            }
+            Stmt::Drop { table_name, if_exists, cascade } => format!(
+                "DROP TABLE {}{}{}",
+                if *if_exists { "IF EXISTS " } else { "" },
+                quote_ident(table_name),
+                if *cascade { " CASCADE" } else { "" }
+            ),
+            Stmt::Vacuum => "VACUUM".to_string(),
+            Stmt::Explain(stmt) => format!("EXPLAIN {}", stmt.to_sql()),
+            Stmt::Begin => "BEGIN".to_string(),
+            Stmt::Commit => "COMMIT".to_string(),
+            Stmt::Rollback => "ROLLBACK".to_string(),
+            Stmt::Savepoint { name } => format!("SAVEPOINT {}", quote_ident(name)),
+            Stmt::RollbackTo { name } => format!("ROLLBACK TO {}", quote_ident(name)),
+            Stmt::Release { name } => format!("RELEASE {}", quote_ident(name)),
+        }
+    }
+}
+
+/// Writes `name` so that it reads back as the same identifier: bare when it
+/// lexes as one, otherwise in double quotes, e.g. keywords or names with
+/// spaces.
+pub fn quote_ident(name: &str) -> Cow<'_, str> {
+    match tokenize(name).as_deref() {
+        Ok([Token::Ident(ident), Token::Eof]) if *ident == name => Cow::Borrowed(name),
+        _ => Cow::Owned(format!("\"{name}\"")),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOp {
+    Union,
+    Intersect,
+    Except,
+}
+
+impl SetOp {
+    fn from_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::Union => Some(SetOp::Union),
+            Token::Intersect => Some(SetOp::Intersect),
+            Token::Except => Some(SetOp::Except),
+            _ => None,
+        }
+    }
+
+    /// INTERSECT binds tighter than UNION and EXCEPT, as in standard SQL.
+    pub fn precedence(self) -> u8 {
+        match self {
+            SetOp::Union | SetOp::Except => 1,
+            SetOp::Intersect => 2,
+        }
+    }
+
+    pub fn keyword(self) -> &'static str {
+        match self {
+            SetOp::Union => "UNION",
+            SetOp::Intersect => "INTERSECT",
+            SetOp::Except => "EXCEPT",
+        }
+    }
+}
+
+// {<expr> | <position>} [ASC | DESC] [NULLS FIRST | NULLS LAST]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    pub expr: Expr,                // col name (or expr)
+    pub desc: bool,                // DESC flag
+    pub nulls_first: Option<bool>, // explicit NULLS FIRST / LAST
+}
+
+impl OrderBy {
+    /// Whether NULL keys sort before the others. Without NULLS FIRST or
+    /// NULLS LAST, NULL counts as the smallest value: first when ascending,
+    /// last when descending.
+    pub fn nulls_first(&self) -> bool {
+        self.nulls_first.unwrap_or(!self.desc)
+    }
+
+    pub fn to_sql(&self) -> String {
+        let mut sql = self.expr.to_sql();
+        if self.desc {
+            sql.push_str(" DESC");
+        }
+        match self.nulls_first {
+            Some(true) => sql.push_str(" NULLS FIRST"),
+            Some(false) => sql.push_str(" NULLS LAST"),
+            None => {}
+        }
+        sql
+    }
+}
+
+// <col> <type> [<constraint> ...]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnDef {
+    pub name: Box<str>,                     // col name
+    pub data_type: ColumnType,              // col type
+    pub constraints: Vec<ColumnConstraint>, // col constraints
+}
+
+// FOREIGN KEY (<col>) REFERENCES <table>(<col>) [ON DELETE CASCADE | RESTRICT]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignKeyDef {
+    pub column: Box<str>,        // referencing col name
+    pub ref_table: Box<str>,     // referenced table name
+    pub ref_column: Box<str>,    // referenced col name
+    pub on_delete_cascade: bool, // delete referencing rows too
+}
+
+impl ColumnDef {
+    pub fn to_sql(&self) -> String {
+        let mut sql = format!("{} {}", quote_ident(&self.name), self.data_type);
+        for constraint in &self.constraints {
+            sql.push(' ');
+            sql.push_str(&constraint.to_sql());
+        }
+        sql
+    }
+}
+
+impl ForeignKeyDef {
+    pub fn to_sql(&self) -> String {
+        format!(
+            "FOREIGN KEY ({}) REFERENCES {}({}){}",
+            quote_ident(&self.column),
+            quote_ident(&self.ref_table),
+            quote_ident(&self.ref_column),
+            if self.on_delete_cascade { " ON DELETE CASCADE" } else { "" }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ColumnConstraint {
+    Null,               // NULL
+    NotNull,            // NOT NULL
+    Default(Expr),      // DEFAULT <expr>
+    PrimaryKey,         // PRIMARY KEY
+    Unique,             // UNIQUE
+    Autoincrement,      // AUTOINCREMENT
+    Check(Expr),        // CHECK (<expr>)
+    Collate(Collation), // COLLATE {NOCASE | BINARY}
+}
+
+impl ColumnConstraint {
+    pub fn to_sql(&self) -> String {
+        match self {
+            ColumnConstraint::Null => "NULL".to_string(),
+            ColumnConstraint::NotNull => "NOT NULL".to_string(),
+            ColumnConstraint::Default(expr) => format!("DEFAULT {}", expr.to_sql()),
+            ColumnConstraint::PrimaryKey => "PRIMARY KEY".to_string(),
+            ColumnConstraint::Unique => "UNIQUE".to_string(),
+            ColumnConstraint::Autoincrement => "AUTOINCREMENT".to_string(),
+            ColumnConstraint::Check(expr) => format!("CHECK ({})", expr.to_sql()),
+            ColumnConstraint::Collate(collation) => format!("COLLATE {collation}"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -94,6 +503,23 @@ pub enum SelectSource {
     Subquery { query: Box<Stmt>, alias: Option<Box<str>> },
 }
 
+impl SelectSource {
+    pub fn to_sql(&self) -> String {
+        let (source, alias) = match self {
+            SelectSource::Table { name, alias } => {
+                (quote_ident(name).into_owned(), alias)
+            }
+            SelectSource::Subquery { query, alias } => {
+                (format!("({})", query.to_sql()), alias)
+            }
+        };
+        match alias {
+            Some(alias) => format!("{source} AS {}", quote_ident(alias)),
+            None => source,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 #[non_exhaustive]
 pub enum Expr {
@@ -102,40 +528,223 @@ pub enum Expr {
     Real(f64),
     Bool(bool),
     Text(Box<str>),
+    Date(i32),      // DATE '<YYYY-MM-DD>'
+    Timestamp(i64), // TIMESTAMP '<YYYY-MM-DD HH:MM:SS>'
+    Decimal(Decimal),
+    Blob(Box<[u8]>), // X'<hex>'
     Ident(Box<str>),
+    Param(Param),
     Wildcard,
+    QualifiedIdent(Box<str>, Box<str>), // <table>.<col>
+    QualifiedWildcard(Box<str>),        // <table>.*
     List(Vec<Expr>),
-    Call { name: Box<str>, args: Vec<Expr> },
+    Call { name: Box<str>, args: Vec<Expr>, distinct: bool },
+    Cast { expr: Box<Expr>, ty: ColumnType }, // CAST(<expr> AS <type>)
     Alias { expr: Box<Expr>, alias: Box<str> },
-    Unary { op: Token, right: Box<Expr> },
-    Binary { op: Token, left: Box<Expr>, right: Box<Expr> },
+    Collate { expr: Box<Expr>, collation: Collation }, // <expr> COLLATE <collation>
+    Like { expr: Box<Expr>, pattern: Box<Expr>, escape: char }, // <expr> LIKE <pattern> [ESCAPE '<c>']
+    Unary { op: Token<'static>, right: Box<Expr> },
+    Binary { op: Token<'static>, left: Box<Expr>, right: Box<Expr> },
 }
 
 impl Expr {
     pub fn boxed(self) -> Box<Self> {
         Box::new(self)
     }
+
+    /// Renders the expression as SQL that parses back to the same tree.
+    /// Nested operators are parenthesized rather than relying on precedence.
+    pub fn to_sql(&self) -> String {
+        let nested = |expr: &Expr| match expr {
+            Expr::Unary { .. } | Expr::Binary { .. } | Expr::Like { .. } => {
+                format!("({})", expr.to_sql())
+            }
+            _ => expr.to_sql(),
+        };
+        let list = |exprs: &[Expr]| {
+            exprs.iter().map(Expr::to_sql).collect::<Vec<_>>().join(", ")
+        };
+        match self {
+            Expr::Nil => "NULL".to_string(),
+            Expr::Int(value) => value.to_string(),
+            Expr::Real(value) => {
+                let text = value.to_string();
+                if text.contains('.') { text } else { format!("{text}.0") }
+            }
+            Expr::Bool(value) => value.to_string().to_uppercase(),
+            Expr::Text(text) => {
+                let mut sql = String::from('\'');
+                for ch in text.chars() {
+                    match ch {
+                        '\\' => sql.push_str("\\\\"),
+                        '\'' => sql.push_str("\\'"),
+                        // the lexer rejects raw line breaks inside literals
+                        '\n' => sql.push_str("\\n"),
+                        '\r' => sql.push_str("\\r"),
+                        '\t' => sql.push_str("\\t"),
+                        _ => sql.push(ch),
+                    }
+                }
+                sql.push('\'');
+                sql
+            }
+            Expr::Date(days) => format!("DATE '{}'", format_date(*days)),
+            Expr::Timestamp(secs) => format!("TIMESTAMP '{}'", format_timestamp(*secs)),
+            Expr::Decimal(value) => value.to_string(),
+            Expr::Blob(bytes) => {
+                format!(
+                    "X'{}'",
+                    bytes.iter().map(|b| format!("{b:02X}")).collect::<String>()
+                )
+            }
+            Expr::Ident(name) => quote_ident(name).into_owned(),
+            Expr::Param(Param::Positional(_)) => "?".to_string(),
+            Expr::Param(Param::Named(name)) => format!(":{name}"),
+            Expr::Wildcard => "*".to_string(),
+            Expr::QualifiedIdent(table, column) => {
+                format!("{}.{}", quote_ident(table), quote_ident(column))
+            }
+            Expr::QualifiedWildcard(table) => format!("{}.*", quote_ident(table)),
+            Expr::List(exprs) => format!("({})", list(exprs)),
+            Expr::Call { name, args, distinct } => {
+                format!(
+                    "{name}({}{})",
+                    if *distinct { "DISTINCT " } else { "" },
+                    list(args)
+                )
+            }
+            Expr::Cast { expr, ty } => format!("CAST({} AS {ty})", expr.to_sql()),
+            Expr::Alias { expr, alias } => {
+                format!("{} AS {}", expr.to_sql(), quote_ident(alias))
+            }
+            Expr::Collate { expr, collation } => {
+                format!("{} COLLATE {collation}", nested(expr))
+            }
+            Expr::Like { expr, pattern, escape } => {
+                let mut sql = format!("{} LIKE {}", nested(expr), nested(pattern));
+                if *escape != '\\' {
+                    let escape = Expr::Text(escape.to_string().into());
+                    sql.push_str(&format!(" ESCAPE {}", escape.to_sql()));
+                }
+                sql
+            }
+            Expr::Unary { op: Token::Not, right } => format!("NOT {}", nested(right)),
+            Expr::Unary { right, .. } => format!("-{}", nested(right)),
+            Expr::Binary { op, left, right } => {
+                let op = match op {
+                    Token::And => "AND",
+                    Token::Or => "OR",
+                    Token::In => "IN",
+                    Token::OpEq => "=",
+                    Token::OpGt => ">",
+                    Token::OpLt => "<",
+                    Token::OpGe => ">=",
+                    Token::OpLe => "<=",
+                    Token::OpSafeEq => "<=>",
+                    Token::OpConcat => "||",
+                    Token::OpAdd => "+",
+                    Token::OpSub => "-",
+                    Token::OpMul => "*",
+                    Token::OpDiv => "/",
+                    // the parser only builds the operators above
+                    _ => return format!("{self:?}"),
+                };
+                format!("{} {op} {}", nested(left), nested(right))
+            }
+        }
+    }
 }
 
-pub struct Parser {
-    lexer: Lexer,
-    curr: SpannedToken,
-    peek: SpannedToken,
+#[derive(Debug, Clone, PartialEq)]
+pub enum Param {
+    Positional(usize), // ? (0-based, in order of appearance)
+    Named(Box<str>),   // :name
 }
 
-impl Parser {
-    pub fn new(mut lexer: Lexer) -> Result<Self> {
-        let curr = lexer.next()?;
-        let peek = lexer.next()?;
-        Ok(Self { lexer, curr, peek })
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
+    curr: SpannedToken<'a>,
+    peek: SpannedToken<'a>,
+    comments: Vec<SpannedToken<'a>>,
+    params: usize,
+    depth: usize,
+    max_depth: usize,
+    chain: usize,
+    max_chain: usize,
+    tokens: usize,
+    max_tokens: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// How deeply parentheses, prefix operators, calls and subqueries may
+    /// nest by default. Each level costs a few stack frames, so this stays
+    /// well inside a 2 MiB thread stack.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+    /// How many binary operators may chain together by default, as in
+    /// `a = 1 OR a = 2 OR ...`. Parsing and evaluating a chain does not
+    /// recurse, but cloning and dropping the tree does, one cheap frame per
+    /// operator. Set operations such as `UNION` chain the same way and count
+    /// two each, as their frames are larger.
+    pub const DEFAULT_MAX_CHAIN: usize = 1000;
+    /// How many tokens the whole input may have by default.
+    pub const DEFAULT_MAX_TOKENS: usize = 1_000_000;
+
+    pub fn new(mut lexer: Lexer<'a>) -> Result<Self> {
+        let mut comments = Vec::new();
+        let curr = Self::lex(&mut lexer, &mut comments)?;
+        let peek = Self::lex(&mut lexer, &mut comments)?;
+        Ok(Self {
+            lexer,
+            curr,
+            peek,
+            comments,
+            params: 0,
+            depth: 0,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            chain: 0,
+            max_chain: Self::DEFAULT_MAX_CHAIN,
+            tokens: 2,
+            max_tokens: Self::DEFAULT_MAX_TOKENS,
+        })
+    }
+
+    /// Sets how deeply expressions and subqueries may nest before parsing
+    /// fails, e.g. `Parser::new(lexer)?.with_max_depth(32)`.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
     }
 
+    /// Sets how many binary operators may chain together before parsing
+    /// fails.
+    pub fn with_max_chain(mut self, max_chain: usize) -> Self {
+        self.max_chain = max_chain;
+        self
+    }
+
+    /// Sets how many tokens the input may have before parsing fails.
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Binding strength of binary operators, loosest first. Prefix NOT sits
+    /// between AND and the comparisons, so `NOT a = 1` is `NOT (a = 1)`.
+    const COMPARISON: u8 = 4;
+
     fn precedence(token: &Token) -> u8 {
         match token {
             Token::Or => 1,
             Token::And => 2,
-            Token::OpEq | Token::In => 3,
-            Token::OpGt | Token::OpLt | Token::OpGe | Token::OpLe => 4,
+            Token::OpEq
+            | Token::In
+            | Token::Like
+            | Token::OpGt
+            | Token::OpLt
+            | Token::OpGe
+            | Token::OpLe
+            | Token::OpSafeEq
+            | Token::Is => Self::COMPARISON,
             Token::OpAdd | Token::OpSub | Token::OpConcat => 5,
             Token::OpMul | Token::OpDiv => 6,
             Token::LParen => 7,
@@ -143,8 +752,76 @@ impl Parser {
         }
     }
 
-    fn next(&mut self) -> Result<SpannedToken> {
-        Ok(replace(&mut self.curr, replace(&mut self.peek, self.lexer.next()?)))
+    /// Copies an operator token out of the source so it can live in the AST.
+    fn operator(token: &Token) -> Option<Token<'static>> {
+        Some(match token {
+            Token::Not => Token::Not,
+            Token::Or => Token::Or,
+            Token::And => Token::And,
+            Token::OpEq => Token::OpEq,
+            Token::In => Token::In,
+            Token::OpGt => Token::OpGt,
+            Token::OpLt => Token::OpLt,
+            Token::OpGe => Token::OpGe,
+            Token::OpLe => Token::OpLe,
+            Token::OpSafeEq => Token::OpSafeEq,
+            Token::OpAdd => Token::OpAdd,
+            Token::OpSub => Token::OpSub,
+            Token::OpConcat => Token::OpConcat,
+            Token::OpMul => Token::OpMul,
+            Token::OpDiv => Token::OpDiv,
+            Token::LParen => Token::LParen,
+            _ => return None,
+        })
+    }
+
+    /// Comments read so far, in source order, when the lexer keeps them (see
+    /// [`Lexer::with_comments`]). The parser reads a token ahead, so this
+    /// may include a comment just past the last parsed statement.
+    pub fn comments(&self) -> &[SpannedToken<'a>] {
+        &self.comments
+    }
+
+    /// The next token that is not a comment.
+    fn lex(
+        lexer: &mut Lexer<'a>,
+        comments: &mut Vec<SpannedToken<'a>>,
+    ) -> Result<SpannedToken<'a>> {
+        loop {
+            let token = lexer.next()?;
+            if !matches!(token.token, Token::Comment(_)) {
+                return Ok(token);
+            }
+            comments.push(token);
+        }
+    }
+
+    fn next(&mut self) -> Result<SpannedToken<'a>> {
+        let token = Self::lex(&mut self.lexer, &mut self.comments)?;
+        if token.token != Token::Eof {
+            self.tokens += 1;
+            if self.tokens > self.max_tokens {
+                return Err(QueryErr {
+                    kind: QueryErrKind::TooLong(self.max_tokens),
+                    span: token.span,
+                });
+            }
+        }
+        Ok(replace(&mut self.curr, replace(&mut self.peek, token)))
+    }
+
+    /// Goes one nesting level deeper, failing past the depth limit instead of
+    /// letting the parser, or later the executor, overflow the stack. Callers
+    /// restore `depth` once the nested part is parsed.
+    fn descend(&mut self) -> Result<()> {
+        if self.depth >= self.max_depth {
+            return Err(QueryErr {
+                kind: QueryErrKind::TooDeep(self.max_depth),
+                span: self.curr.span,
+            });
+        }
+        self.depth += 1;
+        Ok(())
     }
 
     fn expect(&mut self, tokens: &[Token]) -> Result<()> {
@@ -170,7 +847,18 @@ impl Parser {
         } else if discriminant(&self.curr.token) != discriminant(&tokens[0]) {
             Ok(false)
         } else {
-            self.expect(&tokens).map(|_| true)
+            self.expect(tokens).map(|_| true)
+        }
+    }
+
+    /// Consumes an identifier spelled `word`, ignoring case.
+    fn maybe_word(&mut self, word: &str) -> Result<bool> {
+        match self.curr.token {
+            Token::Ident(ident) if ident.eq_ignore_ascii_case(word) => {
+                self.next()?;
+                Ok(true)
+            }
+            _ => Ok(false),
         }
     }
 
@@ -182,22 +870,40 @@ impl Parser {
                 continue;
             }
             let span = self.curr.span;
+            self.params = 0;
             let stmt = self.parse_stmt()?;
             stmts.push(SpannedStmt { stmt, span });
         }
         Ok(stmts)
     }
 
+    /// Parses a single expression that makes up the whole input.
+    pub fn parse_expression(&mut self) -> Result<Expr> {
+        let expr = self.parse_expr(0)?;
+        self.expect(&[Token::Eof])?;
+        Ok(expr)
+    }
+
     pub fn parse_stmt(&mut self) -> Result<Stmt> {
         match &self.curr.token {
             Token::Create => self.parse_create(),
             Token::Insert => self.parse_insert(),
-            Token::Select => self.parse_select(),
+            Token::Select | Token::LParen | Token::With => self.parse_select_query(),
             Token::Update => self.parse_update(),
             Token::Alter => self.parse_alter(),
             Token::Delete => self.parse_delete(),
             Token::Truncate => self.parse_truncate(),
             Token::Drop => self.parse_drop(),
+            Token::Vacuum => {
+                self.next()?;
+                Ok(Stmt::Vacuum)
+            }
+            Token::Explain => {
+                self.next()?;
+                Ok(Stmt::Explain(self.parse_select_query()?.boxed()))
+            }
+            Token::Begin | Token::Commit | Token::Rollback => self.parse_txn(),
+            Token::Savepoint | Token::Release => self.parse_savepoint(),
             tok => Err(QueryErr {
                 kind: QueryErrKind::UnexpectedToken {
                     expected: "SELECT, INSERT, UPDATE, DELETE, CREATE, DROP".into(),
@@ -213,12 +919,93 @@ impl Parser {
         self.expect(&[Token::Create, Token::Table])?;
         let if_not_exists = self.maybe(&[Token::If, Token::Not, Token::Exists])?;
         let table = self.consume_ident()?;
-        let columns = self.parse_list_clause(true, |p| {
-            let col_name = p.consume_ident()?;
-            let col_type = p.consume_type()?;
-            Ok((col_name, col_type))
+        let mut foreign_keys = Vec::new();
+        let mut checks = Vec::new();
+        let mut columns = Vec::new();
+        self.parse_list_clause(true, |p| {
+            if p.curr.token == Token::Foreign {
+                foreign_keys.push(p.parse_foreign_key()?);
+            } else if p.curr.token == Token::Check {
+                checks.push(p.parse_check()?);
+            } else {
+                columns.push(p.parse_column_def()?);
+            }
+            Ok(())
         })?;
-        Ok(Stmt::Create { table_name: table, defines: columns, if_not_exists })
+        Ok(Stmt::Create {
+            table_name: table,
+            defines: columns,
+            foreign_keys,
+            checks,
+            if_not_exists,
+        })
+    }
+
+    fn parse_check(&mut self) -> Result<Expr> {
+        // CHECK (<expr>)
+        self.expect(&[Token::Check, Token::LParen])?;
+        let expr = self.parse_expr(0)?;
+        self.expect(&[Token::RParen])?;
+        Ok(expr)
+    }
+
+    fn parse_foreign_key(&mut self) -> Result<ForeignKeyDef> {
+        // FOREIGN KEY (<col>) REFERENCES <table>(<col>)
+        //     [ON DELETE CASCADE | ON DELETE RESTRICT]
+        self.expect(&[Token::Foreign, Token::Key, Token::LParen])?;
+        let column = self.consume_ident()?;
+        self.expect(&[Token::RParen, Token::References])?;
+        let ref_table = self.consume_ident()?;
+        self.expect(&[Token::LParen])?;
+        let ref_column = self.consume_ident()?;
+        self.expect(&[Token::RParen])?;
+        let mut on_delete_cascade = false;
+        if self.maybe(&[Token::On, Token::Delete])? {
+            on_delete_cascade = !self.maybe(&[Token::Restrict])?;
+            if on_delete_cascade {
+                self.expect(&[Token::Cascade])?;
+            }
+        }
+        Ok(ForeignKeyDef { column, ref_table, ref_column, on_delete_cascade })
+    }
+
+    fn parse_column_def(&mut self) -> Result<ColumnDef> {
+        // <col_name> <col_type>
+        //     [NULL | NOT NULL | DEFAULT <expr> | PRIMARY KEY | UNIQUE
+        //      | AUTOINCREMENT | CHECK (<expr>) | COLLATE <collation>] ...
+        let name = self.consume_ident()?;
+        let data_type = self.parse_column_type()?;
+        let mut constraints = Vec::new();
+        loop {
+            let constraint = if self.maybe(&[Token::Nil])? {
+                ColumnConstraint::Null
+            } else if self.maybe(&[Token::Not, Token::Nil])? {
+                ColumnConstraint::NotNull
+            } else if self.maybe(&[Token::Default])? {
+                match self.parse_expr(0)? {
+                    // `DEFAULT 'x' COLLATE NOCASE` collates the column
+                    Expr::Collate { expr, collation } => {
+                        constraints.push(ColumnConstraint::Default(*expr));
+                        ColumnConstraint::Collate(collation)
+                    }
+                    expr => ColumnConstraint::Default(expr),
+                }
+            } else if self.maybe(&[Token::Primary, Token::Key])? {
+                ColumnConstraint::PrimaryKey
+            } else if self.maybe(&[Token::Unique])? {
+                ColumnConstraint::Unique
+            } else if self.maybe(&[Token::Autoincrement])? {
+                ColumnConstraint::Autoincrement
+            } else if self.curr.token == Token::Check {
+                ColumnConstraint::Check(self.parse_check()?)
+            } else if self.maybe(&[Token::Collate])? {
+                ColumnConstraint::Collate(self.parse_collation()?)
+            } else {
+                break;
+            };
+            constraints.push(constraint);
+        }
+        Ok(ColumnDef { name, data_type, constraints })
     }
 
     fn parse_insert(&mut self) -> Result<Stmt> {
@@ -232,7 +1019,7 @@ impl Parser {
         };
         if self.maybe(&[Token::Values])? {
             self.parse_insert_values(table, columns)
-        } else if self.maybe(&[Token::Select])? {
+        } else if matches!(self.curr.token, Token::Select | Token::With) {
             self.parse_insert_select(table, columns)
         } else {
             Err(QueryErr {
@@ -250,9 +1037,13 @@ impl Parser {
         table: Box<str>,
         columns: Vec<Box<str>>,
     ) -> Result<Stmt> {
-        // ... VALUES (<val1>, <val2>, ...)
-        let values = self.parse_list_clause(true, |p| p.parse_expr(0))?;
-        Ok(Stmt::InsertValues { table_name: table, columns, values })
+        // ... VALUES (<val1>, <val2>, ...) [, (...)] [RETURNING]
+        let mut values = vec![self.parse_list_clause(true, |p| p.parse_expr(0))?];
+        while self.maybe(&[Token::Comma])? {
+            values.push(self.parse_list_clause(true, |p| p.parse_expr(0))?);
+        }
+        let returning = self.parse_returning_clause()?;
+        Ok(Stmt::InsertValues { table_name: table, columns, values, returning })
     }
 
     fn parse_insert_select(
@@ -261,39 +1052,146 @@ impl Parser {
         columns: Vec<Box<str>>,
     ) -> Result<Stmt> {
         let select = self.parse_select_query()?.boxed();
-        Ok(Stmt::InsertSelect { table_name: table, columns, select })
+        let returning = self.parse_returning_clause()?;
+        Ok(Stmt::InsertSelect { table_name: table, columns, select, returning })
+    }
+
+    fn parse_select_query(&mut self) -> Result<Stmt> {
+        // [WITH ...] <operand> [{UNION | INTERSECT | EXCEPT} [ALL] <operand> ...]
+        //     [ORDER BY] [LIMIT <expr>] [OFFSET <expr>]
+        let (depth, chain) = (self.depth, self.chain);
+        if self.curr.token == Token::With {
+            let stmt = self.parse_with();
+            (self.depth, self.chain) = (depth, chain);
+            return stmt;
+        }
+        let stmt = self.parse_set_op(1);
+        (self.depth, self.chain) = (depth, chain);
+        let mut stmt = stmt?;
+        // a trailing ORDER BY sorts the whole result, not the last operand
+        if let Some(items) = self.parse_order_by_clause()?
+            && let Stmt::Select { order_by, .. } | Stmt::SetOp { order_by, .. } =
+                &mut stmt
+        {
+            *order_by = Some(items);
+        }
+        // evaluated when the query runs, so they may be parameters
+        if let Stmt::Select { limit, offset, .. } | Stmt::SetOp { limit, offset, .. } =
+            &mut stmt
+        {
+            if self.maybe(&[Token::Limit])? {
+                *limit = Some(self.parse_expr(0)?);
+            }
+            if self.maybe(&[Token::Offset])? {
+                *offset = Some(self.parse_expr(0)?);
+            }
+        }
+        if SetOp::from_token(&self.curr.token).is_some() {
+            return Err(QueryErr {
+                kind: QueryErrKind::UnexpectedToken {
+                    expected: "end of query (to sort one side of a set operation, \
+                               put it in parentheses)"
+                        .into(),
+                    found: format!("{:?}", self.curr.token),
+                },
+                span: self.curr.span,
+            });
+        }
+        Ok(stmt)
     }
 
-    fn parse_select(&mut self) -> Result<Stmt> {
-        // SELECT [DISTINCT] <col1>, <col2>, ... FROM <source>
-        //     [WHERE] [GROUP BY] [HAVING] [ORDER BY] [LIMIT]
-        self.expect(&[Token::Select])?;
-        self.parse_select_query()
+    fn parse_with(&mut self) -> Result<Stmt> {
+        // WITH <name> AS (<query>), ... <query>
+        self.descend()?;
+        self.expect(&[Token::With])?;
+        let ctes = self.parse_list_clause(false, |p| {
+            let name = p.consume_ident()?;
+            p.expect(&[Token::As, Token::LParen])?;
+            let query = p.parse_select_query()?;
+            p.expect(&[Token::RParen])?;
+            Ok((name, query))
+        })?;
+        let body = self.parse_select_query()?.boxed();
+        Ok(Stmt::With { ctes, body })
     }
 
-    fn parse_select_query(&mut self) -> Result<Stmt> {
-        let mut stmt = self.parse_select_core()?;
-        while self.maybe(&[Token::Union])? {
-            self.expect(&[Token::All, Token::Select])?;
-            let right = self.parse_select_core()?;
-            stmt = Stmt::UnionAll { left: stmt.boxed(), right: right.boxed() };
+    // operators of the same precedence associate to the left
+    fn parse_set_op(&mut self, prec: u8) -> Result<Stmt> {
+        self.descend()?;
+        let depth = self.depth;
+        let mut stmt = self.parse_set_op_operand(prec)?;
+        while let Some(op) = SetOp::from_token(&self.curr.token)
+            && op.precedence() == prec
+        {
+            // like `a + b + c`, operands nest to the left without recursing,
+            // so they only count against the chain limit
+            if self.chain + 2 > self.max_chain {
+                return Err(QueryErr {
+                    kind: QueryErrKind::TooLongChain(self.max_chain),
+                    span: self.curr.span,
+                });
+            }
+            self.chain += 2;
+            self.depth = depth;
+            self.next()?;
+            let all = self.maybe(&[Token::All])?;
+            let right = self.parse_set_op_operand(prec)?;
+            stmt = Stmt::SetOp {
+                left: stmt.boxed(),
+                right: right.boxed(),
+                op,
+                all,
+                order_by: None,
+                limit: None,
+                offset: None,
+            };
         }
         Ok(stmt)
     }
 
+    fn parse_set_op_operand(&mut self, prec: u8) -> Result<Stmt> {
+        if prec < SetOp::Intersect.precedence() {
+            self.parse_set_op(prec + 1)
+        } else {
+            self.parse_select_operand()
+        }
+    }
+
+    fn parse_select_operand(&mut self) -> Result<Stmt> {
+        // SELECT ... | (<query>), where the query may sort on its own
+        if self.maybe(&[Token::LParen])? {
+            let stmt = self.parse_select_query()?;
+            self.expect(&[Token::RParen])?;
+            Ok(stmt)
+        } else {
+            self.expect(&[Token::Select])?;
+            self.parse_select_core()
+        }
+    }
+
     fn parse_select_core(&mut self) -> Result<Stmt> {
+        // SELECT [DISTINCT] <col1>, <col2>, ... [FROM <source>]
+        //     [WHERE] [GROUP BY] [HAVING]
         let distinct = self.maybe(&[Token::Distinct])?;
-        let columns = if !self.maybe(&[Token::OpMul])? {
-            self.parse_list_clause(false, |p| p.parse_select_expr())?
+        let columns = self.parse_select_list()?;
+        let from = if self.curr.token == Token::From {
+            Some(self.parse_select_from()?)
         } else {
-            vec![]
+            None
         };
-        let from = self.parse_select_from()?;
         let where_clause = self.parse_where_clause()?;
-        let group_by = None;
-        let having = None;
+        // GROUP BY <expr>, ... where a key may be qualified: `t.city`
+        let group_by = if self.maybe(&[Token::Group, Token::By])? {
+            Some(self.parse_list_clause(false, |p| p.parse_expr(0))?)
+        } else {
+            None
+        };
+        let having = if self.maybe(&[Token::Having])? {
+            Some(self.parse_expr(0)?)
+        } else {
+            None
+        };
         let order_by = None;
-        let limit = None;
         Ok(Stmt::Select {
             from,
             distinct,
@@ -302,14 +1200,62 @@ impl Parser {
             group_by,
             having,
             order_by,
-            limit,
+            limit: None,
+            offset: None,
         })
     }
 
+    fn parse_order_by_clause(&mut self) -> Result<Option<Vec<OrderBy>>> {
+        // ORDER BY <expr> [ASC | DESC] [NULLS FIRST | NULLS LAST], ...
+        if !self.maybe(&[Token::Order, Token::By])? {
+            return Ok(None);
+        }
+        let items = self.parse_list_clause(false, |p| {
+            let expr = p.parse_expr(0)?;
+            let desc = p.maybe(&[Token::Desc])?;
+            if !desc {
+                p.maybe(&[Token::Asc])?;
+            }
+            let nulls_first = p.parse_nulls_order()?;
+            // `ORDER BY a FOO` would otherwise fail as the start of the next
+            // statement
+            if matches!(p.curr.token, Token::Ident(_) | Token::Asc | Token::Desc) {
+                return Err(QueryErr {
+                    kind: QueryErrKind::UnexpectedToken {
+                        expected: "ASC, DESC, NULLS FIRST, NULLS LAST or ','".into(),
+                        found: format!("{:?}", p.curr.token),
+                    },
+                    span: p.curr.span,
+                });
+            }
+            Ok(OrderBy { expr, desc, nulls_first })
+        })?;
+        Ok(Some(items))
+    }
+
+    fn parse_nulls_order(&mut self) -> Result<Option<bool>> {
+        // NULLS, FIRST and LAST are not reserved, so columns may use the names
+        if !self.maybe_word("NULLS")? {
+            return Ok(None);
+        }
+        if self.maybe_word("FIRST")? {
+            Ok(Some(true))
+        } else if self.maybe_word("LAST")? {
+            Ok(Some(false))
+        } else {
+            Err(QueryErr {
+                kind: QueryErrKind::UnexpectedToken {
+                    expected: "FIRST or LAST".into(),
+                    found: format!("{:?}", self.curr.token),
+                },
+                span: self.curr.span,
+            })
+        }
+    }
+
     fn parse_select_from(&mut self) -> Result<SelectSource> {
         self.expect(&[Token::From])?;
         if self.maybe(&[Token::LParen])? {
-            self.expect(&[Token::Select])?;
             let query = self.parse_select_query()?.boxed();
             self.expect(&[Token::RParen])?;
             let alias = self.parse_source_alias()?;
@@ -322,16 +1268,25 @@ impl Parser {
     }
 
     fn parse_source_alias(&mut self) -> Result<Option<Box<str>>> {
-        if self.maybe(&[Token::As])? {
-            Ok(Some(self.consume_ident()?))
-        } else if matches!(self.curr.token, Token::Ident(_)) {
+        if self.maybe(&[Token::As])? || matches!(self.curr.token, Token::Ident(_)) {
             Ok(Some(self.consume_ident()?))
         } else {
             Ok(None)
         }
     }
 
+    // <item>, ... where an item may be *; a lone * is stored as no items
+    fn parse_select_list(&mut self) -> Result<Vec<Expr>> {
+        let items = self.parse_list_clause(false, |p| p.parse_select_expr())?;
+        Ok(if items == [Expr::Wildcard] { Vec::new() } else { items })
+    }
+
     fn parse_select_expr(&mut self) -> Result<Expr> {
+        // no expression starts with *, so here it names all columns; after an
+        // operand it is multiplication
+        if self.maybe(&[Token::OpMul])? {
+            return Ok(Expr::Wildcard);
+        }
         let expr = self.parse_expr(0)?;
         if self.maybe(&[Token::As])? {
             let alias = self.consume_ident()?;
@@ -343,6 +1298,7 @@ impl Parser {
 
     fn parse_update(&mut self) -> Result<Stmt> {
         // UPDATE <table> SET <col1> = <val1>, <col2> = <val2>, ... [WHERE]
+        //     [RETURNING]
         self.expect(&[Token::Update])?;
         let table = self.consume_ident()?;
         self.expect(&[Token::Set])?;
@@ -353,7 +1309,8 @@ impl Parser {
             Ok((col_name, val_expr))
         })?;
         let where_clause = self.parse_where_clause()?;
-        Ok(Stmt::Update { table_name: table, assigns, where_clause })
+        let returning = self.parse_returning_clause()?;
+        Ok(Stmt::Update { table_name: table, assigns, where_clause, returning })
     }
 
     fn parse_alter(&mut self) -> Result<Stmt> {
@@ -366,10 +1323,12 @@ impl Parser {
             self.parse_alter_drop(table)
         } else if self.maybe(&[Token::Rename, Token::To])? {
             self.parse_alter_rename(table)
+        } else if self.maybe(&[Token::Alter, Token::Column])? {
+            self.parse_alter_restart(table)
         } else {
             Err(QueryErr {
                 kind: QueryErrKind::UnexpectedToken {
-                    expected: "ADD, DROP, or RENAME".into(),
+                    expected: "ADD, DROP, RENAME, or ALTER".into(),
                     found: format!("{:?}", self.curr.token),
                 },
                 span: self.curr.span,
@@ -377,10 +1336,8 @@ impl Parser {
         }
     }
     fn parse_alter_add(&mut self, table: Box<str>) -> Result<Stmt> {
-        // ... ADD COLUMN <col_name> <col_type>
-        let col_name = self.consume_ident()?;
-        let col_type = self.consume_type()?;
-        let column = (col_name, col_type);
+        // ... ADD COLUMN <col_name> <col_type> [<constraint> ...]
+        let column = self.parse_column_def()?;
         Ok(Stmt::AlterAdd { table_name: table, define: column })
     }
 
@@ -396,12 +1353,48 @@ impl Parser {
         Ok(Stmt::AlterRename { table_name: table, new_name })
     }
 
+    fn parse_alter_restart(&mut self, table: Box<str>) -> Result<Stmt> {
+        // ... ALTER COLUMN <col_name> RESTART WITH <n>
+        // RESTART is not reserved, so columns may use the name
+        let column = self.consume_ident()?;
+        if !self.maybe_word("RESTART")? {
+            return Err(QueryErr {
+                kind: QueryErrKind::UnexpectedToken {
+                    expected: "RESTART".into(),
+                    found: format!("{:?}", self.curr.token),
+                },
+                span: self.curr.span,
+            });
+        }
+        self.expect(&[Token::With])?;
+        let spanned = self.next()?;
+        let Token::Int(value) = spanned.token else {
+            return Err(QueryErr {
+                kind: QueryErrKind::UnexpectedToken {
+                    expected: "sequence value".into(),
+                    found: format!("{:?}", spanned.token),
+                },
+                span: spanned.span,
+            });
+        };
+        Ok(Stmt::AlterRestart { table_name: table, column, value })
+    }
+
     fn parse_delete(&mut self) -> Result<Stmt> {
-        // DELETE FROM <table> [WHERE]
+        // DELETE FROM <table> [WHERE] [RETURNING]
         self.expect(&[Token::Delete, Token::From])?;
         let table = self.consume_ident()?;
         let where_clause = self.parse_where_clause()?;
-        Ok(Stmt::Delete { table_name: table, where_clause })
+        let returning = self.parse_returning_clause()?;
+        Ok(Stmt::Delete { table_name: table, where_clause, returning })
+    }
+
+    fn parse_returning_clause(&mut self) -> Result<Option<Vec<Expr>>> {
+        // RETURNING * | RETURNING <expr1>, <expr2>, ...
+        if !self.maybe(&[Token::Returning])? {
+            return Ok(None);
+        }
+        Ok(Some(self.parse_select_list()?))
     }
 
     fn parse_where_clause(&mut self) -> Result<Option<Expr>> {
@@ -418,6 +1411,36 @@ impl Parser {
         Ok(Stmt::Truncate { table_name: table })
     }
 
+    fn parse_txn(&mut self) -> Result<Stmt> {
+        // BEGIN|COMMIT|ROLLBACK [TRANSACTION]
+        // ROLLBACK [TRANSACTION] TO [SAVEPOINT] <name>
+        let stmt = match self.next()?.token {
+            Token::Begin => Stmt::Begin,
+            Token::Commit => Stmt::Commit,
+            _ => Stmt::Rollback,
+        };
+        self.maybe(&[Token::Transaction])?;
+        if stmt == Stmt::Rollback && self.maybe(&[Token::To])? {
+            self.maybe(&[Token::Savepoint])?;
+            let name = self.consume_ident()?;
+            return Ok(Stmt::RollbackTo { name });
+        }
+        Ok(stmt)
+    }
+
+    fn parse_savepoint(&mut self) -> Result<Stmt> {
+        // SAVEPOINT <name>
+        // RELEASE [SAVEPOINT] <name>
+        if self.maybe(&[Token::Savepoint])? {
+            let name = self.consume_ident()?;
+            return Ok(Stmt::Savepoint { name });
+        }
+        self.expect(&[Token::Release])?;
+        self.maybe(&[Token::Savepoint])?;
+        let name = self.consume_ident()?;
+        Ok(Stmt::Release { name })
+    }
+
     fn parse_drop(&mut self) -> Result<Stmt> {
         // DROP TABLE [IF EXISTS] <table> [RESTRICT|CASCADE]
         self.expect(&[Token::Drop, Token::Table])?;
@@ -455,7 +1478,7 @@ impl Parser {
     fn consume_ident(&mut self) -> Result<Box<str>> {
         let spanned = self.next()?;
         match spanned.token {
-            Token::Ident(name) => Ok(name.into_boxed_str()),
+            Token::Ident(name) => Ok(name.into()),
             tok => Err(QueryErr {
                 kind: QueryErrKind::UnexpectedToken {
                     expected: "identifier".into(),
@@ -466,13 +1489,50 @@ impl Parser {
         }
     }
 
-    fn consume_type(&mut self) -> Result<DataType> {
+    // NOCASE | BINARY
+    fn parse_collation(&mut self) -> Result<Collation> {
+        let spanned = self.next()?;
+        match spanned.token {
+            Token::Nocase => Ok(Collation::Nocase),
+            Token::Ident(name) if name.eq_ignore_ascii_case("BINARY") => {
+                Ok(Collation::Binary)
+            }
+            tok => Err(QueryErr {
+                kind: QueryErrKind::UnexpectedToken {
+                    expected: "collation (NOCASE or BINARY)".into(),
+                    found: format!("{:?}", tok),
+                },
+                span: spanned.span,
+            }),
+        }
+    }
+
+    // <type> [(<n>)]
+    fn parse_column_type(&mut self) -> Result<ColumnType> {
         let spanned = self.next()?;
         match spanned.token {
-            Token::IntType => Ok(DataType::Int),
-            Token::RealType => Ok(DataType::Real),
-            Token::BoolType => Ok(DataType::Bool),
-            Token::TextType => Ok(DataType::Text),
+            Token::IntType => Ok(ColumnType::Int),
+            Token::RealType => Ok(ColumnType::Real),
+            Token::BoolType => Ok(ColumnType::Bool),
+            Token::TextType => Ok(ColumnType::Text),
+            // VARCHAR without a length is plain TEXT
+            Token::VarCharType if self.curr.token != Token::LParen => {
+                Ok(ColumnType::Text)
+            }
+            Token::VarCharType => self.consume_type_len().map(ColumnType::VarChar),
+            // CHAR without a length is CHAR(1)
+            Token::CharType if self.curr.token != Token::LParen => {
+                Ok(ColumnType::Char(1))
+            }
+            Token::CharType => self.consume_type_len().map(ColumnType::Char),
+            Token::DateType => Ok(ColumnType::Date),
+            Token::TimeType => Ok(ColumnType::Timestamp),
+            Token::DecimalType => self.parse_decimal_type(),
+            Token::BlobType => Ok(ColumnType::Blob),
+            Token::Ident(name) => Err(QueryErr {
+                kind: QueryErrKind::UnknownType(name.to_string()),
+                span: spanned.span,
+            }),
             tok => Err(QueryErr {
                 kind: QueryErrKind::UnexpectedToken {
                     expected: "type".into(),
@@ -483,9 +1543,111 @@ impl Parser {
         }
     }
 
+    // DECIMAL [(<precision> [, <scale>])]
+    fn parse_decimal_type(&mut self) -> Result<ColumnType> {
+        if self.curr.token != Token::LParen {
+            return Ok(ColumnType::Decimal(Decimal::MAX_PRECISION, 0));
+        }
+        let span = self.curr.span;
+        self.expect(&[Token::LParen])?;
+        let precision = self.consume_type_num()?;
+        let scale =
+            if self.maybe(&[Token::Comma])? { self.consume_type_num()? } else { 0 };
+        self.expect(&[Token::RParen])?;
+        match (u8::try_from(precision), u8::try_from(scale)) {
+            (Ok(precision @ 1..), Ok(scale))
+                if precision <= Decimal::MAX_PRECISION && scale <= precision =>
+            {
+                Ok(ColumnType::Decimal(precision, scale))
+            }
+            _ => Err(QueryErr {
+                kind: QueryErrKind::UnexpectedToken {
+                    expected: format!(
+                        "precision 1..={} and scale <= precision",
+                        Decimal::MAX_PRECISION
+                    ),
+                    found: format!("DECIMAL({precision}, {scale})"),
+                },
+                span,
+            }),
+        }
+    }
+
+    // (<n>)
+    fn consume_type_len(&mut self) -> Result<u32> {
+        self.expect(&[Token::LParen])?;
+        let len = self.consume_type_num()?;
+        self.expect(&[Token::RParen])?;
+        Ok(len)
+    }
+
+    fn consume_type_num(&mut self) -> Result<u32> {
+        let spanned = self.next()?;
+        let len = match spanned.token {
+            Token::Int(len) => u32::try_from(len).ok(),
+            _ => None,
+        };
+        let Some(len) = len else {
+            return Err(QueryErr {
+                kind: QueryErrKind::UnexpectedToken {
+                    expected: "type length".into(),
+                    found: format!("{:?}", spanned.token),
+                },
+                span: spanned.span,
+            });
+        };
+        Ok(len)
+    }
+
+    // DATE '<YYYY-MM-DD>' | TIMESTAMP '<YYYY-MM-DD HH:MM:SS>'
+    fn parse_date_literal(&mut self, ty: Token) -> Result<Expr> {
+        let spanned = self.next()?;
+        let Token::Text(text) = spanned.token else {
+            return Err(QueryErr {
+                kind: QueryErrKind::UnexpectedToken {
+                    expected: "text literal".into(),
+                    found: format!("{:?}", spanned.token),
+                },
+                span: spanned.span,
+            });
+        };
+        let (name, expr) = match ty {
+            Token::DateType => ("DATE", parse_date(&text).map(Expr::Date)),
+            _ => ("TIMESTAMP", parse_timestamp(&text).map(Expr::Timestamp)),
+        };
+        expr.ok_or(QueryErr {
+            kind: QueryErrKind::InvalidDate(name, text.into_owned()),
+            span: spanned.span,
+        })
+    }
+
     fn parse_expr(&mut self, prec: u8) -> Result<Expr> {
+        let (depth, chain) = (self.depth, self.chain);
+        let expr = self.parse_expr_chain(prec);
+        (self.depth, self.chain) = (depth, chain);
+        expr
+    }
+
+    fn parse_expr_chain(&mut self, prec: u8) -> Result<Expr> {
+        self.descend()?;
         let mut left = self.parse_unary()?;
+        // COLLATE binds to the operand, tighter than any operator
+        while self.maybe(&[Token::Collate])? {
+            left = Expr::Collate {
+                expr: left.boxed(),
+                collation: self.parse_collation()?,
+            };
+        }
         while prec < Self::precedence(&self.curr.token) {
+            // `a + b + c` nests to the left without recursing, so it only
+            // counts against the chain limit
+            if self.chain >= self.max_chain {
+                return Err(QueryErr {
+                    kind: QueryErrKind::TooLongChain(self.max_chain),
+                    span: self.curr.span,
+                });
+            }
+            self.chain += 1;
             left = self.parse_binary(left)?;
         }
         Ok(left)
@@ -498,20 +1660,39 @@ impl Parser {
             Token::Int(n) => Ok(Expr::Int(n)),
             Token::Real(f) => Ok(Expr::Real(f)),
             Token::Bool(b) => Ok(Expr::Bool(b)),
-            Token::Text(t) => Ok(Expr::Text(t.into_boxed_str())),
+            Token::Text(t) => Ok(Expr::Text(t.into())),
+            Token::Blob(b) => Ok(Expr::Blob(b.into_boxed_slice())),
+            tok @ (Token::DateType | Token::TimeType) => self.parse_date_literal(tok),
+            Token::Param => {
+                self.params += 1;
+                Ok(Expr::Param(Param::Positional(self.params - 1)))
+            }
+            Token::NamedParam(name) => Ok(Expr::Param(Param::Named(name.into()))),
             Token::Ident(i) => {
-                let name = i.into_boxed_str();
+                let name: Box<str> = i.into();
                 if self.curr.token == Token::LParen {
                     self.parse_call(name)
+                } else if self.maybe(&[Token::Dot])? {
+                    // <table>.* | <table>.<col>
+                    if self.maybe(&[Token::OpMul])? {
+                        Ok(Expr::QualifiedWildcard(name))
+                    } else {
+                        Ok(Expr::QualifiedIdent(name, self.consume_ident()?))
+                    }
                 } else {
                     Ok(Expr::Ident(name))
                 }
             }
-            op @ (Token::Not | Token::OpSub) => {
+            Token::Not => {
+                let right = self.parse_expr(Self::COMPARISON - 1)?.boxed();
+                Ok(Expr::Unary { op: Token::Not, right })
+            }
+            Token::OpSub => {
                 let right = self.parse_expr(7)?.boxed();
-                Ok(Expr::Unary { op, right })
+                Ok(Expr::Unary { op: Token::OpSub, right })
             }
             Token::LParen => self.parse_group(),
+            Token::Cast => self.parse_cast(),
             tok => Err(QueryErr {
                 kind: QueryErrKind::UnexpectedToken {
                     expected: "expression (literal, identifier, or '(')".into(),
@@ -523,7 +1704,9 @@ impl Parser {
     }
 
     fn parse_call(&mut self, name: Box<str>) -> Result<Expr> {
+        // <name>([DISTINCT] <arg1>, <arg2>, ...)
         self.expect(&[Token::LParen])?;
+        let distinct = self.maybe(&[Token::Distinct])?;
         let mut args = Vec::new();
         if self.curr.token != Token::RParen {
             loop {
@@ -540,7 +1723,17 @@ impl Parser {
             }
         }
         self.expect(&[Token::RParen])?;
-        Ok(Expr::Call { name, args })
+        Ok(Expr::Call { name, args, distinct })
+    }
+
+    fn parse_cast(&mut self) -> Result<Expr> {
+        // CAST(<expr> AS <type>)
+        self.expect(&[Token::LParen])?;
+        let expr = self.parse_expr(0)?.boxed();
+        self.expect(&[Token::As])?;
+        let ty = self.parse_column_type()?;
+        self.expect(&[Token::RParen])?;
+        Ok(Expr::Cast { expr, ty })
     }
 
     fn parse_group(&mut self) -> Result<Expr> {
@@ -551,6 +1744,21 @@ impl Parser {
 
     fn parse_binary(&mut self, left: Expr) -> Result<Expr> {
         let spanned = self.next()?;
+        let prec = Self::precedence(&spanned.token);
+        let expr = self.parse_binary_rest(left, spanned)?;
+        // comparisons do not associate: `a < b < c` needs AND or parentheses
+        if prec == Self::COMPARISON && Self::precedence(&self.curr.token) == prec {
+            return Err(QueryErr {
+                kind: QueryErrKind::InvalidExpr(
+                    "comparisons cannot be chained; use AND or parentheses".into(),
+                ),
+                span: self.curr.span,
+            });
+        }
+        Ok(expr)
+    }
+
+    fn parse_binary_rest(&mut self, left: Expr, spanned: SpannedToken) -> Result<Expr> {
         let prec = Self::precedence(&spanned.token);
         match spanned.token {
             Token::In => {
@@ -560,18 +1768,63 @@ impl Parser {
                         .boxed();
                 Ok(Expr::Binary { op: Token::In, left, right })
             }
-            op if prec > 0 => {
+            // <expr> IS [NOT] DISTINCT FROM <expr>, the spelled-out <=>
+            Token::Is => {
+                let negated = self.maybe(&[Token::Not])?;
+                self.expect(&[Token::Distinct, Token::From])?;
                 let left = left.boxed();
                 let right = self.parse_expr(prec)?.boxed();
-                Ok(Expr::Binary { op, left, right })
+                let expr = Expr::Binary { op: Token::OpSafeEq, left, right };
+                if negated {
+                    Ok(expr)
+                } else {
+                    Ok(Expr::Unary { op: Token::Not, right: expr.boxed() })
+                }
             }
-            tok => Err(QueryErr {
-                kind: QueryErrKind::UnexpectedToken {
-                    expected: "binary operator".to_string(),
-                    found: format!("{:?}", tok),
-                },
-                span: spanned.span,
-            }),
+            // <expr> LIKE <pattern> [ESCAPE '<c>'], `\` escaping by default
+            Token::Like => {
+                let expr = left.boxed();
+                let pattern = self.parse_expr(prec)?.boxed();
+                let mut escape = '\\';
+                if self.maybe(&[Token::Escape])? {
+                    let spanned = self.next()?;
+                    let span = spanned.span;
+                    let Token::Text(text) = spanned.token else {
+                        return Err(QueryErr {
+                            kind: QueryErrKind::UnexpectedToken {
+                                expected: "text literal".into(),
+                                found: format!("{:?}", spanned.token),
+                            },
+                            span,
+                        });
+                    };
+                    let mut chars = text.chars();
+                    let (Some(ch), None) = (chars.next(), chars.next()) else {
+                        return Err(QueryErr {
+                            kind: QueryErrKind::InvalidExpr(
+                                "ESCAPE takes a single character".to_string(),
+                            ),
+                            span,
+                        });
+                    };
+                    escape = ch;
+                }
+                Ok(Expr::Like { expr, pattern, escape })
+            }
+            tok => match Self::operator(&tok) {
+                Some(op) if prec > 0 => {
+                    let left = left.boxed();
+                    let right = self.parse_expr(prec)?.boxed();
+                    Ok(Expr::Binary { op, left, right })
+                }
+                _ => Err(QueryErr {
+                    kind: QueryErrKind::UnexpectedToken {
+                        expected: "binary operator".to_string(),
+                        found: format!("{:?}", tok),
+                    },
+                    span: spanned.span,
+                }),
+            },
         }
     }
 }
@@ -0,0 +1,319 @@
+use super::parser::{ColumnConstraint, ColumnDef, Expr, OrderBy, SelectSource, Stmt};
+
+/// Read-only traversal of a parsed statement.
+///
+/// Each method's default walks into the children, so an override that
+/// still wants the children calls the matching `walk_*` function.
+pub trait Visitor {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    /// Called for every table name the statement refers to.
+    fn visit_table(&mut self, _name: &str) {}
+
+    /// Called for every column name the statement refers to, including
+    /// identifiers in expressions.
+    fn visit_column(&mut self, _name: &str) {}
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match stmt {
+        Stmt::Create { table_name, defines, foreign_keys, checks, .. } => {
+            visitor.visit_table(table_name);
+            defines.iter().for_each(|define| walk_column_def(visitor, define));
+            for fk in foreign_keys {
+                visitor.visit_column(&fk.column);
+                visitor.visit_table(&fk.ref_table);
+                visitor.visit_column(&fk.ref_column);
+            }
+            checks.iter().for_each(|expr| visitor.visit_expr(expr));
+        }
+        Stmt::InsertValues { table_name, columns, values, returning } => {
+            visitor.visit_table(table_name);
+            columns.iter().for_each(|col| visitor.visit_column(col));
+            values.iter().flatten().for_each(|expr| visitor.visit_expr(expr));
+            returning.iter().flatten().for_each(|expr| visitor.visit_expr(expr));
+        }
+        Stmt::InsertSelect { table_name, columns, select, returning } => {
+            visitor.visit_table(table_name);
+            columns.iter().for_each(|col| visitor.visit_column(col));
+            visitor.visit_stmt(select);
+            returning.iter().flatten().for_each(|expr| visitor.visit_expr(expr));
+        }
+        Stmt::Select {
+            from,
+            columns,
+            where_clause,
+            group_by,
+            having,
+            order_by,
+            limit,
+            offset,
+            ..
+        } => {
+            match from {
+                Some(SelectSource::Table { name, .. }) => visitor.visit_table(name),
+                Some(SelectSource::Subquery { query, .. }) => visitor.visit_stmt(query),
+                None => {}
+            }
+            columns.iter().for_each(|expr| visitor.visit_expr(expr));
+            where_clause.iter().for_each(|expr| visitor.visit_expr(expr));
+            group_by.iter().flatten().for_each(|expr| visitor.visit_expr(expr));
+            having.iter().for_each(|expr| visitor.visit_expr(expr));
+            walk_order_by(visitor, order_by, stmt);
+            limit.iter().chain(offset).for_each(|expr| visitor.visit_expr(expr));
+        }
+        Stmt::SetOp { left, right, order_by, limit, offset, .. } => {
+            visitor.visit_stmt(left);
+            visitor.visit_stmt(right);
+            walk_order_by(visitor, order_by, stmt);
+            limit.iter().chain(offset).for_each(|expr| visitor.visit_expr(expr));
+        }
+        Stmt::With { ctes, body } => {
+            ctes.iter().for_each(|(_, query)| visitor.visit_stmt(query));
+            visitor.visit_stmt(body);
+        }
+        Stmt::Explain(query) => visitor.visit_stmt(query),
+        Stmt::Update { table_name, assigns, where_clause, returning } => {
+            visitor.visit_table(table_name);
+            for (col, expr) in assigns {
+                visitor.visit_column(col);
+                visitor.visit_expr(expr);
+            }
+            where_clause.iter().for_each(|expr| visitor.visit_expr(expr));
+            returning.iter().flatten().for_each(|expr| visitor.visit_expr(expr));
+        }
+        Stmt::Delete { table_name, where_clause, returning } => {
+            visitor.visit_table(table_name);
+            where_clause.iter().for_each(|expr| visitor.visit_expr(expr));
+            returning.iter().flatten().for_each(|expr| visitor.visit_expr(expr));
+        }
+        Stmt::AlterAdd { table_name, define } => {
+            visitor.visit_table(table_name);
+            walk_column_def(visitor, define);
+        }
+        Stmt::AlterDrop { table_name, column }
+        | Stmt::AlterRestart { table_name, column, .. } => {
+            visitor.visit_table(table_name);
+            visitor.visit_column(column);
+        }
+        Stmt::AlterRename { table_name, .. }
+        | Stmt::Truncate { table_name }
+        | Stmt::Drop { table_name, .. } => visitor.visit_table(table_name),
+        Stmt::Vacuum
+        | Stmt::Begin
+        | Stmt::Commit
+        | Stmt::Rollback
+        | Stmt::Savepoint { .. }
+        | Stmt::RollbackTo { .. }
+        | Stmt::Release { .. } => {}
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        Expr::Ident(name) | Expr::QualifiedIdent(_, name) => visitor.visit_column(name),
+        Expr::List(items) => items.iter().for_each(|e| visitor.visit_expr(e)),
+        Expr::Call { args, .. } => args.iter().for_each(|e| visitor.visit_expr(e)),
+        Expr::Alias { expr, .. }
+        | Expr::Cast { expr, .. }
+        | Expr::Collate { expr, .. } => visitor.visit_expr(expr),
+        Expr::Unary { right, .. } => visitor.visit_expr(right),
+        Expr::Binary { left, right, .. } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        Expr::Like { expr, pattern, .. } => {
+            visitor.visit_expr(expr);
+            visitor.visit_expr(pattern);
+        }
+        _ => {}
+    }
+}
+
+// column definitions introduce their name, so only the constraints are walked
+fn walk_column_def<V: Visitor + ?Sized>(visitor: &mut V, define: &ColumnDef) {
+    for constraint in &define.constraints {
+        if let ColumnConstraint::Default(expr) | ColumnConstraint::Check(expr) =
+            constraint
+        {
+            visitor.visit_expr(expr);
+        }
+    }
+}
+
+/// Walks the ORDER BY keys of `query`, skipping those that name one of its
+/// output columns, as `who` does in `SELECT name AS who ... ORDER BY who`.
+fn walk_order_by<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    order_by: &Option<Vec<OrderBy>>,
+    query: &Stmt,
+) {
+    let aliases = output_aliases(query);
+    for item in order_by.iter().flatten() {
+        match &item.expr {
+            Expr::Ident(name) if aliases.contains(&&**name) => {}
+            expr => visitor.visit_expr(expr),
+        }
+    }
+}
+
+/// Names given with AS to the output columns of a query. A compound query
+/// takes its names from its first SELECT.
+fn output_aliases(query: &Stmt) -> Vec<&str> {
+    match query {
+        Stmt::Select { columns, .. } => columns
+            .iter()
+            .filter_map(|expr| match expr {
+                Expr::Alias { alias, .. } => Some(&**alias),
+                _ => None,
+            })
+            .collect(),
+        Stmt::SetOp { left, .. } => output_aliases(left),
+        _ => Vec::new(),
+    }
+}
+
+/// Table and column names the statement refers to, each once, in order of
+/// first appearance. Names a statement defines (new columns, aliases) are
+/// not included.
+pub fn collect_idents(stmt: &Stmt) -> Vec<Box<str>> {
+    struct Idents(Vec<Box<str>>);
+
+    impl Idents {
+        fn push(&mut self, name: &str) {
+            if !self.0.iter().any(|seen| **seen == *name) {
+                self.0.push(name.into());
+            }
+        }
+    }
+
+    impl Visitor for Idents {
+        fn visit_table(&mut self, name: &str) {
+            self.push(name);
+        }
+
+        fn visit_column(&mut self, name: &str) {
+            self.push(name);
+        }
+    }
+
+    let mut idents = Idents(Vec::new());
+    idents.visit_stmt(stmt);
+    idents.0
+}
+
+/// Calls `f` on every expression in the statement, children before their
+/// parent, so `f` can rewrite expressions in place.
+pub fn walk_stmt_mut(stmt: &mut Stmt, f: &mut impl FnMut(&mut Expr)) {
+    let walk_define = |define: &mut ColumnDef, f: &mut _| {
+        for constraint in &mut define.constraints {
+            if let ColumnConstraint::Default(expr) | ColumnConstraint::Check(expr) =
+                constraint
+            {
+                walk_expr_mut(expr, f);
+            }
+        }
+    };
+    match stmt {
+        Stmt::Create { defines, checks, .. } => {
+            defines.iter_mut().for_each(|define| walk_define(define, f));
+            checks.iter_mut().for_each(|e| walk_expr_mut(e, f));
+        }
+        Stmt::AlterAdd { define, .. } => walk_define(define, f),
+        Stmt::InsertValues { values, returning, .. } => {
+            values.iter_mut().flatten().for_each(|e| walk_expr_mut(e, f));
+            returning.iter_mut().flatten().for_each(|e| walk_expr_mut(e, f));
+        }
+        Stmt::InsertSelect { select, returning, .. } => {
+            walk_stmt_mut(select, f);
+            returning.iter_mut().flatten().for_each(|e| walk_expr_mut(e, f));
+        }
+        Stmt::Select {
+            from,
+            columns,
+            where_clause,
+            group_by,
+            having,
+            order_by,
+            limit,
+            offset,
+            ..
+        } => {
+            if let Some(SelectSource::Subquery { query, .. }) = from {
+                walk_stmt_mut(query, f);
+            }
+            columns.iter_mut().for_each(|e| walk_expr_mut(e, f));
+            where_clause.iter_mut().for_each(|e| walk_expr_mut(e, f));
+            group_by.iter_mut().flatten().for_each(|e| walk_expr_mut(e, f));
+            having.iter_mut().for_each(|e| walk_expr_mut(e, f));
+            order_by
+                .iter_mut()
+                .flatten()
+                .for_each(|item| walk_expr_mut(&mut item.expr, f));
+            limit.iter_mut().chain(offset).for_each(|e| walk_expr_mut(e, f));
+        }
+        Stmt::SetOp { left, right, order_by, limit, offset, .. } => {
+            walk_stmt_mut(left, f);
+            walk_stmt_mut(right, f);
+            order_by
+                .iter_mut()
+                .flatten()
+                .for_each(|item| walk_expr_mut(&mut item.expr, f));
+            limit.iter_mut().chain(offset).for_each(|e| walk_expr_mut(e, f));
+        }
+        Stmt::With { ctes, body } => {
+            ctes.iter_mut().for_each(|(_, query)| walk_stmt_mut(query, f));
+            walk_stmt_mut(body, f);
+        }
+        Stmt::Explain(query) => walk_stmt_mut(query, f),
+        Stmt::Update { assigns, where_clause, returning, .. } => {
+            assigns.iter_mut().for_each(|(_, e)| walk_expr_mut(e, f));
+            where_clause.iter_mut().for_each(|e| walk_expr_mut(e, f));
+            returning.iter_mut().flatten().for_each(|e| walk_expr_mut(e, f));
+        }
+        Stmt::Delete { where_clause, returning, .. } => {
+            where_clause.iter_mut().for_each(|e| walk_expr_mut(e, f));
+            returning.iter_mut().flatten().for_each(|e| walk_expr_mut(e, f));
+        }
+        Stmt::AlterDrop { .. }
+        | Stmt::AlterRename { .. }
+        | Stmt::AlterRestart { .. }
+        | Stmt::Truncate { .. }
+        | Stmt::Drop { .. }
+        | Stmt::Vacuum
+        | Stmt::Begin
+        | Stmt::Commit
+        | Stmt::Rollback
+        | Stmt::Savepoint { .. }
+        | Stmt::RollbackTo { .. }
+        | Stmt::Release { .. } => {}
+    }
+}
+
+/// Calls `f` on `expr` and every expression inside it, children first.
+pub fn walk_expr_mut(expr: &mut Expr, f: &mut impl FnMut(&mut Expr)) {
+    match expr {
+        Expr::List(items) => items.iter_mut().for_each(|e| walk_expr_mut(e, f)),
+        Expr::Call { args, .. } => args.iter_mut().for_each(|e| walk_expr_mut(e, f)),
+        Expr::Alias { expr, .. } => walk_expr_mut(expr, f),
+        Expr::Cast { expr, .. } => walk_expr_mut(expr, f),
+        Expr::Collate { expr, .. } => walk_expr_mut(expr, f),
+        Expr::Unary { right, .. } => walk_expr_mut(right, f),
+        Expr::Binary { left, right, .. } => {
+            walk_expr_mut(left, f);
+            walk_expr_mut(right, f);
+        }
+        Expr::Like { expr, pattern, .. } => {
+            walk_expr_mut(expr, f);
+            walk_expr_mut(pattern, f);
+        }
+        _ => {}
+    }
+    f(expr);
+}
@@ -0,0 +1,199 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Exact fixed-point number with value `units / 10^scale`.
+///
+/// Addition, subtraction and multiplication are exact (they fail only on
+/// overflow). Division keeps the larger scale of its operands, and
+/// operations that drop digits round half away from zero. Values compare
+/// numerically, so `1.50` equals `1.5`.
+#[derive(Debug, Clone, Copy)]
+pub struct Decimal {
+    units: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    /// Largest supported precision and scale, the digits an `i128` holds.
+    pub const MAX_PRECISION: u8 = 38;
+
+    pub fn new(units: i128, scale: u8) -> Self {
+        Self { units, scale }
+    }
+
+    pub fn from_int(value: i64) -> Self {
+        Self { units: value as i128, scale: 0 }
+    }
+
+    /// Converts a REAL through its shortest decimal representation, so the
+    /// literal `0.1` becomes exactly `0.1`.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        Self::parse(&value.to_string())
+    }
+
+    /// Parses `[+|-]digits[.digits]`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, digits) = match text.as_bytes().first()? {
+            b'-' => (true, &text[1..]),
+            b'+' => (false, &text[1..]),
+            _ => (false, text),
+        };
+        let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+        if int.is_empty() && frac.is_empty()
+            || !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        let scale =
+            u8::try_from(frac.len()).ok().filter(|s| *s <= Self::MAX_PRECISION)?;
+        let mut units: i128 = 0;
+        for b in int.bytes().chain(frac.bytes()) {
+            units = units.checked_mul(10)?.checked_add((b - b'0') as i128)?;
+        }
+        Some(Self { units: if negative { -units } else { units }, scale })
+    }
+
+    pub fn units(&self) -> i128 {
+        self.units
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// Number of significant digits in the unscaled value.
+    pub fn digits(&self) -> u32 {
+        self.units.unsigned_abs().checked_ilog10().map_or(1, |d| d + 1)
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.units as f64 / 10f64.powi(self.scale as i32)
+    }
+
+    /// Changes the scale, rounding half away from zero when digits are
+    /// dropped. Returns `None` on overflow.
+    pub fn rescale(&self, scale: u8) -> Option<Self> {
+        let units = match scale.cmp(&self.scale) {
+            Ordering::Equal => self.units,
+            Ordering::Greater => self.units.checked_mul(pow10(scale - self.scale)?)?,
+            Ordering::Less => div_round(self.units, pow10(self.scale - scale)?),
+        };
+        Some(Self { units, scale })
+    }
+
+    /// Returns whether the value fits `DECIMAL(precision, scale)` without
+    /// losing digits.
+    pub fn fits(&self, precision: u8, scale: u8) -> bool {
+        self.scale == scale && self.digits() <= precision as u32
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let (left, right, scale) = Self::align(self, other)?;
+        Some(Self { units: left.checked_add(right)?, scale })
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let (left, right, scale) = Self::align(self, other)?;
+        Some(Self { units: left.checked_sub(right)?, scale })
+    }
+
+    pub fn checked_mul(self, other: Self) -> Option<Self> {
+        let units = self.units.checked_mul(other.units)?;
+        let scale = self.scale.checked_add(other.scale)?;
+        let product = Self { units, scale };
+        if scale > Self::MAX_PRECISION {
+            product.rescale(Self::MAX_PRECISION)
+        } else {
+            Some(product)
+        }
+    }
+
+    /// Divides, keeping the larger operand scale. Returns `None` on division
+    /// by zero or overflow.
+    pub fn checked_div(self, other: Self) -> Option<Self> {
+        if other.units == 0 {
+            return None;
+        }
+        let scale = self.scale.max(other.scale);
+        // units / 10^s1 / (other / 10^s2) * 10^scale
+        let shift = (scale + other.scale).checked_sub(self.scale)?;
+        let dividend = self.units.checked_mul(pow10(shift)?)?;
+        Some(Self { units: div_round(dividend, other.units), scale })
+    }
+
+    fn align(left: Self, right: Self) -> Option<(i128, i128, u8)> {
+        let scale = left.scale.max(right.scale);
+        Some((left.rescale(scale)?.units, right.rescale(scale)?.units, scale))
+    }
+}
+
+fn pow10(exp: u8) -> Option<i128> {
+    10i128.checked_pow(exp as u32)
+}
+
+/// Integer division rounding half away from zero.
+fn div_round(dividend: i128, divisor: i128) -> i128 {
+    let quotient = dividend / divisor;
+    let remainder = dividend % divisor;
+    if remainder.unsigned_abs() * 2 >= divisor.unsigned_abs() {
+        if (dividend < 0) == (divisor < 0) { quotient + 1 } else { quotient - 1 }
+    } else {
+        quotient
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match Self::align(*self, *other) {
+            Some((left, right, _)) => left.cmp(&right),
+            // rescaling overflowed, so the magnitudes differ widely enough
+            // for the approximate comparison to be exact
+            None => self.to_f64().total_cmp(&other.to_f64()),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // hash the normalized form so equal values hash alike
+        let mut units = self.units;
+        let mut scale = self.scale;
+        while scale > 0 && units % 10 == 0 {
+            units /= 10;
+            scale -= 1;
+        }
+        (units, scale).hash(state);
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.units.unsigned_abs().to_string();
+        let sign = if self.units < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{int}.{frac}")
+    }
+}
@@ -0,0 +1,75 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("value too long for CHAR({width}): {len} characters")]
+pub struct CharErr {
+    pub len: usize,
+    pub width: usize,
+}
+
+/// UTF-8 text right-padded with spaces to exactly `width` characters.
+///
+/// Trailing spaces are not significant: input may exceed the width only by
+/// spaces, and values compare (and hash) with trailing spaces removed, so
+/// `'ab'` and `'ab  '` are equal. Width is counted in characters, as for
+/// [`VarChar`](super::VarChar).
+#[derive(Debug, Clone)]
+pub struct Char {
+    text: Box<str>,
+}
+
+impl Char {
+    pub fn new(text: &str, width: usize) -> Result<Self, CharErr> {
+        let trimmed = text.trim_end_matches(' ');
+        let len = trimmed.chars().count();
+        if len > width {
+            return Err(CharErr { len, width });
+        }
+        let mut padded = String::with_capacity(trimmed.len() + width - len);
+        padded.push_str(trimmed);
+        padded.extend(std::iter::repeat_n(' ', width - len));
+        Ok(Self { text: padded.into_boxed_str() })
+    }
+
+    /// Returns the padded text.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the text without its trailing padding.
+    pub fn trimmed(&self) -> &str {
+        self.text.trim_end_matches(' ')
+    }
+
+    pub fn into_inner(self) -> Box<str> {
+        self.text
+    }
+}
+
+impl PartialEq for Char {
+    fn eq(&self, other: &Self) -> bool {
+        self.trimmed() == other.trimmed()
+    }
+}
+
+impl Eq for Char {}
+
+impl Ord for Char {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.trimmed().cmp(other.trimmed())
+    }
+}
+
+impl PartialOrd for Char {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for Char {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.trimmed().hash(state);
+    }
+}
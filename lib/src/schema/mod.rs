@@ -1,10 +1,27 @@
+mod date_time;
+mod decimal;
+mod fixed_char;
+mod var_char;
+
+pub use date_time::{format_date, format_timestamp, parse_date, parse_timestamp};
+pub use decimal::Decimal;
+pub use fixed_char::{Char, CharErr};
+pub use var_char::{VarChar, VarCharErr};
+
+use std::cmp::Ordering;
+use std::fmt;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DataType {
-    Nil = 1,
-    Int = 2,
-    Real = 3,
-    Bool = 4,
-    Text = 5,
+    Nil,
+    Int,
+    Real,
+    Bool,
+    Text,
+    Date,
+    Timestamp,
+    Decimal,
+    Blob,
 }
 
 impl DataType {
@@ -15,6 +32,212 @@ impl DataType {
             DataType::Real => DataValue::Real(0.0),
             DataType::Bool => DataValue::Bool(false),
             DataType::Text => DataValue::Text(Box::from("")),
+            DataType::Date => DataValue::Date(0),
+            DataType::Timestamp => DataValue::Timestamp(0),
+            DataType::Decimal => DataValue::Decimal(Decimal::from_int(0)),
+            DataType::Blob => DataValue::Blob(Box::default()),
+        }
+    }
+}
+
+/// Declared type of a column.
+///
+/// Several column types can share one [`DataType`]: VARCHAR and CHAR columns
+/// hold TEXT values with a length limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Int,
+    Real,
+    Bool,
+    Text,
+    VarChar(u32), // TEXT limited to n characters
+    Char(u32),    // TEXT padded to exactly n characters
+    Date,
+    Timestamp,
+    Decimal(u8, u8), // precision, scale
+    Blob,
+}
+
+impl ColumnType {
+    /// Returns the type of the values stored in a column of this type.
+    pub fn value_type(&self) -> DataType {
+        match self {
+            ColumnType::Int => DataType::Int,
+            ColumnType::Real => DataType::Real,
+            ColumnType::Bool => DataType::Bool,
+            ColumnType::Text | ColumnType::VarChar(_) | ColumnType::Char(_) => {
+                DataType::Text
+            }
+            ColumnType::Date => DataType::Date,
+            ColumnType::Timestamp => DataType::Timestamp,
+            ColumnType::Decimal(..) => DataType::Decimal,
+            ColumnType::Blob => DataType::Blob,
+        }
+    }
+
+    /// Converts `value` so it can be stored in a column of this type.
+    ///
+    /// NIL fits every column and INT is widened to REAL. DATE and TIMESTAMP
+    /// columns parse TEXT in the literal formats, and a DATE is widened to a
+    /// TIMESTAMP at midnight. DECIMAL columns take any number and round it to
+    /// the column scale. Any other mismatch yields `None`. VARCHAR and CHAR
+    /// accept TEXT of any length, and DECIMAL any precision;
+    /// the limits are checked separately with [`VarChar::new`],
+    /// [`Char::new`] and [`Decimal::fits`].
+    pub fn coerce(&self, value: DataValue) -> Option<DataValue> {
+        match (self, value) {
+            (_, DataValue::Nil) => Some(DataValue::Nil),
+            (ColumnType::Real, DataValue::Int(value)) => {
+                Some(DataValue::Real(value as f64))
+            }
+            (ColumnType::Date, DataValue::Text(text)) => {
+                parse_date(&text).map(DataValue::Date)
+            }
+            (ColumnType::Timestamp, DataValue::Text(text)) => {
+                parse_timestamp(&text).map(DataValue::Timestamp)
+            }
+            (ColumnType::Timestamp, DataValue::Date(days)) => {
+                Some(DataValue::Timestamp(days as i64 * 86_400))
+            }
+            (ColumnType::Decimal(_, scale), value) => {
+                let decimal = match value {
+                    DataValue::Int(value) => Decimal::from_int(value),
+                    DataValue::Real(value) => Decimal::from_f64(value)?,
+                    DataValue::Decimal(value) => value,
+                    _ => return None,
+                };
+                decimal.rescale(*scale).map(DataValue::Decimal)
+            }
+            (ty, value) if value.data_type() == ty.value_type() => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Converts `value` for an explicit `CAST(value AS type)`.
+    ///
+    /// Unlike [`coerce`](Self::coerce), numbers, BOOL and TEXT convert into
+    /// each other: REAL and DECIMAL truncate toward zero into INT, BOOL is 1
+    /// or 0, numbers are true when non-zero, and TEXT is parsed after trimming
+    /// whitespace (`'true'`/`'false'` for BOOL, ignoring case). Every value
+    /// casts to TEXT through its display form; VARCHAR(n) truncates it to n
+    /// characters and CHAR(n) also pads it. TEXT casts to BLOB as its UTF-8
+    /// bytes. Returns `None` when the value can't be converted, for example
+    /// unparsable TEXT or a number outside the target range.
+    pub fn cast(&self, value: DataValue) -> Option<DataValue> {
+        let value = match (self, value) {
+            (_, DataValue::Nil) => DataValue::Nil,
+            (ColumnType::Int, value) => DataValue::Int(match value {
+                DataValue::Int(value) => value,
+                DataValue::Real(value) if value.is_finite() => {
+                    let value = value.trunc();
+                    // i64::MAX as f64 rounds up to 2^63
+                    if value < -(2f64.powi(63)) || value >= 2f64.powi(63) {
+                        return None;
+                    }
+                    value as i64
+                }
+                DataValue::Decimal(value) => {
+                    let unit = 10i128.pow(value.scale() as u32);
+                    i64::try_from(value.units() / unit).ok()?
+                }
+                DataValue::Bool(value) => value as i64,
+                DataValue::Text(text) => text.trim().parse().ok()?,
+                _ => return None,
+            }),
+            (ColumnType::Real, value) => DataValue::Real(match value {
+                DataValue::Int(value) => value as f64,
+                DataValue::Real(value) => value,
+                DataValue::Decimal(value) => value.to_f64(),
+                DataValue::Bool(value) => value as i64 as f64,
+                DataValue::Text(text) => text.trim().parse().ok()?,
+                _ => return None,
+            }),
+            (ColumnType::Bool, value) => DataValue::Bool(match value {
+                DataValue::Bool(value) => value,
+                DataValue::Int(value) => value != 0,
+                DataValue::Real(value) => value != 0.0,
+                DataValue::Decimal(value) => value.units() != 0,
+                DataValue::Text(text) => match text.trim() {
+                    text if text.eq_ignore_ascii_case("true") || text == "1" => true,
+                    text if text.eq_ignore_ascii_case("false") || text == "0" => false,
+                    _ => return None,
+                },
+                _ => return None,
+            }),
+            (ColumnType::Text, value) => DataValue::Text(value.to_string().into()),
+            (ColumnType::VarChar(max_len), value) => {
+                let text = value.to_string();
+                DataValue::Text(text.chars().take(*max_len as usize).collect())
+            }
+            (ColumnType::Char(width), value) => {
+                let text = value.to_string();
+                let text = text.chars().take(*width as usize).collect::<String>();
+                DataValue::Text(Char::new(&text, *width as usize).ok()?.into_inner())
+            }
+            (ColumnType::Decimal(precision, scale), value) => {
+                let decimal = match value {
+                    DataValue::Int(value) => Decimal::from_int(value),
+                    DataValue::Real(value) => Decimal::from_f64(value)?,
+                    DataValue::Decimal(value) => value,
+                    DataValue::Bool(value) => Decimal::from_int(value as i64),
+                    DataValue::Text(text) => Decimal::parse(text.trim())?,
+                    _ => return None,
+                };
+                let decimal = decimal.rescale(*scale)?;
+                if !decimal.fits(*precision, *scale) {
+                    return None;
+                }
+                DataValue::Decimal(decimal)
+            }
+            (ColumnType::Date, DataValue::Timestamp(secs)) => {
+                DataValue::Date(i32::try_from(secs.div_euclid(86_400)).ok()?)
+            }
+            // a DATE keeps the day of a full timestamp
+            (ColumnType::Date | ColumnType::Timestamp, DataValue::Text(text)) => {
+                let secs = parse_timestamp(text.trim())?;
+                return self.cast(DataValue::Timestamp(secs));
+            }
+            (ColumnType::Blob, DataValue::Text(text)) => {
+                DataValue::Blob(text.as_bytes().into())
+            }
+            (ty, value) => return ty.coerce(value),
+        };
+        Some(value)
+    }
+
+    /// Returns whether `value` can be stored as is in a column of this type.
+    pub fn accepts(&self, value: &DataValue) -> bool {
+        match (self, value) {
+            (_, DataValue::Nil) => true,
+            (ColumnType::VarChar(max_len), DataValue::Text(text)) => {
+                VarChar::new(text, *max_len as usize).is_ok()
+            }
+            (ColumnType::Char(width), DataValue::Text(text)) => {
+                text.chars().count() == *width as usize
+            }
+            (ColumnType::Decimal(precision, scale), DataValue::Decimal(value)) => {
+                value.fits(*precision, *scale)
+            }
+            (ty, value) => value.data_type() == ty.value_type(),
+        }
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnType::Int => f.write_str("INT"),
+            ColumnType::Real => f.write_str("REAL"),
+            ColumnType::Bool => f.write_str("BOOL"),
+            ColumnType::Text => f.write_str("TEXT"),
+            ColumnType::VarChar(max_len) => write!(f, "VARCHAR({max_len})"),
+            ColumnType::Char(width) => write!(f, "CHAR({width})"),
+            ColumnType::Date => f.write_str("DATE"),
+            ColumnType::Timestamp => f.write_str("TIMESTAMP"),
+            ColumnType::Decimal(precision, scale) => {
+                write!(f, "DECIMAL({precision}, {scale})")
+            }
+            ColumnType::Blob => f.write_str("BLOB"),
         }
     }
 }
@@ -26,6 +249,10 @@ pub enum DataValue {
     Real(f64),
     Bool(bool),
     Text(Box<str>),
+    Date(i32),      // days since 1970-01-01
+    Timestamp(i64), // seconds since 1970-01-01 00:00:00
+    Decimal(Decimal),
+    Blob(Box<[u8]>),
 }
 
 impl DataValue {
@@ -36,6 +263,100 @@ impl DataValue {
             DataValue::Real(_) => DataType::Real,
             DataValue::Bool(_) => DataType::Bool,
             DataValue::Text(_) => DataType::Text,
+            DataValue::Date(_) => DataType::Date,
+            DataValue::Timestamp(_) => DataType::Timestamp,
+            DataValue::Decimal(_) => DataType::Decimal,
+            DataValue::Blob(_) => DataType::Blob,
         }
     }
 }
+
+/// Compares an INT with a REAL exactly, without rounding the INT to the
+/// nearest REAL first (which would make 2^53 + 1 equal 2^53 as REAL).
+/// Returns `None` when `real` is NaN.
+pub fn compare_int_real(int: i64, real: f64) -> Option<Ordering> {
+    if real.is_nan() {
+        return None;
+    }
+    let whole = real.trunc();
+    // i64::MAX as f64 rounds up to 2^63, which no INT reaches
+    if whole >= 2f64.powi(63) {
+        return Some(Ordering::Less);
+    }
+    if whole < -(2f64.powi(63)) {
+        return Some(Ordering::Greater);
+    }
+    match int.cmp(&(whole as i64)) {
+        // equal whole parts, so the fraction decides
+        Ordering::Equal => 0f64.partial_cmp(&(real - whole)),
+        ord => Some(ord),
+    }
+}
+
+impl fmt::Display for DataValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataValue::Nil => f.write_str("nil"),
+            DataValue::Int(value) => write!(f, "{value}"),
+            DataValue::Real(value) => write!(f, "{value}"),
+            DataValue::Bool(value) => write!(f, "{value}"),
+            DataValue::Text(value) => f.write_str(value),
+            DataValue::Date(days) => f.write_str(&format_date(*days)),
+            DataValue::Timestamp(secs) => f.write_str(&format_timestamp(*secs)),
+            DataValue::Decimal(value) => write!(f, "{value}"),
+            DataValue::Blob(bytes) => {
+                bytes.iter().try_for_each(|byte| write!(f, "{byte:02X}"))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColConstraints {
+    pub not_null: bool,
+    pub unique: bool,
+    pub primary_key: bool,
+    pub autoincrement: bool,
+    pub default: Option<DataValue>,
+    pub collation: Collation,
+}
+
+impl ColConstraints {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How text values compare with each other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Collation {
+    /// Byte by byte.
+    #[default]
+    Binary,
+    /// Ignoring letter case, so `'Alice' = 'alice'`. Applies to comparisons,
+    /// ORDER BY, UNIQUE and foreign keys; DISTINCT and set operations still
+    /// compare byte by byte.
+    Nocase,
+}
+
+impl Collation {
+    /// The value to compare in place of `value`: case-folded text under
+    /// NOCASE, anything else unchanged.
+    pub fn key(self, value: DataValue) -> DataValue {
+        match (self, value) {
+            (Collation::Nocase, DataValue::Text(text)) => {
+                DataValue::Text(text.to_lowercase().into())
+            }
+            (_, value) => value,
+        }
+    }
+}
+
+impl fmt::Display for Collation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Collation::Binary => "BINARY",
+            Collation::Nocase => "NOCASE",
+        })
+    }
+}
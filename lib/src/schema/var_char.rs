@@ -0,0 +1,49 @@
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("value too long for VARCHAR({max_len}): {len} characters")]
+pub struct VarCharErr {
+    pub len: usize,
+    pub max_len: usize,
+}
+
+/// UTF-8 text holding at most a fixed number of characters.
+///
+/// Length is counted in Unicode scalar values (`char`s), not bytes or
+/// grapheme clusters, so `VARCHAR(2)` accepts `'한글'` even though it is six
+/// bytes long. The limit belongs to the column (`VARCHAR(n)`), so it is
+/// checked on construction rather than stored with every value.
+///
+/// Values order byte-wise by their UTF-8 encoding, which matches how TEXT
+/// values compare in queries.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VarChar {
+    text: Box<str>,
+}
+
+impl VarChar {
+    pub fn new(text: &str, max_len: usize) -> Result<Self, VarCharErr> {
+        let len = text.chars().count();
+        if len > max_len {
+            return Err(VarCharErr { len, max_len });
+        }
+        Ok(Self { text: Box::from(text) })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Length in Unicode scalar values.
+    pub fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn into_inner(self) -> Box<str> {
+        self.text
+    }
+}
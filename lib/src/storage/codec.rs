@@ -1,5 +1,5 @@
 use super::error::{Result, StorageErr};
-use crate::schema::{DataType, DataValue};
+use crate::schema::{ColumnType, DataType, DataValue, Decimal};
 use std::io::Read;
 
 pub struct Decoder<R: Read> {
@@ -39,12 +39,24 @@ impl<R: Read> Decoder<R> {
         Ok(u64::from_le_bytes(buf))
     }
 
+    pub fn i32(&mut self) -> Result<i32> {
+        let mut buf = [0u8; 4];
+        self.r.read_exact(&mut buf)?;
+        Ok(i32::from_le_bytes(buf))
+    }
+
     pub fn i64(&mut self) -> Result<i64> {
         let mut buf = [0u8; 8];
         self.r.read_exact(&mut buf)?;
         Ok(i64::from_le_bytes(buf))
     }
 
+    pub fn i128(&mut self) -> Result<i128> {
+        let mut buf = [0u8; 16];
+        self.r.read_exact(&mut buf)?;
+        Ok(i128::from_le_bytes(buf))
+    }
+
     pub fn f64(&mut self) -> Result<f64> {
         let mut buf = [0u8; 8];
         self.r.read_exact(&mut buf)?;
@@ -69,6 +81,13 @@ impl<R: Read> Decoder<R> {
             .map_err(|e| StorageErr::Corrupted(format!("invalid UTF-8: {e}")))
     }
 
+    pub fn bytes(&mut self) -> Result<Box<[u8]>> {
+        let len = self.u32()? as usize;
+        let mut buf = vec![0u8; len];
+        self.r.read_exact(&mut buf)?;
+        Ok(buf.into_boxed_slice())
+    }
+
     pub fn ty(&mut self) -> Result<DataType> {
         let ty_id = self.u8()?;
         match ty_id {
@@ -77,10 +96,33 @@ impl<R: Read> Decoder<R> {
             2 => Ok(DataType::Real),
             3 => Ok(DataType::Bool),
             4 => Ok(DataType::Text),
+            7 => Ok(DataType::Date),
+            8 => Ok(DataType::Timestamp),
+            9 => Ok(DataType::Decimal),
+            10 => Ok(DataType::Blob),
             _ => Err(StorageErr::Corrupted(format!("invalid type id: {ty_id}"))),
         }
     }
 
+    /// Reads a column type. Ids match the [`DataType`] ids for plain types;
+    /// VARCHAR and CHAR are followed by their length.
+    pub fn col_type(&mut self) -> Result<ColumnType> {
+        let ty_id = self.u8()?;
+        match ty_id {
+            1 => Ok(ColumnType::Int),
+            2 => Ok(ColumnType::Real),
+            3 => Ok(ColumnType::Bool),
+            4 => Ok(ColumnType::Text),
+            5 => self.u32().map(ColumnType::VarChar),
+            6 => self.u32().map(ColumnType::Char),
+            7 => Ok(ColumnType::Date),
+            8 => Ok(ColumnType::Timestamp),
+            9 => Ok(ColumnType::Decimal(self.u8()?, self.u8()?)),
+            10 => Ok(ColumnType::Blob),
+            _ => Err(StorageErr::Corrupted(format!("invalid column type id: {ty_id}"))),
+        }
+    }
+
     pub fn value(&mut self, ty: DataType) -> Result<DataValue> {
         match ty {
             DataType::Nil => Ok(DataValue::Nil),
@@ -88,7 +130,32 @@ impl<R: Read> Decoder<R> {
             DataType::Real => self.f64().map(DataValue::Real),
             DataType::Bool => self.bool().map(DataValue::Bool),
             DataType::Text => self.text().map(DataValue::Text),
+            DataType::Date => self.i32().map(DataValue::Date),
+            DataType::Timestamp => self.i64().map(DataValue::Timestamp),
+            DataType::Decimal => {
+                let units = self.i128()?;
+                let scale = self.u8()?;
+                Ok(DataValue::Decimal(Decimal::new(units, scale)))
+            }
+            DataType::Blob => self.bytes().map(DataValue::Blob),
+        }
+    }
+
+    /// Reads a row written by [`Encoder::row`].
+    pub fn row(&mut self) -> Result<Vec<DataValue>> {
+        let count = self.u32()? as usize;
+        let mut nulls = vec![0u8; count.div_ceil(8)];
+        self.r.read_exact(&mut nulls)?;
+        let mut values = Vec::with_capacity(count);
+        for i in 0..count {
+            if nulls[i / 8] & (1 << (i % 8)) != 0 {
+                values.push(DataValue::Nil);
+            } else {
+                let ty = self.ty()?;
+                values.push(self.value(ty)?);
+            }
         }
+        Ok(values)
     }
 }
 
@@ -125,10 +192,18 @@ impl Encoder {
         self.buf.extend_from_slice(&v.to_le_bytes());
     }
 
+    pub fn i32(&mut self, v: i32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
     pub fn i64(&mut self, v: i64) {
         self.buf.extend_from_slice(&v.to_le_bytes());
     }
 
+    pub fn i128(&mut self, v: i128) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
     pub fn f64(&mut self, v: f64) {
         self.buf.extend_from_slice(&v.to_le_bytes());
     }
@@ -142,6 +217,11 @@ impl Encoder {
         self.buf.extend_from_slice(s.as_bytes());
     }
 
+    pub fn bytes(&mut self, b: &[u8]) {
+        self.buf.extend_from_slice(&(b.len() as u32).to_le_bytes());
+        self.buf.extend_from_slice(b);
+    }
+
     pub fn ty(&mut self, ty: DataType) {
         let ty_id = match ty {
             DataType::Nil => 0,
@@ -149,10 +229,38 @@ impl Encoder {
             DataType::Real => 2,
             DataType::Bool => 3,
             DataType::Text => 4,
+            DataType::Date => 7,
+            DataType::Timestamp => 8,
+            DataType::Decimal => 9,
+            DataType::Blob => 10,
         };
         self.u8(ty_id);
     }
 
+    pub fn col_type(&mut self, ty: ColumnType) {
+        let ty_id = match ty {
+            ColumnType::Int => 1,
+            ColumnType::Real => 2,
+            ColumnType::Bool => 3,
+            ColumnType::Text => 4,
+            ColumnType::VarChar(_) => 5,
+            ColumnType::Char(_) => 6,
+            ColumnType::Date => 7,
+            ColumnType::Timestamp => 8,
+            ColumnType::Decimal(..) => 9,
+            ColumnType::Blob => 10,
+        };
+        self.u8(ty_id);
+        match ty {
+            ColumnType::VarChar(len) | ColumnType::Char(len) => self.u32(len),
+            ColumnType::Decimal(precision, scale) => {
+                self.u8(precision);
+                self.u8(scale);
+            }
+            _ => {}
+        }
+    }
+
     pub fn value(&mut self, val: &DataValue) {
         match val {
             DataValue::Nil => (),
@@ -160,6 +268,30 @@ impl Encoder {
             DataValue::Real(r) => self.f64(*r),
             DataValue::Bool(b) => self.bool(*b),
             DataValue::Text(s) => self.text(s),
+            DataValue::Date(d) => self.i32(*d),
+            DataValue::Timestamp(t) => self.i64(*t),
+            DataValue::Decimal(d) => {
+                self.i128(d.units());
+                self.u8(d.scale());
+            }
+            DataValue::Blob(b) => self.bytes(b),
+        }
+    }
+
+    /// Writes a row as a value count, a NULL bitmap (bit `i` set when value
+    /// `i` is NULL) and the tagged non-NULL values in order.
+    pub fn row(&mut self, values: &[DataValue]) {
+        self.u32(values.len() as u32);
+        let mut nulls = vec![0u8; values.len().div_ceil(8)];
+        for (i, value) in values.iter().enumerate() {
+            if *value == DataValue::Nil {
+                nulls[i / 8] |= 1 << (i % 8);
+            }
+        }
+        self.buf.extend_from_slice(&nulls);
+        for value in values.iter().filter(|value| **value != DataValue::Nil) {
+            self.ty(value.data_type());
+            self.value(value);
         }
     }
 }
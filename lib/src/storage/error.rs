@@ -38,6 +38,9 @@ pub enum StorageErr {
     #[error("invalid row: {0}")]
     InvalidRow(&'static str),
 
+    #[error("invalid transaction: {0}")]
+    InvalidTxn(&'static str),
+
     #[error("invalid record tag: {0}")]
     InvalidRecordTag(u8),
 }
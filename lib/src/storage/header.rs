@@ -12,8 +12,11 @@ pub struct FileHeader {
 }
 
 impl FileHeader {
+    /// The header stores a crc32 of its first 8 bytes at offset 8.
+    pub const CHECKSUM: u16 = 1 << 0;
+
     pub fn new() -> Self {
-        Self { flags: 0 }
+        Self { flags: Self::CHECKSUM }
     }
 
     pub fn write_to(&self, w: &mut impl Write) -> Result<()> {
@@ -21,14 +24,18 @@ impl FileHeader {
         e.u32(MAGIC);
         e.u8(VERSION);
         e.u8(HEADER_LEN);
-        e.u16(self.flags);
+        e.u16(self.flags | Self::CHECKSUM);
+        let crc = crc32fast::hash(e.as_slice());
+        e.u32(crc);
         w.write_all(e.as_slice())?;
-        w.write_all(&[0u8; 56])?;
+        w.write_all(&[0u8; 52])?;
         Ok(())
     }
 
     pub fn read_from(r: &mut impl Read) -> Result<Self> {
-        let mut d = Decoder::new(r);
+        let mut buf = [0u8; HEADER_LEN as usize];
+        r.read_exact(&mut buf)?;
+        let mut d = Decoder::new(buf.as_slice());
         if d.u32()? != MAGIC {
             return Err(StorageErr::Corrupted("magic mismatch".into()));
         } else if d.u8()? != VERSION {
@@ -37,8 +44,10 @@ impl FileHeader {
             return Err(StorageErr::Corrupted("unexpected header length".into()));
         }
         let flags = d.u16()?;
-        let mut reserved = [0u8; 56];
-        d.into_inner().read_exact(&mut reserved)?;
+        // files written before the checksum was added leave the flag unset
+        if flags & Self::CHECKSUM != 0 && d.u32()? != crc32fast::hash(&buf[..8]) {
+            return Err(StorageErr::Corrupted("invalid header crc".into()));
+        }
         Ok(Self { flags })
     }
 }
@@ -0,0 +1,146 @@
+use super::RowId;
+use crate::schema::{DataValue, Decimal, compare_int_real};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
+
+/// Index key with a total order across value types.
+///
+/// Values of different types order as BOOL < numbers < TEXT < dates < BLOB.
+/// INT, REAL and DECIMAL keys compare numerically with each other, and DATE
+/// and TIMESTAMP keys compare chronologically.
+#[derive(Debug, Clone)]
+pub struct IndexKey(pub DataValue);
+
+impl IndexKey {
+    fn rank(&self) -> u8 {
+        match self.0 {
+            DataValue::Nil => 0,
+            DataValue::Bool(_) => 1,
+            DataValue::Int(_) | DataValue::Real(_) | DataValue::Decimal(_) => 2,
+            DataValue::Text(_) => 3,
+            DataValue::Date(_) | DataValue::Timestamp(_) => 4,
+            DataValue::Blob(_) => 5,
+        }
+    }
+}
+
+impl Ord for IndexKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.0, &other.0) {
+            (DataValue::Bool(left), DataValue::Bool(right)) => left.cmp(right),
+            (DataValue::Int(left), DataValue::Int(right)) => left.cmp(right),
+            // exact, falling back to the REAL order for NaN and -0.0
+            (DataValue::Int(left), DataValue::Real(right)) => {
+                compare_int_real(*left, *right)
+                    .filter(|ord| ord.is_ne())
+                    .unwrap_or_else(|| (*left as f64).total_cmp(right))
+            }
+            (DataValue::Real(left), DataValue::Int(right)) => {
+                compare_int_real(*right, *left)
+                    .filter(|ord| ord.is_ne())
+                    .unwrap_or_else(|| (*right as f64).total_cmp(left))
+                    .reverse()
+            }
+            (DataValue::Real(left), DataValue::Real(right)) => left.total_cmp(right),
+            (DataValue::Decimal(left), DataValue::Decimal(right)) => left.cmp(right),
+            (DataValue::Decimal(left), DataValue::Int(right)) => {
+                left.cmp(&Decimal::from_int(*right))
+            }
+            (DataValue::Int(left), DataValue::Decimal(right)) => {
+                Decimal::from_int(*left).cmp(right)
+            }
+            // through the REAL's shortest decimal form, as the executor
+            // compares them, falling back to the REAL order for values no
+            // DECIMAL holds
+            (DataValue::Decimal(left), DataValue::Real(right)) => {
+                match Decimal::from_f64(*right) {
+                    Some(right) => left.cmp(&right),
+                    None => left.to_f64().total_cmp(right),
+                }
+            }
+            (DataValue::Real(_), DataValue::Decimal(_)) => other.cmp(self).reverse(),
+            (DataValue::Text(left), DataValue::Text(right)) => left.cmp(right),
+            (DataValue::Date(left), DataValue::Date(right)) => left.cmp(right),
+            (DataValue::Date(left), DataValue::Timestamp(right)) => {
+                (*left as i64 * 86_400).cmp(right)
+            }
+            (DataValue::Timestamp(left), DataValue::Date(right)) => {
+                left.cmp(&(*right as i64 * 86_400))
+            }
+            (DataValue::Timestamp(left), DataValue::Timestamp(right)) => {
+                left.cmp(right)
+            }
+            (DataValue::Blob(left), DataValue::Blob(right)) => left.cmp(right),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for IndexKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for IndexKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for IndexKey {}
+
+/// Ordered index from a column value to the rows holding it.
+///
+/// NULL values are never indexed, so lookups only return rows whose value
+/// is non-NULL.
+#[derive(Debug, Clone, Default)]
+pub struct Index {
+    entries: BTreeMap<IndexKey, BTreeSet<RowId>>,
+}
+
+impl Index {
+    pub fn get(&self, key: &DataValue) -> impl Iterator<Item = RowId> + '_ {
+        self.entries.get(&IndexKey(key.clone())).into_iter().flatten().copied()
+    }
+
+    pub fn insert(&mut self, key: DataValue, row_id: RowId) {
+        if key != DataValue::Nil {
+            self.entries.entry(IndexKey(key)).or_default().insert(row_id);
+        }
+    }
+
+    pub fn delete(&mut self, key: &DataValue, row_id: RowId) {
+        let key = IndexKey(key.clone());
+        if let Some(rows) = self.entries.get_mut(&key) {
+            rows.remove(&row_id);
+            if rows.is_empty() {
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    /// Returns the rows whose key lies between `low` and `high`, in key order.
+    pub fn range(
+        &self,
+        low: Bound<&DataValue>,
+        high: Bound<&DataValue>,
+    ) -> impl Iterator<Item = RowId> + '_ {
+        let to_key = |bound: Bound<&DataValue>| bound.map(|v| IndexKey(v.clone()));
+        let (low, high) = (to_key(low), to_key(high));
+        let empty = match (&low, &high) {
+            (Bound::Included(l), Bound::Included(h)) => l > h,
+            (Bound::Included(l) | Bound::Excluded(l), Bound::Excluded(h))
+            | (Bound::Excluded(l), Bound::Included(h)) => l >= h,
+            _ => false,
+        };
+        // `BTreeMap::range` panics on inverted bounds
+        let entries = if empty { None } else { Some(self.entries.range((low, high))) };
+        entries.into_iter().flatten().flat_map(|(_, rows)| rows.iter().copied())
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,104 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Where the log lives: a file on disk, or a buffer for `:memory:`.
+#[derive(Debug)]
+pub(super) enum LogFile {
+    Disk(File),
+    Memory(MemFile),
+}
+
+/// A growable byte buffer with a cursor, standing in for a file.
+#[derive(Debug, Default)]
+pub(super) struct MemFile {
+    buf: Vec<u8>,
+    pos: u64,
+}
+
+impl LogFile {
+    pub fn len(&self) -> io::Result<u64> {
+        match self {
+            LogFile::Disk(file) => Ok(file.metadata()?.len()),
+            LogFile::Memory(mem) => Ok(mem.buf.len() as u64),
+        }
+    }
+
+    /// Truncates or zero-extends the log. The cursor is left where it was.
+    pub fn set_len(&mut self, len: u64) -> io::Result<()> {
+        match self {
+            LogFile::Disk(file) => file.set_len(len),
+            LogFile::Memory(mem) => {
+                mem.buf.resize(len as usize, 0);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn sync_data(&self) -> io::Result<()> {
+        match self {
+            LogFile::Disk(file) => file.sync_data(),
+            LogFile::Memory(_) => Ok(()),
+        }
+    }
+}
+
+impl Read for LogFile {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        match self {
+            LogFile::Disk(file) => file.read(out),
+            LogFile::Memory(mem) => {
+                let start = (mem.pos as usize).min(mem.buf.len());
+                let n = out.len().min(mem.buf.len() - start);
+                out[..n].copy_from_slice(&mem.buf[start..start + n]);
+                mem.pos += n as u64;
+                Ok(n)
+            }
+        }
+    }
+}
+
+impl Write for LogFile {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        match self {
+            LogFile::Disk(file) => file.write(data),
+            LogFile::Memory(mem) => {
+                let start = mem.pos as usize;
+                let end = start + data.len();
+                if mem.buf.len() < end {
+                    mem.buf.resize(end, 0);
+                }
+                mem.buf[start..end].copy_from_slice(data);
+                mem.pos = end as u64;
+                Ok(data.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            LogFile::Disk(file) => file.flush(),
+            LogFile::Memory(_) => Ok(()),
+        }
+    }
+}
+
+impl Seek for LogFile {
+    fn seek(&mut self, to: SeekFrom) -> io::Result<u64> {
+        match self {
+            LogFile::Disk(file) => file.seek(to),
+            LogFile::Memory(mem) => {
+                let pos = match to {
+                    SeekFrom::Start(pos) => Some(pos),
+                    SeekFrom::End(delta) => {
+                        (mem.buf.len() as u64).checked_add_signed(delta)
+                    }
+                    SeekFrom::Current(delta) => mem.pos.checked_add_signed(delta),
+                };
+                mem.pos = pos.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "seek before start")
+                })?;
+                Ok(mem.pos)
+            }
+        }
+    }
+}
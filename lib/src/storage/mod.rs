@@ -1,17 +1,23 @@
 mod codec;
 mod header;
+mod index;
+mod memory;
 mod record;
 mod state;
 
 pub mod error;
 
-use crate::schema::{DataType, DataValue};
+use crate::schema::{ColConstraints, ColumnType, DataValue};
 use error::Result;
 pub use error::StorageErr;
-use header::FileHeader;
+use header::{FileHeader, HEADER_LEN};
+pub use index::{Index, IndexKey};
+use memory::{LogFile, MemFile};
 use record::*;
-pub use state::{ColState, DbState, RowState, TableState};
+use state::Undo;
+pub use state::{ColState, DbState, ForeignKey, RowState, TableState};
 use std::fs::File;
+use std::io::{BufReader, BufWriter, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -50,54 +56,439 @@ impl From<u32> for SeqNo {
     }
 }
 
+/// When the file is synced to disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Sync on every commit. A committed statement survives a power loss.
+    #[default]
+    Full,
+    /// Sync only on vacuum and when the storage is dropped. A crash of the
+    /// process loses nothing, but a power loss may drop recent commits.
+    /// Recovery still truncates the file back to a consistent commit.
+    Normal,
+    /// Never sync and leave flushing to the OS. Fastest, with the same
+    /// guarantees as `Normal` minus the sync on drop.
+    Off,
+}
+
 #[derive(Debug)]
 pub struct Storage {
     pub path: PathBuf,
     pub state: DbState,
     header: FileHeader,
-    file: File,
+    file: LogFile,
+    batch: Option<Batch>,
+    sync_mode: SyncMode,
+    writes: u64,
+}
+
+/// A position inside the current batch, returned by `Storage::savepoint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Savepoint {
+    offset: u64,
+    seq_no: SeqNo,
+    undo: usize,
+}
+
+/// Writes issued between `Storage::begin` and `Storage::commit`.
+#[derive(Debug)]
+struct Batch {
+    /// File offset where the batch starts.
+    start: u64,
+    /// Sequence number of the first record of the batch.
+    seq_no: SeqNo,
+    /// Whether the `TxnBegin` record has been written.
+    written: bool,
+    /// How to undo each write to the state, oldest first.
+    undo: Vec<Undo>,
 }
 
 impl Storage {
+    /// Database name that stands for an in-memory database.
+    pub const MEMORY: &str = ":memory:";
+
+    /// Opens an existing database file and replays its records.
     pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
         let path = path.into();
-        match File::options().read(true).write(true).open(&path) {
-            Ok(mut file) => {
-                let header = FileHeader::read_from(&mut file)?;
-                let mut storage =
-                    Self { path, file, header, state: DbState::default() };
-                storage.replay()?;
-                Ok(storage)
-            }
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                let mut file = File::options()
-                    .read(true)
-                    .write(true)
-                    .create_new(true)
-                    .open(&path)?;
-                let header = FileHeader::new();
-                header.write_to(&mut file)?;
-                Ok(Self { path, file, header, state: DbState::default() })
+        let mut file = File::options().read(true).write(true).open(&path)?;
+        let header = FileHeader::read_from(&mut file)?;
+        let mut storage = Self {
+            path,
+            file: LogFile::Disk(file),
+            header,
+            state: DbState::default(),
+            batch: None,
+            sync_mode: SyncMode::default(),
+            writes: 0,
+        };
+        storage.recover()?;
+        Ok(storage)
+    }
+
+    /// Creates a new, empty database file. Fails if the file already exists.
+    pub fn create(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut file =
+            File::options().read(true).write(true).create_new(true).open(&path)?;
+        let header = FileHeader::new();
+        header.write_to(&mut file)?;
+        Ok(Self {
+            path,
+            file: LogFile::Disk(file),
+            header,
+            state: DbState::default(),
+            batch: None,
+            sync_mode: SyncMode::default(),
+            writes: 0,
+        })
+    }
+
+    /// Creates a database that lives only in memory and is gone once the
+    /// storage is dropped.
+    pub fn memory() -> Self {
+        let header = FileHeader::new();
+        let mut file = LogFile::Memory(MemFile::default());
+        // ! writing to memory cannot fail
+        header.write_to(&mut file).unwrap();
+        Self {
+            path: PathBuf::from(Self::MEMORY),
+            file,
+            header,
+            state: DbState::default(),
+            batch: None,
+            sync_mode: SyncMode::default(),
+            writes: 0,
+        }
+    }
+
+    /// Opens the database file, creating it first if it does not exist. The
+    /// name [`Storage::MEMORY`] gives a new in-memory database instead.
+    pub fn open_or_create(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if path.as_os_str() == Self::MEMORY {
+            return Ok(Self::memory());
+        }
+        match Self::open(&path) {
+            Err(StorageErr::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Self::create(path)
             }
-            Err(e) => Err(e.into()),
+            result => result,
+        }
+    }
+
+    /// Sets when the file is synced, e.g. `Storage::open(path)?.with_sync_mode(..)`.
+    pub fn with_sync_mode(mut self, sync_mode: SyncMode) -> Self {
+        self.sync_mode = sync_mode;
+        self
+    }
+
+    pub fn sync_mode(&self) -> SyncMode {
+        self.sync_mode
+    }
+
+    /// Number of writes to the log since the storage was opened, not
+    /// counting vacuum.
+    pub fn write_count(&self) -> u64 {
+        self.writes
+    }
+}
+
+impl Drop for Storage {
+    fn drop(&mut self) {
+        if self.sync_mode == SyncMode::Normal {
+            let _ = self.file.sync_data();
         }
     }
 }
 
 impl Storage {
-    fn replay(&mut self) -> Result<()> {
+    /// Rebuilds the in-memory state by replaying the log.
+    ///
+    /// Records between `TxnBegin` and `TxnCommit` are applied only once the
+    /// commit record is read. A torn last record or an uncommitted batch left
+    /// by a crash is truncated, so the file ends at the last commit.
+    pub fn recover(&mut self) -> Result<()> {
+        self.batch = None;
+        let committed = self.replay()?;
+        if committed < self.file.len()? {
+            self.file.set_len(committed)?;
+        }
+        self.file.seek(SeekFrom::Start(committed))?;
+        Ok(())
+    }
+
+    /// Replays the whole file into a fresh state and returns the offset just
+    /// past the last commit.
+    fn replay(&mut self) -> Result<u64> {
+        self.state = DbState::default();
+        let len = self.file.len()?;
+        let mut reader = BufReader::new(&mut self.file);
+        reader.seek(SeekFrom::Start(HEADER_LEN as u64))?;
+        let mut committed = HEADER_LEN as u64;
+        let mut pending: Option<Vec<Record>> = None;
         loop {
-            match read_rec(&mut self.file) {
-                Ok(record) => {
-                    self.state.next_seq_no();
-                    self.state.commit(record);
-                }
+            let offset = reader.stream_position()?;
+            let record = match read_rec(&mut reader) {
+                Ok(record) => record,
                 Err(StorageErr::Io(e))
                     if e.kind() == std::io::ErrorKind::UnexpectedEof =>
                 {
                     break;
                 }
+                Err(StorageErr::Corrupted(_)) if reader.stream_position()? == len => {
+                    break;
+                }
+                Err(StorageErr::Corrupted(msg)) => {
+                    return Err(StorageErr::Corrupted(format!(
+                        "{msg} at offset {offset}"
+                    )));
+                }
                 Err(e) => return Err(e),
+            };
+            self.state.next_seq_no();
+            match (record, &mut pending) {
+                (Record::TxnBegin, None) => pending = Some(Vec::new()),
+                (Record::TxnBegin, Some(_)) => {
+                    return Err(StorageErr::Corrupted(format!(
+                        "nested transaction at offset {offset}"
+                    )));
+                }
+                (Record::TxnCommit, _) => {
+                    for record in pending.take().into_iter().flatten() {
+                        Self::apply(&mut self.state, record, offset)?;
+                    }
+                }
+                (record, Some(batch)) => batch.push(record),
+                (record, None) => Self::apply(&mut self.state, record, offset)?,
+            }
+            if pending.is_none() {
+                committed = reader.stream_position()?;
+            }
+        }
+        Ok(committed)
+    }
+
+    /// Commits a replayed record after checking it against the current schema.
+    fn apply(state: &mut DbState, record: Record, offset: u64) -> Result<()> {
+        state.validate(&record).map_err(|e| match e {
+            StorageErr::Corrupted(msg) => {
+                StorageErr::Corrupted(format!("{msg} at offset {offset}"))
+            }
+            e => e,
+        })?;
+        state.commit(record);
+        Ok(())
+    }
+
+    pub fn in_txn(&self) -> bool {
+        self.batch.is_some()
+    }
+
+    /// Starts a batch of writes that becomes durable as a whole on `commit`.
+    pub fn begin(&mut self) -> Result<()> {
+        if self.batch.is_some() {
+            return Err(StorageErr::InvalidTxn("transaction already active"));
+        }
+        self.batch = Some(Batch {
+            start: self.file.stream_position()?,
+            seq_no: self.state.seq_no(),
+            written: false,
+            undo: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Writes the commit record for the current batch and syncs the file.
+    pub fn commit(&mut self) -> Result<()> {
+        let batch =
+            self.batch.take().ok_or(StorageErr::InvalidTxn("no active transaction"))?;
+        if batch.written {
+            let seq = self.state.next_seq_no();
+            write_rec(&mut self.file, &TxnCommit, seq)?;
+            self.writes += 1;
+            if self.sync_mode == SyncMode::Full {
+                self.file.sync_data()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks the current position in the batch for `rollback_to`.
+    pub fn savepoint(&mut self) -> Result<Savepoint> {
+        let Some(batch) = &self.batch else {
+            return Err(StorageErr::InvalidTxn("no active transaction"));
+        };
+        Ok(Savepoint {
+            offset: self.file.stream_position()?,
+            seq_no: self.state.seq_no(),
+            undo: batch.undo.len(),
+        })
+    }
+
+    /// Discards the writes made after `savepoint` and keeps the batch open.
+    /// The file is cut back to the savepoint and the state restored from the
+    /// batch's undo log, so nothing is replayed.
+    pub fn rollback_to(&mut self, savepoint: Savepoint) -> Result<()> {
+        let batch = match &mut self.batch {
+            Some(batch) if savepoint.offset >= batch.start => batch,
+            Some(_) => {
+                return Err(StorageErr::InvalidTxn(
+                    "savepoint is not in this transaction",
+                ));
+            }
+            None => return Err(StorageErr::InvalidTxn("no active transaction")),
+        };
+        if savepoint.offset >= self.file.stream_position()? {
+            return Ok(());
+        }
+        self.file.set_len(savepoint.offset)?;
+        self.file.seek(SeekFrom::Start(savepoint.offset))?;
+        for undo in batch.undo.drain(savepoint.undo..).rev() {
+            self.state.undo(undo);
+        }
+        self.state.reset_seq_no(savepoint.seq_no);
+        batch.written = savepoint.offset > batch.start;
+        Ok(())
+    }
+
+    /// Discards the current batch, restoring the state as of `begin`.
+    pub fn rollback(&mut self) -> Result<()> {
+        let batch =
+            self.batch.take().ok_or(StorageErr::InvalidTxn("no active transaction"))?;
+        if batch.written {
+            self.file.set_len(batch.start)?;
+            self.file.seek(SeekFrom::Start(batch.start))?;
+        }
+        for undo in batch.undo.into_iter().rev() {
+            self.state.undo(undo);
+        }
+        self.state.reset_seq_no(batch.seq_no);
+        Ok(())
+    }
+
+    /// Remembers how to undo a write the batch is about to commit to the
+    /// state. Writes outside a batch cannot be rolled back.
+    fn save_undo(&mut self, undo: impl FnOnce(&DbState) -> Undo) {
+        if let Some(batch) = &mut self.batch {
+            batch.undo.push(undo(&self.state));
+        }
+    }
+
+    fn append(&mut self, rec: &impl Recordable) -> Result<()> {
+        self.append_all(std::slice::from_ref(rec))
+    }
+
+    /// Appends records with a single write to the file.
+    fn append_all(&mut self, recs: &[impl Recordable]) -> Result<()> {
+        let mut buf = Vec::new();
+        // the begin record is written lazily, so read-only batches leave no trace
+        if let Some(batch) = &mut self.batch
+            && !batch.written
+        {
+            batch.written = true;
+            let seq = self.state.next_seq_no();
+            write_rec(&mut buf, &TxnBegin, seq)?;
+        }
+        for rec in recs {
+            let seq = self.state.next_seq_no();
+            write_rec(&mut buf, rec, seq)?;
+        }
+        self.file.write_all(&buf)?;
+        self.writes += 1;
+        Ok(())
+    }
+}
+
+impl Storage {
+    /// Rewrites the file with only the live tables, columns and rows.
+    ///
+    /// Dropped objects, deleted rows and superseded updates are discarded.
+    /// The compacted log is written to a side file and renamed over the
+    /// original, so a crash leaves either the old or the new file intact.
+    pub fn vacuum(&mut self) -> Result<()> {
+        if self.batch.is_some() {
+            return Err(StorageErr::InvalidTxn("cannot vacuum inside a transaction"));
+        }
+        if let LogFile::Memory(_) = self.file {
+            let mut file = LogFile::Memory(MemFile::default());
+            self.write_compacted(&mut file)?;
+            self.file = file;
+            return self.recover();
+        }
+        let tmp_path = self.path.with_extension("vacuum");
+        let tmp =
+            File::options().write(true).create(true).truncate(true).open(&tmp_path)?;
+        let mut w = BufWriter::new(tmp);
+        self.write_compacted(&mut w)?;
+        let tmp = w.into_inner().map_err(|e| e.into_error())?;
+        if self.sync_mode != SyncMode::Off {
+            tmp.sync_all()?;
+        }
+        drop(tmp);
+
+        std::fs::rename(&tmp_path, &self.path)?;
+        let file = File::options().read(true).write(true).open(&self.path)?;
+        self.file = LogFile::Disk(file);
+        self.recover()
+    }
+
+    /// Writes the header and the records of the live objects.
+    fn write_compacted(&self, w: &mut impl Write) -> Result<()> {
+        self.header.write_to(w)?;
+
+        let mut seq = SeqNo(0);
+        let mut next_seq = || {
+            seq.0 += 1;
+            seq
+        };
+        let mut tables =
+            self.state.tables.values().filter(|t| t.alive).collect::<Vec<_>>();
+        tables.sort_by_key(|table| table.id);
+        for table in tables {
+            let table_id = table.id;
+            let rec = TableCreate { table_id, table_name: table.name.clone() };
+            write_rec(w, &rec, next_seq())?;
+            let cols = table.live_cols().collect::<Vec<_>>();
+            for col in &cols {
+                let rec = ColumnCreate {
+                    table_id,
+                    col_id: col.id,
+                    col_type: col.data_type,
+                    col_name: col.name.clone(),
+                };
+                write_rec(w, &rec, next_seq())?;
+                if !col.constraints.is_empty() {
+                    // keep the sequence, deleted rows no longer carry it
+                    let rec = ColumnConstraint {
+                        table_id,
+                        col_id: col.id,
+                        constraints: col.constraints.clone(),
+                        sequence: col.sequence,
+                    };
+                    write_rec(w, &rec, next_seq())?;
+                }
+            }
+            for row in table.live_rows() {
+                let values = cols.iter().map(|col| row.value(col)).collect::<Vec<_>>();
+                let count = values.len() as u64;
+                let rec = RowInsert { table_id, row_id: row.id, count, values };
+                write_rec(w, &rec, next_seq())?;
+            }
+        }
+        // table constraints go after every table, so the columns they name
+        // exist on replay
+        let mut tables =
+            self.state.tables.values().filter(|t| t.alive).collect::<Vec<_>>();
+        tables.sort_by_key(|table| table.id);
+        for table in tables {
+            for foreign_key in &table.foreign_keys {
+                let rec =
+                    ForeignKeyCreate { table_id: table.id, foreign_key: *foreign_key };
+                write_rec(w, &rec, next_seq())?;
+            }
+            for expr in &table.checks {
+                let rec = CheckCreate { table_id: table.id, expr: expr.clone() };
+                write_rec(w, &rec, next_seq())?;
             }
         }
         Ok(())
@@ -114,10 +505,10 @@ impl Storage {
         }
         // build record
         let table_id = self.state.alloc_table();
-        let seq = self.state.next_seq_no();
         let rec = TableCreate { table_id, table_name: name.into() };
         // write then commit
-        write_rec(&mut self.file, &rec, seq)?;
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_table(table_id));
         self.state.commit_table_create(rec);
         Ok(table_id)
     }
@@ -131,22 +522,22 @@ impl Storage {
             return Err(StorageErr::TableNotFound(table_id));
         }
         // build record
-        let seq = self.state.next_seq_no();
         let rec = TableTruncate { table_id };
         // write then commit
-        write_rec(&mut self.file, &rec, seq)?;
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_table(table_id));
         self.state.commit_table_truncate(rec);
         Ok(())
     }
 
     pub fn rename_table(&mut self, table_id: TableId, new_name: &str) -> Result<()> {
-        if let Some(existing) = self.state.get_table_by_name(new_name) {
-            if existing.id != table_id {
-                return Err(StorageErr::TableAlreadyExists {
-                    id: existing.id,
-                    name: new_name.into(),
-                });
-            }
+        if let Some(existing) = self.state.get_table_by_name(new_name)
+            && existing.id != table_id
+        {
+            return Err(StorageErr::TableAlreadyExists {
+                id: existing.id,
+                name: new_name.into(),
+            });
         }
 
         let table = self
@@ -157,10 +548,10 @@ impl Storage {
             return Err(StorageErr::TableNotFound(table_id));
         }
 
-        let seq = self.state.next_seq_no();
         let rec = TableRename { table_id, new_table_name: new_name.into() };
 
-        write_rec(&mut self.file, &rec, seq)?;
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_table(table_id));
         self.state.commit_table_rename(rec);
         Ok(())
     }
@@ -173,11 +564,16 @@ impl Storage {
         if !table.alive {
             return Err(StorageErr::TableNotFound(table_id));
         }
+        if self.state.referencing(table_id).any(|(child, _)| child.id != table_id) {
+            return Err(StorageErr::InvalidSchema(
+                "table is referenced by a FOREIGN KEY",
+            ));
+        }
         // build record
-        let seq = self.state.next_seq_no();
         let rec = TableDrop { table_id };
         // write then commit
-        write_rec(&mut self.file, &rec, seq)?;
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_table(table_id));
         self.state.commit_table_drop(rec);
         Ok(())
     }
@@ -191,8 +587,9 @@ impl Storage {
     pub fn create_column(
         &mut self,
         table_id: TableId,
-        col_type: DataType,
+        col_type: ColumnType,
         name: &str,
+        constraints: ColConstraints,
     ) -> Result<ColId> {
         // validate
         let table = self
@@ -210,20 +607,83 @@ impl Storage {
 
         // build record
         let col_id = self.state.alloc_col();
-        let seq = self.state.next_seq_no();
         let rec = ColumnCreate { table_id, col_id, col_type, col_name: name.into() };
 
         // write then commit
-        write_rec(&mut self.file, &rec, seq)?;
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_table(table_id));
         self.state.commit_column_create(rec);
+        if !constraints.is_empty() {
+            let rec = ColumnConstraint { table_id, col_id, constraints, sequence: 0 };
+            self.append(&rec)?;
+            self.state.commit_column_constraint(rec);
+        }
         Ok(col_id)
     }
 
+    /// Adds a FOREIGN KEY to a table. The referenced column must be UNIQUE
+    /// and hold the same type of values.
+    pub fn create_foreign_key(
+        &mut self,
+        table_id: TableId,
+        foreign_key: ForeignKey,
+    ) -> Result<()> {
+        let live_col = |table_id: TableId, col_id: ColId| {
+            let table = self
+                .state
+                .get_table(&table_id)
+                .filter(|table| table.alive)
+                .ok_or(StorageErr::TableNotFound(table_id))?;
+            table
+                .get_col(&col_id)
+                .filter(|col| col.alive)
+                .ok_or(StorageErr::ColumnNotFound(col_id))
+        };
+        let col = live_col(table_id, foreign_key.col_id)?;
+        let ref_col = live_col(foreign_key.ref_table_id, foreign_key.ref_col_id)?;
+        if !ref_col.constraints.unique {
+            return Err(StorageErr::InvalidSchema(
+                "FOREIGN KEY must reference a UNIQUE column",
+            ));
+        }
+        if col.data_type.value_type() != ref_col.data_type.value_type() {
+            return Err(StorageErr::InvalidSchema(
+                "FOREIGN KEY column type does not match the referenced column",
+            ));
+        }
+
+        let rec = ForeignKeyCreate { table_id, foreign_key };
+
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_table(table_id));
+        self.state.commit_foreign_key_create(rec);
+        Ok(())
+    }
+
+    /// Adds a CHECK constraint to a table. The predicate is stored as SQL
+    /// text and is not validated here.
+    pub fn create_check(&mut self, table_id: TableId, expr: &str) -> Result<()> {
+        let table = self
+            .state
+            .get_table(&table_id)
+            .ok_or(StorageErr::TableNotFound(table_id))?;
+        if !table.alive {
+            return Err(StorageErr::TableNotFound(table_id));
+        }
+
+        let rec = CheckCreate { table_id, expr: expr.into() };
+
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_table(table_id));
+        self.state.commit_check_create(rec);
+        Ok(())
+    }
+
     pub fn alter_column(
         &mut self,
         table_id: TableId,
         col_id: ColId,
-        new_col_type: DataType,
+        new_col_type: ColumnType,
         new_name: &str,
     ) -> Result<()> {
         let table = self
@@ -242,8 +702,10 @@ impl Storage {
                 });
             }
         }
+        if table.live_rows().any(|row| !new_col_type.accepts(&row.value(col))) {
+            return Err(StorageErr::InvalidRow("column type mismatch"));
+        }
 
-        let seq = self.state.next_seq_no();
         let rec = ColumnAlter {
             table_id,
             col_id,
@@ -251,7 +713,8 @@ impl Storage {
             new_col_name: new_name.into(),
         };
 
-        write_rec(&mut self.file, &rec, seq)?;
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_table(table_id));
         self.state.commit_column_alter(rec);
         Ok(())
     }
@@ -266,14 +729,53 @@ impl Storage {
             return Err(StorageErr::ColumnNotFound(col_id));
         }
 
-        let seq = self.state.next_seq_no();
+        let in_foreign_key = table.foreign_keys.iter().any(|fk| fk.col_id == col_id)
+            || self.state.referencing(table_id).any(|(_, fk)| fk.ref_col_id == col_id);
+        if in_foreign_key {
+            return Err(StorageErr::InvalidSchema("column is part of a FOREIGN KEY"));
+        }
+
         let rec = ColumnDrop { table_id, col_id };
 
-        write_rec(&mut self.file, &rec, seq)?;
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_table(table_id));
         self.state.commit_column_drop(rec);
         Ok(())
     }
 
+    /// Moves an AUTOINCREMENT sequence so the next value handed out follows
+    /// `sequence`. The sequence never moves backwards.
+    pub fn restart_sequence(
+        &mut self,
+        table_id: TableId,
+        col_id: ColId,
+        sequence: i64,
+    ) -> Result<()> {
+        let table = self
+            .state
+            .get_table(&table_id)
+            .ok_or(StorageErr::TableNotFound(table_id))?;
+        let col = table.get_col(&col_id).ok_or(StorageErr::ColumnNotFound(col_id))?;
+        if !col.alive {
+            return Err(StorageErr::ColumnNotFound(col_id));
+        }
+        if !col.constraints.autoincrement {
+            return Err(StorageErr::InvalidSchema("column is not AUTOINCREMENT"));
+        }
+
+        let rec = ColumnConstraint {
+            table_id,
+            col_id,
+            constraints: col.constraints.clone(),
+            sequence,
+        };
+
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_table(table_id));
+        self.state.commit_column_constraint(rec);
+        Ok(())
+    }
+
     pub fn get_column(&self, table_id: TableId, name: &str) -> Result<&ColState> {
         let table = self
             .state
@@ -290,27 +792,69 @@ impl Storage {
         values: Vec<DataValue>,
     ) -> Result<RowId> {
         // validate
-        let table = self
-            .state
-            .get_table(&table_id)
-            .ok_or(StorageErr::TableNotFound(table_id))?;
-        let live_col_count = table.live_cols().count();
-        if values.len() != live_col_count {
-            return Err(StorageErr::InvalidRow("column count mismatch"));
-        }
+        self.validate_row(table_id, &values)?;
 
         // build record
         let count = values.len() as u64;
         let row_id = self.state.alloc_row();
-        let seq = self.state.next_seq_no();
         let rec = RowInsert { table_id, row_id, count, values };
 
         // write then commit
-        write_rec(&mut self.file, &rec, seq)?;
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_row(table_id, row_id));
         self.state.commit_row_insert(rec);
         Ok(row_id)
     }
 
+    /// Inserts many rows at once. All rows are checked before any is written,
+    /// and their records go to the file in a single write.
+    pub fn bulk_insert(
+        &mut self,
+        table_id: TableId,
+        rows: Vec<Vec<DataValue>>,
+    ) -> Result<Vec<RowId>> {
+        // validate
+        for values in &rows {
+            self.validate_row(table_id, values)?;
+        }
+
+        // build records
+        let recs = rows
+            .into_iter()
+            .map(|values| {
+                let count = values.len() as u64;
+                let row_id = self.state.alloc_row();
+                RowInsert { table_id, row_id, count, values }
+            })
+            .collect::<Vec<_>>();
+
+        // write then commit
+        self.append_all(&recs)?;
+        let row_ids = recs.iter().map(|rec| rec.row_id).collect();
+        for rec in recs {
+            self.save_undo(|state| state.undo_row(table_id, rec.row_id));
+            self.state.commit_row_insert(rec);
+        }
+        Ok(row_ids)
+    }
+
+    fn validate_row(&self, table_id: TableId, values: &[DataValue]) -> Result<()> {
+        let table = self
+            .state
+            .get_table(&table_id)
+            .ok_or(StorageErr::TableNotFound(table_id))?;
+        let live_col_count = table.live_cols().count();
+        if values.len() != live_col_count {
+            return Err(StorageErr::InvalidRow("column count mismatch"));
+        }
+        for (col, value) in table.live_cols().zip(values) {
+            if !col.data_type.accepts(value) {
+                return Err(StorageErr::InvalidRow("column type mismatch"));
+            }
+        }
+        Ok(())
+    }
+
     pub fn update_row(
         &mut self,
         table_id: TableId,
@@ -336,16 +880,16 @@ impl Storage {
             if !col.alive {
                 return Err(StorageErr::ColumnNotFound(*col_id));
             }
-            if value.data_type() != col.data_type {
+            if !col.data_type.accepts(value) {
                 return Err(StorageErr::InvalidRow("column type mismatch"));
             }
         }
 
         let count = patches.len() as u64;
-        let seq = self.state.next_seq_no();
         let rec = RowUpdate { table_id, row_id, count, patches };
 
-        write_rec(&mut self.file, &rec, seq)?;
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_row(table_id, row_id));
         self.state.commit_row_update(rec);
         Ok(())
     }
@@ -360,14 +904,26 @@ impl Storage {
             return Err(StorageErr::RowNotFound(row_id));
         }
 
-        let seq = self.state.next_seq_no();
         let rec = RowDelete { table_id, row_id };
 
-        write_rec(&mut self.file, &rec, seq)?;
+        self.append(&rec)?;
+        self.save_undo(|state| state.undo_row(table_id, row_id));
         self.state.commit_row_delete(rec);
         Ok(())
     }
 
+    /// Lazily iterates the live rows of a table in insertion order.
+    pub fn scan(&self, table_id: TableId) -> Result<impl Iterator<Item = &RowState>> {
+        let table = self
+            .state
+            .get_table(&table_id)
+            .ok_or(StorageErr::TableNotFound(table_id))?;
+        if !table.alive {
+            return Err(StorageErr::TableNotFound(table_id));
+        }
+        Ok(table.live_rows())
+    }
+
     pub fn get_row(&self, table_id: TableId, row_id: RowId) -> Result<&RowState> {
         let table = self
             .state
@@ -1,7 +1,8 @@
 use super::codec::{Decoder, Encoder};
 use super::error::{Result, StorageErr};
+use super::state::ForeignKey;
 use super::{ColId, RowId, SeqNo, TableId};
-use crate::schema::{DataType, DataValue};
+use crate::schema::{ColConstraints, Collation, ColumnType, DataValue};
 use std::io::{Read, Write};
 
 pub(super) fn write_rec(
@@ -56,9 +57,15 @@ pub(super) fn read_rec(r: &mut impl Read) -> Result<Record> {
         ColumnCreate::TAG => ColumnCreate::decode(&mut dec)?,
         ColumnAlter::TAG => ColumnAlter::decode(&mut dec)?,
         ColumnDrop::TAG => ColumnDrop::decode(&mut dec)?,
+        ColumnConstraint::TAG => ColumnConstraint::decode(&mut dec)?,
+        ForeignKeyCreate::TAG => ForeignKeyCreate::decode(&mut dec)?,
+        CheckCreate::TAG => CheckCreate::decode(&mut dec)?,
         RowInsert::TAG => RowInsert::decode(&mut dec)?,
+        RowInsert::LEGACY_TAG => RowInsert::decode_legacy(&mut dec)?,
         RowUpdate::TAG => RowUpdate::decode(&mut dec)?,
         RowDelete::TAG => RowDelete::decode(&mut dec)?,
+        TxnBegin::TAG => TxnBegin::decode(&mut dec)?,
+        TxnCommit::TAG => TxnCommit::decode(&mut dec)?,
         _ => return Err(StorageErr::InvalidRecordTag(tag)),
     };
     //
@@ -73,9 +80,14 @@ pub enum Record {
     ColumnCreate(ColumnCreate),
     ColumnAlter(ColumnAlter),
     ColumnDrop(ColumnDrop),
+    ColumnConstraint(ColumnConstraint),
+    ForeignKeyCreate(ForeignKeyCreate),
+    CheckCreate(CheckCreate),
     RowInsert(RowInsert),
     RowUpdate(RowUpdate),
     RowDelete(RowDelete),
+    TxnBegin,
+    TxnCommit,
 }
 
 pub trait Recordable: Sized {
@@ -167,7 +179,7 @@ impl Recordable for TableRename {
 pub struct ColumnCreate {
     pub table_id: TableId,
     pub col_id: ColId,
-    pub col_type: DataType,
+    pub col_type: ColumnType,
     pub col_name: Box<str>,
 }
 
@@ -177,7 +189,7 @@ impl Recordable for ColumnCreate {
     fn encode(&self, enc: &mut Encoder) {
         enc.u64(self.table_id.0);
         enc.u64(self.col_id.0);
-        enc.ty(self.col_type);
+        enc.col_type(self.col_type);
         enc.text(&self.col_name);
     }
 
@@ -185,7 +197,7 @@ impl Recordable for ColumnCreate {
         Ok(Record::ColumnCreate(Self {
             table_id: TableId(dec.u64()?),
             col_id: ColId(dec.u64()?),
-            col_type: dec.ty()?,
+            col_type: dec.col_type()?,
             col_name: dec.text()?,
         }))
     }
@@ -194,7 +206,7 @@ impl Recordable for ColumnCreate {
 pub struct ColumnAlter {
     pub table_id: TableId,
     pub col_id: ColId,
-    pub new_col_type: DataType,
+    pub new_col_type: ColumnType,
     pub new_col_name: Box<str>,
 }
 
@@ -204,7 +216,7 @@ impl Recordable for ColumnAlter {
     fn encode(&self, enc: &mut Encoder) {
         enc.u64(self.table_id.0);
         enc.u64(self.col_id.0);
-        enc.ty(self.new_col_type);
+        enc.col_type(self.new_col_type);
         enc.text(&self.new_col_name);
     }
 
@@ -212,7 +224,7 @@ impl Recordable for ColumnAlter {
         Ok(Record::ColumnAlter(Self {
             table_id: TableId(dec.u64()?),
             col_id: ColId(dec.u64()?),
-            new_col_type: dec.ty()?,
+            new_col_type: dec.col_type()?,
             new_col_name: dec.text()?,
         }))
     }
@@ -239,27 +251,151 @@ impl Recordable for ColumnDrop {
     }
 }
 
-pub struct RowInsert {
+pub struct ColumnConstraint {
     pub table_id: TableId,
-    pub row_id: RowId,
-    pub count: u64,
-    pub values: Vec<DataValue>,
+    pub col_id: ColId,
+    pub constraints: ColConstraints,
+    pub sequence: i64, // last AUTOINCREMENT value, stored only with the flag
 }
 
-impl Recordable for RowInsert {
-    const TAG: u8 = 51;
+impl ColumnConstraint {
+    const NOT_NULL: u16 = 1 << 0;
+    const HAS_DEFAULT: u16 = 1 << 1;
+    const UNIQUE: u16 = 1 << 2;
+    const PRIMARY_KEY: u16 = 1 << 3;
+    const AUTOINCREMENT: u16 = 1 << 4;
+    const NOCASE: u16 = 1 << 5;
+}
+
+impl Recordable for ColumnConstraint {
+    const TAG: u8 = 34;
 
     fn encode(&self, enc: &mut Encoder) {
+        let mut flags = 0;
+        if self.constraints.not_null {
+            flags |= Self::NOT_NULL;
+        }
+        if self.constraints.default.is_some() {
+            flags |= Self::HAS_DEFAULT;
+        }
+        if self.constraints.unique {
+            flags |= Self::UNIQUE;
+        }
+        if self.constraints.primary_key {
+            flags |= Self::PRIMARY_KEY;
+        }
+        if self.constraints.autoincrement {
+            flags |= Self::AUTOINCREMENT;
+        }
+        if self.constraints.collation == Collation::Nocase {
+            flags |= Self::NOCASE;
+        }
         enc.u64(self.table_id.0);
-        enc.u64(self.row_id.0);
-        enc.u64(self.count);
-        for value in &self.values {
+        enc.u64(self.col_id.0);
+        enc.u16(flags);
+        if let Some(value) = &self.constraints.default {
             enc.ty(value.data_type());
             enc.value(value);
         }
+        if self.constraints.autoincrement {
+            enc.i64(self.sequence);
+        }
+    }
+
+    fn decode(dec: &mut Decoder<&[u8]>) -> Result<Record> {
+        let table_id = TableId(dec.u64()?);
+        let col_id = ColId(dec.u64()?);
+        let flags = dec.u16()?;
+        let default = if flags & Self::HAS_DEFAULT != 0 {
+            let ty = dec.ty()?;
+            Some(dec.value(ty)?)
+        } else {
+            None
+        };
+        let autoincrement = flags & Self::AUTOINCREMENT != 0;
+        let sequence = if autoincrement { dec.i64()? } else { 0 };
+        let constraints = ColConstraints {
+            not_null: flags & Self::NOT_NULL != 0,
+            unique: flags & Self::UNIQUE != 0,
+            primary_key: flags & Self::PRIMARY_KEY != 0,
+            autoincrement,
+            default,
+            collation: if flags & Self::NOCASE != 0 {
+                Collation::Nocase
+            } else {
+                Collation::Binary
+            },
+        };
+        Ok(Record::ColumnConstraint(Self { table_id, col_id, constraints, sequence }))
+    }
+}
+
+pub struct ForeignKeyCreate {
+    pub table_id: TableId,
+    pub foreign_key: ForeignKey,
+}
+
+impl ForeignKeyCreate {
+    const ON_DELETE_CASCADE: u8 = 1 << 0;
+}
+
+impl Recordable for ForeignKeyCreate {
+    const TAG: u8 = 35;
+
+    fn encode(&self, enc: &mut Encoder) {
+        let fk = &self.foreign_key;
+        enc.u64(self.table_id.0);
+        enc.u64(fk.col_id.0);
+        enc.u64(fk.ref_table_id.0);
+        enc.u64(fk.ref_col_id.0);
+        enc.u8(if fk.on_delete_cascade { Self::ON_DELETE_CASCADE } else { 0 });
     }
 
     fn decode(dec: &mut Decoder<&[u8]>) -> Result<Record> {
+        let table_id = TableId(dec.u64()?);
+        let foreign_key = ForeignKey {
+            col_id: ColId(dec.u64()?),
+            ref_table_id: TableId(dec.u64()?),
+            ref_col_id: ColId(dec.u64()?),
+            on_delete_cascade: dec.u8()? & Self::ON_DELETE_CASCADE != 0,
+        };
+        Ok(Record::ForeignKeyCreate(Self { table_id, foreign_key }))
+    }
+}
+
+pub struct CheckCreate {
+    pub table_id: TableId,
+    pub expr: Box<str>,
+}
+
+impl Recordable for CheckCreate {
+    const TAG: u8 = 36;
+
+    fn encode(&self, enc: &mut Encoder) {
+        enc.u64(self.table_id.0);
+        enc.text(&self.expr);
+    }
+
+    fn decode(dec: &mut Decoder<&[u8]>) -> Result<Record> {
+        Ok(Record::CheckCreate(Self {
+            table_id: TableId(dec.u64()?),
+            expr: dec.text()?,
+        }))
+    }
+}
+
+pub struct RowInsert {
+    pub table_id: TableId,
+    pub row_id: RowId,
+    pub count: u64,
+    pub values: Vec<DataValue>,
+}
+
+impl RowInsert {
+    /// Tag of the original encoding, which stored a type tag per value.
+    const LEGACY_TAG: u8 = 51;
+
+    fn decode_legacy(dec: &mut Decoder<&[u8]>) -> Result<Record> {
         let table_id = TableId(dec.u64()?);
         let row_id = RowId(dec.u64()?);
         let count = dec.u64()?;
@@ -273,6 +409,24 @@ impl Recordable for RowInsert {
     }
 }
 
+impl Recordable for RowInsert {
+    const TAG: u8 = 54;
+
+    fn encode(&self, enc: &mut Encoder) {
+        enc.u64(self.table_id.0);
+        enc.u64(self.row_id.0);
+        enc.row(&self.values);
+    }
+
+    fn decode(dec: &mut Decoder<&[u8]>) -> Result<Record> {
+        let table_id = TableId(dec.u64()?);
+        let row_id = RowId(dec.u64()?);
+        let values = dec.row()?;
+        let count = values.len() as u64;
+        Ok(Record::RowInsert(Self { table_id, row_id, count, values }))
+    }
+}
+
 pub struct RowUpdate {
     pub table_id: TableId,
     pub row_id: RowId,
@@ -329,3 +483,29 @@ impl Recordable for RowDelete {
         }))
     }
 }
+
+/// Opens a batch of records that is replayed only once its `TxnCommit`
+/// record is on disk.
+pub struct TxnBegin;
+
+impl Recordable for TxnBegin {
+    const TAG: u8 = 71;
+
+    fn encode(&self, _enc: &mut Encoder) {}
+
+    fn decode(_dec: &mut Decoder<&[u8]>) -> Result<Record> {
+        Ok(Record::TxnBegin)
+    }
+}
+
+pub struct TxnCommit;
+
+impl Recordable for TxnCommit {
+    const TAG: u8 = 72;
+
+    fn encode(&self, _enc: &mut Encoder) {}
+
+    fn decode(_dec: &mut Decoder<&[u8]>) -> Result<Record> {
+        Ok(Record::TxnCommit)
+    }
+}
@@ -1,7 +1,9 @@
+use super::error::{Result, StorageErr};
+use super::index::Index;
 use super::record::*;
 use super::{ColId, RowId, SeqNo, TableId};
-use crate::schema::{DataType, DataValue};
-use std::collections::HashMap;
+use crate::schema::{Char, ColConstraints, ColumnType, DataValue, VarChar};
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone)]
 pub struct RowState {
@@ -10,12 +12,44 @@ pub struct RowState {
     pub values: HashMap<ColId, DataValue>,
 }
 
+impl RowState {
+    /// Returns the value stored for `col`, falling back to the column default
+    /// for rows written before the column was added.
+    pub fn value(&self, col: &ColState) -> DataValue {
+        match self.values.get(&col.id) {
+            Some(value) => value.clone(),
+            None => col.constraints.default.clone().unwrap_or(DataValue::Nil),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ColState {
     pub id: ColId,
     pub name: Box<str>,
     pub alive: bool,
-    pub data_type: DataType,
+    pub data_type: ColumnType,
+    pub constraints: ColConstraints,
+    /// Largest value an AUTOINCREMENT column has held, counting rows that
+    /// were deleted or updated since, so values are never handed out twice.
+    pub sequence: i64,
+}
+
+impl ColState {
+    /// The key `value` is indexed and checked for uniqueness under, so
+    /// values equal under the column's collation share one key.
+    pub fn index_key(&self, value: DataValue) -> DataValue {
+        self.constraints.collation.key(value)
+    }
+}
+
+/// A FOREIGN KEY from a column of the owning table to a UNIQUE column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForeignKey {
+    pub col_id: ColId,
+    pub ref_table_id: TableId,
+    pub ref_col_id: ColId,
+    pub on_delete_cascade: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -24,7 +58,10 @@ pub struct TableState {
     pub name: Box<str>,
     pub alive: bool,
     pub cols: Vec<ColState>,
-    pub rows: HashMap<RowId, RowState>,
+    pub rows: BTreeMap<RowId, RowState>,
+    pub indexes: HashMap<ColId, Index>,
+    pub foreign_keys: Vec<ForeignKey>,
+    pub checks: Vec<Box<str>>, // CHECK predicates as SQL text
 }
 
 impl TableState {
@@ -47,6 +84,56 @@ impl TableState {
     pub fn live_cols(&self) -> impl Iterator<Item = &ColState> {
         self.cols.iter().filter(|c| c.alive)
     }
+
+    /// Iterates rows that have not been deleted, in insertion order. Deleted
+    /// rows stay in `rows` as tombstones until the next vacuum.
+    pub fn live_rows(&self) -> impl Iterator<Item = &RowState> {
+        self.rows.values().filter(|row| row.alive)
+    }
+
+    pub fn get_index(&self, col_id: &ColId) -> Option<&Index> {
+        self.indexes.get(col_id)
+    }
+
+    fn index_row(&mut self, row_id: &RowId) {
+        let Some(row) = self.rows.get(row_id) else { return };
+        for col in &self.cols {
+            if let Some(index) = self.indexes.get_mut(&col.id) {
+                index.insert(col.index_key(row.value(col)), row.id);
+            }
+        }
+    }
+
+    fn advance_sequences(&mut self, row_id: &RowId) {
+        let Some(row) = self.rows.get(row_id) else { return };
+        for col in &mut self.cols {
+            if col.constraints.autoincrement
+                && let Some(DataValue::Int(value)) = row.values.get(&col.id)
+            {
+                col.sequence = col.sequence.max(*value);
+            }
+        }
+    }
+
+    fn unindex_row(&mut self, row_id: &RowId) {
+        let Some(row) = self.rows.get(row_id) else { return };
+        for col in &self.cols {
+            if let Some(index) = self.indexes.get_mut(&col.id) {
+                index.delete(&col.index_key(row.value(col)), row.id);
+            }
+        }
+    }
+}
+
+/// How to undo one write to the state, so part of a batch can be rolled back
+/// without replaying the log.
+#[derive(Debug)]
+pub(super) enum Undo {
+    /// The table as it was, or `None` if the write created it.
+    Table(TableId, Option<TableState>),
+    /// The row as it was, or `None` if the write inserted it, along with the
+    /// AUTOINCREMENT sequences of the table's columns.
+    Row { table_id: TableId, row_id: RowId, row: Option<RowState>, sequences: Vec<i64> },
 }
 
 #[derive(Debug)]
@@ -87,6 +174,19 @@ impl DbState {
         self.tables.values_mut().find(|t| t.alive && &*t.name == name)
     }
 
+    /// Iterates the foreign keys of live tables that reference `table_id`,
+    /// with the table that owns each.
+    pub fn referencing(
+        &self,
+        table_id: TableId,
+    ) -> impl Iterator<Item = (&TableState, &ForeignKey)> {
+        self.tables
+            .values()
+            .filter(|table| table.alive)
+            .flat_map(|table| table.foreign_keys.iter().map(move |fk| (table, fk)))
+            .filter(move |(_, fk)| fk.ref_table_id == table_id)
+    }
+
     pub(super) fn alloc_table(&mut self) -> TableId {
         let id = self.next_table_id;
         self.next_table_id.0 += 1;
@@ -111,6 +211,112 @@ impl DbState {
         id
     }
 
+    /// The sequence number the next record gets, to restore with
+    /// [`Self::reset_seq_no`] when the records after it are discarded.
+    pub(super) fn seq_no(&self) -> SeqNo {
+        self.next_seq_no
+    }
+
+    pub(super) fn reset_seq_no(&mut self, seq_no: SeqNo) {
+        self.next_seq_no = seq_no;
+    }
+
+    /// Captures how to undo a write to the table as a whole. Call it before
+    /// the write is committed.
+    pub(super) fn undo_table(&self, table_id: TableId) -> Undo {
+        Undo::Table(table_id, self.tables.get(&table_id).cloned())
+    }
+
+    /// Captures how to undo a write to one row. Call it before the write is
+    /// committed.
+    pub(super) fn undo_row(&self, table_id: TableId, row_id: RowId) -> Undo {
+        let table = self.tables.get(&table_id);
+        Undo::Row {
+            table_id,
+            row_id,
+            row: table.and_then(|table| table.rows.get(&row_id).cloned()),
+            sequences: table
+                .map(|table| table.cols.iter().map(|col| col.sequence).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Restores what a write changed. Undoing the writes of a batch newest
+    /// first leaves the state as it was before them.
+    pub(super) fn undo(&mut self, undo: Undo) {
+        match undo {
+            Undo::Table(table_id, Some(table)) => {
+                self.tables.insert(table_id, table);
+            }
+            Undo::Table(table_id, None) => {
+                self.tables.remove(&table_id);
+            }
+            Undo::Row { table_id, row_id, row, sequences } => {
+                let table = self
+                    .get_table_mut(&table_id)
+                    .expect("corrupted: table not found during undo");
+                table.unindex_row(&row_id);
+                match row {
+                    Some(row) => {
+                        let alive = row.alive;
+                        table.rows.insert(row_id, row);
+                        if alive {
+                            table.index_row(&row_id);
+                        }
+                    }
+                    None => {
+                        table.rows.remove(&row_id);
+                    }
+                }
+                for (col, sequence) in table.cols.iter_mut().zip(sequences) {
+                    col.sequence = sequence;
+                }
+            }
+        }
+    }
+
+    /// Checks that the values a replayed record stores still fit their
+    /// column types, e.g. after a VARCHAR limit was narrowed.
+    pub fn validate(&self, record: &Record) -> Result<()> {
+        let check = |col: &ColState, value: &DataValue| -> Result<()> {
+            let err = match (col.data_type, value) {
+                (ColumnType::VarChar(max_len), DataValue::Text(text)) => {
+                    VarChar::new(text, max_len as usize).err().map(|e| e.to_string())
+                }
+                (ColumnType::Char(width), DataValue::Text(text)) => {
+                    Char::new(text, width as usize).err().map(|e| e.to_string())
+                }
+                _ => None,
+            };
+            match err {
+                Some(e) => {
+                    Err(StorageErr::Corrupted(format!("{e} in column '{}'", col.name)))
+                }
+                None => Ok(()),
+            }
+        };
+        match record {
+            Record::RowInsert(rec) => {
+                if let Some(table) = self.get_table(&rec.table_id) {
+                    for (col, value) in table.live_cols().zip(&rec.values) {
+                        check(col, value)?;
+                    }
+                }
+            }
+            Record::RowUpdate(rec) => {
+                if let Some(table) = self.get_table(&rec.table_id) {
+                    for (col_id, value) in &rec.patches {
+                        if let Some(col) = table.get_col(col_id) {
+                            check(col, value)?;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     pub fn commit(&mut self, record: Record) {
         match record {
             Record::TableCreate(rec) => self.commit_table_create(rec),
@@ -120,9 +326,14 @@ impl DbState {
             Record::ColumnCreate(rec) => self.commit_column_create(rec),
             Record::ColumnAlter(rec) => self.commit_column_alter(rec),
             Record::ColumnDrop(rec) => self.commit_column_drop(rec),
+            Record::ColumnConstraint(rec) => self.commit_column_constraint(rec),
+            Record::ForeignKeyCreate(rec) => self.commit_foreign_key_create(rec),
+            Record::CheckCreate(rec) => self.commit_check_create(rec),
             Record::RowInsert(rec) => self.commit_row_insert(rec),
             Record::RowUpdate(rec) => self.commit_row_update(rec),
             Record::RowDelete(rec) => self.commit_row_delete(rec),
+            // batch markers are resolved during replay
+            Record::TxnBegin | Record::TxnCommit => {}
         }
     }
 
@@ -135,7 +346,10 @@ impl DbState {
                 name: rec.table_name,
                 alive: true,
                 cols: Vec::new(),
-                rows: HashMap::new(),
+                rows: BTreeMap::new(),
+                indexes: HashMap::new(),
+                foreign_keys: Vec::new(),
+                checks: Vec::new(),
             },
         );
     }
@@ -145,6 +359,7 @@ impl DbState {
             .get_table_mut(&rec.table_id)
             .expect("corrupted: table not found during commit");
         table.rows.clear();
+        table.indexes.values_mut().for_each(Index::clear);
     }
 
     pub fn commit_table_rename(&mut self, rec: TableRename) {
@@ -171,6 +386,8 @@ impl DbState {
             name: rec.col_name,
             alive: true,
             data_type: rec.col_type,
+            constraints: ColConstraints::default(),
+            sequence: 0,
         });
     }
 
@@ -193,6 +410,43 @@ impl DbState {
             .get_col_mut(&rec.col_id)
             .expect("corrupted: column not found during commit");
         col.alive = false;
+        table.indexes.remove(&rec.col_id);
+    }
+
+    pub fn commit_column_constraint(&mut self, rec: ColumnConstraint) {
+        let table = self
+            .get_table_mut(&rec.table_id)
+            .expect("corrupted: table not found during commit");
+        let col = table
+            .get_col_mut(&rec.col_id)
+            .expect("corrupted: column not found during commit");
+        col.constraints = rec.constraints;
+        col.sequence = col.sequence.max(rec.sequence);
+        // unique columns are indexed for constraint checks and lookups
+        if col.constraints.unique {
+            let col = col.clone();
+            let mut index = Index::default();
+            for row in table.live_rows() {
+                index.insert(col.index_key(row.value(&col)), row.id);
+            }
+            table.indexes.insert(col.id, index);
+        } else {
+            table.indexes.remove(&rec.col_id);
+        }
+    }
+
+    pub fn commit_foreign_key_create(&mut self, rec: ForeignKeyCreate) {
+        let table = self
+            .get_table_mut(&rec.table_id)
+            .expect("corrupted: table not found during commit");
+        table.foreign_keys.push(rec.foreign_key);
+    }
+
+    pub fn commit_check_create(&mut self, rec: CheckCreate) {
+        let table = self
+            .get_table_mut(&rec.table_id)
+            .expect("corrupted: table not found during commit");
+        table.checks.push(rec.expr);
     }
 
     pub fn commit_row_insert(&mut self, rec: RowInsert) {
@@ -204,12 +458,15 @@ impl DbState {
         let values = live_cols.into_iter().zip(rec.values).collect();
         let row = RowState { id: rec.row_id, values, alive: true };
         table.rows.insert(rec.row_id, row);
+        table.index_row(&rec.row_id);
+        table.advance_sequences(&rec.row_id);
     }
 
     pub fn commit_row_update(&mut self, rec: RowUpdate) {
         let table = self
             .get_table_mut(&rec.table_id)
             .expect("corrupted: table not found during commit");
+        table.unindex_row(&rec.row_id);
         let row = table
             .rows
             .get_mut(&rec.row_id)
@@ -217,12 +474,15 @@ impl DbState {
         for (col_id, value) in rec.patches {
             row.values.insert(col_id, value);
         }
+        table.index_row(&rec.row_id);
+        table.advance_sequences(&rec.row_id);
     }
 
     pub fn commit_row_delete(&mut self, rec: RowDelete) {
         let table = self
             .get_table_mut(&rec.table_id)
             .expect("corrupted: table not found during commit");
+        table.unindex_row(&rec.row_id);
         let row = table
             .rows
             .get_mut(&rec.row_id)
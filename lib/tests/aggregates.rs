@@ -0,0 +1,74 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::DataValue;
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(&mut exec, "CREATE TABLE t (city TEXT, n INT)").unwrap();
+    for values in [
+        "'Seoul', 3",
+        "'Busan', 1",
+        "'Seoul', NULL",
+        "NULL, 2",
+        "'Daegu', 1",
+        "'Seoul', 2",
+    ] {
+        run(&mut exec, &format!("INSERT INTO t VALUES ({values})")).unwrap();
+    }
+    (dir, exec)
+}
+
+fn row(exec: &mut Executor, sql: &str) -> Vec<DataValue> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { mut rows, .. } => {
+            assert_eq!(rows.len(), 1, "{sql}");
+            rows.remove(0)
+        }
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+#[test]
+fn count_distinct_skips_duplicates_and_nulls() {
+    let (_dir, mut exec) = setup();
+    let counts = row(
+        &mut exec,
+        "SELECT COUNT(*), COUNT(city), COUNT(DISTINCT city), COUNT(DISTINCT n) FROM t",
+    );
+    assert_eq!(
+        counts,
+        [DataValue::Int(6), DataValue::Int(5), DataValue::Int(3), DataValue::Int(3)]
+    );
+    assert_eq!(row(&mut exec, "SELECT SUM(DISTINCT n) FROM t"), [DataValue::Int(6)]);
+}
+
+#[test]
+fn group_concat_joins_values_in_input_order() {
+    let (_dir, mut exec) = setup();
+    let text = |text: &str| DataValue::Text(text.into());
+    assert_eq!(
+        row(&mut exec, "SELECT GROUP_CONCAT(city) FROM t"),
+        [text("Seoul,Busan,Seoul,Daegu,Seoul")]
+    );
+    assert_eq!(
+        row(&mut exec, "SELECT GROUP_CONCAT(DISTINCT city, ' / ') FROM t"),
+        [text("Seoul / Busan / Daegu")]
+    );
+    // NULLs are skipped, and other values are joined as text
+    assert_eq!(row(&mut exec, "SELECT GROUP_CONCAT(n, '') FROM t"), [text("31212")]);
+    assert_eq!(
+        row(&mut exec, "SELECT GROUP_CONCAT(n) FROM t WHERE city = 'Busan' AND n = 2"),
+        [DataValue::Nil]
+    );
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "async")]
+
+use litesqrl::AsyncDatabase;
+use litesqrl::schema::DataValue;
+use std::future::Future;
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    runtime.block_on(future)
+}
+
+#[test]
+fn insert_and_query_on_the_runtime() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    block_on(async {
+        let db = AsyncDatabase::create(&path).await.unwrap();
+        db.execute_async("CREATE TABLE t (a INT)").await.unwrap();
+        let inserted = db.execute_async("INSERT INTO t VALUES (1), (2)").await.unwrap();
+        assert_eq!(inserted, 2);
+        // clones share the same database
+        let other = db.clone();
+        other.execute_async("INSERT INTO t VALUES (3)").await.unwrap();
+        let rows = db.query_async("SELECT SUM(a) FROM t").await.unwrap();
+        assert_eq!(rows.into_rows(), [[DataValue::Int(6)]]);
+        assert!(db.query_async("SELECT * FROM missing").await.is_err());
+    });
+
+    let reopened = block_on(async {
+        let db = AsyncDatabase::open(&path).await.unwrap();
+        db.query_async("SELECT a FROM t").await.unwrap().len()
+    });
+    assert_eq!(reopened, 3);
+}
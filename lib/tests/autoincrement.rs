@@ -0,0 +1,94 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::storage::Storage;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn ids(exec: &mut Executor) -> Vec<String> {
+    match run(exec, "SELECT id FROM t ORDER BY id").unwrap() {
+        QueryResult::Rows { rows, .. } => {
+            rows.iter().map(|row| row[0].to_string()).collect()
+        }
+        _ => panic!("SELECT returned no rows"),
+    }
+}
+
+#[test]
+fn omitted_keys_take_the_next_value() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("db.sqrl")).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (id INT PRIMARY KEY AUTOINCREMENT, name TEXT);
+         INSERT INTO t (name) VALUES ('a');
+         INSERT INTO t (name) VALUES ('b');
+         INSERT INTO t (name) VALUES ('c');",
+    )
+    .unwrap();
+    assert_eq!(ids(&mut exec), ["1", "2", "3"]);
+    // an explicit key moves the sequence past it
+    run(
+        &mut exec,
+        "INSERT INTO t VALUES (10, 'd'); INSERT INTO t (name) VALUES ('e');",
+    )
+    .unwrap();
+    assert_eq!(ids(&mut exec), ["1", "2", "3", "10", "11"]);
+}
+
+#[test]
+fn keys_are_not_reused_after_deletes_or_reopening() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let mut exec = Executor::new(Storage::create(&path).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (id INT PRIMARY KEY AUTOINCREMENT, name TEXT);
+         INSERT INTO t (name) VALUES ('a');
+         INSERT INTO t (name) VALUES ('b');
+         INSERT INTO t (name) VALUES ('c');
+         DELETE FROM t WHERE id >= 2;",
+    )
+    .unwrap();
+    drop(exec);
+
+    let mut exec = Executor::new(Storage::open(&path).unwrap());
+    run(&mut exec, "INSERT INTO t (name) VALUES ('d')").unwrap();
+    assert_eq!(ids(&mut exec), ["1", "4"]);
+    run(&mut exec, "DELETE FROM t").unwrap();
+    drop(exec);
+
+    let mut exec = Executor::new(Storage::open(&path).unwrap());
+    run(&mut exec, "INSERT INTO t (name) VALUES ('e')").unwrap();
+    assert_eq!(ids(&mut exec), ["5"]);
+}
+
+#[test]
+fn restart_moves_the_sequence_forward_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("db.sqrl")).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (id INT PRIMARY KEY AUTOINCREMENT, name TEXT);
+         INSERT INTO t (name) VALUES ('a');
+         ALTER TABLE t ALTER COLUMN id RESTART WITH 10;
+         INSERT INTO t (name) VALUES ('b');
+         ALTER TABLE t ALTER COLUMN id RESTART WITH 2;
+         INSERT INTO t (name) VALUES ('c');",
+    )
+    .unwrap();
+    assert_eq!(ids(&mut exec), ["1", "10", "11"]);
+    assert!(matches!(
+        run(&mut exec, "ALTER TABLE t ALTER COLUMN name RESTART WITH 1"),
+        Err(SQRLErr::StorageErr(_))
+    ));
+    assert!(matches!(
+        run(&mut exec, "ALTER TABLE t ALTER COLUMN x RESTART WITH 1"),
+        Err(SQRLErr::ColumnNotFound(_))
+    ));
+}
@@ -0,0 +1,85 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::error::{QueryErr, QueryErrKind};
+use litesqrl::query::lexer::Token;
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::DataValue;
+use litesqrl::storage::Storage;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn values(exec: &mut Executor, sql: &str) -> Vec<Vec<DataValue>> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => rows,
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token<'_>>, QueryErr> {
+    let mut lexer = Lexer::new(src);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next()?.token;
+        let eof = token == Token::Eof;
+        tokens.push(token);
+        if eof {
+            return Ok(tokens);
+        }
+    }
+}
+
+fn blob(bytes: &[u8]) -> DataValue {
+    DataValue::Blob(bytes.into())
+}
+
+#[test]
+fn hex_literals_lex_to_bytes() {
+    let tokens = tokenize("X'DEADbeef' x'' X'00'").unwrap();
+    assert_eq!(
+        tokens,
+        [
+            Token::Blob(vec![0xde, 0xad, 0xbe, 0xef]),
+            Token::Blob(vec![]),
+            Token::Blob(vec![0]),
+            Token::Eof,
+        ]
+    );
+    // a lone X is still an identifier
+    assert_eq!(tokenize("x").unwrap().len(), 2);
+}
+
+#[test]
+fn malformed_hex_literals_fail() {
+    for (sql, hex) in [("X'ABC'", "ABC"), ("X'GG'", "GG"), ("X'0 1'", "0 1")] {
+        let err = tokenize(sql).unwrap_err();
+        assert_eq!(err.kind, QueryErrKind::InvalidBlob(hex.into()), "{sql}");
+    }
+    assert_eq!(tokenize("X'AB").unwrap_err().kind, QueryErrKind::UnterminatedText);
+}
+
+#[test]
+fn blobs_survive_reopening_and_compare_bytewise() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let mut exec = Executor::new(Storage::create(&path).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (b BLOB);
+         INSERT INTO t VALUES (X'FF');
+         INSERT INTO t VALUES (X'');
+         INSERT INTO t VALUES (X'00FF');",
+    )
+    .unwrap();
+    drop(exec);
+
+    let mut exec = Executor::new(Storage::open(&path).unwrap());
+    let rows = values(&mut exec, "SELECT b FROM t ORDER BY b");
+    assert_eq!(rows, [[blob(&[])], [blob(&[0, 0xff])], [blob(&[0xff])]]);
+    let rows = values(&mut exec, "SELECT b FROM t WHERE b > X'00'");
+    assert_eq!(rows, [[blob(&[0xff])], [blob(&[0, 0xff])]]);
+}
@@ -0,0 +1,50 @@
+use litesqrl::Database;
+use litesqrl::query::{Lexer, Parser, StmtCache};
+
+#[test]
+fn cache_hits_return_the_parsed_statements() {
+    let mut cache = StmtCache::new(4);
+    let sql = "SELECT a FROM t WHERE a = 1; DELETE FROM t";
+    let parsed = Parser::new(Lexer::new(sql)).and_then(|mut p| p.parse()).unwrap();
+    assert_eq!(cache.parse(sql).unwrap(), parsed);
+    assert!(cache.contains(sql));
+    assert_eq!(cache.parse(sql).unwrap(), parsed);
+    assert_eq!(cache.len(), 1);
+
+    // text that fails to parse is not kept
+    assert!(cache.parse("SELECT FROM").is_err());
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn least_recently_used_entries_are_evicted() {
+    let mut cache = StmtCache::new(2);
+    cache.parse("SELECT 1").unwrap();
+    cache.parse("SELECT 2").unwrap();
+    cache.parse("SELECT 1").unwrap();
+    cache.parse("SELECT 3").unwrap();
+    assert_eq!(cache.len(), 2);
+    assert!(cache.contains("SELECT 1") && cache.contains("SELECT 3"));
+    assert!(!cache.contains("SELECT 2"));
+
+    cache.set_capacity(1);
+    assert!(cache.contains("SELECT 3") && !cache.contains("SELECT 1"));
+    cache.set_capacity(0);
+    cache.parse("SELECT 4").unwrap();
+    assert!(cache.is_empty());
+    assert_eq!(StmtCache::default().capacity(), StmtCache::DEFAULT_CAPACITY);
+}
+
+#[test]
+fn database_cache_can_be_resized_and_cleared() {
+    let mut db = Database::memory();
+    assert_eq!(db.cache().capacity(), StmtCache::DEFAULT_CAPACITY);
+    db.execute("CREATE TABLE t (a INT)").unwrap();
+    db.query("SELECT a FROM t").unwrap();
+    assert!(db.cache().contains("SELECT a FROM t"));
+    db.cache().clear();
+    assert!(db.cache().is_empty());
+    db.cache().set_capacity(0);
+    db.query("SELECT a FROM t").unwrap();
+    assert!(db.cache().is_empty());
+}
@@ -0,0 +1,75 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::DataValue;
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+/// A database with a one-row table `one` to evaluate casts against.
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(&mut exec, "CREATE TABLE one (n INT); INSERT INTO one VALUES (1);").unwrap();
+    (dir, exec)
+}
+
+fn row(exec: &mut Executor, sql: &str) -> Vec<DataValue> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { mut rows, .. } => {
+            assert_eq!(rows.len(), 1, "{sql}");
+            rows.remove(0)
+        }
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+#[test]
+fn numeric_casts() {
+    let (_dir, mut exec) = setup();
+    assert_eq!(
+        row(
+            &mut exec,
+            "SELECT CAST(3 AS REAL), CAST(1.9 AS INT), CAST(-1.9 AS INT) FROM one"
+        ),
+        [DataValue::Real(3.0), DataValue::Int(1), DataValue::Int(-1)]
+    );
+    assert_eq!(
+        row(
+            &mut exec,
+            "SELECT CAST(TRUE AS INT), CAST(0 AS BOOL), CAST(12 AS TEXT) FROM one"
+        ),
+        [DataValue::Int(1), DataValue::Bool(false), DataValue::Text("12".into())]
+    );
+    assert_eq!(row(&mut exec, "SELECT CAST(NULL AS INT) FROM one"), [DataValue::Nil]);
+
+    run(&mut exec, "CREATE TABLE t (price REAL); INSERT INTO t VALUES (9.99);")
+        .unwrap();
+    assert_eq!(row(&mut exec, "SELECT CAST(price AS INT) FROM t"), [DataValue::Int(9)]);
+}
+
+#[test]
+fn text_casts_parse_or_fail() {
+    let (_dir, mut exec) = setup();
+    assert_eq!(
+        row(
+            &mut exec,
+            "SELECT CAST('42' AS INT), CAST(' 7 ' AS INT), CAST('true' AS BOOL) FROM one"
+        ),
+        [DataValue::Int(42), DataValue::Int(7), DataValue::Bool(true)]
+    );
+    for sql in
+        ["SELECT CAST('abc' AS INT) FROM one", "SELECT CAST('1.5' AS INT) FROM one"]
+    {
+        let Err(err) = run(&mut exec, sql) else {
+            panic!("{sql} succeeded");
+        };
+        assert!(matches!(err, SQRLErr::InvalidCast(_)), "{sql}: {err:?}");
+    }
+}
@@ -0,0 +1,94 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::ColumnType;
+use litesqrl::storage::Storage;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn rows(exec: &mut Executor, sql: &str) -> Vec<Vec<String>> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => rows
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+#[test]
+fn schema_survives_reopening() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let schema = {
+        let mut exec = Executor::new(Storage::create(&path).unwrap());
+        run(
+            &mut exec,
+            "CREATE TABLE users (id INT PRIMARY KEY,
+                                 name TEXT NOT NULL,
+                                 code TEXT UNIQUE,
+                                 email TEXT DEFAULT 'none',
+                                 active BOOL, ratio FLOAT);
+             CREATE TABLE gone (a INT);
+             DROP TABLE gone;",
+        )
+        .unwrap();
+        exec.schema_ddl()
+    };
+
+    let storage = Storage::open(&path).unwrap();
+    assert!(storage.get_table("gone").is_err());
+    let users = storage.get_table("users").unwrap();
+    let types = users.cols.iter().map(|col| col.data_type).collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        [
+            ColumnType::Int,
+            ColumnType::Text,
+            ColumnType::Text,
+            ColumnType::Text,
+            ColumnType::Bool,
+            ColumnType::Real,
+        ]
+    );
+    let mut exec = Executor::new(storage);
+    assert_eq!(exec.schema_ddl(), schema);
+
+    // the constraints are enforced after reopening too
+    run(&mut exec, "INSERT INTO users (id, name, code) VALUES (1, 'a', 'x')").unwrap();
+    assert!(
+        run(&mut exec, "INSERT INTO users (id, name, code) VALUES (2, 'b', 'x')")
+            .is_err()
+    );
+    assert!(run(&mut exec, "INSERT INTO users (id, name) VALUES (1, 'c')").is_err());
+    assert!(run(&mut exec, "INSERT INTO users (id, code) VALUES (3, 'y')").is_err());
+    assert_eq!(rows(&mut exec, "SELECT id, email FROM users"), [["1", "none"]]);
+}
+
+#[test]
+fn altered_schema_survives_reopening() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let schema = {
+        let mut exec = Executor::new(Storage::create(&path).unwrap());
+        run(
+            &mut exec,
+            "CREATE TABLE t (a INT, b TEXT);
+             INSERT INTO t VALUES (1, 'x');
+             ALTER TABLE t ADD COLUMN c FLOAT DEFAULT 1.5;
+             ALTER TABLE t DROP COLUMN b;
+             ALTER TABLE t RENAME TO u;",
+        )
+        .unwrap();
+        exec.schema_ddl()
+    };
+
+    let mut exec = Executor::new(Storage::open(&path).unwrap());
+    assert_eq!(exec.schema_ddl(), schema);
+    assert_eq!(rows(&mut exec, "SELECT * FROM u"), [["1", "1.5"]]);
+}
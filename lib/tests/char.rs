@@ -0,0 +1,117 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::Char;
+use litesqrl::storage::Storage;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn texts(exec: &mut Executor, sql: &str) -> Vec<String> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => {
+            rows.iter().map(|row| row[0].to_string()).collect()
+        }
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+#[test]
+fn char_pads_to_its_width() {
+    let value = Char::new("ab", 4).unwrap();
+    assert_eq!(value.as_str(), "ab  ");
+    assert_eq!(value.trimmed(), "ab");
+    assert_eq!(Char::new("다람", 3).unwrap().as_str(), "다람 ");
+
+    // trailing spaces past the width are dropped, anything else is too long
+    assert_eq!(Char::new("ab    ", 4).unwrap().as_str(), "ab  ");
+    let err = Char::new("acorn", 4).unwrap_err();
+    assert_eq!((err.len, err.width), (5, 4));
+    assert_eq!(err.to_string(), "value too long for CHAR(4): 5 characters");
+}
+
+#[test]
+fn char_trailing_spaces_are_not_significant() {
+    assert_eq!(Char::new("ab", 2).unwrap(), Char::new("ab ", 5).unwrap());
+    assert_ne!(Char::new("ab", 5).unwrap(), Char::new(" ab", 5).unwrap());
+    assert!(Char::new("ab", 5).unwrap() < Char::new("ab!", 3).unwrap());
+}
+
+#[test]
+fn char_columns_read_back_without_padding() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("test.db");
+    let mut exec = Executor::new(Storage::create(&path).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (c CHAR(5) UNIQUE);
+         INSERT INTO t VALUES ('ab');
+         INSERT INTO t VALUES ('cd   ');",
+    )
+    .unwrap();
+    assert_eq!(texts(&mut exec, "SELECT c FROM t"), ["ab", "cd"]);
+    assert_eq!(texts(&mut exec, "SELECT c FROM t WHERE c = 'cd'"), ["cd"]);
+
+    let Err(err) = run(&mut exec, "INSERT INTO t VALUES ('acorns')") else {
+        panic!("long value was inserted");
+    };
+    assert!(matches!(&err, SQRLErr::ValueTooLong { column, .. } if column == "c"));
+    // padding does not make a value distinct
+    assert!(run(&mut exec, "INSERT INTO t VALUES ('ab ')").is_err());
+
+    drop(exec);
+    let mut exec = Executor::new(Storage::open(&path).unwrap());
+    assert_eq!(texts(&mut exec, "SELECT c FROM t"), ["ab", "cd"]);
+}
+
+fn char_table() -> (tempfile::TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (c CHAR(5), k CHAR(5) UNIQUE, n CHAR(5) COLLATE NOCASE);
+         INSERT INTO t VALUES ('ab', 'ab', 'Ab'), ('cd', 'cd', 'Cd');",
+    )
+    .unwrap();
+    (dir, exec)
+}
+
+#[test]
+fn char_comparisons_ignore_trailing_spaces() {
+    let (_dir, mut exec) = char_table();
+    for sql in [
+        "SELECT c FROM t WHERE c = 'ab   '",
+        "SELECT c FROM t WHERE c = 'ab       '",
+        "SELECT c FROM t WHERE 'ab ' = c",
+        "SELECT c FROM t WHERE c IN ('x', 'ab ')",
+        "SELECT c FROM t WHERE c < 'ab  ' OR c <= 'ab  '",
+        "SELECT c FROM t WHERE n = 'AB   '",
+    ] {
+        assert_eq!(texts(&mut exec, sql), ["ab"], "{sql}");
+    }
+    assert!(texts(&mut exec, "SELECT c FROM t WHERE c = ' ab'").is_empty());
+}
+
+#[test]
+fn char_index_seeks_ignore_trailing_spaces() {
+    let (_dir, mut exec) = char_table();
+    assert_eq!(texts(&mut exec, "SELECT k FROM t WHERE k = 'cd'"), ["cd"]);
+    assert_eq!(texts(&mut exec, "SELECT k FROM t WHERE k = 'cd        '"), ["cd"]);
+    let Err(err) = run(&mut exec, "INSERT INTO t (k) VALUES ('cd  ')") else {
+        panic!("padded duplicate was inserted");
+    };
+    assert!(err.to_string().contains("UNIQUE constraint failed"), "{err}");
+}
+
+#[test]
+fn rtrim_drops_trailing_spaces() {
+    let (_dir, mut exec) = char_table();
+    assert_eq!(
+        texts(&mut exec, "SELECT RTRIM('  a b  ') || '|' FROM t WHERE c = 'ab'"),
+        ["  a b|"]
+    );
+}
@@ -0,0 +1,138 @@
+use litesqrl::Database;
+use litesqrl::executor::SQRLErr;
+use litesqrl::schema::DataType;
+
+fn db() -> Database {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE t (
+            id INT UNIQUE, r REAL, name TEXT, code CHAR(3), day DATE,
+            at TIMESTAMP, price DECIMAL(8, 2), ok BOOL
+        );
+        INSERT INTO t VALUES (1, 1, 'a', 'abc', '2024-01-31', '2024-01-31', 1, TRUE);",
+    )
+    .unwrap();
+    db
+}
+
+fn check_err(db: &Database, sql: &str) -> SQRLErr {
+    let err = db.check(sql).expect_err(sql);
+    // running reports the same error
+    let mut copy = self::db();
+    assert_eq!(copy.execute(sql).expect_err(sql).to_string(), err.to_string(), "{sql}");
+    err
+}
+
+#[test]
+fn valid_statements_pass() {
+    let db = db();
+    for sql in [
+        "SELECT id + 1, r * 2, id / 2, -id, NOT ok, name || id FROM t",
+        "SELECT id FROM t WHERE id = 1.0 AND name LIKE 'a%' OR id IN (1, 2)",
+        "SELECT COUNT(*) + 1 FROM t",
+        "SELECT CAST(name AS INT) + 1 FROM t",
+        "SELECT price + 1, price * r FROM t WHERE price > 2",
+        "SELECT id FROM t WHERE ? AND id = ?",
+        "SELECT a + 1 FROM (SELECT name AS a FROM t) AS s",
+        "INSERT INTO t VALUES (2, 1, 'b', 'abc', '2024-01-31', DATE '2024-01-31', 3, TRUE)",
+        "INSERT INTO t (id, r, price, name) VALUES (NULL, 1 + 2, 1.5, NULL)",
+        "INSERT INTO t (day, at) VALUES (?, :at)",
+        "UPDATE t SET r = id, name = name || '!', day = name WHERE id = 1",
+        "DELETE FROM t WHERE NOT ok RETURNING id * 2",
+    ] {
+        db.check(sql).unwrap_or_else(|err| panic!("{sql}: {err}"));
+    }
+}
+
+#[test]
+fn invalid_operands_fail() {
+    let db = db();
+    for sql in [
+        "SELECT 1 + 'a'",
+        "SELECT id - name FROM t",
+        "SELECT NOT id FROM t",
+        "SELECT id FROM t WHERE name = 1",
+        "SELECT id FROM t WHERE id IN (1, 'a')",
+        "SELECT id FROM t WHERE id AND ok",
+        "DELETE FROM t WHERE (id + 1) * day > 0",
+        "UPDATE t SET id = 1 RETURNING name + 1",
+    ] {
+        let err = check_err(&db, sql);
+        assert!(
+            matches!(err, SQRLErr::InvalidBinaryOp(_) | SQRLErr::InvalidUnaryOp(_)),
+            "{sql}: {err}"
+        );
+    }
+    assert!(matches!(
+        check_err(&db, "SELECT id FROM t WHERE name"),
+        SQRLErr::InvalidPredicate(DataType::Text)
+    ));
+}
+
+#[test]
+fn mistyped_values_fail() {
+    let db = db();
+    for (sql, column, got) in [
+        ("UPDATE t SET id = 'x'", "id", DataType::Text),
+        ("UPDATE t SET ok = id WHERE id = 1", "ok", DataType::Int),
+        ("INSERT INTO t (id) VALUES (1.5)", "id", DataType::Real),
+        ("INSERT INTO t (name, day) VALUES ('a', 1)", "day", DataType::Int),
+        ("INSERT INTO t (id) VALUES (1), ('2')", "id", DataType::Text),
+    ] {
+        match check_err(&db, sql) {
+            SQRLErr::TypeMismatch { column: c, got: g, .. } => {
+                assert_eq!((c.as_str(), g), (column, got), "{sql}")
+            }
+            err => panic!("{sql}: {err}"),
+        }
+    }
+}
+
+#[test]
+fn checking_changes_nothing() {
+    let mut db = db();
+    for sql in [
+        "INSERT INTO t (id, name) VALUES (2, 'b')",
+        "UPDATE t SET name = 'z'",
+        "DELETE FROM t",
+        "CREATE TABLE u (a INT)",
+        "DROP TABLE t",
+    ] {
+        db.check(sql).unwrap_or_else(|err| panic!("{sql}: {err}"));
+    }
+    let rows = db.query("SELECT id, name FROM t").unwrap();
+    assert_eq!(rows.len(), 1);
+    assert!(db.check("SELECT * FROM u").is_err());
+}
+
+#[test]
+fn missing_and_clashing_names_fail() {
+    let db = db();
+    for sql in [
+        "SELECT * FROM nope",
+        "SELECT nope FROM t",
+        "SELECT id FROM t WHERE nope = 1 ORDER BY id",
+        "INSERT INTO t (nope) VALUES (1)",
+        "UPDATE t SET nope = 1",
+        "DELETE FROM t WHERE nope > 0",
+        "CREATE TABLE t (a INT)",
+        "CREATE TABLE u (a INT, a TEXT)",
+        "ROLLBACK TO nope",
+    ] {
+        assert!(db.check(sql).is_err(), "{sql}");
+    }
+    db.check("CREATE TABLE IF NOT EXISTS t (a INT)").unwrap();
+}
+
+#[test]
+fn value_and_column_counts_must_match() {
+    let db = db();
+    for sql in [
+        "INSERT INTO t (id, name) VALUES (1)",
+        "INSERT INTO t (id) SELECT id, name FROM t",
+        "SELECT id FROM t UNION SELECT id, name FROM t",
+    ] {
+        let err = db.check(sql).expect_err(sql);
+        assert!(matches!(err, SQRLErr::ColumnCountMismatch { .. }), "{sql}: {err}");
+    }
+}
@@ -0,0 +1,68 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn rows(exec: &mut Executor, sql: &str) -> Vec<Vec<String>> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => rows
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE people (name TEXT, age INT CHECK (age >= 0), min INT, max INT,
+                              CHECK (min <= max))",
+    )
+    .unwrap();
+    (dir, exec)
+}
+
+fn assert_check_fails(exec: &mut Executor, sql: &str) {
+    match run(exec, sql) {
+        Err(SQRLErr::ConstraintViolation(msg)) => {
+            assert!(msg.starts_with("CHECK constraint failed"), "{sql}: {msg}")
+        }
+        Err(err) => panic!("{sql}: {err:?}"),
+        Ok(_) => panic!("{sql} ran"),
+    }
+}
+
+#[test]
+fn rows_must_pass_their_checks() {
+    let (_dir, mut exec) = setup();
+    run(&mut exec, "INSERT INTO people VALUES ('a', 30, 1, 2)").unwrap();
+    assert_check_fails(&mut exec, "INSERT INTO people VALUES ('b', -1, 1, 2)");
+    assert_check_fails(&mut exec, "INSERT INTO people VALUES ('b', 1, 3, 2)");
+    assert_check_fails(&mut exec, "UPDATE people SET age = age - 31");
+    assert_check_fails(&mut exec, "UPDATE people SET min = 5");
+    assert_eq!(rows(&mut exec, "SELECT age, min FROM people"), [["30", "1"]]);
+}
+
+#[test]
+fn checks_that_evaluate_to_null_pass() {
+    let (_dir, mut exec) = setup();
+    run(
+        &mut exec,
+        "INSERT INTO people VALUES ('a', NULL, 1, 2);
+         INSERT INTO people VALUES ('b', 5, NULL, 0);
+         UPDATE people SET max = NULL;",
+    )
+    .unwrap();
+    assert_eq!(rows(&mut exec, "SELECT * FROM people").len(), 2);
+}
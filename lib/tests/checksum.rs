@@ -0,0 +1,85 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::storage::{Storage, StorageErr};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn create(dir: &Path) -> PathBuf {
+    let path = dir.join("db.sqrl");
+    let mut exec = Executor::new(Storage::create(&path).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE squirrels (name TEXT);
+         INSERT INTO squirrels VALUES ('acorn');
+         INSERT INTO squirrels VALUES ('hazel');",
+    )
+    .unwrap();
+    path
+}
+
+fn corruption(path: &Path) -> String {
+    match Storage::open(path) {
+        Err(StorageErr::Corrupted(msg)) => msg,
+        Err(e) => panic!("not corruption: {e:?}"),
+        Ok(_) => panic!("corruption went unnoticed"),
+    }
+}
+
+fn edit(path: &Path, f: impl FnOnce(&mut Vec<u8>)) {
+    let mut bytes = fs::read(path).unwrap();
+    f(&mut bytes);
+    fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn corrupt_records_fail_with_their_offset() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = create(dir.path());
+    edit(&path, |bytes| {
+        let at = bytes.windows(5).position(|w| w == b"acorn").unwrap();
+        bytes[at] = b'b';
+    });
+    let msg = corruption(&path);
+    assert!(msg.starts_with("invalid crc at offset "), "{msg}");
+}
+
+#[test]
+fn corrupt_headers_fail() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = create(dir.path());
+    // an unknown flag bit, which the header crc covers
+    edit(&path, |bytes| bytes[7] ^= 0x80);
+    assert_eq!(corruption(&path), "invalid header crc");
+    edit(&path, |bytes| {
+        bytes[7] ^= 0x80;
+        bytes[0] ^= 0xff;
+    });
+    assert_eq!(corruption(&path), "magic mismatch");
+}
+
+#[test]
+fn vacuum_adds_the_header_checksum() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = create(dir.path());
+    let header = fs::read(&path).unwrap()[..12].to_vec();
+    assert_eq!(header[8..12], crc32fast::hash(&header[..8]).to_le_bytes());
+
+    // a file from before the checksum: no flag, no crc
+    edit(&path, |bytes| {
+        bytes[6] &= !1;
+        bytes[8..12].fill(0);
+    });
+    let mut exec = Executor::new(Storage::open(&path).unwrap());
+    run(&mut exec, "VACUUM").unwrap();
+    drop(exec);
+    assert_eq!(fs::read(&path).unwrap()[..12], header);
+    Storage::open(&path).unwrap();
+}
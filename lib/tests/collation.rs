@@ -0,0 +1,113 @@
+use litesqrl::Database;
+use litesqrl::schema::DataValue;
+
+fn texts(db: &mut Database, sql: &str) -> Vec<String> {
+    db.query(sql).unwrap().into_iter().map(|row| row[0].to_string()).collect()
+}
+
+fn unique_failed(db: &mut Database, sql: &str) {
+    let err = db.execute(sql).expect_err(sql).to_string();
+    assert!(err.contains("UNIQUE constraint failed"), "{sql}: {err}");
+}
+
+#[test]
+fn nocase_compares_and_sorts_without_case() {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE t (name TEXT COLLATE NOCASE, code TEXT);
+         INSERT INTO t VALUES ('b', 'b'), ('A', 'A'), ('c', 'c');",
+    )
+    .unwrap();
+    assert_eq!(texts(&mut db, "SELECT name FROM t WHERE name = 'a'"), ["A"]);
+    assert_eq!(texts(&mut db, "SELECT name FROM t ORDER BY name"), ["A", "b", "c"]);
+    assert!(texts(&mut db, "SELECT code FROM t WHERE code = 'a'").is_empty());
+    assert_eq!(
+        texts(&mut db, "SELECT code FROM t WHERE code = 'a' COLLATE NOCASE"),
+        ["A"]
+    );
+    assert_eq!(
+        db.query("SELECT 'ABC' LIKE 'abc', 'ABC' COLLATE NOCASE LIKE 'abc'")
+            .unwrap()
+            .into_rows(),
+        [[DataValue::Bool(false), DataValue::Bool(true)]]
+    );
+}
+
+#[test]
+fn nocase_unique_rejects_case_variants() {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE t (id INT, name TEXT COLLATE NOCASE UNIQUE);
+         INSERT INTO t VALUES (1, 'Alice'), (2, 'Bob');",
+    )
+    .unwrap();
+    unique_failed(&mut db, "INSERT INTO t VALUES (3, 'ALICE')");
+    unique_failed(&mut db, "INSERT INTO t VALUES (3, 'carol'), (4, 'Carol')");
+    unique_failed(&mut db, "UPDATE t SET name = 'bob' WHERE id = 1");
+    assert_eq!(texts(&mut db, "SELECT name FROM t WHERE name = 'alice'"), ["Alice"]);
+
+    // changing only the case of a value keeps it unique
+    db.execute("UPDATE t SET name = 'ALICE' WHERE id = 1").unwrap();
+    assert_eq!(texts(&mut db, "SELECT name FROM t WHERE id = 1"), ["ALICE"]);
+    db.execute("INSERT INTO t VALUES (3, 'alicia')").unwrap();
+}
+
+#[test]
+fn nocase_unique_survives_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("t.sqrl");
+    {
+        let mut db = Database::create(&path).unwrap();
+        db.execute(
+            "CREATE TABLE t (name TEXT COLLATE NOCASE UNIQUE);
+             INSERT INTO t VALUES ('Alice');",
+        )
+        .unwrap();
+    }
+    let mut db = Database::open(&path).unwrap();
+    unique_failed(&mut db, "INSERT INTO t VALUES ('aLiCe')");
+}
+
+#[test]
+fn foreign_keys_use_the_referenced_collation() {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE p (name TEXT COLLATE NOCASE UNIQUE);
+         CREATE TABLE c (p_name TEXT, FOREIGN KEY (p_name) REFERENCES p(name));
+         INSERT INTO p VALUES ('Alice');
+         INSERT INTO c VALUES ('ALICE');",
+    )
+    .unwrap();
+    let err = db.execute("DELETE FROM p WHERE name = 'alice'").unwrap_err();
+    assert!(err.to_string().contains("FOREIGN KEY constraint failed"), "{err}");
+    let err = db.execute("INSERT INTO c VALUES ('Bob')").unwrap_err();
+    assert!(err.to_string().contains("FOREIGN KEY constraint failed"), "{err}");
+}
+
+#[test]
+fn nocase_survives_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("t.sqrl");
+    {
+        let mut db = Database::create(&path).unwrap();
+        db.execute(
+            "CREATE TABLE t (name TEXT COLLATE NOCASE);
+             INSERT INTO t VALUES ('Alice'), ('bob');",
+        )
+        .unwrap();
+    }
+    let mut db = Database::open(&path).unwrap();
+    assert_eq!(texts(&mut db, "SELECT name FROM t WHERE name = 'ALICE'"), ["Alice"]);
+    assert_eq!(
+        texts(&mut db, "SELECT name FROM t ORDER BY name DESC"),
+        ["bob", "Alice"]
+    );
+}
+
+#[test]
+fn unknown_collations_fail() {
+    let mut db = Database::memory();
+    for sql in ["CREATE TABLE t (a TEXT COLLATE RTRIM)", "SELECT 'a' COLLATE BINARY2"] {
+        assert!(db.execute(sql).is_err(), "{sql}");
+    }
+}
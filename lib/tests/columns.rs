@@ -0,0 +1,57 @@
+use litesqrl::Database;
+use litesqrl::executor::SQRLErr;
+use tempfile::TempDir;
+
+fn setup() -> (TempDir, Database) {
+    let dir = tempfile::tempdir().unwrap();
+    let db = Database::create(dir.path().join("test.db")).unwrap();
+    (dir, db)
+}
+
+fn duplicate(db: &mut Database, sql: &str) -> String {
+    match db.execute(sql) {
+        Err(SQRLErr::DuplicateColumn(name)) => name,
+        other => panic!("{sql}: {other:?}"),
+    }
+}
+
+#[test]
+fn duplicate_column_names_are_rejected() {
+    let (_dir, mut db) = setup();
+    assert_eq!(duplicate(&mut db, "CREATE TABLE t (a INT, b TEXT, a TEXT)"), "a");
+    assert!(db.executor().table_names().is_empty());
+
+    // names compare case-sensitively
+    db.execute("CREATE TABLE t (a INT, A TEXT, b INT)").unwrap();
+    let columns = db.query("SELECT * FROM t").unwrap();
+    assert_eq!(columns.columns(), ["a", "A", "b"]);
+    assert_eq!(duplicate(&mut db, "ALTER TABLE t ADD COLUMN b REAL"), "b");
+    assert_eq!(duplicate(&mut db, "INSERT INTO t (a, b, a) VALUES (1, 2, 3)"), "a");
+    assert_eq!(duplicate(&mut db, "UPDATE t SET b = 1, b = 2"), "b");
+}
+
+#[test]
+fn unknown_and_ambiguous_columns_are_errors() {
+    let (_dir, mut db) = setup();
+    db.execute("CREATE TABLE t (a INT, b INT)").unwrap();
+    // an empty table evaluates no rows, but the reference still fails
+    for sql in [
+        "SELECT nope FROM t",
+        "SELECT a FROM t WHERE nope = 1",
+        "SELECT a FROM t ORDER BY nope",
+        "UPDATE t SET a = nope",
+        "DELETE FROM t WHERE nope = 1",
+    ] {
+        let err = db.query(sql).unwrap_err();
+        assert!(
+            matches!(&err, SQRLErr::ColumnNotFound(name) if name == "nope"),
+            "{sql}: {err:?}"
+        );
+    }
+
+    db.execute("INSERT INTO t VALUES (1, 2)").unwrap();
+    let err = db.query("SELECT a FROM (SELECT a, b AS a FROM t) AS s").unwrap_err();
+    assert!(matches!(&err, SQRLErr::AmbiguousColumn(name) if name == "a"), "{err:?}");
+    // output labels can be used by ORDER BY
+    assert_eq!(db.query("SELECT a AS x FROM t ORDER BY x").unwrap().len(), 1);
+}
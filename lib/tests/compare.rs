@@ -0,0 +1,129 @@
+use litesqrl::Database;
+use litesqrl::executor::SQRLErr;
+use litesqrl::schema::DataValue;
+
+fn bools(db: &mut Database, sql: &str) -> Vec<DataValue> {
+    db.query(sql).unwrap().into_rows().remove(0)
+}
+
+#[test]
+fn int_and_real_compare_exactly() {
+    let mut db = Database::memory();
+    // 2^53 + 1 has no exact REAL, and rounds to 2^53
+    assert_eq!(
+        bools(
+            &mut db,
+            "SELECT 9007199254740993 = 9007199254740992.0,
+                    9007199254740993 > 9007199254740992.0,
+                    9007199254740992.0 < 9007199254740993,
+                    9007199254740992 = 9007199254740992.0,
+                    9223372036854775807 < 9223372036854775807.0,
+                    2 > 1.5, -1 > -1.5, 1 <= 1.0, -0.0 = 0"
+        ),
+        [false, true, true, true, true, true, true, true, true].map(DataValue::Bool)
+    );
+}
+
+#[test]
+fn indexed_ints_compare_exactly_with_reals() {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE t (a INT UNIQUE);
+         INSERT INTO t VALUES (9007199254740992), (9007199254740993);",
+    )
+    .unwrap();
+    let ints = |db: &mut Database, sql| {
+        db.query(sql).unwrap().into_iter().map(|row| row[0].clone()).collect::<Vec<_>>()
+    };
+    assert_eq!(
+        ints(&mut db, "SELECT a FROM t WHERE a = 9007199254740992.0"),
+        [DataValue::Int(9007199254740992)]
+    );
+    assert_eq!(
+        ints(&mut db, "SELECT a FROM t WHERE a > 9007199254740992.0"),
+        [DataValue::Int(9007199254740993)]
+    );
+    assert_eq!(
+        ints(&mut db, "SELECT a FROM t WHERE a IN (9007199254740992.0)"),
+        [DataValue::Int(9007199254740992)]
+    );
+}
+
+#[test]
+fn null_safe_equality_is_never_null() {
+    let mut db = Database::memory();
+    assert_eq!(
+        bools(
+            &mut db,
+            "SELECT NULL <=> NULL, NULL <=> 1, 1 <=> NULL, 1 <=> 1, 1 <=> 2, 1 <=> 1.0,
+                    'a' <=> 'a'"
+        ),
+        [true, false, false, true, false, true, true].map(DataValue::Bool)
+    );
+    assert_eq!(
+        bools(
+            &mut db,
+            "SELECT NULL IS DISTINCT FROM NULL, NULL IS DISTINCT FROM 1,
+                    1 IS DISTINCT FROM 2, 1 IS NOT DISTINCT FROM 1,
+                    NULL IS NOT DISTINCT FROM NULL, 2 IS NOT DISTINCT FROM NULL"
+        ),
+        [false, true, true, true, true, false].map(DataValue::Bool)
+    );
+}
+
+#[test]
+fn null_safe_equality_filters_rows() {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE t (id INT, a INT);
+         INSERT INTO t VALUES (1, NULL), (2, 5), (3, NULL);",
+    )
+    .unwrap();
+    let ids = |db: &mut Database, sql| db.query(sql).unwrap().into_rows();
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM t WHERE a <=> NULL"),
+        [[DataValue::Int(1)], [DataValue::Int(3)]]
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM t WHERE a IS DISTINCT FROM NULL"),
+        [[DataValue::Int(2)]]
+    );
+    assert_eq!(
+        ids(&mut db, "SELECT id FROM t WHERE a IS NOT DISTINCT FROM 5"),
+        [[DataValue::Int(2)]]
+    );
+}
+
+#[test]
+fn numbers_compare_by_value() {
+    let mut db = Database::memory();
+    assert_eq!(
+        bools(
+            &mut db,
+            "SELECT 1 = 1.0, 2 IN (1.0, 2.0), 1.5 > 1, 3 < 2.5, \
+                    CAST(2 AS DECIMAL(3, 1)) = 2, NOT 1 = 1.0"
+        ),
+        [true, true, true, false, true, false].map(DataValue::Bool)
+    );
+}
+
+#[test]
+fn mixed_type_equality_fails() {
+    let mut db = Database::memory();
+    db.execute("CREATE TABLE t (id INT, flag BOOL); INSERT INTO t VALUES (1, TRUE);")
+        .unwrap();
+    for sql in [
+        "SELECT id FROM t WHERE id = '1'",
+        "SELECT id FROM t WHERE flag = 1",
+        "SELECT id FROM t WHERE id IN (2, 'a')",
+        "SELECT 'a' = 1",
+    ] {
+        let err = db.query(sql).unwrap_err();
+        assert!(matches!(err, SQRLErr::InvalidBinaryOp(_)), "{sql}: {err:?}");
+    }
+    // NULL equals only NULL
+    assert_eq!(
+        bools(&mut db, "SELECT id = NULL, NULL = NULL FROM t"),
+        [false, true].map(DataValue::Bool)
+    );
+}
@@ -0,0 +1,101 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn rows(exec: &mut Executor, sql: &str) -> Vec<Vec<String>> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => rows
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+fn setup(ddl: &str) -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(&mut exec, ddl).unwrap();
+    (dir, exec)
+}
+
+fn violation(exec: &mut Executor, sql: &str) -> String {
+    match run(exec, sql) {
+        Err(SQRLErr::ConstraintViolation(message)) => message,
+        Err(err) => panic!("{sql}: {err:?}"),
+        Ok(_) => panic!("{sql} ran"),
+    }
+}
+
+fn count(exec: &mut Executor, table: &str) -> usize {
+    rows(exec, &format!("SELECT * FROM {table}")).len()
+}
+
+#[test]
+fn not_null_rejects_null() {
+    let (_dir, mut exec) = setup("CREATE TABLE t (a INT NOT NULL, b TEXT)");
+    let message = violation(&mut exec, "INSERT INTO t VALUES (NULL, 'x')");
+    assert_eq!(message, "NOT NULL constraint failed: t.a");
+
+    run(&mut exec, "INSERT INTO t VALUES (1, 'x')").unwrap();
+    violation(&mut exec, "UPDATE t SET a = NULL");
+    assert_eq!(rows(&mut exec, "SELECT a FROM t"), [["1"]]);
+}
+
+#[test]
+fn not_null_rejects_omitted_columns() {
+    let (_dir, mut exec) = setup("CREATE TABLE t (a INT NOT NULL, b TEXT)");
+    violation(&mut exec, "INSERT INTO t (b) VALUES ('x')");
+    assert_eq!(count(&mut exec, "t"), 0);
+}
+
+#[test]
+fn defaults_satisfy_not_null() {
+    let (_dir, mut exec) = setup("CREATE TABLE t (a INT NOT NULL DEFAULT 7, b TEXT)");
+    run(&mut exec, "INSERT INTO t (b) VALUES ('x')").unwrap();
+    assert_eq!(rows(&mut exec, "SELECT a FROM t"), [["7"]]);
+}
+
+#[test]
+fn primary_keys_are_unique_and_not_null() {
+    let (_dir, mut exec) = setup("CREATE TABLE t (id INT PRIMARY KEY, name TEXT)");
+    run(&mut exec, "INSERT INTO t VALUES (1, 'a')").unwrap();
+    let message = violation(&mut exec, "INSERT INTO t VALUES (1, 'b')");
+    assert_eq!(message, "UNIQUE constraint failed: t.id");
+    violation(&mut exec, "INSERT INTO t VALUES (NULL, 'b')");
+    assert_eq!(count(&mut exec, "t"), 1);
+}
+
+#[test]
+fn unique_rejects_colliding_updates() {
+    let (_dir, mut exec) = setup(
+        "CREATE TABLE t (id INT, email TEXT UNIQUE);
+         INSERT INTO t VALUES (1, 'a@x');
+         INSERT INTO t VALUES (2, 'b@x');
+         INSERT INTO t VALUES (3, NULL);
+         INSERT INTO t VALUES (4, NULL);",
+    );
+    let message = violation(&mut exec, "UPDATE t SET email = 'a@x' WHERE id = 2");
+    assert_eq!(message, "UNIQUE constraint failed: t.email");
+    // a collision between rows of one statement is caught too
+    violation(&mut exec, "UPDATE t SET email = 'same'");
+    // swapping values within one statement leaves them unique
+    run(
+        &mut exec,
+        "CREATE TABLE u (n INT UNIQUE);
+         INSERT INTO u VALUES (1);
+         INSERT INTO u VALUES (2);",
+    )
+    .unwrap();
+    run(&mut exec, "UPDATE u SET n = 3 - n").unwrap();
+    assert_eq!(rows(&mut exec, "SELECT n FROM u"), [["2"], ["1"]]);
+}
@@ -0,0 +1,172 @@
+use litesqrl::executor::QueryResult;
+use litesqrl::schema::DataValue;
+use litesqrl::{Database, OnError};
+
+#[test]
+fn open_create_insert_query() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("people.sqrl");
+    {
+        let mut db = Database::create(&path).unwrap();
+        let written = db
+            .execute(
+                "CREATE TABLE people (id INT UNIQUE, name TEXT, score REAL);
+                 INSERT INTO people VALUES (1, 'Ada', 9.5), (2, 'Bo', NULL);",
+            )
+            .unwrap();
+        assert_eq!(written, 2);
+    }
+
+    let mut db = Database::open(&path).unwrap();
+    let rows = db.query("SELECT id, name, score FROM people ORDER BY id").unwrap();
+    assert_eq!(rows.columns(), ["id", "name", "score"]);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows.column_index("name"), Some(1));
+    assert_eq!(rows.get(0, "name"), Some(&DataValue::Text("Ada".into())));
+    assert_eq!(rows.get(1, "score"), Some(&DataValue::Nil));
+    assert_eq!(
+        rows.into_rows(),
+        [
+            [DataValue::Int(1), DataValue::Text("Ada".into()), DataValue::Real(9.5)],
+            [DataValue::Int(2), DataValue::Text("Bo".into()), DataValue::Nil],
+        ]
+    );
+}
+
+#[test]
+fn execute_counts_only_written_rows() {
+    let mut db = Database::memory();
+    db.execute("CREATE TABLE t (a INT); INSERT INTO t VALUES (1), (2), (3);").unwrap();
+    assert_eq!(db.execute("SELECT * FROM t").unwrap(), 0);
+    assert_eq!(db.execute("UPDATE t SET a = a + 1 WHERE a > 1").unwrap(), 2);
+    assert_eq!(db.execute("DELETE FROM t WHERE a = 1 RETURNING a").unwrap(), 1);
+    assert_eq!(
+        db.execute("INSERT INTO t VALUES (7); SELECT a FROM t; DELETE FROM t").unwrap(),
+        4
+    );
+}
+
+#[test]
+fn query_returns_the_last_result() {
+    let mut db = Database::memory();
+    let rows = db.query("CREATE TABLE t (a INT)").unwrap();
+    assert!(rows.is_empty() && rows.columns().is_empty());
+    let rows = db.query("INSERT INTO t VALUES (1), (2) RETURNING a * 10").unwrap();
+    assert_eq!(rows.into_rows(), [[DataValue::Int(10)], [DataValue::Int(20)]]);
+}
+
+#[test]
+fn errors_leave_earlier_statements_applied() {
+    let mut db = Database::memory();
+    db.execute("CREATE TABLE t (a INT UNIQUE)").unwrap();
+    assert!(db.execute("INSERT INTO t VALUES (1); INSERT INTO t VALUES (1)").is_err());
+    assert_eq!(db.query("SELECT a FROM t").unwrap().len(), 1);
+
+    let results = db
+        .execute_batch(
+            "INSERT INTO t VALUES (1); INSERT INTO t VALUES (2)",
+            OnError::Continue,
+        )
+        .unwrap();
+    assert!(matches!(results[0], QueryResult::Err(_)), "{results:?}");
+    assert_eq!(results[1], QueryResult::Count(1));
+}
+
+#[test]
+fn execute_batch_reports_each_statement() {
+    let mut db = Database::memory();
+    let results = db
+        .execute_batch(
+            "CREATE TABLE t (a INT); INSERT INTO t VALUES (1), (2); SELECT a FROM t",
+            OnError::Stop,
+        )
+        .unwrap();
+    assert_eq!(
+        results,
+        [
+            QueryResult::Success,
+            QueryResult::Count(2),
+            QueryResult::Rows {
+                columns: vec!["a".to_string()],
+                rows: vec![vec![DataValue::Int(1)], vec![DataValue::Int(2)]],
+            },
+        ]
+    );
+
+    // stopping skips what follows the failure
+    let results = db
+        .execute_batch(
+            "INSERT INTO t VALUES (3); SELECT * FROM missing; INSERT INTO t VALUES (4)",
+            OnError::Stop,
+        )
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(matches!(results[1], QueryResult::Err(_)), "{results:?}");
+    assert_eq!(db.query("SELECT a FROM t").unwrap().len(), 3);
+
+    // text that does not parse runs nothing
+    assert!(
+        db.execute_batch("INSERT INTO t VALUES (5); SELEC", OnError::Continue).is_err()
+    );
+    assert_eq!(db.query("SELECT a FROM t").unwrap().len(), 3);
+}
+
+#[test]
+fn in_memory_crud() {
+    let mut db = Database::open_or_create(":memory:").unwrap();
+    db.execute(
+        "CREATE TABLE t (id INT PRIMARY KEY, name TEXT);
+         INSERT INTO t VALUES (1, 'a'), (2, 'b'), (3, 'c');
+         UPDATE t SET name = 'B' WHERE id = 2;
+         DELETE FROM t WHERE id = 3;",
+    )
+    .unwrap();
+    let rows = db.query("SELECT name FROM t").unwrap().into_rows();
+    assert_eq!(rows, [[DataValue::Text("a".into())], [DataValue::Text("B".into())]]);
+    // nothing was written to disk
+    assert!(!std::path::Path::new(":memory:").exists());
+
+    // every in-memory database starts out empty
+    let mut other = Database::open_or_create(":memory:").unwrap();
+    assert!(other.query("SELECT * FROM t").is_err());
+}
+
+#[test]
+fn writes_report_the_rows_they_affect() {
+    let mut db = Database::memory();
+    let results = db
+        .execute_batch(
+            "CREATE TABLE t (a INT);
+             INSERT INTO t VALUES (1), (2), (3);
+             UPDATE t SET a = a * 10 WHERE a > 1;
+             UPDATE t SET a = 0 WHERE a > 100;
+             DELETE FROM t WHERE a = 1;
+             INSERT INTO t SELECT a FROM t;
+             DELETE FROM t;",
+            OnError::Stop,
+        )
+        .unwrap();
+    assert_eq!(results[0], QueryResult::Success);
+    assert_eq!(results[1..], [3, 2, 0, 1, 2, 4].map(QueryResult::Count));
+    assert_eq!(db.execute("INSERT INTO t VALUES (5), (6)").unwrap(), 2);
+}
+
+#[test]
+fn update_reads_every_assignment_from_the_old_row() {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE t (id INT, a INT, b INT);
+         INSERT INTO t VALUES (1, 10, 20), (2, 30, 40);",
+    )
+    .unwrap();
+    db.execute("UPDATE t SET a = b, b = a WHERE id = 1").unwrap();
+    db.execute("UPDATE t SET a = a + 1, b = a WHERE id = 2").unwrap();
+    let rows = db.query("SELECT a, b FROM t ORDER BY id").unwrap().into_rows();
+    assert_eq!(
+        rows,
+        [
+            [DataValue::Int(20), DataValue::Int(10)],
+            [DataValue::Int(31), DataValue::Int(30)],
+        ]
+    );
+}
@@ -0,0 +1,89 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::{DataValue, parse_date, parse_timestamp};
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn values(exec: &mut Executor, sql: &str) -> Vec<Vec<DataValue>> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => rows,
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+fn texts(exec: &mut Executor, sql: &str) -> Vec<String> {
+    values(exec, sql).iter().map(|row| row[0].to_string()).collect()
+}
+
+/// A database with a one-row table `one` to evaluate literals against.
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(&mut exec, "CREATE TABLE one (n INT); INSERT INTO one VALUES (1);").unwrap();
+    (dir, exec)
+}
+
+#[test]
+fn date_and_timestamp_literals() {
+    let (_dir, mut exec) = setup();
+    let sql = "SELECT DATE '1970-01-02', TIMESTAMP '1970-01-01 00:01:05', \
+               DATE '1969-12-31' FROM one";
+    assert_eq!(
+        values(&mut exec, sql),
+        [[DataValue::Date(1), DataValue::Timestamp(65), DataValue::Date(-1)]]
+    );
+    assert_eq!(texts(&mut exec, "SELECT DATE '2024-02-29' FROM one"), ["2024-02-29"]);
+    assert_eq!(
+        texts(&mut exec, "SELECT TIMESTAMP '2024-01-01T12:30:00' FROM one"),
+        ["2024-01-01 12:30:00"]
+    );
+}
+
+#[test]
+fn dates_compare_and_sort_chronologically() {
+    let (_dir, mut exec) = setup();
+    run(
+        &mut exec,
+        "CREATE TABLE t (d DATE, at TIMESTAMP);
+         INSERT INTO t VALUES ('2024-01-10', '2024-01-10 09:00:00');
+         INSERT INTO t VALUES ('1999-12-31', '2024-01-10 23:59:59');
+         INSERT INTO t VALUES ('2024-01-09', '2000-06-01 00:00:00');",
+    )
+    .unwrap();
+    assert_eq!(
+        texts(&mut exec, "SELECT d FROM t ORDER BY d"),
+        ["1999-12-31", "2024-01-09", "2024-01-10"]
+    );
+    assert_eq!(
+        texts(&mut exec, "SELECT at FROM t ORDER BY at DESC"),
+        ["2024-01-10 23:59:59", "2024-01-10 09:00:00", "2000-06-01 00:00:00"]
+    );
+    assert_eq!(
+        texts(&mut exec, "SELECT d FROM t WHERE d > DATE '2000-01-01' ORDER BY d"),
+        ["2024-01-09", "2024-01-10"]
+    );
+}
+
+#[test]
+fn invalid_dates_are_rejected() {
+    for text in ["2023-02-29", "2024-13-01", "2024-1-01", "yesterday"] {
+        assert_eq!(parse_date(text), None, "{text}");
+    }
+    assert_eq!(parse_timestamp("2024-01-01 24:00:00"), None);
+
+    let (_dir, mut exec) = setup();
+    let Err(err) = run(&mut exec, "SELECT DATE '2023-02-29' FROM one") else {
+        panic!("invalid date was accepted");
+    };
+    assert!(err.to_string().contains("Invalid DATE literal"), "{err}");
+    run(&mut exec, "CREATE TABLE t (d DATE)").unwrap();
+    assert!(run(&mut exec, "INSERT INTO t VALUES ('2023-02-30')").is_err());
+}
@@ -0,0 +1,78 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::Decimal;
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn texts(exec: &mut Executor, sql: &str) -> Vec<String> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => {
+            rows.iter().map(|row| row[0].to_string()).collect()
+        }
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+fn setup(ddl: &str) -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(&mut exec, ddl).unwrap();
+    (dir, exec)
+}
+
+#[test]
+fn decimal_addition_is_exact() {
+    let (_dir, mut exec) = setup(
+        "CREATE TABLE m (amount DECIMAL(10, 2), r FLOAT);
+         INSERT INTO m VALUES (0.1, 0.1);
+         INSERT INTO m VALUES (0.2, 0.2);",
+    );
+    assert_eq!(texts(&mut exec, "SELECT SUM(r) FROM m"), ["0.30000000000000004"]);
+    assert_eq!(texts(&mut exec, "SELECT SUM(amount) FROM m"), ["0.30"]);
+    assert_eq!(
+        texts(&mut exec, "SELECT amount FROM m WHERE amount + 0.2 = 0.3"),
+        ["0.10"]
+    );
+
+    let a = Decimal::parse("0.1").unwrap();
+    let b = Decimal::parse("0.2").unwrap();
+    assert_eq!(a.checked_add(b), Decimal::parse("0.3"));
+    assert_eq!(b.checked_sub(a), Some(a));
+    assert_eq!(a.checked_mul(b), Decimal::parse("0.02"));
+}
+
+#[test]
+fn decimal_columns_keep_their_scale() {
+    let (_dir, mut exec) = setup(
+        "CREATE TABLE m (amount DECIMAL(5, 2));
+         INSERT INTO m VALUES (2);
+         INSERT INTO m VALUES (1.005);
+         INSERT INTO m VALUES (-1.005);",
+    );
+    // extra digits round half away from zero
+    assert_eq!(texts(&mut exec, "SELECT amount FROM m"), ["2.00", "1.01", "-1.01"]);
+    // the larger scale wins, and division rounds to it
+    assert_eq!(
+        texts(&mut exec, "SELECT amount + 0.001 FROM m WHERE amount = 2"),
+        ["2.001"]
+    );
+    assert_eq!(texts(&mut exec, "SELECT amount / 3 FROM m WHERE amount = 2"), ["0.67"]);
+    // values that do not fit the precision are refused, not truncated
+    let Err(err) = run(&mut exec, "INSERT INTO m VALUES (1000)") else {
+        panic!("out of range value was inserted");
+    };
+    assert!(err.to_string().contains("out of range for DECIMAL(5, 2)"), "{err}");
+
+    let value = Decimal::parse("1.50").unwrap();
+    assert_eq!(value.scale(), 2);
+    assert_eq!(value, Decimal::parse("1.5").unwrap());
+    assert!(value.fits(3, 2) && !value.fits(2, 2));
+}
@@ -0,0 +1,121 @@
+use litesqrl::Database;
+use litesqrl::schema::DataValue;
+
+fn dump(db: &mut Database) -> String {
+    db.executor().dump()
+}
+
+#[test]
+fn dump_loads_into_an_empty_database() {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE u (id INT PRIMARY KEY, name TEXT);
+         CREATE TABLE t (id INT, owner INT, r REAL, d DECIMAL(10, 2), b BLOB,
+                         FOREIGN KEY (owner) REFERENCES u(id));
+         INSERT INTO u VALUES (1, 'it\\'s');
+         INSERT INTO t VALUES (1, 1, 1.5, CAST('12.30' AS DECIMAL(10, 2)), X'00FF');
+         INSERT INTO t VALUES (2, NULL, NULL, NULL, NULL);",
+    )
+    .unwrap();
+    let script = dump(&mut db);
+    // referenced tables come first
+    assert!(
+        script.find("CREATE TABLE u").unwrap() < script.find("CREATE TABLE t").unwrap()
+    );
+
+    let mut copy = Database::memory();
+    copy.execute(&script).unwrap();
+    assert_eq!(dump(&mut copy), script);
+    for select in ["SELECT * FROM u", "SELECT * FROM t ORDER BY id"] {
+        assert_eq!(
+            copy.query(select).unwrap().into_rows(),
+            db.query(select).unwrap().into_rows()
+        );
+    }
+    let rows = copy.query("SELECT name FROM u").unwrap().into_rows();
+    assert_eq!(rows, [[DataValue::Text("it's".into())]]);
+}
+
+#[test]
+fn dump_skips_deleted_rows() {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE t (id INT PRIMARY KEY, name TEXT);
+         INSERT INTO t VALUES (1, 'a'), (2, 'b');
+         DELETE FROM t WHERE id = 1;",
+    )
+    .unwrap();
+    let script = dump(&mut db);
+    assert!(!script.contains("VALUES (1, "), "{script}");
+    assert!(script.contains("VALUES (2, 'b')"), "{script}");
+}
+
+#[test]
+fn dump_round_trips() {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE t (id INT PRIMARY KEY AUTOINCREMENT, r REAL, d DECIMAL(10, 2),
+                         s TEXT, b BLOB);
+         INSERT INTO t (r, d, s, b) VALUES (1.5, CAST('12.30' AS DECIMAL(10, 2)),
+                                            'it\\'s', X'00FF');
+         INSERT INTO t (r, d, s, b) VALUES (NULL, NULL, NULL, NULL);
+         INSERT INTO t (r, d, s, b) VALUES (-0.25, NULL, 'x', NULL);
+         DELETE FROM t WHERE r = -0.25;
+         UPDATE t SET s = 'y' WHERE id = 2;",
+    )
+    .unwrap();
+    let script = dump(&mut db);
+
+    let mut copy = Database::memory();
+    copy.execute(&script).unwrap();
+    assert_eq!(dump(&mut copy), script);
+    let select = "SELECT * FROM t ORDER BY id";
+    assert_eq!(copy.query(select).unwrap(), db.query(select).unwrap());
+    assert_eq!(
+        copy.query(select).unwrap().get(1, "s"),
+        Some(&DataValue::Text("y".into()))
+    );
+}
+
+#[test]
+fn dump_keeps_autoincrement_sequences() {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE t (id INT PRIMARY KEY AUTOINCREMENT, name TEXT);
+         INSERT INTO t (name) VALUES ('a'), ('b'), ('c');
+         DELETE FROM t WHERE id = 3;",
+    )
+    .unwrap();
+    let script = dump(&mut db);
+
+    let mut copy = Database::memory();
+    copy.execute(&script).unwrap();
+    assert_eq!(dump(&mut copy), script);
+    // the deleted row's key is not handed out again
+    for db in [&mut db, &mut copy] {
+        db.execute("INSERT INTO t (name) VALUES ('d')").unwrap();
+        let rows = db.query("SELECT id FROM t WHERE name = 'd'").unwrap().into_rows();
+        assert_eq!(rows, [[DataValue::Int(4)]]);
+    }
+}
+
+#[test]
+fn dump_quotes_identifiers() {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE \"my t\" (\"a b\" INT UNIQUE, \"select\" TEXT, ok INT,
+                              FOREIGN KEY (ok) REFERENCES \"my t\"(\"a b\"),
+                              CHECK (\"a b\" > 0));
+         INSERT INTO \"my t\" VALUES (1, 'x', NULL), (2, 'y', 1);",
+    )
+    .unwrap();
+    let script = dump(&mut db);
+    assert!(
+        script.contains("CREATE TABLE \"my t\" (\n  \"a b\" INT UNIQUE,"),
+        "{script}"
+    );
+
+    let mut copy = Database::memory();
+    copy.execute(&script).unwrap();
+    assert_eq!(dump(&mut copy), script);
+}
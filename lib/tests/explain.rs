@@ -0,0 +1,66 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn plan(exec: &mut Executor, sql: &str) -> Vec<String> {
+    match run(exec, &format!("EXPLAIN {sql}")).unwrap() {
+        QueryResult::Rows { columns, rows } => {
+            assert_eq!(columns, ["plan"]);
+            rows.iter().map(|row| row[0].to_string()).collect()
+        }
+        _ => panic!("EXPLAIN {sql} returned no rows"),
+    }
+}
+
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (id INT UNIQUE, name TEXT);
+         INSERT INTO t VALUES (1, 'a');
+         INSERT INTO t VALUES (2, 'b');
+         INSERT INTO t VALUES (3, 'c');",
+    )
+    .unwrap();
+    (dir, exec)
+}
+
+#[test]
+fn explain_shows_a_full_scan() {
+    let (_dir, mut exec) = setup();
+    assert_eq!(
+        plan(&mut exec, "SELECT name FROM t WHERE name = 'b' ORDER BY name DESC"),
+        [
+            "SORT BY name DESC NULLS LAST",
+            "  PROJECT name",
+            "    FILTER name = 'b'",
+            "      FULL SCAN t",
+        ]
+    );
+}
+
+#[test]
+fn explain_shows_an_index_seek() {
+    let (_dir, mut exec) = setup();
+    assert_eq!(
+        plan(&mut exec, "SELECT * FROM t WHERE id = 2"),
+        ["PROJECT *", "  FILTER id = 2", "    INDEX SEEK t ON id"]
+    );
+}
+
+#[test]
+fn explain_does_not_run_the_query() {
+    let (_dir, mut exec) = setup();
+    plan(&mut exec, "SELECT * FROM t WHERE 1 / 0 = 1");
+    assert!(run(&mut exec, "SELECT * FROM t WHERE 1 / 0 = 1").is_err());
+}
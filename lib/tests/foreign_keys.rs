@@ -0,0 +1,81 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+/// Inserts each of `rows` into `table` with its own statement.
+fn insert(exec: &mut Executor, table: &str, rows: &[&str]) {
+    for row in rows {
+        run(exec, &format!("INSERT INTO {table} VALUES ({row})")).unwrap();
+    }
+}
+
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE parent (id INT PRIMARY KEY);
+         CREATE TABLE child (id INT, pid INT,
+                             FOREIGN KEY (pid) REFERENCES parent(id) ON DELETE CASCADE);
+         CREATE TABLE pinned (pid INT, FOREIGN KEY (pid) REFERENCES parent(id));",
+    )
+    .unwrap();
+    insert(&mut exec, "parent", &["1", "2"]);
+    (dir, exec)
+}
+
+fn ids(exec: &mut Executor, table: &str) -> Vec<String> {
+    match run(exec, &format!("SELECT id FROM {table} ORDER BY id")).unwrap() {
+        QueryResult::Rows { rows, .. } => {
+            rows.iter().map(|row| row[0].to_string()).collect()
+        }
+        _ => panic!("SELECT returned no rows"),
+    }
+}
+
+fn assert_fk_violation(exec: &mut Executor, sql: &str) {
+    match run(exec, sql) {
+        Err(SQRLErr::ConstraintViolation(msg)) => {
+            assert!(msg.starts_with("FOREIGN KEY constraint failed"), "{sql}: {msg}")
+        }
+        Err(err) => panic!("{sql}: {err:?}"),
+        Ok(_) => panic!("{sql} ran"),
+    }
+}
+
+#[test]
+fn references_must_exist() {
+    let (_dir, mut exec) = setup();
+    // NULL references nothing, so it is allowed
+    insert(&mut exec, "child", &["1, 1", "2, 2", "3, NULL"]);
+    assert_fk_violation(&mut exec, "INSERT INTO child VALUES (4, 9)");
+    assert_fk_violation(&mut exec, "UPDATE child SET pid = 9 WHERE id = 1");
+    assert_eq!(ids(&mut exec, "child"), ["1", "2", "3"]);
+}
+
+#[test]
+fn deleting_a_referenced_row_is_restricted_by_default() {
+    let (_dir, mut exec) = setup();
+    insert(&mut exec, "pinned", &["2"]);
+    assert_fk_violation(&mut exec, "DELETE FROM parent WHERE id = 2");
+    assert_fk_violation(&mut exec, "UPDATE parent SET id = 5 WHERE id = 2");
+    assert_eq!(ids(&mut exec, "parent"), ["1", "2"]);
+}
+
+#[test]
+fn on_delete_cascade_removes_referencing_rows() {
+    let (_dir, mut exec) = setup();
+    insert(&mut exec, "child", &["1, 1", "2, 1", "3, 2"]);
+    run(&mut exec, "DELETE FROM parent WHERE id = 1").unwrap();
+    assert_eq!(ids(&mut exec, "parent"), ["2"]);
+    assert_eq!(ids(&mut exec, "child"), ["3"]);
+}
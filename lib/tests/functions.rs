@@ -0,0 +1,151 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::DataValue;
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn rows(exec: &mut Executor, sql: &str) -> Vec<Vec<DataValue>> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => rows,
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+/// A database with a one-row table `one` to evaluate expressions against.
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(&mut exec, "CREATE TABLE one (n INT); INSERT INTO one VALUES (1);").unwrap();
+    (dir, exec)
+}
+
+/// Evaluates `exprs` once, against the row of `one`.
+fn row(exprs: &str) -> Vec<DataValue> {
+    let (_dir, mut exec) = setup();
+    let mut rows = rows(&mut exec, &format!("SELECT {exprs} FROM one"));
+    assert_eq!(rows.len(), 1, "{exprs}");
+    rows.remove(0)
+}
+
+/// Evaluates `expr` against the row of `one`, expecting it to fail.
+fn error(expr: &str) -> SQRLErr {
+    let (_dir, mut exec) = setup();
+    match run(&mut exec, &format!("SELECT {expr} FROM one")) {
+        Err(err) => err,
+        Ok(_) => panic!("{expr} succeeded"),
+    }
+}
+
+fn text(text: &str) -> DataValue {
+    DataValue::Text(text.into())
+}
+
+#[test]
+fn coalesce_and_nullif() {
+    assert_eq!(
+        row("COALESCE(NULL, 2, 3), COALESCE(1, NULL), COALESCE(NULL, NULL)"),
+        [DataValue::Int(2), DataValue::Int(1), DataValue::Nil]
+    );
+    assert_eq!(
+        row("NULLIF(1, 1), NULLIF(1, 2), NULLIF('a', 'b'), NULLIF(NULL, 1)"),
+        [DataValue::Nil, DataValue::Int(1), text("a"), DataValue::Nil]
+    );
+
+    let (_dir, mut exec) = setup();
+    run(
+        &mut exec,
+        "CREATE TABLE t (a INT, b INT);
+         INSERT INTO t VALUES (NULL, 5);
+         INSERT INTO t VALUES (1, 6);",
+    )
+    .unwrap();
+    assert_eq!(
+        rows(&mut exec, "SELECT COALESCE(a, b) FROM t"),
+        [[DataValue::Int(5)], [DataValue::Int(1)]]
+    );
+}
+
+#[test]
+fn string_functions() {
+    assert_eq!(
+        row("UPPER('abç'), LOWER('ABÇ'), TRIM('  a b  '), LENGTH('한글')"),
+        [text("ABÇ"), text("abç"), text("a b"), DataValue::Int(2)]
+    );
+    assert_eq!(
+        row("SUBSTR('hello', 2, 3), SUBSTR('héllo', 2), SUBSTR('다람쥐', 3, 1)"),
+        [text("ell"), text("éllo"), text("쥐")]
+    );
+    // out-of-range positions clamp to the string
+    assert_eq!(
+        row("SUBSTR('hello', 0, 2), SUBSTR('hello', 4, 10), SUBSTR('hello', 9)"),
+        [text("h"), text("lo"), text("")]
+    );
+    assert_eq!(
+        row("UPPER(NULL), LENGTH(NULL), SUBSTR(NULL, 1, 2), TRIM(NULL)"),
+        vec![DataValue::Nil; 4]
+    );
+}
+
+#[test]
+fn numeric_functions_keep_their_argument_type() {
+    assert_eq!(
+        row("ABS(-3), ABS(-1.5), CEIL(1.2), FLOOR(-1.2), CEIL(3)"),
+        [
+            DataValue::Int(3),
+            DataValue::Real(1.5),
+            DataValue::Real(2.0),
+            DataValue::Real(-2.0),
+            DataValue::Int(3),
+        ]
+    );
+    assert_eq!(
+        row("ROUND(2.567, 2), ROUND(2.5), ROUND(-2.5), ROUND(1250, -2)"),
+        [
+            DataValue::Real(2.57),
+            DataValue::Real(3.0),
+            DataValue::Real(-3.0),
+            DataValue::Int(1300),
+        ]
+    );
+    assert_eq!(
+        row("MOD(7, 3), MOD(-7, 3), MOD(7.5, 2)"),
+        [DataValue::Int(1), DataValue::Int(-1), DataValue::Real(1.5)]
+    );
+}
+
+#[test]
+fn numeric_functions_fail_cleanly() {
+    for expr in ["MOD(1, 0)", "MOD(1.5, 0)", "ROUND(1.5, 39)", "ABS('a')"] {
+        let err = error(expr);
+        assert!(matches!(err, SQRLErr::InvalidFunction(_)), "{expr}: {err:?}");
+    }
+    let err = error("ABS(-9223372036854775807 - 1)");
+    assert!(matches!(err, SQRLErr::Overflow), "{err:?}");
+    assert_eq!(
+        row("ABS(NULL), ROUND(NULL, 1), ROUND(1.5, NULL), MOD(NULL, 0)"),
+        vec![DataValue::Nil; 4]
+    );
+}
+
+#[test]
+fn division_of_ints_gives_a_real() {
+    assert_eq!(
+        row("5 / 2, 4 / 2, -7 / 2, 5.0 / 2, 1 / 4.0"),
+        [2.5, 2.0, -3.5, 2.5, 0.25].map(DataValue::Real)
+    );
+    assert_eq!(
+        row("DIV(5, 2), DIV(-7, 2), DIV(7, -2), DIV(NULL, 2)"),
+        [DataValue::Int(2), DataValue::Int(-3), DataValue::Int(-3), DataValue::Nil]
+    );
+    for expr in ["1 / 0", "DIV(1, 0)", "DIV(1.5, 2)"] {
+        error(expr);
+    }
+}
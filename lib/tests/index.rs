@@ -0,0 +1,126 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::{DataValue, Decimal};
+use litesqrl::storage::{Index, RowId, Storage};
+use std::ops::Bound;
+
+fn ids(rows: impl Iterator<Item = RowId>) -> Vec<u64> {
+    rows.map(|row_id| row_id.0).collect()
+}
+
+fn int_index(keys: &[(i64, u64)]) -> Index {
+    let mut index = Index::default();
+    for &(key, row) in keys {
+        index.insert(DataValue::Int(key), RowId(row));
+    }
+    index
+}
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+#[test]
+fn insert_and_get() {
+    let mut index = int_index(&[(5, 1), (3, 2), (5, 3)]);
+    index.insert(DataValue::Nil, RowId(4));
+    assert_eq!(ids(index.get(&DataValue::Int(5))), [1, 3]);
+    assert_eq!(ids(index.get(&DataValue::Int(3))), [2]);
+    assert_eq!(ids(index.get(&DataValue::Int(4))), [0; 0]);
+    // NULLs are never indexed
+    assert_eq!(ids(index.get(&DataValue::Nil)), [0; 0]);
+    // INT and REAL keys are the same number
+    assert_eq!(ids(index.get(&DataValue::Real(3.0))), [2]);
+}
+
+#[test]
+fn range_scans_in_key_order() {
+    let index = int_index(&[(40, 1), (10, 2), (30, 3), (20, 4), (50, 5)]);
+    let range = |low, high| ids(index.range(low, high));
+    let (ten, forty) = (DataValue::Int(10), DataValue::Int(40));
+    assert_eq!(range(Bound::Unbounded, Bound::Unbounded), [2, 4, 3, 1, 5]);
+    assert_eq!(range(Bound::Included(&ten), Bound::Included(&forty)), [2, 4, 3, 1]);
+    assert_eq!(range(Bound::Excluded(&ten), Bound::Excluded(&forty)), [4, 3]);
+    assert_eq!(range(Bound::Included(&forty), Bound::Unbounded), [1, 5]);
+    // inverted bounds are empty rather than a panic
+    assert_eq!(range(Bound::Included(&forty), Bound::Included(&ten)), [0; 0]);
+    assert_eq!(range(Bound::Excluded(&ten), Bound::Excluded(&ten)), [0; 0]);
+}
+
+#[test]
+fn keys_order_across_types() {
+    let mut index = Index::default();
+    let keys = [
+        DataValue::Blob([1].into()),
+        DataValue::Text("a".into()),
+        DataValue::Real(2.5),
+        DataValue::Int(2),
+        DataValue::Int(3),
+        DataValue::Bool(true),
+        DataValue::Text("B".into()),
+    ];
+    for (row, key) in keys.into_iter().enumerate() {
+        index.insert(key, RowId(row as u64));
+    }
+    // BOOL < numbers < TEXT < BLOB, with INT and REAL interleaved
+    assert_eq!(
+        ids(index.range(Bound::Unbounded, Bound::Unbounded)),
+        [5, 3, 2, 4, 6, 1, 0]
+    );
+}
+
+#[test]
+fn decimal_keys_compare_exactly_with_reals() {
+    let mut index = Index::default();
+    // 0.30000000000000001 is 0.3 once rounded to a REAL
+    let key = Decimal::new(30_000_000_000_000_001, 17);
+    index.insert(DataValue::Decimal(key), RowId(1));
+    index.insert(DataValue::Decimal(Decimal::new(1, 1)), RowId(2));
+    assert_eq!(ids(index.get(&DataValue::Real(0.3))), [0; 0]);
+    assert_eq!(ids(index.get(&DataValue::Real(0.1))), [2]);
+    let real = DataValue::Real(0.3);
+    assert_eq!(ids(index.range(Bound::Excluded(&real), Bound::Unbounded)), [1]);
+    // REALs no DECIMAL holds still order by value
+    let huge = DataValue::Real(1e300);
+    assert_eq!(ids(index.range(Bound::Unbounded, Bound::Included(&huge))), [2, 1]);
+}
+
+#[test]
+fn delete_removes_only_that_row() {
+    let mut index = int_index(&[(1, 1), (1, 2), (2, 3)]);
+    index.delete(&DataValue::Int(1), RowId(1));
+    assert_eq!(ids(index.get(&DataValue::Int(1))), [2]);
+    index.delete(&DataValue::Int(1), RowId(2));
+    index.delete(&DataValue::Int(9), RowId(3));
+    assert_eq!(ids(index.range(Bound::Unbounded, Bound::Unbounded)), [3]);
+}
+
+#[test]
+fn unique_lookups_follow_writes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("test.db");
+    let mut exec = Executor::new(Storage::create(&path).unwrap());
+    run(&mut exec, "CREATE TABLE t (id INT UNIQUE, name TEXT)").unwrap();
+    for n in 0..200 {
+        run(&mut exec, &format!("INSERT INTO t VALUES ({n}, 'n{n}')")).unwrap();
+    }
+    run(&mut exec, "DELETE FROM t WHERE id >= 100 AND id <= 149").unwrap();
+    run(&mut exec, "UPDATE t SET id = id + 1000 WHERE id < 10").unwrap();
+
+    // a freed key can be taken again, a moved key only at its new value
+    let check = |exec: &mut Executor| {
+        run(exec, "INSERT INTO t VALUES (120, 'again')").unwrap();
+        run(exec, "INSERT INTO t VALUES (5, 'again')").unwrap();
+        let err = run(exec, "INSERT INTO t VALUES (1005, 'dup')").err().unwrap();
+        assert!(matches!(err, SQRLErr::ConstraintViolation(_)), "{err:?}");
+        run(exec, "DELETE FROM t WHERE name = 'again'").unwrap();
+    };
+    check(&mut exec);
+    drop(exec);
+    // indexes are rebuilt when the log is replayed
+    check(&mut Executor::new(Storage::open(&path).unwrap()));
+}
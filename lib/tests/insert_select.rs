@@ -0,0 +1,44 @@
+use litesqrl::Database;
+use litesqrl::schema::DataValue;
+use tempfile::TempDir;
+
+fn setup() -> (TempDir, Database) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut db = Database::create(dir.path().join("test.db")).unwrap();
+    db.execute(
+        "CREATE TABLE src (x INT, y TEXT);
+         CREATE TABLE dest (id INT PRIMARY KEY AUTOINCREMENT, a INT, b TEXT);
+         INSERT INTO src VALUES (1, 'one'), (2, 'two'), (3, 'three');",
+    )
+    .unwrap();
+    (dir, db)
+}
+
+#[test]
+fn insert_select_copies_filtered_rows() {
+    let (_dir, mut db) = setup();
+    let copied =
+        db.execute("INSERT INTO dest (a, b) SELECT x, y FROM src WHERE x >= 2");
+    assert_eq!(copied.unwrap(), 2);
+    let rows = db.query("SELECT * FROM dest").unwrap().into_rows();
+    assert_eq!(
+        rows,
+        [
+            [DataValue::Int(1), DataValue::Int(2), DataValue::Text("two".into())],
+            [DataValue::Int(2), DataValue::Int(3), DataValue::Text("three".into())],
+        ]
+    );
+}
+
+#[test]
+fn insert_select_checks_columns_and_types() {
+    let (_dir, mut db) = setup();
+    for sql in [
+        "INSERT INTO dest (a, b) SELECT x FROM src",
+        "INSERT INTO dest (a) SELECT x, y FROM src",
+        "INSERT INTO dest (a, b) SELECT y, x FROM src",
+    ] {
+        assert!(db.execute(sql).is_err(), "{sql}");
+    }
+    assert!(db.query("SELECT * FROM dest").unwrap().is_empty());
+}
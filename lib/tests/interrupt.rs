@@ -0,0 +1,27 @@
+use litesqrl::Database;
+use litesqrl::executor::SQRLErr;
+use litesqrl::schema::DataValue;
+use std::sync::atomic::Ordering;
+
+#[test]
+fn interrupt_stops_the_statement_and_rolls_it_back() {
+    let mut db = Database::memory();
+    db.execute("CREATE TABLE t (a INT); INSERT INTO t VALUES (1), (2);").unwrap();
+    let interrupt = db.executor().interrupt_handle();
+
+    interrupt.store(true, Ordering::Relaxed);
+    let err = db.execute("INSERT INTO t VALUES (3), (4)").unwrap_err();
+    assert!(matches!(err, SQRLErr::Interrupted), "{err:?}");
+    // the flag is cleared, so the next statement runs
+    assert!(!interrupt.load(Ordering::Relaxed));
+    assert_eq!(db.query("SELECT * FROM t").unwrap().len(), 2);
+
+    for sql in ["SELECT * FROM t", "UPDATE t SET a = 0", "DELETE FROM t"] {
+        interrupt.store(true, Ordering::Relaxed);
+        let err = db.execute(sql).unwrap_err();
+        assert!(matches!(err, SQRLErr::Interrupted), "{sql}: {err:?}");
+    }
+    let rows = db.query("SELECT a FROM t").unwrap().into_rows();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|row| row[0] != DataValue::Int(0)));
+}
@@ -0,0 +1,216 @@
+use litesqrl::Database;
+use litesqrl::query::error::QueryErrKind;
+use litesqrl::query::lexer::Token;
+use litesqrl::query::{Dialect, Lexer, Parser, tokenize};
+use litesqrl::schema::DataValue;
+
+#[test]
+fn true_and_false_are_keywords_in_any_case() {
+    assert_eq!(
+        tokenize("TRUE false True fAlSe").unwrap(),
+        [
+            Token::Bool(true),
+            Token::Bool(false),
+            Token::Bool(true),
+            Token::Bool(false),
+            Token::Eof,
+        ]
+    );
+    // only the whole word is a keyword
+    assert_eq!(tokenize("trueish").unwrap(), [Token::Ident("trueish"), Token::Eof]);
+
+    let mut db = Database::memory();
+    for sql in ["CREATE TABLE true (a INT)", "CREATE TABLE t (false INT)"] {
+        assert!(db.execute(sql).is_err(), "{sql}");
+    }
+    db.execute(
+        "CREATE TABLE t (id INT, active BOOL);
+         INSERT INTO t VALUES (1, TRUE), (2, false);",
+    )
+    .unwrap();
+    let rows = db.query("SELECT id FROM t WHERE active = TRUE").unwrap();
+    assert_eq!(rows.into_rows(), [[DataValue::Int(1)]]);
+}
+
+#[test]
+fn unicode_identifiers() {
+    assert_eq!(
+        tokenize("SELECT 名前, café, _x1, naïve_2 FROM 다람쥐").unwrap(),
+        [
+            Token::Select,
+            Token::Ident("名前"),
+            Token::Comma,
+            Token::Ident("café"),
+            Token::Comma,
+            Token::Ident("_x1"),
+            Token::Comma,
+            Token::Ident("naïve_2"),
+            Token::From,
+            Token::Ident("다람쥐"),
+            Token::Eof,
+        ]
+    );
+
+    let mut db = Database::memory();
+    db.execute("CREATE TABLE 다람쥐 (名前 TEXT, café INT); INSERT INTO 다람쥐 VALUES ('a', 1);")
+        .unwrap();
+    let rows = db.query("SELECT café FROM 다람쥐 WHERE 名前 = 'a'").unwrap();
+    assert_eq!(rows.into_rows(), [[DataValue::Int(1)]]);
+}
+
+#[test]
+fn identifiers_do_not_start_with_a_digit() {
+    assert_eq!(
+        tokenize("1abc").unwrap(),
+        [Token::Int(1), Token::Ident("abc"), Token::Eof]
+    );
+    let mut db = Database::memory();
+    for sql in ["CREATE TABLE 1t (a INT)", "CREATE TABLE t (9lives INT)"] {
+        assert!(db.execute(sql).is_err(), "{sql}");
+    }
+    // symbols outside XID_Continue end the identifier
+    let err = tokenize("a€b").unwrap_err();
+    assert_eq!(err.kind, QueryErrKind::InvalidToken('€'));
+}
+
+const COMMENTED: &str = "SELECT a -- the key\n, /* multi\nline */ b FROM t /**/";
+
+fn lex_all(mut lexer: Lexer) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next().unwrap().token;
+        if token == Token::Eof {
+            return tokens;
+        }
+        tokens.push(token);
+    }
+}
+
+#[test]
+fn comments_are_dropped_by_default() {
+    let plain = [
+        Token::Select,
+        Token::Ident("a"),
+        Token::Comma,
+        Token::Ident("b"),
+        Token::From,
+        Token::Ident("t"),
+    ];
+    assert_eq!(lex_all(Lexer::new(COMMENTED)), plain);
+    assert_eq!(lex_all(Lexer::new(COMMENTED).with_comments(false)), plain);
+}
+
+#[test]
+fn kept_comments_are_tokens_the_parser_skips() {
+    assert_eq!(
+        lex_all(Lexer::new(COMMENTED).with_comments(true)),
+        [
+            Token::Select,
+            Token::Ident("a"),
+            Token::Comment("-- the key"),
+            Token::Comma,
+            Token::Comment("/* multi\nline */"),
+            Token::Ident("b"),
+            Token::From,
+            Token::Ident("t"),
+            Token::Comment("/**/"),
+        ]
+    );
+
+    let mut parser = Parser::new(Lexer::new(COMMENTED).with_comments(true)).unwrap();
+    let stmts = parser.parse().unwrap();
+    assert_eq!(stmts.len(), 1);
+    let comments =
+        parser.comments().iter().map(|c| c.token.clone()).collect::<Vec<_>>();
+    assert_eq!(
+        comments,
+        [
+            Token::Comment("-- the key"),
+            Token::Comment("/* multi\nline */"),
+            Token::Comment("/**/"),
+        ]
+    );
+    // the statement is the same with or without the comments
+    let plain = Parser::new(Lexer::new(COMMENTED)).unwrap().parse().unwrap();
+    assert_eq!(stmts[0].stmt, plain[0].stmt);
+}
+
+#[test]
+fn unterminated_block_comment_fails() {
+    for keep in [false, true] {
+        let mut lexer = Lexer::new("SELECT 1 /* open").with_comments(keep);
+        let err = loop {
+            match lexer.next() {
+                Ok(spanned) => assert_ne!(spanned.token, Token::Eof),
+                Err(err) => break err,
+            }
+        };
+        assert_eq!(err.kind, QueryErrKind::UnterminatedComment);
+    }
+}
+
+#[test]
+fn tokenize_returns_every_token_through_eof() {
+    assert_eq!(
+        tokenize("SELECT name, age * 2 FROM users WHERE age >= 18.5 AND name = 'A';")
+            .unwrap(),
+        [
+            Token::Select,
+            Token::Ident("name"),
+            Token::Comma,
+            Token::Ident("age"),
+            Token::OpMul,
+            Token::Int(2),
+            Token::From,
+            Token::Ident("users"),
+            Token::Where,
+            Token::Ident("age"),
+            Token::OpGe,
+            Token::Real(18.5),
+            Token::And,
+            Token::Ident("name"),
+            Token::OpEq,
+            Token::Text("A".into()),
+            Token::Semicolon,
+            Token::Eof,
+        ]
+    );
+    assert_eq!(tokenize("").unwrap(), [Token::Eof]);
+    // comments are skipped
+    assert_eq!(tokenize("-- note\n1 /* x */").unwrap(), [Token::Int(1), Token::Eof]);
+}
+
+#[test]
+fn tokenize_stops_at_a_lexing_error() {
+    let err = tokenize("SELECT 1, 'open").unwrap_err();
+    assert_eq!(err.kind, QueryErrKind::UnterminatedText);
+    let err = tokenize("SELECT # FROM t").unwrap_err();
+    assert_eq!(err.kind, QueryErrKind::InvalidToken('#'));
+    assert_eq!(err.span.pos, 7);
+}
+
+fn lex_dialect(src: &str, dialect: Dialect) -> Vec<Token<'_>> {
+    lex_all(Lexer::new(src).with_dialect(dialect))
+}
+
+#[test]
+fn dialects_read_concat_and_double_quotes_differently() {
+    let src = "a || \"order\"";
+    assert_eq!(
+        lex_dialect(src, Dialect::Standard),
+        [Token::Ident("a"), Token::OpConcat, Token::Ident("order")]
+    );
+    assert_eq!(
+        lex_dialect(src, Dialect::MySql),
+        [Token::Ident("a"), Token::Or, Token::Text("order".into())]
+    );
+    assert_eq!(tokenize("\"order\"").unwrap(), [Token::Ident("order"), Token::Eof]);
+
+    for (src, kind) in [
+        ("\"open", QueryErrKind::UnterminatedIdent),
+        ("\"a\nb\"", QueryErrKind::UnterminatedIdent),
+        ("\"\"", QueryErrKind::InvalidToken('"')),
+    ] {
+        assert_eq!(tokenize(src).unwrap_err().kind, kind, "{src}");
+    }
+}
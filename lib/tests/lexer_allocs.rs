@@ -0,0 +1,59 @@
+use litesqrl::query::Lexer;
+use litesqrl::query::lexer::Token;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::borrow::Cow;
+use std::cell::Cell;
+
+/// Counts the allocations made by the current thread.
+struct Counting;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCS.try_with(|n| n.set(n.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn allocs() -> usize {
+    ALLOCS.with(Cell::get)
+}
+
+#[test]
+fn lexing_borrows_identifiers_and_text() {
+    let row = "(42, 'squirrel', \"Mixed Name\", some_column, 1.5, TRUE, NULL)";
+    let sql = format!(
+        "INSERT INTO nuts (a, b, c) VALUES {}; -- done",
+        vec![row; 2_000].join(", ")
+    );
+    let mut lexer = Lexer::new(&sql);
+    let before = allocs();
+    let mut tokens = 0;
+    loop {
+        let token = lexer.next().unwrap().token;
+        if token == Token::Eof {
+            break;
+        }
+        tokens += 1;
+    }
+    let made = allocs() - before;
+    assert!(tokens > 30_000);
+    assert_eq!(made, 0, "{made} allocations for {tokens} tokens");
+
+    // only text with escapes needs a copy
+    let mut lexer = Lexer::new(r"'it\'s' 'plain'");
+    let escaped = lexer.next().unwrap().token;
+    assert!(matches!(escaped, Token::Text(Cow::Owned(ref text)) if text == "it's"));
+    let plain = lexer.next().unwrap().token;
+    assert!(matches!(plain, Token::Text(Cow::Borrowed("plain"))));
+}
@@ -0,0 +1,133 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn rows(exec: &mut Executor, sql: &str) -> Vec<Vec<String>> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => rows
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+/// A database with a one-row table `one` to evaluate expressions against.
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(&mut exec, "CREATE TABLE one (n INT); INSERT INTO one VALUES (1);").unwrap();
+    (dir, exec)
+}
+
+fn eval(exec: &mut Executor, expr: &str) -> String {
+    rows(exec, &format!("SELECT {expr} FROM one")).remove(0).remove(0)
+}
+
+fn like(exec: &mut Executor, text: &str, pattern: &str) -> bool {
+    let expr = format!("'{text}' LIKE '{pattern}'");
+    match eval(exec, &expr).as_str() {
+        "true" => true,
+        "false" => false,
+        value => panic!("{expr} gave {value}"),
+    }
+}
+
+#[test]
+fn like_matches_wildcards() {
+    let (_dir, mut exec) = setup();
+    let exec = &mut exec;
+    assert!(like(exec, "Apple", "A%"));
+    assert!(!like(exec, "apple", "A%"));
+    assert!(like(exec, "quiz", "%z"));
+    assert!(!like(exec, "zoo", "%z"));
+    assert!(like(exec, "a midpoint", "%mid%"));
+    assert!(like(exec, "mid", "%mid%"));
+    assert!(!like(exec, "mi d", "%mid%"));
+    assert!(like(exec, "abc", "a_c"));
+    assert!(!like(exec, "ac", "a_c"));
+    assert!(!like(exec, "abbc", "a_c"));
+    assert!(like(exec, "", "%"));
+    assert!(like(exec, "anything", "%"));
+}
+
+#[test]
+fn like_counts_characters_not_bytes() {
+    let (_dir, mut exec) = setup();
+    assert!(like(&mut exec, "día", "d_a"));
+    assert!(like(&mut exec, "다람쥐", "_람_"));
+    assert!(like(&mut exec, "다람쥐", "%쥐"));
+}
+
+#[test]
+fn like_escapes_wildcards() {
+    let (_dir, mut exec) = setup();
+    assert!(like(&mut exec, "100%", "100\\\\%"));
+    assert!(!like(&mut exec, "1000", "100\\\\%"));
+    assert!(like(&mut exec, "a_b", "a\\\\_b"));
+    assert!(!like(&mut exec, "axb", "a\\\\_b"));
+}
+
+#[test]
+fn like_with_null_is_null() {
+    let (_dir, mut exec) = setup();
+    assert_eq!(eval(&mut exec, "NULL LIKE 'a%'"), "nil");
+    assert_eq!(eval(&mut exec, "'a' LIKE NULL"), "nil");
+
+    run(
+        &mut exec,
+        "CREATE TABLE t (name TEXT);
+         INSERT INTO t VALUES ('ant');
+         INSERT INTO t VALUES ('bee');
+         INSERT INTO t VALUES ('anvil');",
+    )
+    .unwrap();
+    let names = rows(&mut exec, "SELECT name FROM t WHERE name LIKE 'an%'");
+    assert_eq!(names, [["ant"], ["anvil"]]);
+}
+
+fn like_escape(exec: &mut Executor, text: &str, pattern: &str, escape: &str) -> bool {
+    like(exec, text, &format!("{pattern}' ESCAPE '{escape}"))
+}
+
+#[test]
+fn escape_clause_picks_the_escape_character() {
+    let (_dir, mut exec) = setup();
+    let exec = &mut exec;
+    assert!(like_escape(exec, "50%", "50!%", "!"));
+    assert!(!like_escape(exec, "500", "50!%", "!"));
+    assert!(like_escape(exec, "a_b", "a#_b", "#"));
+    assert!(!like_escape(exec, "axb", "a#_b", "#"));
+    // a doubled escape character matches itself
+    assert!(like_escape(exec, "a!b", "a!!b", "!"));
+    // other wildcards keep working
+    assert!(like_escape(exec, "10% off", "%!% off", "!"));
+
+    run(
+        exec,
+        "CREATE TABLE t (code TEXT); INSERT INTO t VALUES ('5%'), ('50'), ('5x');",
+    )
+    .unwrap();
+    assert_eq!(
+        rows(exec, "SELECT code FROM t WHERE code LIKE '5=%' ESCAPE '='"),
+        [["5%"]]
+    );
+}
+
+#[test]
+fn escape_must_be_one_character() {
+    let (_dir, mut exec) = setup();
+    for escape in ["!!", ""] {
+        let sql = format!("SELECT 'a' LIKE 'a' ESCAPE '{escape}' FROM one");
+        assert!(run(&mut exec, &sql).is_err(), "{sql}");
+    }
+}
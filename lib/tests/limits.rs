@@ -0,0 +1,95 @@
+use litesqrl::Database;
+use litesqrl::query::error::QueryErrKind;
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::DataValue;
+
+fn parse_err(sql: &str) -> QueryErrKind {
+    Parser::new(Lexer::new(sql)).and_then(|mut p| p.parse()).unwrap_err().kind
+}
+
+fn terms(n: usize, term: impl Fn(usize) -> String, op: &str) -> String {
+    (0..n).map(term).collect::<Vec<_>>().join(op)
+}
+
+#[test]
+fn long_operator_chains_are_not_too_deep() {
+    let mut db = Database::memory();
+    db.execute("CREATE TABLE t (a INT, s TEXT); INSERT INTO t VALUES (150, 'x');")
+        .unwrap();
+
+    let or = terms(200, |i| format!("a = {i}"), " OR ");
+    let rows = db.query(&format!("SELECT a FROM t WHERE {or}")).unwrap();
+    assert_eq!(rows.into_rows(), [[DataValue::Int(150)]]);
+
+    let concat = terms(200, |_| "s".to_string(), " || ");
+    let rows = db.query(&format!("SELECT {concat} FROM t")).unwrap();
+    assert_eq!(rows.into_rows(), [[DataValue::Text("x".repeat(200).into())]]);
+}
+
+#[test]
+fn long_unions_are_not_too_deep() {
+    let mut db = Database::memory();
+    let union = terms(200, |i| format!("SELECT {i} AS n"), " UNION ALL ");
+    let rows = db.query(&union).unwrap();
+    assert_eq!(rows.len(), 200);
+    assert_eq!(rows.get(199, "n"), Some(&DataValue::Int(199)));
+
+    // nor is one in a subquery
+    let rows =
+        db.query(&format!("SELECT * FROM ({union}) AS u WHERE n > 100")).unwrap();
+    assert_eq!(rows.len(), 99);
+
+    // each operand counts twice against the chain limit
+    let max = Parser::DEFAULT_MAX_CHAIN;
+    let union = terms(max / 2 + 1, |i| format!("SELECT {i}"), " UNION ALL ");
+    assert_eq!(db.query(&union).unwrap().len(), max / 2 + 1);
+    assert!(db.query(&format!("EXPLAIN {union}")).is_ok());
+    let union = terms(max / 2 + 2, |i| format!("SELECT {i}"), " UNION ALL ");
+    assert_eq!(parse_err(&union), QueryErrKind::TooLongChain(max));
+}
+
+#[test]
+fn deep_nesting_is_too_deep() {
+    let sql = format!("SELECT {}1{}", "(".repeat(200), ")".repeat(200));
+    assert_eq!(parse_err(&sql), QueryErrKind::TooDeep(Parser::DEFAULT_MAX_DEPTH));
+
+    let sql = format!("SELECT {}1", "NOT ".repeat(200));
+    assert_eq!(parse_err(&sql), QueryErrKind::TooDeep(Parser::DEFAULT_MAX_DEPTH));
+
+    let sql = format!(
+        "SELECT a FROM {}t{}",
+        "(SELECT a FROM ".repeat(200),
+        ") AS s".repeat(200)
+    );
+    assert_eq!(parse_err(&sql), QueryErrKind::TooDeep(Parser::DEFAULT_MAX_DEPTH));
+}
+
+#[test]
+fn over_long_chains_are_an_error() {
+    let max = Parser::DEFAULT_MAX_CHAIN;
+    let sql = format!("SELECT {}", terms(max + 2, |i| i.to_string(), " + "));
+    assert_eq!(parse_err(&sql), QueryErrKind::TooLongChain(max));
+
+    // chains nested in parentheses add up
+    let half = terms(max / 2 + 1, |i| i.to_string(), " + ");
+    assert_eq!(
+        parse_err(&format!("SELECT {half} + ({half})")),
+        QueryErrKind::TooLongChain(max)
+    );
+}
+
+#[test]
+fn limits_can_be_changed() {
+    let sql = format!("SELECT {}1{}", "(".repeat(10), ")".repeat(10));
+    let parse = |parser: Parser| parser.with_max_tokens(100).parse();
+    assert!(parse(Parser::new(Lexer::new(&sql)).unwrap()).is_ok());
+    let err =
+        parse(Parser::new(Lexer::new(&sql)).unwrap().with_max_depth(5)).unwrap_err();
+    assert_eq!(err.kind, QueryErrKind::TooDeep(5));
+
+    let long = format!("SELECT 1{}", ", 1".repeat(100));
+    let err = Parser::new(Lexer::new(&long))
+        .and_then(|p| p.with_max_tokens(100).parse())
+        .unwrap_err();
+    assert_eq!(err.kind, QueryErrKind::TooLong(100));
+}
@@ -0,0 +1,149 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn ids(exec: &mut Executor, sql: &str) -> Vec<String> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => {
+            rows.iter().map(|row| row[0].to_string()).collect()
+        }
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (id INT, a INT, b VARCHAR(3));
+         INSERT INTO t VALUES (1, 3, 'x');
+         INSERT INTO t VALUES (2, NULL, 'y');
+         INSERT INTO t VALUES (3, 1, 'x');
+         INSERT INTO t VALUES (4, NULL, 'x');
+         INSERT INTO t VALUES (5, 2, 'y');
+         INSERT INTO t VALUES (6, 1, 'y');",
+    )
+    .unwrap();
+    (dir, exec)
+}
+
+#[test]
+fn nulls_sort_first_and_desc_reverses() {
+    let (_dir, mut exec) = setup();
+    assert_eq!(
+        ids(&mut exec, "SELECT id FROM t ORDER BY a"),
+        ["2", "4", "3", "6", "5", "1"]
+    );
+    assert_eq!(
+        ids(&mut exec, "SELECT id FROM t ORDER BY a ASC"),
+        ids(&mut exec, "SELECT id FROM t ORDER BY a")
+    );
+    assert_eq!(
+        ids(&mut exec, "SELECT id FROM t ORDER BY a DESC"),
+        ["1", "5", "3", "6", "2", "4"]
+    );
+}
+
+#[test]
+fn later_keys_break_ties() {
+    let (_dir, mut exec) = setup();
+    assert_eq!(
+        ids(&mut exec, "SELECT id FROM t ORDER BY b DESC, a"),
+        ["2", "6", "5", "4", "3", "1"]
+    );
+    assert_eq!(
+        ids(&mut exec, "SELECT id FROM t ORDER BY b, id DESC"),
+        ["4", "3", "1", "6", "5", "2"]
+    );
+}
+
+#[test]
+fn keys_may_be_expressions_or_result_labels() {
+    let (_dir, mut exec) = setup();
+    assert_eq!(
+        ids(&mut exec, "SELECT id FROM t WHERE b = 'x' ORDER BY 0 - id"),
+        ["4", "3", "1"]
+    );
+    assert_eq!(
+        ids(&mut exec, "SELECT a AS id FROM t WHERE b = 'y' ORDER BY id DESC"),
+        ["2", "1", "nil"]
+    );
+}
+
+#[test]
+fn nulls_first_and_last_ignore_the_direction() {
+    let (_dir, mut exec) = setup();
+    let mut sorted = |order: &str| {
+        ids(&mut exec, &format!("SELECT id FROM t ORDER BY a {order}, id"))
+    };
+    assert_eq!(sorted("NULLS FIRST"), ["2", "4", "3", "6", "5", "1"]);
+    assert_eq!(sorted("ASC NULLS LAST"), ["3", "6", "5", "1", "2", "4"]);
+    assert_eq!(sorted("DESC NULLS FIRST"), ["2", "4", "1", "5", "3", "6"]);
+    assert_eq!(sorted("DESC NULLS LAST"), ["1", "5", "3", "6", "2", "4"]);
+}
+
+#[test]
+fn integer_keys_are_result_positions() {
+    let (_dir, mut exec) = setup();
+    assert_eq!(
+        ids(&mut exec, "SELECT id, b, a FROM t ORDER BY 2, 3 DESC"),
+        ["1", "3", "4", "5", "6", "2"]
+    );
+    // the position counts result columns, not table columns
+    assert_eq!(
+        ids(&mut exec, "SELECT id, a FROM t ORDER BY 2 NULLS LAST, 1 DESC"),
+        ["6", "3", "5", "1", "4", "2"]
+    );
+    // an empty result is still checked
+    for (sql, pos, count) in [
+        ("SELECT id, a FROM t ORDER BY 3", 3, 2),
+        ("SELECT id, a FROM t ORDER BY 0", 0, 2),
+        ("SELECT id FROM t WHERE id > 10 ORDER BY 2", 2, 1),
+    ] {
+        let Err(err) = run(&mut exec, sql) else {
+            panic!("{sql} ran");
+        };
+        assert!(
+            matches!(err, SQRLErr::OrderByPositionOutOfRange { pos: p, count: c }
+                if p == pos && c == count),
+            "{sql}: {err}"
+        );
+    }
+    let err = run(&mut exec, "SELECT id FROM t ORDER BY 2").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "ORDER BY position 2 is out of range: the result has 1 column"
+    );
+}
+
+#[test]
+fn incomparable_keys_are_an_error() {
+    let (_dir, mut exec) = setup();
+    let Err(err) = run(&mut exec, "SELECT id FROM t ORDER BY COALESCE(a, 'x')") else {
+        panic!("mixed INT and TEXT keys sorted");
+    };
+    assert!(err.to_string().contains("cannot compare"), "{err}");
+}
+
+#[test]
+fn sort_is_stable() {
+    let (_dir, mut exec) = setup();
+    assert_eq!(
+        ids(&mut exec, "SELECT id FROM t ORDER BY b"),
+        ["1", "3", "4", "2", "5", "6"]
+    );
+    assert_eq!(
+        ids(&mut exec, "SELECT id FROM t ORDER BY b DESC, a NULLS LAST"),
+        ["6", "5", "2", "3", "1", "4"]
+    );
+}
@@ -0,0 +1,49 @@
+use litesqrl::Database;
+use litesqrl::executor::SQRLErr;
+use litesqrl::schema::DataValue;
+
+fn scalar(db: &mut Database, sql: &str) -> litesqrl::executor::Result<DataValue> {
+    Ok(db.query(sql)?.into_rows().remove(0).remove(0))
+}
+
+fn assert_overflow(db: &mut Database, sql: &str) {
+    let err = scalar(db, sql).expect_err(sql);
+    assert!(matches!(err, SQRLErr::Overflow), "{sql}: {err:?}");
+}
+
+#[test]
+fn int_arithmetic_overflow_is_an_error() {
+    let mut db = Database::memory();
+    for sql in [
+        "SELECT 9223372036854775807 + 1",
+        "SELECT -9223372036854775807 - 2",
+        "SELECT 4611686018427387904 * 2",
+        "SELECT DIV(-9223372036854775807 - 1, -1)",
+        "SELECT -(-9223372036854775807 - 1)",
+        "SELECT ABS(-9223372036854775807 - 1)",
+        "SELECT ROUND(9223372036854775807, -1)",
+    ] {
+        assert_overflow(&mut db, sql);
+    }
+    assert_eq!(
+        scalar(&mut db, "SELECT 9223372036854775806 + 1").unwrap(),
+        DataValue::Int(i64::MAX)
+    );
+}
+
+#[test]
+fn sum_overflow_is_an_error() {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE t (a INT); INSERT INTO t VALUES (9223372036854775807), (1);",
+    )
+    .unwrap();
+    assert_overflow(&mut db, "SELECT SUM(a) FROM t");
+
+    // the process keeps working after the error
+    db.execute("DELETE FROM t WHERE a = 1").unwrap();
+    assert_eq!(
+        scalar(&mut db, "SELECT SUM(a) FROM t").unwrap(),
+        DataValue::Int(i64::MAX)
+    );
+}
@@ -0,0 +1,264 @@
+use litesqrl::query::error::QueryErrKind;
+use litesqrl::query::{Expr, Lexer, OrderBy, Parser, Stmt, quote_ident};
+use litesqrl::schema::ColumnType;
+
+fn parse_one(sql: &str) -> Stmt {
+    let mut stmts = Parser::new(Lexer::new(sql)).and_then(|mut p| p.parse()).unwrap();
+    assert_eq!(stmts.len(), 1, "{sql}");
+    stmts.remove(0).stmt
+}
+
+fn qualified(table: &str, column: &str) -> Expr {
+    Expr::QualifiedIdent(table.into(), column.into())
+}
+
+#[test]
+fn qualified_order_by_keys() {
+    let Stmt::Select { order_by: Some(order_by), .. } =
+        parse_one("SELECT name FROM users ORDER BY users.name DESC, id")
+    else {
+        panic!("not a sorted SELECT");
+    };
+    assert_eq!(
+        order_by,
+        [
+            OrderBy { expr: qualified("users", "name"), desc: true, nulls_first: None },
+            OrderBy { expr: Expr::Ident("id".into()), desc: false, nulls_first: None },
+        ]
+    );
+}
+
+#[test]
+fn qualified_group_by_keys() {
+    let sql = "SELECT t.city, COUNT(*) FROM t GROUP BY t.city, country \
+               HAVING COUNT(*) > 1";
+    let stmt = parse_one(sql);
+    let Stmt::Select { group_by: Some(group_by), having: Some(_), .. } = &stmt else {
+        panic!("not a grouped SELECT");
+    };
+    assert_eq!(group_by, &[qualified("t", "city"), Expr::Ident("country".into())]);
+    assert_eq!(stmt.to_sql(), sql);
+    assert_eq!(parse_one(&stmt.to_sql()), stmt);
+}
+
+#[test]
+fn group_by_requires_a_key() {
+    let err = Parser::new(Lexer::new("SELECT a FROM t GROUP BY"))
+        .and_then(|mut p| p.parse())
+        .unwrap_err();
+    assert!(err.to_string().contains("found Eof"), "{err}");
+}
+
+#[test]
+fn to_sql_quotes_identifiers_that_need_it() {
+    for sql in [
+        "SELECT \"select\" FROM t",
+        "SELECT t.\"a b\" AS \"x y\" FROM \"my t\" AS t ORDER BY \"x y\"",
+        "CREATE TABLE \"my t\" (\"a b\" INT, ok TEXT)",
+        "INSERT INTO \"order\" (\"from\", ok) VALUES (1, 2)",
+        "UPDATE \"order\" SET \"from\" = 1 WHERE ok = 2",
+        "WITH \"with\" AS (SELECT 1) SELECT * FROM \"with\"",
+        "SAVEPOINT \"begin\"",
+    ] {
+        let stmt = parse_one(sql);
+        assert_eq!(stmt.to_sql(), sql);
+        assert_eq!(parse_one(&stmt.to_sql()), stmt);
+    }
+    assert_eq!(quote_ident("name"), "name");
+    assert_eq!(quote_ident("Select"), "\"Select\"");
+    assert_eq!(quote_ident("1st"), "\"1st\"");
+}
+
+#[test]
+fn column_types() {
+    let Stmt::Create { defines, .. } = parse_one(
+        "CREATE TABLE t (a INT, b INTEGER, c REAL, d FLOAT, e BOOL, f BOOLEAN, g TEXT,
+                         h VARCHAR(20), i VARCHAR, j CHAR(3), k CHAR)",
+    ) else {
+        panic!("not a CREATE TABLE");
+    };
+    let types = defines.iter().map(|def| def.data_type).collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        [
+            ColumnType::Int,
+            ColumnType::Int,
+            ColumnType::Real,
+            ColumnType::Real,
+            ColumnType::Bool,
+            ColumnType::Bool,
+            ColumnType::Text,
+            ColumnType::VarChar(20),
+            ColumnType::Text,
+            ColumnType::Char(3),
+            ColumnType::Char(1),
+        ]
+    );
+}
+
+#[test]
+fn unknown_column_types_fail() {
+    let err = Parser::new(Lexer::new("CREATE TABLE t (a FOOBAR)"))
+        .and_then(|mut p| p.parse())
+        .unwrap_err();
+    assert_eq!(err.kind, QueryErrKind::UnknownType("FOOBAR".into()));
+    for sql in ["CREATE TABLE t (a VARCHAR(x))", "CREATE TABLE t (a CHAR(3)"] {
+        assert!(
+            Parser::new(Lexer::new(sql)).and_then(|mut p| p.parse()).is_err(),
+            "{sql}"
+        );
+    }
+}
+
+#[test]
+fn to_sql_round_trips_every_statement_kind() {
+    for sql in [
+        "CREATE TABLE IF NOT EXISTS t (id INT PRIMARY KEY AUTOINCREMENT, \
+         name VARCHAR(20) NOT NULL UNIQUE COLLATE NOCASE, code CHAR(3) DEFAULT 'abc', \
+         price DECIMAL(8, 2) CHECK (price >= 0), day DATE, at TIMESTAMP, b BLOB, \
+         r REAL, ok BOOL, owner INT, FOREIGN KEY (owner) REFERENCES u(id) \
+         ON DELETE CASCADE, CHECK (r < 10))",
+        "INSERT INTO t (a, b) VALUES (1, 'it\\'s\\n'), (NULL, X'00FF') RETURNING *",
+        "INSERT INTO t SELECT a, b FROM u WHERE a > 1",
+        "SELECT DISTINCT t.a AS x, COUNT(DISTINCT b), s.* FROM t AS s \
+         WHERE NOT (a = 1 OR b LIKE 'a%') AND c IN (1, 2) \
+         ORDER BY x DESC NULLS LAST, 2 LIMIT 10 OFFSET 5",
+        "SELECT a FROM (SELECT a FROM t) AS s",
+        "SELECT 1 + 2 * 3, -a, 'x' || 'y', DATE '2024-01-31', \
+         TIMESTAMP '2024-01-31 10:00:00', CAST(a AS DECIMAL(5, 2)), ?, :name",
+        "SELECT a FROM t UNION ALL SELECT a FROM u EXCEPT SELECT a FROM v",
+        "WITH s AS (SELECT a FROM t) SELECT * FROM s",
+        "UPDATE t SET a = a + 1, b = NULL WHERE id = 3 RETURNING id",
+        "DELETE FROM t WHERE a <= 2.5",
+        "ALTER TABLE t ADD COLUMN c TEXT DEFAULT 'x'",
+        "ALTER TABLE t DROP COLUMN c",
+        "ALTER TABLE t RENAME TO u",
+        "ALTER TABLE t ALTER COLUMN id RESTART WITH 5",
+        "TRUNCATE TABLE t",
+        "DROP TABLE IF EXISTS t",
+        "VACUUM",
+        "EXPLAIN SELECT a FROM t WHERE a = 1",
+        "BEGIN",
+        "COMMIT",
+        "ROLLBACK",
+        "SAVEPOINT s",
+        "ROLLBACK TO s",
+        "RELEASE s",
+    ] {
+        let stmt = parse_one(sql);
+        let rendered = stmt.to_sql();
+        assert_eq!(parse_one(&rendered), stmt, "{sql}\n{rendered}");
+        // rendering is canonical, so it is stable
+        assert_eq!(parse_one(&rendered).to_sql(), rendered, "{sql}");
+    }
+}
+
+#[test]
+fn nulls_ordering_follows_the_direction() {
+    let Stmt::Select { order_by: Some(order_by), .. } =
+        parse_one("SELECT a FROM t ORDER BY a NULLS FIRST, b DESC NULLS LAST, c DESC")
+    else {
+        panic!("not a sorted SELECT");
+    };
+    let nulls = order_by.iter().map(|item| (item.desc, item.nulls_first));
+    assert_eq!(
+        nulls.collect::<Vec<_>>(),
+        [(false, Some(true)), (true, Some(false)), (true, None)]
+    );
+    let explicit = order_by.iter().map(OrderBy::nulls_first).collect::<Vec<_>>();
+    assert_eq!(explicit, [true, false, false]);
+    // NULLS comes after the direction
+    let err = Parser::new(Lexer::new("SELECT a FROM t ORDER BY a NULLS FIRST DESC"))
+        .and_then(|mut p| p.parse());
+    assert!(err.is_err());
+}
+
+#[test]
+fn qualified_wildcards() {
+    let Stmt::Select { columns, .. } = parse_one("SELECT u.* FROM users AS u") else {
+        panic!("not a SELECT");
+    };
+    assert_eq!(columns, [Expr::QualifiedWildcard("u".into())]);
+    let Stmt::Select { columns, .. } =
+        parse_one("SELECT u.*, o.total, 1 FROM users AS u")
+    else {
+        panic!("not a SELECT");
+    };
+    assert_eq!(
+        columns,
+        [Expr::QualifiedWildcard("u".into()), qualified("o", "total"), Expr::Int(1)]
+    );
+}
+
+fn parse_expr(sql: &str) -> Result<Expr, QueryErrKind> {
+    Parser::new(Lexer::new(sql))
+        .and_then(|mut p| p.parse_expression())
+        .map_err(|e| e.kind)
+}
+
+#[test]
+fn chained_comparisons_fail() {
+    for sql in
+        ["a < b < c", "a = 1 = 2", "a >= 1 <= 2", "a LIKE 'x' = b", "a IN (1) = b"]
+    {
+        let err = parse_expr(sql).unwrap_err();
+        assert!(matches!(err, QueryErrKind::InvalidExpr(_)), "{sql}: {err:?}");
+    }
+    // parentheses make the grouping explicit
+    assert!(parse_expr("(a < b) = c").is_ok());
+}
+
+#[test]
+fn comparisons_bind_between_arithmetic_and_logic() {
+    for (sql, grouped) in [
+        ("a + 1 = b * 2", "(a + 1) = (b * 2)"),
+        ("a || 'x' LIKE b", "(a || 'x') LIKE b"),
+        ("a = 1 AND b < 2 OR c > 3", "((a = 1) AND (b < 2)) OR (c > 3)"),
+        ("a OR b AND c = d", "a OR (b AND (c = d))"),
+        ("NOT a = 1 AND b", "(NOT (a = 1)) AND b"),
+        ("a - 1 < -b + 2", "(a - 1) < ((-b) + 2)"),
+    ] {
+        assert_eq!(parse_expr(sql), parse_expr(grouped), "{sql}");
+    }
+}
+
+#[test]
+fn boolean_literals_in_where() {
+    let Stmt::Select { where_clause: Some(cond), .. } =
+        parse_one("SELECT * FROM t WHERE active = TRUE OR NOT FALSE")
+    else {
+        panic!("not a SELECT with WHERE");
+    };
+    assert_eq!(Ok(cond), parse_expr("(active = TRUE) OR (NOT FALSE)"));
+}
+
+fn order_by(sql: &str) -> Vec<OrderBy> {
+    let Stmt::Select { order_by: Some(order_by), .. } = parse_one(sql) else {
+        panic!("not a sorted SELECT: {sql}");
+    };
+    order_by
+}
+
+#[test]
+fn order_by_defaults_to_ascending() {
+    let key = |desc| OrderBy { expr: Expr::Ident("a".into()), desc, nulls_first: None };
+    assert_eq!(order_by("SELECT a FROM t ORDER BY a"), [key(false)]);
+    assert_eq!(order_by("SELECT a FROM t ORDER BY a ASC"), [key(false)]);
+    assert_eq!(order_by("SELECT a FROM t ORDER BY a desc"), [key(true)]);
+}
+
+#[test]
+fn order_by_rejects_unknown_direction_words() {
+    for sql in [
+        "SELECT a FROM t ORDER BY a BOGUS",
+        "SELECT a FROM t ORDER BY a ASC DESC",
+        "SELECT a FROM t ORDER BY a DESC, b UP",
+    ] {
+        let err = Parser::new(Lexer::new(sql)).and_then(|mut p| p.parse()).unwrap_err();
+        assert!(
+            matches!(&err.kind, QueryErrKind::UnexpectedToken { expected, .. }
+                if expected.contains("ASC, DESC")),
+            "{sql}: {err:?}"
+        );
+    }
+}
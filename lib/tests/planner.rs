@@ -0,0 +1,100 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::DataValue;
+use litesqrl::storage::{Storage, SyncMode};
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+/// Builds the same rows in a table with UNIQUE columns and in one without.
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Storage::create(dir.path().join("test.db")).unwrap();
+    let mut exec = Executor::new(storage.with_sync_mode(SyncMode::Off));
+    run(
+        &mut exec,
+        "CREATE TABLE indexed (k INT UNIQUE, code TEXT UNIQUE, n INT);
+         CREATE TABLE plain (k INT, code TEXT, n INT);",
+    )
+    .unwrap();
+    for k in 0..200 {
+        for table in ["indexed", "plain"] {
+            let sql = format!("INSERT INTO {table} VALUES ({k}, 'c{k}', {})", k % 7);
+            run(&mut exec, &sql).unwrap();
+        }
+    }
+    (dir, exec)
+}
+
+fn rows(exec: &mut Executor, sql: &str) -> Vec<Vec<DataValue>> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => rows,
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+fn scan_line(exec: &mut Executor, sql: &str) -> String {
+    let plan = rows(exec, &format!("EXPLAIN {sql}"));
+    plan.last().unwrap()[0].to_string().trim().to_string()
+}
+
+#[test]
+fn index_seeks_match_full_scans() {
+    let (_dir, mut exec) = setup();
+    for filter in [
+        "k = 42",
+        "42 = k",
+        "code = 'c7'",
+        "k = 1000",
+        "k = 4.0",
+        "k = NULL",
+        "k = 3 AND n = 3",
+        "k = 3 AND n = 4",
+        "k = 3 OR k = 4",
+    ] {
+        let indexed = rows(&mut exec, &format!("SELECT * FROM indexed WHERE {filter}"));
+        let plain = rows(&mut exec, &format!("SELECT * FROM plain WHERE {filter}"));
+        assert_eq!(indexed, plain, "{filter}");
+    }
+}
+
+#[test]
+fn equality_on_a_unique_column_seeks_the_index() {
+    let (_dir, mut exec) = setup();
+    let sql = "SELECT * FROM indexed WHERE k = 42";
+    assert_eq!(scan_line(&mut exec, sql), "INDEX SEEK indexed ON k");
+    assert_eq!(rows(&mut exec, sql)[0][2], DataValue::Int(0));
+    assert_eq!(
+        scan_line(&mut exec, "SELECT * FROM plain WHERE k = 42"),
+        "FULL SCAN plain"
+    );
+    // without an AND-ed equality there is nothing to seek
+    let sql = "SELECT * FROM indexed WHERE k = 3 OR k = 4";
+    assert_eq!(scan_line(&mut exec, sql), "FULL SCAN indexed");
+}
+
+#[test]
+fn and_terms_use_the_most_selective_index() {
+    let (_dir, mut exec) = setup();
+    for (filter, scan) in [
+        ("n = 3 AND k > 150", "INDEX RANGE indexed ON k"),
+        ("k >= 10 AND k < 20 AND n = 1", "INDEX RANGE indexed ON k"),
+        // a seek reads at most one row, so it beats any range
+        ("k > 5 AND code = 'c42'", "INDEX SEEK indexed ON code"),
+        ("code = 'c42' AND k > 5", "INDEX SEEK indexed ON code"),
+        // two bounds narrow more than one
+        ("code > 'c5' AND k > 10 AND k < 20", "INDEX RANGE indexed ON k"),
+        ("n > 3", "FULL SCAN indexed"),
+    ] {
+        let sql = format!("SELECT * FROM indexed WHERE {filter}");
+        assert_eq!(scan_line(&mut exec, &sql), scan, "{filter}");
+        let plain = format!("SELECT * FROM plain WHERE {filter}");
+        assert_eq!(rows(&mut exec, &sql), rows(&mut exec, &plain), "{filter}");
+    }
+}
@@ -0,0 +1,122 @@
+use litesqrl::executor::{Executor, Prepared, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::DataValue;
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn prepare(sql: &str) -> Prepared {
+    let mut stmts = Parser::new(Lexer::new(sql)).and_then(|mut p| p.parse()).unwrap();
+    Prepared::new(stmts.remove(0).stmt)
+}
+
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    for sql in [
+        "CREATE TABLE t (id INT, name TEXT)",
+        "INSERT INTO t VALUES (1, 'a')",
+        "INSERT INTO t VALUES (2, 'b')",
+        "INSERT INTO t VALUES (3, 'c')",
+    ] {
+        exec.run(prepare(sql).bind(&[]).unwrap().into_stmt()).unwrap();
+    }
+    (dir, exec)
+}
+
+fn rows(result: QueryResult) -> Vec<Vec<String>> {
+    match result {
+        QueryResult::Rows { rows, .. } => rows
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+        _ => panic!("no rows"),
+    }
+}
+
+#[test]
+fn positional_params_bind_in_order() {
+    let (_dir, mut exec) = setup();
+    let prepared = prepare("SELECT name FROM t WHERE id = ? OR name = ?");
+    assert_eq!(prepared.param_count(), 2);
+    let bound =
+        prepared.bind(&[DataValue::Int(2), DataValue::Text("c".into())]).unwrap();
+    assert_eq!(rows(exec.run(bound.into_stmt()).unwrap()), [["b"], ["c"]]);
+
+    let insert = prepare("INSERT INTO t VALUES (?, ?)");
+    let bound = insert.bind(&[DataValue::Int(4), DataValue::Text("it's".into())]);
+    exec.run(bound.unwrap().into_stmt()).unwrap();
+    let select = prepare("SELECT name FROM t WHERE id = ?");
+    let bound = select.bind(&[DataValue::Int(4)]).unwrap();
+    assert_eq!(rows(exec.run(bound.into_stmt()).unwrap()), [["it's"]]);
+}
+
+#[test]
+fn named_params_share_a_slot() {
+    let (_dir, mut exec) = setup();
+    let prepared = prepare("SELECT id FROM t WHERE id = :id OR id = :id + :step");
+    assert_eq!(prepared.param_count(), 2);
+    let bound = prepared
+        .bind_named(&[("step", DataValue::Int(2)), ("id", DataValue::Int(1))])
+        .unwrap();
+    assert_eq!(rows(exec.run(bound.into_stmt()).unwrap()), [["1"], ["3"]]);
+
+    // named slots can be bound by position too, in order of appearance
+    let bound = prepared.bind(&[DataValue::Int(2), DataValue::Int(1)]).unwrap();
+    assert_eq!(rows(exec.run(bound.into_stmt()).unwrap()), [["2"], ["3"]]);
+}
+
+#[test]
+fn wrong_arity_is_an_error() {
+    let prepared = prepare("SELECT * FROM t WHERE id = ? AND name = ?");
+    let err = prepared.bind(&[DataValue::Int(1)]).unwrap_err();
+    assert!(
+        matches!(err, SQRLErr::ParamCountMismatch { expected: 2, got: 1 }),
+        "{err:?}"
+    );
+    let err = prepared.bind(&vec![DataValue::Int(1); 3]).unwrap_err();
+    assert!(matches!(err, SQRLErr::ParamCountMismatch { expected: 2, got: 3 }));
+
+    let err = prepared
+        .bind_named(&[("a", DataValue::Int(1)), ("b", DataValue::Int(2))])
+        .unwrap_err();
+    assert!(matches!(err, SQRLErr::UnboundParam(_)), "{err:?}");
+    let prepared = prepare("SELECT * FROM t WHERE id = :a");
+    let err = prepared.bind_named(&[("b", DataValue::Int(1))]).unwrap_err();
+    assert!(matches!(&err, SQRLErr::UnboundParam(name) if name == ":a"), "{err:?}");
+}
+
+#[test]
+fn limit_and_offset_take_params() {
+    let (_dir, mut exec) = setup();
+    let prepared = prepare("SELECT id FROM t ORDER BY id LIMIT ? OFFSET ?");
+    for (limit, offset, ids) in [(2, 0, &["1", "2"][..]), (2, 2, &["3"]), (0, 0, &[])] {
+        let bound = prepared.bind(&[DataValue::Int(limit), DataValue::Int(offset)]);
+        let result = exec.run(bound.unwrap().into_stmt()).unwrap();
+        let expected = ids.iter().map(|id| vec![id.to_string()]).collect::<Vec<_>>();
+        assert_eq!(rows(result), expected, "LIMIT {limit} OFFSET {offset}");
+    }
+    // simple expressions work without params
+    let stmt = prepare("SELECT id FROM t LIMIT 1 + 1 OFFSET 3 - 2").bind(&[]).unwrap();
+    assert_eq!(rows(exec.run(stmt.into_stmt()).unwrap()), [["2"], ["3"]]);
+}
+
+#[test]
+fn bad_limit_values_fail_when_run() {
+    let (_dir, mut exec) = setup();
+    let prepared = prepare("SELECT id FROM t LIMIT ?");
+    for value in [DataValue::Int(-1), DataValue::Real(1.5), DataValue::Nil] {
+        let bound = prepared.bind(std::slice::from_ref(&value)).unwrap();
+        let Err(err) = exec.run(bound.into_stmt()) else {
+            panic!("LIMIT {value:?} ran");
+        };
+        assert!(
+            matches!(err, SQRLErr::InvalidLimit { clause: "LIMIT", .. }),
+            "{value:?}: {err:?}"
+        );
+    }
+    let stmt = prepare("SELECT id FROM t LIMIT 1 OFFSET 0 - 1").bind(&[]).unwrap();
+    let Err(err) = exec.run(stmt.into_stmt()) else {
+        panic!("a negative OFFSET ran");
+    };
+    assert!(matches!(err, SQRLErr::InvalidLimit { clause: "OFFSET", .. }), "{err:?}");
+}
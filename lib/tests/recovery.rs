@@ -0,0 +1,114 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::{ColConstraints, ColumnType, DataValue};
+use litesqrl::storage::{Storage, StorageErr};
+use std::fs;
+use std::path::Path;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn rows(exec: &mut Executor, sql: &str) -> Vec<Vec<String>> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => rows
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+fn count(storage: &Storage) -> usize {
+    storage.get_table("t").unwrap().rows.values().filter(|row| row.alive).count()
+}
+
+/// Opens a copy of the first `len` bytes of `path`, as a crash after they
+/// reached the disk would leave it.
+fn crash_copy(path: &Path, len: usize) -> Storage {
+    let copy = path.with_extension("crash");
+    fs::write(&copy, &fs::read(path).unwrap()[..len]).unwrap();
+    Storage::open(&copy).unwrap()
+}
+
+#[test]
+fn crash_mid_transaction_drops_it() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let mut storage = Storage::create(&path).unwrap();
+    let table_id = storage.create_table("t").unwrap();
+    storage
+        .create_column(table_id, ColumnType::Int, "a", ColConstraints::default())
+        .unwrap();
+    storage.insert_row(table_id, vec![DataValue::Int(1)]).unwrap();
+    storage.begin().unwrap();
+    storage.insert_row(table_id, vec![DataValue::Int(2)]).unwrap();
+    storage.insert_row(table_id, vec![DataValue::Int(3)]).unwrap();
+
+    let len = fs::metadata(&path).unwrap().len() as usize;
+    let mut crashed = crash_copy(&path, len);
+    assert_eq!(count(&crashed), 1);
+    // the uncommitted tail is cut off, so new writes land after the commit
+    crashed.insert_row(table_id, vec![DataValue::Int(4)]).unwrap();
+    drop(crashed);
+    assert_eq!(count(&Storage::open(path.with_extension("crash")).unwrap()), 2);
+
+    storage.commit().unwrap();
+    let len = fs::metadata(&path).unwrap().len() as usize;
+    assert_eq!(count(&crash_copy(&path, len)), 3);
+}
+
+#[test]
+fn torn_statements_recover_to_either_side() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let mut exec = Executor::new(Storage::create(&path).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (a INT, b TEXT);
+         INSERT INTO t VALUES (1, 'a');
+         INSERT INTO t VALUES (2, 'b');
+         INSERT INTO t VALUES (3, 'c');",
+    )
+    .unwrap();
+    let before = fs::metadata(&path).unwrap().len() as usize;
+    run(&mut exec, "UPDATE t SET b = 'z'").unwrap();
+    let after = fs::metadata(&path).unwrap().len() as usize;
+    assert!(after > before);
+
+    let updated = |len| {
+        let crashed = crash_copy(&path, len);
+        let mut exec = Executor::new(crashed);
+        rows(&mut exec, "SELECT b FROM t WHERE b = 'z'").len()
+    };
+    for len in before..after {
+        assert_eq!(updated(len), 0, "torn at {len}");
+        assert_eq!(
+            fs::metadata(path.with_extension("crash")).unwrap().len() as usize,
+            before
+        );
+    }
+    assert_eq!(updated(after), 3);
+}
+
+#[test]
+fn damage_before_the_tail_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let mut exec = Executor::new(Storage::create(&path).unwrap());
+    run(&mut exec, "CREATE TABLE squirrels (a INT); INSERT INTO squirrels VALUES (1);")
+        .unwrap();
+    drop(exec);
+
+    // flip a byte of the table name, which the CREATE TABLE record holds
+    let mut bytes = fs::read(&path).unwrap();
+    let name = bytes.windows(9).position(|w| w == b"squirrels").unwrap();
+    bytes[name] ^= 0xff;
+    fs::write(&path, bytes).unwrap();
+    let err = Storage::open(&path).err().unwrap();
+    assert!(matches!(&err, StorageErr::Corrupted(_)), "{err:?}");
+}
@@ -0,0 +1,81 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::DataValue;
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn query(exec: &mut Executor, sql: &str) -> (Vec<String>, Vec<Vec<DataValue>>) {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { columns, rows } => (columns, rows),
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+fn rows(exec: &mut Executor, sql: &str) -> Vec<Vec<DataValue>> {
+    query(exec, sql).1
+}
+
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(&mut exec, "CREATE TABLE t (id INT PRIMARY KEY, name TEXT)").unwrap();
+    (dir, exec)
+}
+
+fn fill(exec: &mut Executor) {
+    for (id, name) in [(1, "a"), (2, "b"), (3, "c")] {
+        run(exec, &format!("INSERT INTO t VALUES ({id}, '{name}')")).unwrap();
+    }
+}
+
+fn text(text: &str) -> DataValue {
+    DataValue::Text(text.into())
+}
+
+#[test]
+fn insert_returning_hands_back_the_row() {
+    let (_dir, mut exec) = setup();
+    let (columns, returned) =
+        query(&mut exec, "INSERT INTO t VALUES (1, 'a') RETURNING id, name AS n");
+    assert_eq!(columns, ["id", "n"]);
+    assert_eq!(returned, [[DataValue::Int(1), text("a")]]);
+    assert_eq!(
+        rows(&mut exec, "INSERT INTO t (name, id) VALUES ('c', 3) RETURNING *"),
+        [[DataValue::Int(3), text("c")]]
+    );
+}
+
+#[test]
+fn update_returning_shows_new_values() {
+    let (_dir, mut exec) = setup();
+    fill(&mut exec);
+    assert_eq!(
+        rows(
+            &mut exec,
+            "UPDATE t SET name = name || '!' WHERE id > 1 RETURNING id, name"
+        ),
+        [[DataValue::Int(2), text("b!")], [DataValue::Int(3), text("c!")]]
+    );
+    assert!(
+        rows(&mut exec, "UPDATE t SET name = 'x' WHERE id > 9 RETURNING id").is_empty()
+    );
+}
+
+#[test]
+fn delete_returning_shows_removed_rows() {
+    let (_dir, mut exec) = setup();
+    fill(&mut exec);
+    assert_eq!(
+        rows(&mut exec, "DELETE FROM t WHERE id = 1 OR id = 3 RETURNING name"),
+        [[text("a")], [text("c")]]
+    );
+    assert_eq!(rows(&mut exec, "SELECT name FROM t"), [[text("b")]]);
+}
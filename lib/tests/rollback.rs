@@ -0,0 +1,84 @@
+use litesqrl::Database;
+
+fn dump(db: &mut Database) -> String {
+    db.executor().dump()
+}
+
+/// The state left by a rollback matches what replaying the file gives.
+fn assert_reopens_the_same(db: &mut Database, path: &std::path::Path) {
+    let before = dump(db);
+    let mut reopened = Database::open(path).unwrap();
+    assert_eq!(dump(&mut reopened), before);
+}
+
+fn setup() -> (tempfile::TempDir, std::path::PathBuf, Database) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("t.sqrl");
+    let mut db = Database::create(&path).unwrap();
+    db.execute(
+        "CREATE TABLE t (id INT PRIMARY KEY AUTOINCREMENT, name TEXT UNIQUE);
+         INSERT INTO t (name) VALUES ('a'), ('b');",
+    )
+    .unwrap();
+    (dir, path, db)
+}
+
+#[test]
+fn rollback_to_restores_rows_schema_and_indexes() {
+    let (_dir, path, mut db) = setup();
+    db.execute(
+        "BEGIN;
+         INSERT INTO t (name) VALUES ('c');
+         SAVEPOINT s;
+         INSERT INTO t (name) VALUES ('d');
+         UPDATE t SET name = 'z' WHERE name = 'a';
+         DELETE FROM t WHERE name = 'b';
+         ALTER TABLE t ADD COLUMN extra INT;
+         CREATE TABLE u (x INT);
+         ROLLBACK TO s;",
+    )
+    .unwrap();
+    let after = dump(&mut db);
+    assert!(after.contains("'c'") && !after.contains("'d'"), "{after}");
+    assert!(
+        !after.contains("extra")
+            && !after.contains("\"u\"")
+            && !after.contains("TABLE u")
+    );
+    // the index lost 'z' and got 'a' and 'b' back
+    assert!(db.execute("INSERT INTO t (name) VALUES ('a')").is_err());
+    assert!(db.execute("INSERT INTO t (name) VALUES ('b')").is_err());
+    db.execute("INSERT INTO t (name) VALUES ('z'); COMMIT").unwrap();
+    assert_reopens_the_same(&mut db, &path);
+}
+
+#[test]
+fn failed_statements_roll_back_alone() {
+    let (_dir, path, mut db) = setup();
+    db.execute("BEGIN; INSERT INTO t (name) VALUES ('c')").unwrap();
+    // the second row is a duplicate, so neither row is kept
+    assert!(db.execute("INSERT INTO t (name) VALUES ('d'), ('a')").is_err());
+    db.execute("INSERT INTO t (name) VALUES ('d'); COMMIT").unwrap();
+    // the rolled back row's AUTOINCREMENT value is handed out again
+    let rows = db.query("SELECT id, name FROM t ORDER BY id").unwrap().into_rows();
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[3][0].to_string(), "4");
+    assert_reopens_the_same(&mut db, &path);
+}
+
+#[test]
+fn rollback_restores_emptied_and_dropped_tables() {
+    let (_dir, path, mut db) = setup();
+    db.execute("CREATE TABLE u (x INT)").unwrap();
+    let before = dump(&mut db);
+    db.execute(
+        "BEGIN;
+         INSERT INTO u VALUES (1);
+         DELETE FROM t;
+         DROP TABLE u;
+         ROLLBACK;",
+    )
+    .unwrap();
+    assert_eq!(dump(&mut db), before);
+    assert_reopens_the_same(&mut db, &path);
+}
@@ -0,0 +1,107 @@
+use litesqrl::Database;
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::DataValue;
+use litesqrl::storage::Storage;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn query(exec: &mut Executor, sql: &str) -> (Vec<String>, Vec<Vec<DataValue>>) {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { columns, rows } => (columns, rows),
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+#[test]
+fn select_without_from_yields_one_row() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    let (columns, rows) = query(&mut exec, "SELECT 1 + 1, 'hi' AS greeting");
+    assert_eq!(columns[1], "greeting");
+    assert_eq!(rows, [[DataValue::Int(2), DataValue::Text("hi".into())]]);
+    let (_, rows) = query(&mut exec, "SELECT UPPER('a') || 'b', ABS(-2) * 3, NULL");
+    assert_eq!(
+        rows,
+        [[DataValue::Text("Ab".into()), DataValue::Int(6), DataValue::Nil]]
+    );
+    // a constant WHERE filters the single row
+    assert!(query(&mut exec, "SELECT 1 WHERE 1 = 2").1.is_empty());
+}
+
+#[test]
+fn qualified_wildcards_expand_to_the_source_columns() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut db = Database::create(dir.path().join("test.db")).unwrap();
+    db.execute(
+        "CREATE TABLE users (id INT, name TEXT); INSERT INTO users VALUES (1, 'a');",
+    )
+    .unwrap();
+    let result = db.query("SELECT u.*, u.id AS again FROM users AS u").unwrap();
+    assert_eq!(result.columns(), ["id", "name", "again"]);
+    assert_eq!(
+        result.into_rows(),
+        [[DataValue::Int(1), DataValue::Text("a".into()), DataValue::Int(1)]]
+    );
+    let result = db.query("SELECT s.* FROM (SELECT name FROM users) AS s").unwrap();
+    assert_eq!(result.columns(), ["name"]);
+
+    // an alias hides the table name
+    for sql in ["SELECT x.* FROM users AS u", "SELECT users.* FROM users AS u"] {
+        assert!(db.query(sql).is_err(), "{sql}");
+    }
+}
+
+#[test]
+fn qualified_columns_name_the_source() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(&mut exec, "CREATE TABLE t (a INT, b TEXT); INSERT INTO t VALUES (1, 'x');")
+        .unwrap();
+    let (columns, rows) =
+        query(&mut exec, "SELECT t.a, b FROM t WHERE t.b = 'x' ORDER BY t.a");
+    assert_eq!(columns, ["a", "b"]);
+    assert_eq!(rows, [[DataValue::Int(1), DataValue::Text("x".into())]]);
+    let (_, rows) = query(&mut exec, "SELECT s.a FROM (SELECT a FROM t) AS s");
+    assert_eq!(rows, [[DataValue::Int(1)]]);
+    run(&mut exec, "UPDATE t SET b = t.b || 'y' WHERE t.a = 1").unwrap();
+    let (_, rows) = query(&mut exec, "SELECT b FROM t");
+    assert_eq!(rows, [[DataValue::Text("xy".into())]]);
+
+    // an alias hides the table name
+    for sql in
+        ["SELECT u.a FROM t", "SELECT t.a FROM t AS s", "DELETE FROM t WHERE u.a = 1"]
+    {
+        let Err(err) = run(&mut exec, sql) else {
+            panic!("{sql} ran");
+        };
+        assert!(matches!(err, SQRLErr::TableNotFound(_)), "{sql}: {err:?}");
+    }
+}
+
+#[test]
+fn star_is_all_columns_only_in_the_select_list() {
+    let mut db = Database::memory();
+    db.execute("CREATE TABLE t (a INT, b INT); INSERT INTO t VALUES (3, 4);").unwrap();
+    let all = db.query("SELECT * FROM t").unwrap();
+    assert_eq!(all.columns(), ["a", "b"]);
+    assert_eq!(all.into_rows(), [[DataValue::Int(3), DataValue::Int(4)]]);
+    let rows = db.query("SELECT a * b, a*b*2 FROM t WHERE a * b > 10").unwrap();
+    assert_eq!(rows.into_rows(), [[DataValue::Int(12), DataValue::Int(24)]]);
+    // both meanings in one list
+    let rows = db.query("SELECT *, a * b AS p FROM t").unwrap();
+    assert_eq!(rows.columns(), ["a", "b", "p"]);
+    assert_eq!(
+        rows.into_rows(),
+        [[DataValue::Int(3), DataValue::Int(4), DataValue::Int(12)]]
+    );
+    // a bare `*` is not a value
+    assert!(db.query("SELECT a * * FROM t").is_err());
+    assert!(db.query("SELECT * FROM t WHERE * = 1").is_err());
+}
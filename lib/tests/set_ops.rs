@@ -0,0 +1,160 @@
+use litesqrl::Database;
+use litesqrl::executor::SQRLErr;
+use litesqrl::schema::DataValue;
+
+fn ints(db: &mut Database, sql: &str) -> Vec<i64> {
+    db.query(sql)
+        .unwrap()
+        .into_rows()
+        .into_iter()
+        .map(|row| match row[..] {
+            [DataValue::Int(n)] => n,
+            _ => panic!("not an INT row: {row:?}"),
+        })
+        .collect()
+}
+
+fn setup() -> Database {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE a (n INT, s TEXT);
+         CREATE TABLE b (n INT, s TEXT);
+         INSERT INTO a VALUES (1, 'x'), (2, 'y'), (2, 'y'), (3, 'z');
+         INSERT INTO b VALUES (2, 'y'), (3, 'w'), (4, 'v');",
+    )
+    .unwrap();
+    db
+}
+
+#[test]
+fn union_removes_duplicates() {
+    let mut db = setup();
+    let rows = db.query("SELECT n, s FROM a UNION SELECT n, s FROM b").unwrap();
+    assert_eq!(rows.len(), 5);
+    assert_eq!(
+        ints(&mut db, "SELECT n FROM a UNION SELECT n FROM b ORDER BY 1"),
+        [1, 2, 3, 4]
+    );
+    // duplicates within one side are removed too
+    assert_eq!(ints(&mut db, "SELECT n FROM a UNION SELECT n FROM a"), [1, 2, 3]);
+}
+
+#[test]
+fn union_all_keeps_duplicates() {
+    let mut db = setup();
+    assert_eq!(
+        ints(&mut db, "SELECT n FROM a UNION ALL SELECT n FROM b"),
+        [1, 2, 2, 3, 2, 3, 4]
+    );
+}
+
+#[test]
+fn union_dedups_many_rows() {
+    let mut db = Database::memory();
+    db.execute("CREATE TABLE t (n INT)").unwrap();
+    let values = (0..20_000).map(|n| format!("({})", n % 5_000)).collect::<Vec<_>>();
+    db.execute(&format!("INSERT INTO t VALUES {}", values.join(", "))).unwrap();
+    let rows = db.query("SELECT n FROM t UNION SELECT n FROM t").unwrap();
+    assert_eq!(rows.len(), 5_000);
+}
+
+#[test]
+fn union_sides_must_match() {
+    let mut db = setup();
+    let err = db.query("SELECT n, s FROM a UNION SELECT n FROM b").unwrap_err();
+    assert!(
+        matches!(err, SQRLErr::ColumnCountMismatch { expected: 2, got: 1 }),
+        "{err:?}"
+    );
+    // INT widens to REAL, so 1 and 1.0 are the same row
+    let rows = db.query("SELECT 1 UNION SELECT 1.0").unwrap();
+    assert_eq!(rows.into_rows(), [[DataValue::Real(1.0)]]);
+    let err = db.query("SELECT n FROM a UNION SELECT s FROM b").unwrap_err();
+    assert!(matches!(err, SQRLErr::TypeMismatch { .. }), "{err:?}");
+}
+
+#[test]
+fn parenthesized_operands_keep_their_own_order_and_limit() {
+    let mut db = setup();
+    assert_eq!(
+        ints(
+            &mut db,
+            "(SELECT n FROM a ORDER BY n DESC LIMIT 1)
+             UNION ALL (SELECT n FROM b ORDER BY n LIMIT 1)"
+        ),
+        [3, 2]
+    );
+    assert_eq!(
+        ints(&mut db, "(SELECT n FROM a) UNION ((SELECT n FROM b)) ORDER BY n DESC"),
+        [4, 3, 2, 1]
+    );
+}
+
+#[test]
+fn trailing_order_by_and_limit_apply_to_the_whole_union() {
+    let mut db = setup();
+    assert_eq!(
+        ints(&mut db, "SELECT n FROM a UNION ALL SELECT n FROM b ORDER BY n DESC"),
+        [4, 3, 3, 2, 2, 2, 1]
+    );
+    assert_eq!(
+        ints(&mut db, "SELECT n FROM a UNION ALL SELECT n FROM b ORDER BY n LIMIT 2"),
+        [1, 2]
+    );
+    // in parentheses the ORDER BY belongs to the second operand alone
+    assert_eq!(
+        ints(&mut db, "SELECT n FROM a UNION ALL (SELECT n FROM b ORDER BY n DESC)"),
+        [1, 2, 2, 3, 4, 3, 2]
+    );
+}
+
+#[test]
+fn unmatched_parentheses_fail() {
+    let mut db = setup();
+    for sql in [
+        "(SELECT n FROM a UNION SELECT n FROM b",
+        "(SELECT n FROM a) UNION (SELECT n FROM b",
+        "SELECT n FROM a UNION (SELECT n FROM b))",
+    ] {
+        let err = db.query(sql).unwrap_err();
+        assert!(matches!(err, SQRLErr::QueryErr(_)), "{sql}: {err:?}");
+    }
+}
+
+#[test]
+fn intersect_and_except() {
+    let mut db = setup();
+    assert_eq!(ints(&mut db, "SELECT n FROM a INTERSECT SELECT n FROM b"), [2, 3]);
+    assert_eq!(ints(&mut db, "SELECT n FROM a EXCEPT SELECT n FROM b"), [1]);
+    assert_eq!(ints(&mut db, "SELECT n FROM b EXCEPT SELECT n FROM a"), [4]);
+    // whole rows are compared, not just the first column
+    let rows = db.query("SELECT n, s FROM a INTERSECT SELECT n, s FROM b").unwrap();
+    assert_eq!(rows.into_rows(), [[DataValue::Int(2), DataValue::Text("y".into())]]);
+}
+
+#[test]
+fn intersect_all_and_except_all_count_duplicates() {
+    let mut db = setup();
+    db.execute("INSERT INTO b VALUES (2, 'y'), (2, 'y')").unwrap();
+    // a has 2 twice and b three times
+    assert_eq!(
+        ints(&mut db, "SELECT n FROM a INTERSECT ALL SELECT n FROM b"),
+        [2, 2, 3]
+    );
+    assert_eq!(ints(&mut db, "SELECT n FROM a INTERSECT SELECT n FROM b"), [2, 3]);
+    assert_eq!(ints(&mut db, "SELECT n FROM b EXCEPT ALL SELECT n FROM a"), [4, 2]);
+    assert_eq!(ints(&mut db, "SELECT n FROM a EXCEPT ALL SELECT n FROM a"), []);
+    assert_eq!(ints(&mut db, "SELECT n FROM a EXCEPT ALL SELECT 2"), [1, 2, 3]);
+}
+
+#[test]
+fn intersect_and_except_sides_must_match() {
+    let mut db = setup();
+    for sql in [
+        "SELECT n, s FROM a INTERSECT SELECT n FROM b",
+        "SELECT n FROM a EXCEPT ALL SELECT n, s FROM b",
+    ] {
+        let err = db.query(sql).unwrap_err();
+        assert!(matches!(err, SQRLErr::ColumnCountMismatch { .. }), "{sql}: {err:?}");
+    }
+}
@@ -0,0 +1,65 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::DataValue;
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn rows(exec: &mut Executor, sql: &str) -> Vec<Vec<DataValue>> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => rows,
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+fn value(exec: &mut Executor, sql: &str) -> DataValue {
+    rows(exec, sql).remove(0).remove(0)
+}
+
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    (dir, exec)
+}
+
+#[test]
+fn and_or_skip_the_right_side_when_decided() {
+    let (_dir, mut exec) = setup();
+    assert!(run(&mut exec, "SELECT 1 / 0 = 0").is_err());
+    assert_eq!(
+        value(&mut exec, "SELECT FALSE AND (1 / 0 = 0)"),
+        DataValue::Bool(false)
+    );
+    assert_eq!(value(&mut exec, "SELECT TRUE OR (1 / 0 = 0)"), DataValue::Bool(true));
+    // otherwise the right side is still evaluated
+    assert!(run(&mut exec, "SELECT TRUE AND (1 / 0 = 0)").is_err());
+    assert!(run(&mut exec, "SELECT NULL AND (1 / 0 = 0)").is_err());
+}
+
+#[test]
+fn short_circuits_apply_per_row() {
+    let (_dir, mut exec) = setup();
+    run(
+        &mut exec,
+        "CREATE TABLE t (a INT);
+         INSERT INTO t VALUES (0);
+         INSERT INTO t VALUES (2);
+         INSERT INTO t VALUES (4);",
+    )
+    .unwrap();
+    assert_eq!(
+        rows(&mut exec, "SELECT a FROM t WHERE a > 0 AND 8 / a > 2"),
+        [[DataValue::Int(2)]]
+    );
+    assert_eq!(
+        rows(&mut exec, "SELECT a FROM t WHERE a = 0 OR 8 / a = 2"),
+        [[DataValue::Int(0)], [DataValue::Int(4)]]
+    );
+}
@@ -0,0 +1,247 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::{ColConstraints, ColumnType, DataValue};
+use litesqrl::storage::{Storage, StorageErr, SyncMode, TableId};
+use std::io::ErrorKind;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+#[test]
+fn create_then_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let (table_id, row_id) = {
+        let mut storage = Storage::create(&path).unwrap();
+        let table_id = storage.create_table("t").unwrap();
+        storage
+            .create_column(table_id, ColumnType::Int, "a", ColConstraints::default())
+            .unwrap();
+        storage
+            .create_column(table_id, ColumnType::Text, "b", ColConstraints::default())
+            .unwrap();
+        let row_id = storage
+            .insert_row(table_id, vec![DataValue::Int(1), DataValue::Text("x".into())])
+            .unwrap();
+        (table_id, row_id)
+    };
+
+    let storage = Storage::open(&path).unwrap();
+    let table = storage.get_table("t").unwrap();
+    assert_eq!(table.id, table_id);
+    let names = table.cols.iter().map(|col| &*col.name).collect::<Vec<_>>();
+    assert_eq!(names, ["a", "b"]);
+    assert_eq!(table.rows.len(), 1);
+    let a = storage.get_column(table_id, "a").unwrap();
+    let row = storage.get_row(table_id, row_id).unwrap();
+    assert_eq!(row.value(a), DataValue::Int(1));
+}
+
+#[test]
+fn open_needs_an_existing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let err = Storage::open(&path).err().unwrap();
+    assert!(matches!(&err, StorageErr::Io(e) if e.kind() == ErrorKind::NotFound));
+    assert!(!path.exists());
+
+    Storage::create(&path).unwrap();
+    let err = Storage::create(&path).err().unwrap();
+    assert!(matches!(&err, StorageErr::Io(e) if e.kind() == ErrorKind::AlreadyExists));
+    Storage::open(&path).unwrap();
+}
+
+#[test]
+fn open_rejects_a_foreign_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    std::fs::write(&path, "not a database").unwrap();
+    assert!(Storage::open(&path).is_err());
+}
+
+#[test]
+fn every_sync_mode_reopens() {
+    let dir = tempfile::tempdir().unwrap();
+    for mode in [SyncMode::Full, SyncMode::Normal, SyncMode::Off] {
+        let path = dir.path().join(format!("{mode:?}.sqrl"));
+        {
+            let storage = Storage::create(&path).unwrap().with_sync_mode(mode);
+            assert_eq!(storage.sync_mode(), mode);
+            let mut exec = Executor::new(storage);
+            run(
+                &mut exec,
+                "CREATE TABLE t (a INT);
+                 INSERT INTO t VALUES (1);
+                 INSERT INTO t VALUES (2);
+                 UPDATE t SET a = a + 2;
+                 VACUUM;
+                 INSERT INTO t VALUES (5);",
+            )
+            .unwrap();
+        }
+        let storage = Storage::open(&path).unwrap();
+        assert_eq!(storage.sync_mode(), SyncMode::Full);
+        assert_eq!(storage.get_table("t").unwrap().rows.len(), 3, "{mode:?}");
+    }
+}
+
+#[test]
+fn reads_after_open_never_touch_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let (table_id, rows) = {
+        let mut storage = Storage::create(&path).unwrap();
+        let table_id = storage.create_table("t").unwrap();
+        storage
+            .create_column(table_id, ColumnType::Int, "a", ColConstraints::default())
+            .unwrap();
+        let rows = [1, 2]
+            .map(|n| storage.insert_row(table_id, vec![DataValue::Int(n)]).unwrap());
+        (table_id, rows)
+    };
+
+    let storage = Storage::open(&path).unwrap();
+    let col = storage.get_column(table_id, "a").unwrap().clone();
+    // the whole state was replayed into memory, so a clobbered file does not
+    // change what reads see
+    std::fs::write(&path, b"").unwrap();
+    for _ in 0..3 {
+        let values =
+            rows.map(|row_id| storage.get_row(table_id, row_id).unwrap().value(&col));
+        assert_eq!(values, [DataValue::Int(1), DataValue::Int(2)]);
+    }
+}
+
+#[test]
+fn deleted_rows_are_tombstones_until_vacuum() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let mut storage = Storage::create(&path).unwrap();
+    let table_id = storage.create_table("t").unwrap();
+    storage
+        .create_column(table_id, ColumnType::Int, "a", ColConstraints::default())
+        .unwrap();
+    let rows = (0..3)
+        .map(|n| storage.insert_row(table_id, vec![DataValue::Int(n)]).unwrap())
+        .collect::<Vec<_>>();
+    storage.delete_row(table_id, rows[1]).unwrap();
+
+    let live = |storage: &Storage| {
+        storage.scan(table_id).unwrap().map(|row| row.id).collect::<Vec<_>>()
+    };
+    assert_eq!(live(&storage), [rows[0], rows[2]]);
+    assert!(matches!(
+        storage.get_row(table_id, rows[1]),
+        Err(StorageErr::RowNotFound(_))
+    ));
+    assert!(matches!(
+        storage.delete_row(table_id, rows[1]),
+        Err(StorageErr::RowNotFound(_))
+    ));
+    // the row stays behind, flagged, and so it does in the file
+    let table = storage.get_table("t").unwrap();
+    assert!(!table.rows[&rows[1]].alive);
+    drop(storage);
+    let mut storage = Storage::open(&path).unwrap();
+    assert_eq!(live(&storage), [rows[0], rows[2]]);
+    assert!(!storage.get_table("t").unwrap().rows[&rows[1]].alive);
+
+    storage.vacuum().unwrap();
+    assert_eq!(live(&storage), [rows[0], rows[2]]);
+    let table = storage.get_table("t").unwrap();
+    assert!(!table.rows.contains_key(&rows[1]));
+}
+
+#[test]
+fn scan_streams_rows_in_id_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let mut storage = Storage::create(&path).unwrap().with_sync_mode(SyncMode::Off);
+    let table_id = storage.create_table("t").unwrap();
+    storage
+        .create_column(table_id, ColumnType::Int, "a", ColConstraints::default())
+        .unwrap();
+    let ids = (0..3_000)
+        .map(|n| storage.insert_row(table_id, vec![DataValue::Int(n)]).unwrap())
+        .collect::<Vec<_>>();
+    for row_id in ids.iter().step_by(3) {
+        storage.delete_row(table_id, *row_id).unwrap();
+    }
+
+    let col = storage.get_column(table_id, "a").unwrap().clone();
+    let mut scan = storage.scan(table_id).unwrap();
+    // rows come lazily, so taking a few reads only those
+    let first = scan.by_ref().take(2).map(|row| row.value(&col)).collect::<Vec<_>>();
+    assert_eq!(first, [DataValue::Int(1), DataValue::Int(2)]);
+    assert_eq!(scan.count(), 2_000 - 2);
+    let scanned = storage.scan(table_id).unwrap().map(|row| row.id).collect::<Vec<_>>();
+    assert!(scanned.is_sorted());
+}
+
+#[test]
+fn scan_of_a_missing_table_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut storage = Storage::create(dir.path().join("db.sqrl")).unwrap();
+    let table_id = storage.create_table("t").unwrap();
+    storage.drop_table(table_id).unwrap();
+    assert!(matches!(storage.scan(table_id).err(), Some(StorageErr::TableNotFound(_))));
+    let unknown = TableId(table_id.0 + 100);
+    assert!(matches!(storage.scan(unknown).err(), Some(StorageErr::TableNotFound(_))));
+}
+
+#[test]
+fn bulk_insert_writes_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let load = |bulk: bool| {
+        let path = dir.path().join(if bulk { "bulk.db" } else { "naive.db" });
+        let mut storage = Storage::create(path).unwrap().with_sync_mode(SyncMode::Off);
+        let table_id = storage.create_table("t").unwrap();
+        storage
+            .create_column(table_id, ColumnType::Int, "a", ColConstraints::default())
+            .unwrap();
+        let rows = (0..1000).map(|n| vec![DataValue::Int(n)]).collect::<Vec<_>>();
+        let before = storage.write_count();
+        storage.begin().unwrap();
+        if bulk {
+            storage.bulk_insert(table_id, rows).unwrap();
+        } else {
+            for row in rows {
+                storage.insert_row(table_id, row).unwrap();
+            }
+        }
+        storage.commit().unwrap();
+        let writes = storage.write_count() - before;
+        let col = storage.get_column(table_id, "a").unwrap().clone();
+        let values = storage.scan(table_id).unwrap().map(|row| row.value(&col));
+        (writes, values.collect::<Vec<_>>())
+    };
+    let (naive_writes, naive_rows) = load(false);
+    let (bulk_writes, bulk_rows) = load(true);
+    // the rows, then the commit
+    assert_eq!(bulk_writes, 2);
+    assert_eq!(naive_writes, 1001);
+    assert_eq!(bulk_rows, naive_rows);
+}
+
+#[test]
+fn multi_row_values_insert_all_or_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(&mut exec, "CREATE TABLE t (a INT UNIQUE, b TEXT)").unwrap();
+    let Ok(QueryResult::Count(3)) =
+        run(&mut exec, "INSERT INTO t VALUES (1, 'x'), (2, 'y'), (3, NULL)")
+    else {
+        panic!("three rows were not inserted");
+    };
+    // the duplicate in the last row rejects the whole statement
+    assert!(run(&mut exec, "INSERT INTO t VALUES (4, 'z'), (1, 'w')").is_err());
+    let Ok(QueryResult::Rows { rows, .. }) = run(&mut exec, "SELECT a FROM t") else {
+        panic!("SELECT returned no rows");
+    };
+    assert_eq!(rows, [[DataValue::Int(1)], [DataValue::Int(2)], [DataValue::Int(3)]]);
+}
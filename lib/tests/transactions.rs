@@ -0,0 +1,135 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::storage::{Storage, StorageErr};
+use std::path::Path;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn open(path: &Path) -> Executor {
+    Executor::new(Storage::open(path).unwrap())
+}
+
+fn count(exec: &mut Executor) -> usize {
+    match run(exec, "SELECT * FROM t").unwrap() {
+        QueryResult::Rows { rows, .. } => rows.len(),
+        _ => panic!("SELECT returned no rows"),
+    }
+}
+
+fn assert_invalid_txn(exec: &mut Executor, sql: &str) {
+    match run(exec, sql) {
+        Err(SQRLErr::StorageErr(StorageErr::InvalidTxn(_))) => {}
+        Err(err) => panic!("{sql}: {err:?}"),
+        Ok(_) => panic!("{sql} ran"),
+    }
+}
+
+fn missing_savepoint(exec: &mut Executor, sql: &str) -> String {
+    match run(exec, sql) {
+        Err(SQRLErr::SavepointNotFound(name)) => name,
+        Err(err) => panic!("{sql}: {err:?}"),
+        Ok(_) => panic!("{sql} ran"),
+    }
+}
+
+#[test]
+fn rollback_reverts_and_commit_persists() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let mut exec = Executor::new(Storage::create(&path).unwrap());
+    run(&mut exec, "CREATE TABLE t (a INT)").unwrap();
+
+    run(&mut exec, "BEGIN; INSERT INTO t VALUES (1); INSERT INTO t VALUES (2);")
+        .unwrap();
+    // the transaction sees its own writes
+    assert_eq!(count(&mut exec), 2);
+    run(&mut exec, "ROLLBACK").unwrap();
+    assert_invalid_txn(&mut exec, "COMMIT");
+    assert_eq!(count(&mut exec), 0);
+
+    run(&mut exec, "BEGIN; INSERT INTO t VALUES (3); COMMIT;").unwrap();
+    // outside a transaction every statement commits on its own
+    run(&mut exec, "INSERT INTO t VALUES (4)").unwrap();
+    drop(exec);
+    assert_eq!(count(&mut open(&path)), 2);
+}
+
+#[test]
+fn uncommitted_writes_are_lost_on_close() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let mut exec = Executor::new(Storage::create(&path).unwrap());
+    run(&mut exec, "CREATE TABLE t (a INT); BEGIN; INSERT INTO t VALUES (1);").unwrap();
+    drop(exec);
+    let mut exec = open(&path);
+    assert_eq!(count(&mut exec), 0);
+    assert_invalid_txn(&mut exec, "COMMIT");
+}
+
+#[test]
+fn transaction_statements_out_of_place_fail() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("db.sqrl")).unwrap());
+    run(&mut exec, "CREATE TABLE t (a INT)").unwrap();
+    assert_invalid_txn(&mut exec, "COMMIT");
+    assert_invalid_txn(&mut exec, "ROLLBACK");
+    run(&mut exec, "BEGIN; INSERT INTO t VALUES (1)").unwrap();
+    // BEGIN does not nest; the open transaction carries on
+    assert_invalid_txn(&mut exec, "BEGIN");
+    run(&mut exec, "COMMIT").unwrap();
+    assert_eq!(count(&mut exec), 1);
+}
+
+#[test]
+fn rollback_to_keeps_earlier_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("db.sqrl")).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (a INT);
+         BEGIN;
+         INSERT INTO t VALUES (1);
+         SAVEPOINT first;
+         INSERT INTO t VALUES (2);
+         SAVEPOINT second;
+         INSERT INTO t VALUES (3);
+         ROLLBACK TO second;",
+    )
+    .unwrap();
+    assert_eq!(count(&mut exec), 2);
+    // the savepoint is kept, so it can be rolled back to again
+    run(&mut exec, "INSERT INTO t VALUES (4); ROLLBACK TO second;").unwrap();
+    assert_eq!(count(&mut exec), 2);
+    // rolling back to an earlier savepoint drops the later ones
+    run(&mut exec, "ROLLBACK TO first").unwrap();
+    assert_eq!(count(&mut exec), 1);
+    assert_eq!(missing_savepoint(&mut exec, "ROLLBACK TO second"), "second");
+    run(&mut exec, "COMMIT").unwrap();
+    assert_eq!(count(&mut exec), 1);
+}
+
+#[test]
+fn released_and_unknown_savepoints_fail() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("db.sqrl")).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (a INT);
+         BEGIN;
+         SAVEPOINT s;
+         INSERT INTO t VALUES (1);
+         RELEASE s;",
+    )
+    .unwrap();
+    assert_eq!(missing_savepoint(&mut exec, "ROLLBACK TO s"), "s");
+    assert_eq!(missing_savepoint(&mut exec, "ROLLBACK TO nowhere"), "nowhere");
+    // releasing keeps the changes made since the savepoint
+    run(&mut exec, "COMMIT").unwrap();
+    assert_eq!(count(&mut exec), 1);
+}
@@ -0,0 +1,74 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::storage::Storage;
+use tempfile::TempDir;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn rows(exec: &mut Executor, sql: &str) -> Vec<Vec<String>> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => rows
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+fn setup() -> (TempDir, Executor) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(&mut exec, "CREATE TABLE t (i INT, r FLOAT, s TEXT, b BOOL)").unwrap();
+    (dir, exec)
+}
+
+fn type_mismatch(exec: &mut Executor, sql: &str) -> String {
+    match run(exec, sql) {
+        Err(SQRLErr::TypeMismatch { column, .. }) => column,
+        Err(err) => panic!("{sql}: {err:?}"),
+        Ok(_) => panic!("{sql} ran"),
+    }
+}
+
+#[test]
+fn values_of_the_declared_type_are_stored() {
+    let (_dir, mut exec) = setup();
+    run(
+        &mut exec,
+        "INSERT INTO t VALUES (1, 1.5, 'a', TRUE);
+         INSERT INTO t VALUES (NULL, NULL, NULL, NULL);",
+    )
+    .unwrap();
+    run(&mut exec, "UPDATE t SET i = 2, s = 'b' WHERE i = 1").unwrap();
+    assert_eq!(
+        rows(&mut exec, "SELECT i, r, s, b FROM t WHERE i = 2"),
+        [["2", "1.5", "b", "true"]]
+    );
+}
+
+#[test]
+fn mismatched_values_are_rejected() {
+    let (_dir, mut exec) = setup();
+    assert_eq!(type_mismatch(&mut exec, "INSERT INTO t (i) VALUES ('abc')"), "i");
+    assert_eq!(type_mismatch(&mut exec, "INSERT INTO t (i) VALUES (1.5)"), "i");
+    assert_eq!(type_mismatch(&mut exec, "INSERT INTO t (s) VALUES (1)"), "s");
+    assert_eq!(type_mismatch(&mut exec, "INSERT INTO t (b) VALUES (1)"), "b");
+
+    run(&mut exec, "INSERT INTO t (i) VALUES (1)").unwrap();
+    assert_eq!(type_mismatch(&mut exec, "UPDATE t SET i = 'abc'"), "i");
+    assert_eq!(rows(&mut exec, "SELECT i FROM t"), [["1"]]);
+}
+
+#[test]
+fn ints_widen_to_real() {
+    let (_dir, mut exec) = setup();
+    run(&mut exec, "INSERT INTO t (r) VALUES (3)").unwrap();
+    run(&mut exec, "UPDATE t SET r = r + 1.5").unwrap();
+    assert_eq!(rows(&mut exec, "SELECT r FROM t"), [["4.5"]]);
+}
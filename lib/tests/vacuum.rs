@@ -0,0 +1,82 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::{ColConstraints, ColumnType, DataValue};
+use litesqrl::storage::Storage;
+use std::fs;
+use std::path::Path;
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn file_len(path: &Path) -> u64 {
+    fs::metadata(path).unwrap().len()
+}
+
+fn rows(exec: &mut Executor) -> Vec<Vec<String>> {
+    match run(exec, "SELECT * FROM t").unwrap() {
+        QueryResult::Rows { rows, .. } => rows
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+        _ => panic!("SELECT returned no rows"),
+    }
+}
+
+#[test]
+fn vacuum_shrinks_the_file_and_keeps_live_rows() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let mut exec = Executor::new(Storage::create(&path).unwrap());
+    run(&mut exec, "CREATE TABLE t (id INT PRIMARY KEY, name TEXT)").unwrap();
+    for id in 1..=500 {
+        run(&mut exec, &format!("INSERT INTO t VALUES ({id}, 'squirrel')")).unwrap();
+    }
+    run(
+        &mut exec,
+        "DELETE FROM t WHERE id > 50;
+         UPDATE t SET name = 'chipmunk' WHERE id <= 10;
+         CREATE TABLE gone (a INT);
+         INSERT INTO gone VALUES (1);
+         DROP TABLE gone;",
+    )
+    .unwrap();
+    let live = rows(&mut exec);
+    let before = file_len(&path);
+
+    run(&mut exec, "VACUUM").unwrap();
+    let after = file_len(&path);
+    assert!(after * 5 < before, "{before} -> {after}");
+    assert_eq!(rows(&mut exec), live);
+
+    // new rows are written after the compacted ones
+    run(&mut exec, "INSERT INTO t VALUES (501, 'marmot')").unwrap();
+    assert!(file_len(&path) > after);
+    drop(exec);
+    let storage = Storage::open(&path).unwrap();
+    assert!(storage.get_table("gone").is_err());
+    let mut exec = Executor::new(storage);
+    let reopened = rows(&mut exec);
+    assert_eq!(reopened[..50], live[..]);
+    assert_eq!(reopened[50], ["501", "marmot"]);
+}
+
+#[test]
+fn vacuum_is_refused_in_a_transaction() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut storage = Storage::create(dir.path().join("db.sqrl")).unwrap();
+    let table_id = storage.create_table("t").unwrap();
+    storage
+        .create_column(table_id, ColumnType::Int, "id", ColConstraints::default())
+        .unwrap();
+    storage.begin().unwrap();
+    storage.insert_row(table_id, vec![DataValue::Int(1)]).unwrap();
+    assert!(storage.vacuum().is_err());
+    storage.commit().unwrap();
+    storage.vacuum().unwrap();
+    assert_eq!(storage.get_table("t").unwrap().rows.len(), 1);
+}
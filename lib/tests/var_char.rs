@@ -0,0 +1,136 @@
+use litesqrl::executor::{Executor, QueryResult, SQRLErr};
+use litesqrl::query::{Lexer, Parser};
+use litesqrl::schema::VarChar;
+use litesqrl::storage::{Storage, StorageErr};
+
+fn run(exec: &mut Executor, sql: &str) -> Result<QueryResult, SQRLErr> {
+    let mut result = QueryResult::Success;
+    for stmt in Parser::new(Lexer::new(sql))?.parse()? {
+        result = exec.run(stmt.stmt)?;
+    }
+    Ok(result)
+}
+
+fn rows(exec: &mut Executor, sql: &str) -> Vec<Vec<String>> {
+    match run(exec, sql).unwrap() {
+        QueryResult::Rows { rows, .. } => rows
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+        _ => panic!("{sql} returned no rows"),
+    }
+}
+
+#[test]
+fn var_char_checks_its_length() {
+    let value = VarChar::new("acorn", 5).unwrap();
+    assert_eq!(value.as_str(), "acorn");
+    assert_eq!(value.len(), 5);
+    assert!(VarChar::new("", 0).unwrap().is_empty());
+
+    let err = VarChar::new("acorns", 5).unwrap_err();
+    assert_eq!((err.len, err.max_len), (6, 5));
+    assert_eq!(err.to_string(), "value too long for VARCHAR(5): 6 characters");
+}
+
+#[test]
+fn var_char_counts_multibyte_characters_once() {
+    let value = VarChar::new("다람쥐", 3).unwrap();
+    assert_eq!(value.len(), 3);
+    assert_eq!(value.as_str().len(), 9);
+}
+
+#[test]
+fn var_char_columns_reject_long_values() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (name VARCHAR(5));
+         INSERT INTO t VALUES ('acorn');
+         INSERT INTO t VALUES (NULL);",
+    )
+    .unwrap();
+    let Err(err) = run(&mut exec, "INSERT INTO t VALUES ('acorns')") else {
+        panic!("long value was inserted");
+    };
+    assert!(matches!(&err, SQRLErr::ValueTooLong { column, .. } if column == "name"));
+    let Err(err) = run(&mut exec, "UPDATE t SET name = 'hazelnut'") else {
+        panic!("long value was stored");
+    };
+    assert!(matches!(err, SQRLErr::ValueTooLong { .. }), "{err:?}");
+    assert_eq!(rows(&mut exec, "SELECT name FROM t"), [["acorn"], ["nil"]]);
+}
+
+#[test]
+fn var_char_limits_count_characters_not_bytes() {
+    // ASCII, accented and CJK text right at the limit and one past it
+    for (fits, too_long) in [("abc", "abcd"), ("café", "cafés"), ("한글", "한글로")]
+    {
+        let max_len = fits.chars().count();
+        assert!(VarChar::new(fits, max_len).is_ok(), "{fits}");
+        let err = VarChar::new(too_long, max_len).unwrap_err();
+        assert_eq!(err.len, max_len + 1, "{too_long}");
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut exec = Executor::new(Storage::create(dir.path().join("test.db")).unwrap());
+    run(&mut exec, "CREATE TABLE t (name VARCHAR(2))").unwrap();
+    run(&mut exec, "INSERT INTO t VALUES ('한글')").unwrap();
+    assert!(run(&mut exec, "INSERT INTO t VALUES ('한글로')").is_err());
+}
+
+#[test]
+fn var_char_values_survive_reopening() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let mut exec = Executor::new(Storage::create(&path).unwrap());
+    run(
+        &mut exec,
+        "CREATE TABLE t (name VARCHAR(3));
+         INSERT INTO t VALUES ('abc');
+         INSERT INTO t VALUES ('한글');
+         INSERT INTO t VALUES ('');",
+    )
+    .unwrap();
+    let before = rows(&mut exec, "SELECT name FROM t");
+    drop(exec);
+    let mut exec = Executor::new(Storage::open(&path).unwrap());
+    assert_eq!(rows(&mut exec, "SELECT name FROM t"), before);
+    assert!(run(&mut exec, "INSERT INTO t VALUES ('abcd')").is_err());
+}
+
+#[test]
+fn var_char_values_too_long_for_the_column_are_corruption() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.sqrl");
+    let mut exec = Executor::new(Storage::create(&path).unwrap());
+    run(&mut exec, "CREATE TABLE t (name VARCHAR(5)); INSERT INTO t VALUES ('acorn');")
+        .unwrap();
+    drop(exec);
+
+    // narrow the column to VARCHAR(3) behind the engine's back: the
+    // ColumnCreate (tag 31) payload is table_id, col_id, then the type tag and
+    // its length
+    let mut bytes = std::fs::read(&path).unwrap();
+    let mut at = 64;
+    loop {
+        let len = u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+        if bytes[at + 12] == 31 {
+            let payload = &mut bytes[at + 16..at + len];
+            assert_eq!(payload[16..21], [5, 5, 0, 0, 0]);
+            payload[17] = 3;
+            let crc = crc32fast::hash(payload);
+            bytes[at + 4..at + 8].copy_from_slice(&crc.to_le_bytes());
+            break;
+        }
+        at += len;
+    }
+    std::fs::write(&path, bytes).unwrap();
+
+    let Err(StorageErr::Corrupted(msg)) = Storage::open(&path) else {
+        panic!("over-long value went unnoticed");
+    };
+    assert!(msg.contains("value too long for VARCHAR(3)"), "{msg}");
+    assert!(msg.contains("at offset"), "{msg}");
+}
@@ -0,0 +1,56 @@
+use litesqrl::query::visit::walk_stmt_mut;
+use litesqrl::query::{Expr, Lexer, Parser, Stmt, Visitor, collect_idents};
+
+fn parse_one(sql: &str) -> Stmt {
+    let mut stmts = Parser::new(Lexer::new(sql)).and_then(|mut p| p.parse()).unwrap();
+    stmts.remove(0).stmt
+}
+
+#[test]
+fn collect_idents_from_a_filtered_select() {
+    let stmt = parse_one(
+        "WITH recent AS (SELECT id, placed FROM orders WHERE placed > 10)
+         SELECT u.name AS who, total FROM users AS u
+         WHERE u.id IN (1, 2) AND total > (1 + bonus)
+         UNION SELECT name, total FROM recent
+         ORDER BY who",
+    );
+    let idents = collect_idents(&stmt);
+    assert_eq!(
+        idents.iter().map(|ident| &**ident).collect::<Vec<_>>(),
+        ["orders", "id", "placed", "users", "name", "total", "bonus", "recent"]
+    );
+}
+
+#[test]
+fn visitors_see_tables_and_columns_separately() {
+    #[derive(Default)]
+    struct Names {
+        tables: Vec<String>,
+        columns: Vec<String>,
+    }
+    impl Visitor for Names {
+        fn visit_table(&mut self, name: &str) {
+            self.tables.push(name.to_string());
+        }
+        fn visit_column(&mut self, name: &str) {
+            self.columns.push(name.to_string());
+        }
+    }
+
+    let mut names = Names::default();
+    names.visit_stmt(&parse_one("UPDATE t SET a = b + 1 WHERE c = 2"));
+    assert_eq!(names.tables, ["t"]);
+    assert_eq!(names.columns, ["a", "b", "c"]);
+}
+
+#[test]
+fn walk_stmt_mut_rewrites_literals() {
+    let mut stmt = parse_one("SELECT a + 1 FROM t WHERE b = 2 LIMIT 5");
+    walk_stmt_mut(&mut stmt, &mut |expr| {
+        if let Expr::Int(n) = expr {
+            *n *= 10;
+        }
+    });
+    assert_eq!(stmt.to_sql(), "SELECT a + 10 FROM t WHERE b = 20 LIMIT 50");
+}
@@ -0,0 +1,72 @@
+use litesqrl::Database;
+use litesqrl::schema::DataValue;
+
+fn setup() -> Database {
+    let mut db = Database::memory();
+    db.execute(
+        "CREATE TABLE orders (id INT, total INT);
+         INSERT INTO orders VALUES (1, 5), (2, 50), (3, 20), (4, 70);",
+    )
+    .unwrap();
+    db
+}
+
+fn ints(db: &mut Database, sql: &str) -> Vec<i64> {
+    db.query(sql)
+        .unwrap()
+        .into_iter()
+        .map(|row| match row[0] {
+            DataValue::Int(n) => n,
+            ref other => panic!("not an INT: {other:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn single_cte() {
+    let mut db = setup();
+    assert_eq!(
+        ints(
+            &mut db,
+            "WITH big AS (SELECT id FROM orders WHERE total > 10)
+             SELECT id FROM big ORDER BY id DESC"
+        ),
+        [4, 3, 2]
+    );
+}
+
+#[test]
+fn cte_referenced_twice() {
+    let mut db = setup();
+    assert_eq!(
+        ints(
+            &mut db,
+            "WITH big AS (SELECT id FROM orders WHERE total > 10)
+             SELECT id FROM big WHERE id < 3
+             UNION ALL SELECT id FROM big WHERE id > 3"
+        ),
+        [2, 4]
+    );
+    // a later CTE can read an earlier one
+    assert_eq!(
+        ints(
+            &mut db,
+            "WITH big AS (SELECT id, total FROM orders WHERE total > 10),
+                  bigger AS (SELECT id FROM big WHERE total > 30)
+             SELECT id FROM bigger"
+        ),
+        [2, 4]
+    );
+}
+
+#[test]
+fn cte_shadows_a_table_of_the_same_name() {
+    let mut db = setup();
+    assert_eq!(
+        ints(&mut db, "WITH orders AS (SELECT 9 AS id) SELECT id FROM orders"),
+        [9]
+    );
+    // only for the statement it belongs to
+    assert_eq!(ints(&mut db, "SELECT COUNT(*) FROM orders"), [4]);
+    assert!(db.query("WITH x AS (SELECT 1) SELECT * FROM y").is_err());
+}